@@ -0,0 +1,121 @@
+//! Access logging for long-running instances.
+//!
+//! With `--access-log`, every request is logged once its response has been
+//! produced: time, client address, method, path, status, duration and the
+//! repository being served. Streamed responses (archives, live updates) are
+//! logged when their headers go out, so the duration doesn't cover the body.
+//!
+//! - `text`: One line per request, for reading
+//! - `json`: One JSON object per line, for log shippers and audits
+//!
+//! Lines go to `--log-file` (appended to, together with the server's own
+//! diagnostics) or to stdout. A `token` query parameter is never logged.
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::git::SharedRepo;
+use crate::routes::blocking::blocking;
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum AccessLogFormat {
+    Text,
+    Json,
+}
+
+pub struct AccessLog {
+    format: AccessLogFormat,
+    repo: SharedRepo,
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+/// One logged request (the `json` format's fields)
+#[derive(Serialize)]
+struct AccessRecord<'a> {
+    time: String,
+    client: Option<String>,
+    method: &'a str,
+    path: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    query: Option<String>,
+    status: u16,
+    duration_ms: f64,
+    repo: Option<String>,
+}
+
+impl AccessLog {
+    pub fn new(format: AccessLogFormat, repo: SharedRepo, out: Box<dyn Write + Send>) -> Self {
+        Self {
+            format,
+            repo,
+            out: Mutex::new(out),
+        }
+    }
+
+    fn write(&self, record: &AccessRecord) {
+        let line = match self.format {
+            AccessLogFormat::Json => match serde_json::to_string(record) {
+                Ok(json) => json,
+                Err(_) => return,
+            },
+            AccessLogFormat::Text => format!(
+                "{} {} \"{} {}{}\" {} {:.1}ms {}",
+                record.time,
+                record.client.as_deref().unwrap_or("-"),
+                record.method,
+                record.path,
+                record.query.as_ref().map(|q| format!("?{}", q)).unwrap_or_default(),
+                record.status,
+                record.duration_ms,
+                record.repo.as_deref().unwrap_or("-"),
+            ),
+        };
+
+        // A full disk or closed stdout shouldn't fail the request
+        if let Ok(mut out) = self.out.lock() {
+            let _ = writeln!(out, "{}", line).and_then(|_| out.flush());
+        }
+    }
+}
+
+/// Middleware: log each request with its response status and duration
+pub async fn log_requests(State(log): State<Arc<AccessLog>>, request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let query = request.uri().query().and_then(without_token);
+    let client = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0.ip().to_string());
+    // The repository the request was made against, before a switch replaces it
+    let repo = blocking(log.repo.clone(), |repo| Ok(repo.path.clone())).await.ok();
+
+    let response = next.run(request).await;
+
+    log.write(&AccessRecord {
+        time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        client,
+        method: method.as_str(),
+        path: &path,
+        query,
+        status: response.status().as_u16(),
+        duration_ms: (started.elapsed().as_secs_f64() * 1e6).round() / 1e3,
+        repo,
+    });
+    response
+}
+
+/// The query string minus any `token` parameter; `None` if nothing is left
+fn without_token(query: &str) -> Option<String> {
+    let kept: Vec<&str> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty() && *pair != "token" && !pair.starts_with("token="))
+        .collect();
+    (!kept.is_empty()).then(|| kept.join("&"))
+}
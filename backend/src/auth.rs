@@ -0,0 +1,178 @@
+//! Token-based access control for the API.
+//!
+//! With `--auth-token`, every `/api` request must present the token, so a
+//! forwarded or proxied port doesn't hand whoever reaches it read and
+//! checkout access to the repository. The token is accepted as:
+//! - `Authorization: Bearer <token>` (scripts, curl)
+//! - the `git_viewer_token` cookie, set when the UI is opened through the
+//!   printed `?token=` URL, so fetches, the live update stream, images and
+//!   downloads carry it without the frontend knowing about it. The cookie is
+//!   `SameSite=Strict`, so other sites can't ride on it despite the
+//!   permissive CORS policy.
+//! - a `token` query parameter
+//!
+//! Static assets stay public: they are the same for everyone and hold no
+//! repository data.
+
+use axum::{
+    extract::{Query, Request, State},
+    http::{header, HeaderValue, Uri},
+    middleware::Next,
+    response::Response,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::{AppError, Result};
+
+/// Cookie carrying the token for browser requests
+const TOKEN_COOKIE: &str = "git_viewer_token";
+
+/// Random bytes in a generated token (hex encoded, so twice as many characters)
+const GENERATED_TOKEN_BYTES: usize = 24;
+
+#[derive(Clone)]
+pub struct AuthToken(Arc<str>);
+
+impl AuthToken {
+    /// A token given on the command line; limited to URL-safe characters so it
+    /// survives query strings and cookies unescaped
+    pub fn new(token: &str) -> anyhow::Result<Self> {
+        if token.is_empty() || !token.bytes().all(|b| b.is_ascii_alphanumeric() || b"-._~".contains(&b)) {
+            anyhow::bail!("auth token may only contain letters, digits and - . _ ~");
+        }
+        Ok(Self(token.into()))
+    }
+
+    pub fn generate() -> anyhow::Result<Self> {
+        let mut bytes = [0u8; GENERATED_TOKEN_BYTES];
+        getrandom::fill(&mut bytes).map_err(|e| anyhow::anyhow!("Failed to generate auth token: {}", e))?;
+        let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        Ok(Self(token.into()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Compare in constant time, so response timing doesn't leak a matching prefix
+    fn matches(&self, candidate: &str) -> bool {
+        let (expected, candidate) = (self.0.as_bytes(), candidate.as_bytes());
+        expected.len() == candidate.len()
+            && expected.iter().zip(candidate).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+}
+
+/// Middleware: reject `/api` requests without the token; a page load with a
+/// valid `?token=` gets the session cookie
+pub async fn require_token(State(token): State<AuthToken>, request: Request, next: Next) -> Result<Response> {
+    let path = request.uri().path();
+    let is_api = path == "/api" || path.starts_with("/api/");
+
+    if !is_api {
+        let set_cookie = query_token(request.uri()).is_some_and(|t| token.matches(&t));
+        let mut response = next.run(request).await;
+        if set_cookie {
+            let cookie = format!("{}={}; Path=/; HttpOnly; SameSite=Strict", TOKEN_COOKIE, token.as_str());
+            if let Ok(value) = HeaderValue::from_str(&cookie) {
+                response.headers_mut().append(header::SET_COOKIE, value);
+            }
+        }
+        return Ok(response);
+    }
+
+    let authorized = bearer_token(&request).is_some_and(|t| token.matches(t))
+        || cookie_token(&request).is_some_and(|t| token.matches(t))
+        || query_token(request.uri()).is_some_and(|t| token.matches(&t));
+    if !authorized {
+        return Err(AppError::Unauthorized(
+            "Missing or invalid access token; open the URL printed at startup".to_string(),
+        ));
+    }
+    Ok(next.run(request).await)
+}
+
+fn bearer_token(request: &Request) -> Option<&str> {
+    request.headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+fn cookie_token(request: &Request) -> Option<&str> {
+    request.headers()
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .find_map(|cookie| cookie.trim().strip_prefix(TOKEN_COOKIE)?.strip_prefix('='))
+}
+
+fn query_token(uri: &Uri) -> Option<String> {
+    let Query(mut params) = Query::<HashMap<String, String>>::try_from_uri(uri).ok()?;
+    params.remove("token")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo::TestRepo;
+    use crate::routes::test_client::{request, send};
+    use axum::{http::StatusCode, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    const TOKEN: &str = "s3cret-token";
+
+    fn app(test: &TestRepo) -> Router {
+        crate::routes::create_router(test.shared())
+            .route("/", get(|| async { "page" }))
+            .layer(middleware::from_fn_with_state(AuthToken::new(TOKEN).unwrap(), require_token))
+    }
+
+    #[tokio::test]
+    async fn api_requests_need_the_token_in_a_header_cookie_or_query() {
+        let mut test = TestRepo::new();
+        test.commit(("Ada", "ada@example.com"), "a.txt", "a\n", "first");
+        let app = app(&test);
+
+        let (status, body) = send(&app, request("GET", "/api/v1/repository", None)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(body["error"].as_str().unwrap().contains("access token"));
+
+        let mut wrong = request("GET", "/api/v1/repository", None);
+        wrong.headers_mut().insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer s3cret-tokem"));
+        assert_eq!(send(&app, wrong).await.0, StatusCode::UNAUTHORIZED);
+
+        let mut bearer = request("GET", "/api/v1/repository", None);
+        bearer.headers_mut().insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer s3cret-token"));
+        let (status, body) = send(&app, bearer).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["head_commit"]["message"], "first");
+
+        let mut cookie = request("GET", "/api/v1/repository", None);
+        cookie.headers_mut().insert(header::COOKIE, HeaderValue::from_static("theme=dark; git_viewer_token=s3cret-token"));
+        assert_eq!(send(&app, cookie).await.0, StatusCode::OK);
+
+        let query = request("GET", "/api/v1/repository?token=s3cret-token", None);
+        assert_eq!(send(&app, query).await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn page_load_with_the_token_sets_the_session_cookie() {
+        let test = TestRepo::new();
+        let app = app(&test);
+
+        let response = app.clone().oneshot(request("GET", "/?token=s3cret-token", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+        assert!(cookie.starts_with("git_viewer_token=s3cret-token;"), "{}", cookie);
+        assert!(cookie.contains("HttpOnly") && cookie.contains("SameSite=Strict"));
+
+        // Pages stay public, but a wrong token gets no cookie
+        let response = app.oneshot(request("GET", "/?token=guess", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(header::SET_COOKIE).is_none());
+    }
+}
@@ -5,19 +5,28 @@
 //! with JSON error bodies.
 //!
 //! Error mappings:
-//! - `RepoNotFound`, `PathNotFound`, `CommitNotFound` → 404
-//! - `InvalidPath` → 400
+//! - `Warming` → 202 with `Retry-After` (the commit cache is still being built)
+//! - `RepoNotFound`, `PathNotFound`, `CommitNotFound`, `ObjectNotFound`, `AuthorNotFound` → 404
+//! - `InvalidPath`, `BadRequest` → 400
+//! - `Unauthorized` → 401
+//! - `Forbidden` → 403
 //! - `CheckoutConflict` → 409
+//! - `TooManyRequests` → 429
 //! - `Git`, `Internal` → 500
+//! - `Busy` → 503
+//! - `Timeout` → 504 (stopped after `--request-timeout`)
 
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
 use thiserror::Error;
 
+/// When a client should retry a request answered with `Warming`
+const WARMING_RETRY_AFTER_SECS: u64 = 1;
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Git error: {0}")]
@@ -32,12 +41,39 @@ pub enum AppError {
     #[error("Commit not found: {0}")]
     CommitNotFound(String),
 
+    #[error("Object not found: {0}")]
+    ObjectNotFound(String),
+
+    #[error("Author not found: {0}")]
+    AuthorNotFound(String),
+
     #[error("Invalid path: {0}")]
     InvalidPath(String),
 
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("Checkout conflict: {0}")]
     CheckoutConflict(String),
 
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
+
+    #[error("Warming up: {0}")]
+    Warming(String),
+
+    #[error("Busy: {0}")]
+    Busy(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -55,10 +91,23 @@ impl IntoResponse for AppError {
             AppError::CommitNotFound(oid) => {
                 (StatusCode::NOT_FOUND, format!("Commit not found: {}", oid))
             }
+            AppError::ObjectNotFound(oid) => {
+                (StatusCode::NOT_FOUND, format!("Object not found: {}", oid))
+            }
+            AppError::AuthorNotFound(email) => {
+                (StatusCode::NOT_FOUND, format!("Author not found: {}", email))
+            }
             AppError::InvalidPath(path) => {
                 (StatusCode::BAD_REQUEST, format!("Invalid path: {}", path))
             }
+            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
+            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg.clone()),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg.clone()),
             AppError::CheckoutConflict(msg) => (StatusCode::CONFLICT, msg.clone()),
+            AppError::TooManyRequests(msg) => (StatusCode::TOO_MANY_REQUESTS, msg.clone()),
+            AppError::Warming(msg) => (StatusCode::ACCEPTED, msg.clone()),
+            AppError::Busy(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg.clone()),
+            AppError::Timeout(msg) => (StatusCode::GATEWAY_TIMEOUT, msg.clone()),
             AppError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.clone()),
         };
 
@@ -66,6 +115,9 @@ impl IntoResponse for AppError {
             "error": error_message,
         }));
 
+        if matches!(self, AppError::Warming(_)) {
+            return (status, [(header::RETRY_AFTER, WARMING_RETRY_AFTER_SECS.to_string())], body).into_response();
+        }
         (status, body).into_response()
     }
 }
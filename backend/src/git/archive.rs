@@ -0,0 +1,82 @@
+//! Tree snapshots as zip or tar.gz archives.
+//!
+//! The archive is produced by `git archive` from the commit's tree objects, so
+//! it works on bare repositories and ignores the working tree. Files are placed
+//! under a `<repo>-<short oid>/` directory, matching what forges offer as
+//! "download source".
+//!
+//! Supports: GET /api/v1/repository/archive
+
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+
+use git2::Oid;
+
+use crate::error::{AppError, Result};
+use crate::git::repository::{resolve_commit_or_head, GitRepository};
+use crate::models::ArchiveFormat;
+
+/// A resolved archive request, ready to be handed to `git archive`
+pub struct ArchiveSpec {
+    git_dir: PathBuf,
+    commit: Oid,
+    /// Top-level directory inside the archive (and file name stem)
+    pub prefix: String,
+}
+
+impl GitRepository {
+    /// Resolve `rev` (HEAD when `None`) to the commit to archive
+    pub fn archive_spec(&self, rev: Option<&str>) -> Result<ArchiveSpec> {
+        let commit = self.with_repo(|repo| Ok(resolve_commit_or_head(repo, rev)?.id()))?;
+
+        let name = Path::new(&self.path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "repository".to_string());
+        let name = name.strip_suffix(".git").unwrap_or(&name);
+        let short = commit.to_string()[..7].to_string();
+
+        Ok(ArchiveSpec {
+            git_dir: self.git_dir()?,
+            commit,
+            prefix: format!("{}-{}", name, short),
+        })
+    }
+}
+
+impl ArchiveSpec {
+    /// Start `git archive`; the archive is written to the child's stdout
+    pub fn spawn(&self, format: ArchiveFormat) -> Result<Child> {
+        tracing::info!("Archiving {} as {}", self.commit, extension(format));
+        Command::new("git")
+            .arg("--git-dir")
+            .arg(&self.git_dir)
+            .arg("archive")
+            .arg(format!("--format={}", extension(format)))
+            .arg(format!("--prefix={}/", self.prefix))
+            .arg(self.commit.to_string())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| AppError::Internal(format!("Failed to run git: {}", e)))
+    }
+
+    /// Download file name, e.g. `repo-1a2b3c4.tar.gz`
+    pub fn file_name(&self, format: ArchiveFormat) -> String {
+        format!("{}.{}", self.prefix, extension(format))
+    }
+}
+
+pub fn extension(format: ArchiveFormat) -> &'static str {
+    match format {
+        ArchiveFormat::Zip => "zip",
+        ArchiveFormat::TarGz => "tar.gz",
+    }
+}
+
+pub fn content_type(format: ArchiveFormat) -> &'static str {
+    match format {
+        ArchiveFormat::Zip => "application/zip",
+        ArchiveFormat::TarGz => "application/gzip",
+    }
+}
@@ -0,0 +1,50 @@
+//! `.gitattributes` that change how a file is shown in a diff.
+//!
+//! - `binary`, `-diff`: the file is binary whatever its bytes look like, so it
+//!   gets no hunks and its contents aren't loaded
+//! - `linguist-generated`: the file is generated; its hunks are kept but its
+//!   contents aren't loaded, and the viewer starts it collapsed
+//!
+//! Attributes are looked up the way `git diff` does: the working tree's
+//! `.gitattributes` files, then the index, plus `info/attributes` and the
+//! global/system files. Bare repositories have neither, so HEAD's are used.
+
+use git2::{AttrCheckFlags, AttrValue, Repository};
+use std::path::Path;
+
+/// `GIT_ATTR_CHECK_INCLUDE_HEAD`, which git2 doesn't expose: also read
+/// `.gitattributes` from HEAD's tree
+const CHECK_INCLUDE_HEAD: u32 = 1 << 3;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiffAttributes {
+    pub binary: bool,
+    pub generated: bool,
+}
+
+/// Diff-relevant attributes of `path`; unreadable attributes count as unset
+pub fn diff_attributes(repo: &Repository, path: Option<&str>) -> DiffAttributes {
+    let Some(path) = path else {
+        return DiffAttributes::default();
+    };
+    let flags = if repo.is_bare() {
+        AttrCheckFlags::INDEX_ONLY | AttrCheckFlags::from_bits_retain(CHECK_INCLUDE_HEAD)
+    } else {
+        AttrCheckFlags::FILE_THEN_INDEX
+    };
+    let attr = |name: &str| {
+        repo.get_attr_bytes(Path::new(path), name, flags)
+            .map(AttrValue::from_bytes)
+            .unwrap_or(AttrValue::Unspecified)
+    };
+
+    // `binary` is a macro for `-diff -merge -text`; check both in case the
+    // macro isn't expanded
+    let binary = matches!(attr("binary"), AttrValue::True) || matches!(attr("diff"), AttrValue::False);
+    let generated = match attr("linguist-generated") {
+        AttrValue::True => true,
+        AttrValue::String(value) => value.eq_ignore_ascii_case("true") || value == "1",
+        _ => false,
+    };
+    DiffAttributes { binary, generated }
+}
@@ -0,0 +1,449 @@
+//! Per-line blame.
+//!
+//! Provides:
+//! - `get_blame()`: Author attribution for every line of a file at a commit
+//! - `stream_blame()`: The same, emitted in chunks of lines as they are ready
+//!
+//! Commits listed in `.git-blame-ignore-revs` (or the file named by
+//! `blame.ignoreRevsFile`), plus any passed explicitly, are looked through like
+//! `git blame --ignore-revs-file`: a line last touched by an ignored commit is
+//! attributed to whoever wrote the corresponding line in that commit's parent.
+//! Lines an ignored commit added without a counterpart keep the ignored commit.
+//!
+//! Renames are followed, so blame does not stop at the commit that moved a
+//! file; each line carries its path and line number in the commit it is
+//! attributed to, for following it further with `before`.
+//!
+//! `before` blames the file as it was just before a commit (at its first
+//! parent), so a line's history can be followed past the commit that last
+//! touched it; `oldest` stops at a commit, attributing older lines to it.
+//!
+//! Supports frontend: DiffViewer blame gutter
+
+use git2::{DiffOptions, Oid, Patch, Repository};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::error::{AppError, Result};
+use crate::git::deadline;
+use crate::git::profiling::OpLog;
+use crate::git::repository::{open_handle, resolve_commit, resolve_commit_or_head, GitRepository, RepoLocation};
+use crate::models::{BlameEvent, BlameLine, BlameResponse};
+
+/// Ignore list read from the blamed commit's tree when `blame.ignoreRevsFile` is unset
+pub const IGNORE_REVS_FILE: &str = ".git-blame-ignore-revs";
+
+/// Most consecutive ignored commits followed for one line
+const MAX_IGNORE_DEPTH: usize = 50;
+
+/// Files longer than this are blamed on a repository handle of their own,
+/// outside the repository lock
+const LARGE_BLAME_LINES: usize = 5000;
+
+/// Most chunks a streamed blame is split into; each one walks history again
+const MAX_BLAME_CHUNKS: usize = 20;
+
+/// Where blame starts and stops, and which commits it looks through
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlameParams<'a> {
+    /// Blame at this commit's first parent instead of `commit` (OID or ref)
+    pub before: Option<&'a str>,
+    /// Do not look further back than this commit (OID or ref)
+    pub oldest: Option<&'a str>,
+    /// Commits to ignore (OIDs or refs) in addition to the ignore file
+    pub ignore_revs: &'a [String],
+    /// Read the ignore file (`blame.ignoreRevsFile` or `.git-blame-ignore-revs`)
+    pub use_ignore_file: bool,
+}
+
+impl GitRepository {
+    /// Get blame information for a file at a specific commit
+    pub fn get_blame(&self, path: &str, commit_oid: Option<&str>, params: BlameParams) -> Result<BlameResponse> {
+        let _op = self.op_log.start("blame", format!("path={} commit={}", path, commit_oid.unwrap_or("HEAD")));
+        let (commit_id, ignore, oldest, large) = self.with_repo(|repo| {
+            let commit_id = resolve_blame_start(repo, commit_oid, params.before)?;
+            let ignore = ignored_commits(repo, commit_id, params)?;
+            let oldest = params.oldest.map(|spec| resolve_commit(repo, spec).map(|c| c.id())).transpose()?;
+            let large = line_count(repo, commit_id, path)? > LARGE_BLAME_LINES;
+            Ok((commit_id, ignore, oldest, large.then(|| (repo.path().to_path_buf(), repo.workdir().map(Path::to_path_buf)))))
+        })?;
+
+        // libgit2 can't interrupt a blame, and one of a huge file can take
+        // minutes; on its own handle, other requests aren't held up by it,
+        // and a request past its deadline is answered right away
+        let mut lines = match large {
+            Some((git_dir, workdir)) => {
+                let repo = open_handle(&git_dir, workdir.as_deref())?;
+                Blamer::new(&repo, &ignore, oldest).lines(path, commit_id, None)?
+            }
+            None => self.with_repo(|repo| Blamer::new(repo, &ignore, oldest).lines(path, commit_id, None))?,
+        };
+        deadline::check()?;
+
+        // Sort by line number
+        lines.sort_by_key(|l| l.line_number);
+
+        Ok(BlameResponse {
+            path: path.to_string(),
+            commit: commit_id.to_string(),
+            ignored_revs: sorted_oids(&ignore),
+            lines,
+        })
+    }
+
+    /// Prepare a blame of a file in chunks of `chunk_lines`
+    ///
+    /// Only the start commit, ignored commits and `oldest` are resolved here,
+    /// under the repository lock; the chunks are blamed on a handle of their
+    /// own (see `BlameStream::run`).
+    pub fn stream_blame(
+        &self,
+        path: &str,
+        commit_oid: Option<&str>,
+        chunk_lines: usize,
+        params: BlameParams,
+    ) -> Result<BlameStream> {
+        let (location, commit_id, ignore, oldest) = self.with_repo(|repo| {
+            let commit_id = resolve_blame_start(repo, commit_oid, params.before)?;
+            let ignore = ignored_commits(repo, commit_id, params)?;
+            let oldest = params.oldest.map(|spec| resolve_commit(repo, spec).map(|c| c.id())).transpose()?;
+            Ok((RepoLocation::of(repo), commit_id, ignore, oldest))
+        })?;
+
+        Ok(BlameStream {
+            location,
+            op_log: self.op_log.clone(),
+            path: path.to_string(),
+            commit_id,
+            ignore,
+            oldest,
+            chunk_lines,
+        })
+    }
+}
+
+/// A chunked blame prepared by `GitRepository::stream_blame`
+pub struct BlameStream {
+    location: RepoLocation,
+    op_log: Arc<OpLog>,
+    path: String,
+    commit_id: Oid,
+    ignore: HashSet<Oid>,
+    oldest: Option<Oid>,
+    chunk_lines: usize,
+}
+
+impl BlameStream {
+    /// Blame the file chunk by chunk, emitting each chunk as soon as it is ready
+    ///
+    /// libgit2 only returns a blame once the whole file is attributed, so huge
+    /// files are split into line ranges; the first gutter data arrives after one
+    /// chunk instead of the full file. Each chunk walks history again for its
+    /// range, so the whole file costs up to one full blame per chunk; chunks
+    /// grow past `chunk_lines` to keep their number at `MAX_BLAME_CHUNKS`.
+    /// Stops early when `emit` returns false.
+    pub fn run<F>(self, mut emit: F) -> Result<()>
+    where
+        F: FnMut(BlameEvent) -> bool,
+    {
+        let _op = self.op_log.start("blame_stream", format!("path={} commit={}", self.path, self.commit_id));
+        let repo = self.location.open()?;
+        let path = self.path.as_str();
+        let commit_id = self.commit_id;
+
+        let total_lines = line_count(&repo, commit_id, path)?;
+
+        if !emit(BlameEvent::Start {
+            path: path.to_string(),
+            commit: commit_id.to_string(),
+            total_lines,
+            ignored_revs: sorted_oids(&self.ignore),
+        }) {
+            return Ok(());
+        }
+
+        // Shared across chunks so each ignored commit's parent is blamed once
+        let mut blamer = Blamer::new(&repo, &self.ignore, self.oldest);
+        let chunk_lines = self.chunk_lines.max(total_lines.div_ceil(MAX_BLAME_CHUNKS)).max(1);
+        let mut start = 1;
+        while start <= total_lines {
+            let end = (start + chunk_lines - 1).min(total_lines);
+
+            let mut lines = blamer.lines(path, commit_id, Some((start, end)))?;
+            lines.retain(|l| (start..=end).contains(&(l.line_number as usize)));
+            lines.sort_by_key(|l| l.line_number);
+
+            if !emit(BlameEvent::Lines { lines }) {
+                return Ok(());
+            }
+            start = end + 1;
+        }
+
+        emit(BlameEvent::Done);
+        Ok(())
+    }
+}
+
+/// Number of lines of `path` at `commit`
+fn line_count(repo: &Repository, commit: Oid, path: &str) -> Result<usize> {
+    let entry = repo.find_commit(commit)?
+        .tree()?
+        .get_path(Path::new(path))
+        .map_err(|_| AppError::PathNotFound(path.to_string()))?;
+    let blob = repo.find_blob(entry.id())
+        .map_err(|_| AppError::PathNotFound(path.to_string()))?;
+    let content = blob.content();
+    Ok(content.iter().filter(|&&b| b == b'\n').count() + usize::from(content.last().is_some_and(|&b| b != b'\n')))
+}
+
+/// Commit to blame at: the first parent of `before`, else `commit`, or HEAD
+fn resolve_blame_start(repo: &Repository, commit_oid: Option<&str>, before: Option<&str>) -> Result<Oid> {
+    let Some(before) = before else {
+        return Ok(resolve_commit_or_head(repo, commit_oid)?.id());
+    };
+    if commit_oid.is_some() {
+        return Err(AppError::BadRequest("Pass either commit or before, not both".to_string()));
+    }
+    let commit = resolve_commit(repo, before)?;
+    commit.parent_id(0)
+        .map_err(|_| AppError::BadRequest(format!("{} has no parent to blame at", before)))
+}
+
+/// Commits to look through: explicit revs (which must resolve) plus the
+/// ignore file's entries (unknown ones are skipped, as the file may list
+/// commits from other branches)
+fn ignored_commits(repo: &Repository, commit_id: Oid, params: BlameParams) -> Result<HashSet<Oid>> {
+    let mut ignore = HashSet::new();
+    for spec in params.ignore_revs.iter().filter(|s| !s.is_empty()) {
+        let commit = repo.revparse_single(spec)
+            .and_then(|obj| obj.peel_to_commit())
+            .map_err(|_| AppError::CommitNotFound(spec.clone()))?;
+        ignore.insert(commit.id());
+    }
+
+    if params.use_ignore_file
+        && let Some(text) = ignore_file_contents(repo, commit_id)
+    {
+        for line in text.lines() {
+            let spec = line.split('#').next().unwrap_or("").trim();
+            if spec.is_empty() {
+                continue;
+            }
+            if let Ok(commit) = repo.revparse_single(spec).and_then(|obj| obj.peel_to_commit()) {
+                ignore.insert(commit.id());
+            }
+        }
+    }
+    Ok(ignore)
+}
+
+/// The file named by `blame.ignoreRevsFile` (relative to the working tree), or
+/// `.git-blame-ignore-revs` as committed in the blamed commit
+fn ignore_file_contents(repo: &Repository, commit_id: Oid) -> Option<String> {
+    let configured = repo.config().ok().and_then(|c| c.get_path("blame.ignoreRevsFile").ok());
+    if let Some(path) = configured {
+        let path = match repo.workdir() {
+            Some(workdir) if path.is_relative() => workdir.join(path),
+            _ => path,
+        };
+        return std::fs::read_to_string(path).ok();
+    }
+
+    let entry = repo.find_commit(commit_id).ok()?.tree().ok()?.get_path(Path::new(IGNORE_REVS_FILE)).ok()?;
+    let blob = repo.find_blob(entry.id()).ok()?;
+    Some(String::from_utf8_lossy(blob.content()).to_string())
+}
+
+fn sorted_oids(oids: &HashSet<Oid>) -> Vec<String> {
+    let mut sorted: Vec<String> = oids.iter().map(|oid| oid.to_string()).collect();
+    sorted.sort();
+    sorted
+}
+
+/// One blame hunk, detached from the libgit2 blame it came from
+#[derive(Debug, Clone)]
+struct HunkInfo {
+    final_start: usize,
+    lines: usize,
+    commit: Oid,
+    /// Where the lines were in `commit`'s version of the file
+    orig_start: usize,
+    orig_path: String,
+    author_name: String,
+    author_email: String,
+    timestamp: i64,
+}
+
+/// Blames files while looking through ignored commits
+struct Blamer<'r> {
+    repo: &'r Repository,
+    ignore: &'r HashSet<Oid>,
+    oldest: Option<Oid>,
+    /// Whole-file blames of ignored commits' parents, by (parent, path)
+    parent_blames: HashMap<(Oid, String), Option<Vec<HunkInfo>>>,
+}
+
+impl<'r> Blamer<'r> {
+    fn new(repo: &'r Repository, ignore: &'r HashSet<Oid>, oldest: Option<Oid>) -> Self {
+        Self {
+            repo,
+            ignore,
+            oldest,
+            parent_blames: HashMap::new(),
+        }
+    }
+
+    /// Blame options bounded by `newest` and, if set, the oldest commit
+    fn options(&self, newest: Oid) -> git2::BlameOptions {
+        let mut blame_opts = git2::BlameOptions::new();
+        // Whole-file renames are always followed; moves and copies between files
+        // are traced as far as libgit2 implements them. Authors come out mapped
+        // through .mailmap, as everywhere else
+        blame_opts
+            .newest_commit(newest)
+            .use_mailmap(true)
+            .track_copies_same_commit_moves(true)
+            .track_copies_same_commit_copies(true);
+        if let Some(oldest) = self.oldest {
+            blame_opts.oldest_commit(oldest);
+        }
+        blame_opts
+    }
+
+    /// Lines of `path` at `commit`, optionally limited to an inclusive line range
+    fn lines(&mut self, path: &str, commit: Oid, range: Option<(usize, usize)>) -> Result<Vec<BlameLine>> {
+        deadline::check()?;
+        let mut blame_opts = self.options(commit);
+        if let Some((min, max)) = range {
+            blame_opts.min_line(min).max_line(max);
+        }
+        let blame = self.repo.blame_file(Path::new(path), Some(&mut blame_opts))
+            .map_err(|e| AppError::PathNotFound(format!("Cannot blame file '{}': {}", path, e)))?;
+
+        let mut lines = Vec::new();
+        for hunk in blame_hunks(&blame, path) {
+            for i in 0..hunk.lines {
+                let own_line = hunk.orig_start + i;
+                let origin = if self.ignore.contains(&hunk.commit) {
+                    self.look_through(hunk.commit, &hunk.orig_path, own_line, 0)?
+                } else {
+                    None
+                };
+                let (origin, orig_line) = origin.as_ref().map(|(h, l)| (h, *l)).unwrap_or((&hunk, own_line));
+                lines.push(BlameLine {
+                    line_number: (hunk.final_start + i) as u32,
+                    author_name: origin.author_name.clone(),
+                    author_email: origin.author_email.clone(),
+                    commit_oid: origin.commit.to_string(),
+                    timestamp: origin.timestamp,
+                    orig_path: (origin.orig_path != path).then(|| origin.orig_path.clone()),
+                    orig_line_number: orig_line as u32,
+                });
+            }
+        }
+        Ok(lines)
+    }
+
+    /// The hunk that wrote the line which `line` of `path` replaced in the
+    /// ignored `commit`, with the line's number in that hunk's commit; `None`
+    /// when there is no such line
+    fn look_through(&mut self, commit: Oid, path: &str, line: usize, depth: usize) -> Result<Option<(HunkInfo, usize)>> {
+        if depth >= MAX_IGNORE_DEPTH {
+            return Ok(None);
+        }
+        deadline::check()?;
+        let commit = self.repo.find_commit(commit)?;
+        if commit.parent_count() == 0 {
+            return Ok(None);
+        }
+        let parent = commit.parent(0)?;
+
+        let old_blob = match parent.tree()?.get_path(Path::new(path)) {
+            Ok(entry) => self.repo.find_blob(entry.id())?,
+            Err(_) => return Ok(None),
+        };
+        let new_entry = commit.tree()?.get_path(Path::new(path))
+            .map_err(|_| AppError::PathNotFound(path.to_string()))?;
+        let new_blob = self.repo.find_blob(new_entry.id())?;
+
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.context_lines(0);
+        let patch = Patch::from_blobs(&old_blob, None, &new_blob, None, Some(&mut diff_opts))?;
+        let Some(parent_line) = line_in_parent(&patch, line)? else {
+            return Ok(None);
+        };
+
+        let key = (parent.id(), path.to_string());
+        if !self.parent_blames.contains_key(&key) {
+            let mut blame_opts = self.options(parent.id());
+            let hunks = self.repo.blame_file(Path::new(path), Some(&mut blame_opts))
+                .ok()
+                .map(|blame| blame_hunks(&blame, path));
+            self.parent_blames.insert(key.clone(), hunks);
+        }
+        let Some(hunk) = self.parent_blames[&key]
+            .as_ref()
+            .and_then(|hunks| hunks.iter().find(|h| (h.final_start..h.final_start + h.lines).contains(&parent_line)))
+            .cloned()
+        else {
+            return Ok(None);
+        };
+
+        let orig_line = hunk.orig_start + (parent_line - hunk.final_start);
+        if self.ignore.contains(&hunk.commit)
+            && let Some(deeper) = self.look_through(hunk.commit, &hunk.orig_path.clone(), orig_line, depth + 1)?
+        {
+            return Ok(Some(deeper));
+        }
+        Ok(Some((hunk, orig_line)))
+    }
+}
+
+fn blame_hunks(blame: &git2::Blame, path: &str) -> Vec<HunkInfo> {
+    blame
+        .iter()
+        .map(|hunk| {
+            let sig = hunk.final_signature();
+            HunkInfo {
+                final_start: hunk.final_start_line(),
+                lines: hunk.lines_in_hunk(),
+                commit: hunk.final_commit_id(),
+                orig_start: hunk.orig_start_line(),
+                orig_path: hunk
+                    .path()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.to_string()),
+                author_name: sig.name().unwrap_or("Unknown").to_string(),
+                author_email: sig.email().unwrap_or("").to_string(),
+                timestamp: sig.when().seconds(),
+            }
+        })
+        .collect()
+}
+
+/// Line of the old side of a zero-context patch corresponding to `line` on the
+/// new side: unchanged lines shift by the preceding hunks, and the k-th line of
+/// a replaced block maps to the k-th line it replaced (or the block's last one)
+fn line_in_parent(patch: &Patch, line: usize) -> Result<Option<usize>> {
+    let mut delta: i64 = 0;
+    for idx in 0..patch.num_hunks() {
+        let (hunk, _) = patch.hunk(idx)?;
+        let (old_start, old_lines) = (hunk.old_start() as usize, hunk.old_lines() as usize);
+        let (new_start, new_lines) = (hunk.new_start() as usize, hunk.new_lines() as usize);
+
+        if new_lines > 0 && (new_start..new_start + new_lines).contains(&line) {
+            if old_lines == 0 {
+                return Ok(None);
+            }
+            return Ok(Some(old_start + (line - new_start).min(old_lines - 1)));
+        }
+        // A pure deletion sits after new line `new_start`
+        let first_after = if new_lines > 0 { new_start } else { new_start + 1 };
+        if line < first_after {
+            break;
+        }
+        delta += old_lines as i64 - new_lines as i64;
+    }
+    Ok(Some((line as i64 + delta) as usize))
+}
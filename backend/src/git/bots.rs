@@ -0,0 +1,70 @@
+//! Bot author detection.
+//!
+//! Authors whose name or email matches one of the configured patterns are
+//! treated as bots and left out of contributor lists and diff author badges
+//! unless a request passes `include_bots=true`. Patterns are matched
+//! case-insensitively and only `*` is special, so `*[bot]` matches
+//! `dependabot[bot]` literally.
+//!
+//! Supports: commit list contributors, StatusTab contributors, DiffViewer author badges
+
+use crate::git::repository::GitRepository;
+use crate::models::DiffResponse;
+
+/// Patterns used unless more are configured with `--bot-pattern`
+pub const DEFAULT_BOT_PATTERNS: &[&str] = &["*[bot]", "*[bot]@*"];
+
+impl GitRepository {
+    /// Add patterns on top of the defaults
+    pub fn with_bot_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.bot_patterns.extend(patterns);
+        self
+    }
+
+    pub fn is_bot(&self, name: &str, email: &str) -> bool {
+        self.bot_patterns
+            .iter()
+            .any(|pattern| wildcard_match(pattern, name) || wildcard_match(pattern, email))
+    }
+
+    /// Drop bot authors from a diff's contributor list and per-file badges
+    pub fn strip_bots_from_diff(&self, response: &mut DiffResponse) {
+        response.contributors.retain(|a| !self.is_bot(&a.name, &a.email));
+
+        for file in &mut response.files {
+            file.authors.retain(|a| !self.is_bot(&a.name, &a.email));
+            // Authors are sorted by commit count, so the first human is the biggest change
+            file.biggest_change_author = file.authors.first().map(|a| a.email.clone());
+        }
+    }
+}
+
+pub fn default_bot_patterns() -> Vec<String> {
+    DEFAULT_BOT_PATTERNS.iter().map(|p| p.to_string()).collect()
+}
+
+/// Case-insensitive match where `*` stands for any run of characters
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let text = text.to_lowercase();
+
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*` at all: exact match
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
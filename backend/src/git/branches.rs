@@ -0,0 +1,209 @@
+//! Branch lifecycle operations.
+//!
+//! - `create_branch()`: New local branch at any revision, optionally checked out
+//! - `delete_branch()`: Remove a local branch that is merged (or any, with `force`)
+//! - `rename_branch()`: Rename a local branch, keeping HEAD and tracking config pointing at it
+//!
+//! Listing and switching live in repository.rs next to the other HEAD
+//! operations.
+//!
+//! Supports frontend: BranchSwitcher's new-branch form, renaming and stale branch cleanup
+
+use git2::{Branch, BranchType, Repository};
+
+use crate::error::{AppError, Result};
+use crate::git::mailmap;
+use crate::git::repository::{commit_to_info, resolve_commit, switch_to, GitRepository};
+use crate::git::status::ensure_clean_for_checkout;
+use crate::models::{BranchInfo, DeletedBranch, RenamedBranch, UpstreamStatus};
+
+impl GitRepository {
+    /// Create a local branch at `start_point` (default HEAD) and optionally switch to it
+    ///
+    /// Starting from a remote-tracking branch (`origin/feature`) sets it as the
+    /// new branch's upstream, as `git branch` does by default.
+    pub fn create_branch(&self, name: &str, start_point: Option<&str>, checkout: bool) -> Result<BranchInfo> {
+        let repo = self.repo.lock().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
+
+        if !Branch::name_is_valid(name)? {
+            return Err(AppError::BadRequest(format!("Invalid branch name: {}", name)));
+        }
+        if repo.find_branch(name, BranchType::Local).is_ok() {
+            return Err(AppError::BadRequest(format!("Local branch '{}' already exists", name)));
+        }
+
+        let start_point = start_point.unwrap_or("HEAD");
+        let commit = resolve_commit(&repo, start_point)?;
+
+        // Check before creating anything, so a refused checkout leaves no branch behind
+        if checkout {
+            if repo.is_bare() {
+                return Err(AppError::BadRequest("Cannot check out a branch in a bare repository".to_string()));
+            }
+            ensure_clean_for_checkout(&repo)?;
+        }
+
+        let mut branch = repo.branch(name, &commit, false)?;
+
+        // Tracking is skipped when the remote isn't configured, like git does
+        let upstream = if repo.find_branch(start_point, BranchType::Remote).is_ok()
+            && branch.set_upstream(Some(start_point)).is_ok()
+        {
+            Some(UpstreamStatus { name: start_point.to_string(), ahead: 0, behind: 0 })
+        } else {
+            None
+        };
+
+        if checkout {
+            let refname = branch.get().name()
+                .ok_or_else(|| AppError::Internal("Invalid branch reference".to_string()))?;
+            switch_to(&repo, refname, &commit)?;
+        }
+
+        tracing::info!(
+            "Created branch '{}' at {} ({}){}",
+            name,
+            commit.id(),
+            start_point,
+            if checkout { " and checked it out" } else { "" }
+        );
+
+        let mailmap = mailmap::load(&repo);
+        Ok(BranchInfo {
+            name: name.to_string(),
+            is_current: checkout,
+            is_remote: false,
+            last_commit: Some(commit_to_info(&commit, mailmap.as_ref())),
+            upstream,
+        })
+    }
+
+    /// Delete a local branch, returning the commit it pointed at
+    ///
+    /// The checked-out branch is never deleted. Without `force`, the branch
+    /// must be merged into its upstream (or HEAD when it has none), matching
+    /// `git branch -d`.
+    pub fn delete_branch(&self, name: &str, force: bool) -> Result<DeletedBranch> {
+        self.with_repo(|repo| {
+            let mut branch = repo.find_branch(name, BranchType::Local)
+                .map_err(|_| AppError::PathNotFound(format!("Branch not found: {}", name)))?;
+
+            if branch.is_head() {
+                return Err(AppError::BadRequest(format!(
+                    "Cannot delete branch '{}': it is currently checked out",
+                    name
+                )));
+            }
+
+            let tip = branch.get().peel_to_commit()?.id();
+            // (name, tip) of the upstream; `None` without one or if it is gone
+            let upstream = branch.upstream().ok().and_then(|u| {
+                let upstream_name = u.name().ok().flatten()?.to_string();
+                Some((upstream_name, u.get().target()?))
+            });
+            let (merged_into, base) = match &upstream {
+                Some((upstream_name, upstream_tip)) => (upstream_name.clone(), Some(*upstream_tip)),
+                None => ("HEAD".to_string(), repo.head().ok().and_then(|h| h.target())),
+            };
+            let is_merged = match base {
+                Some(base) => base == tip || repo.graph_descendant_of(base, tip)?,
+                None => false,
+            };
+
+            if !is_merged && !force {
+                return Err(AppError::BadRequest(format!(
+                    "Branch '{}' is not fully merged into {}; pass force=true to delete it anyway",
+                    name, merged_into
+                )));
+            }
+
+            branch.delete()?;
+
+            tracing::info!("Deleted branch '{}' (was {}{})", name, tip, if is_merged { "" } else { ", unmerged" });
+
+            Ok(DeletedBranch {
+                name: name.to_string(),
+                commit: tip.to_string(),
+                upstream: upstream.map(|(upstream_name, _)| upstream_name),
+                was_merged: is_merged,
+            })
+        })
+    }
+
+    /// Rename a local branch
+    ///
+    /// The branch's own upstream settings move with it, HEAD follows when the
+    /// branch is checked out (no working tree change), and local branches
+    /// that track it (`branch.<x>.remote = .`) are repointed at the new name.
+    pub fn rename_branch(&self, name: &str, new_name: &str) -> Result<RenamedBranch> {
+        self.with_repo(|repo| {
+            let mut branch = repo.find_branch(name, BranchType::Local)
+                .map_err(|_| AppError::PathNotFound(format!("Branch not found: {}", name)))?;
+
+            if !Branch::name_is_valid(new_name)? {
+                return Err(AppError::BadRequest(format!("Invalid branch name: {}", new_name)));
+            }
+            if new_name == name {
+                return Err(AppError::BadRequest(format!("Branch is already named '{}'", name)));
+            }
+            if repo.find_branch(new_name, BranchType::Local).is_ok() {
+                return Err(AppError::BadRequest(format!("Local branch '{}' already exists", new_name)));
+            }
+
+            let was_head = branch.is_head();
+            let old_ref = format!("refs/heads/{}", name);
+            let new_ref = format!("refs/heads/{}", new_name);
+
+            // libgit2 moves the branch's config section along with the reference
+            let renamed = branch.rename(new_name, false)?;
+
+            // HEAD is a symbolic ref, so point it at the new name; the tree is unchanged
+            if was_head {
+                repo.set_head(&new_ref)?;
+            }
+
+            let retracked = retrack_local_branches(repo, &old_ref, &new_ref)?;
+
+            let upstream = renamed.upstream().ok()
+                .and_then(|u| u.name().ok().flatten().map(|n| n.to_string()));
+
+            tracing::info!("Renamed branch '{}' to '{}'", name, new_name);
+
+            Ok(RenamedBranch {
+                old_name: name.to_string(),
+                name: new_name.to_string(),
+                is_current: was_head,
+                upstream,
+                retracked,
+            })
+        })
+    }
+}
+
+/// Point local branches tracking `old_ref` (remote `.`) at `new_ref`, returning their names
+fn retrack_local_branches(repo: &Repository, old_ref: &str, new_ref: &str) -> Result<Vec<String>> {
+    let mut config = repo.config()?;
+    let snapshot = config.snapshot()?;
+
+    let mut tracking = Vec::new();
+    let mut entries = snapshot.entries(Some(r"^branch\..*\.merge$"))?;
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+        let (Some(key), Some(value)) = (entry.name(), entry.value()) else {
+            continue;
+        };
+        let Some(branch) = key.strip_prefix("branch.").and_then(|k| k.strip_suffix(".merge")) else {
+            continue;
+        };
+        let is_local = snapshot.get_string(&format!("branch.{}.remote", branch)).ok().as_deref() == Some(".");
+        if value == old_ref && is_local {
+            tracking.push(branch.to_string());
+        }
+    }
+
+    for branch in &tracking {
+        config.set_str(&format!("branch.{}.merge", branch), new_ref)?;
+    }
+    tracking.sort();
+    Ok(tracking)
+}
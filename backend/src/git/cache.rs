@@ -1,8 +1,20 @@
 //! Commit cache for fast history queries.
 //!
 //! Provides in-memory caching of commit history to avoid repeated git walks.
-//! - Global cache: All commits loaded once (~1-3s for 30K commits)
-//! - Path indices: Built lazily per path, then instant lookups
+//! - Global cache: All commits loaded once (~1-3s for 30K commits); author and
+//!   committer names/emails and OID strings are interned, so each distinct value
+//!   is stored once however many commits share it
+//! - Path indices: Built lazily per path, then instant lookups; persisted in
+//!   `.git/git-viewer/` (see path_index.rs) so restarts only diff new commits
+//! - Glob paths (`**/*.sql`, `src/**/test_*`): matched against per-commit
+//!   changed-file lists, computed once and kept alongside the path entries
+//! - Multiple paths: each path's entry is cached separately, then the index
+//!   lists are merged (union or intersection) per query
+//! - Pickaxe searches (`-S`/`-G`, see pickaxe.rs): results are stored like path
+//!   entries under a key that cannot be a path, sharing their LRU and budget
+//! - Path eviction: LRU with per-entry size accounting, bounded by a byte budget
+//! - Memory budget: Commits and path entries share `--cache-max-mb`; over-budget
+//!   repos are served by `query_commits_uncached()` without retaining anything
 //! - Cache invalidation: Checks HEAD on each request
 //!
 //! Performance: First query for a path is slow (walks history), subsequent
@@ -12,50 +24,157 @@
 //! Used by: `GitRepository::get_commits()` in history.rs
 //! Supports: HistoryTab commit list, contributor filtering
 
-use git2::{Oid, Repository, Sort};
-use std::collections::HashMap;
+use git2::{Mailmap, Oid, Repository, Sort};
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use std::time::Instant;
 
-use crate::error::Result;
-use crate::models::{AuthorInfo, CommitDetail, CommitListResponse, ContributorInfo};
-use crate::git::repository::format_relative_time;
+use crate::error::{AppError, Result};
+use crate::git::{conventional, mailmap};
+use crate::git::path_index::{commits_since, PathIndex};
+use crate::git::profiling::OpLog;
+use crate::models::{
+    AuthorInfo, CacheStats, CommitDetail, CommitInfo, CommitListResponse, ContributorInfo, PathMode, QueryMatchCounts,
+    SignatureStatus,
+};
+use crate::git::cache_build::CacheBuild;
+use crate::git::commit_graph::CommitGraph;
+use crate::git::deadline;
+use crate::git::repository::{format_relative_time, head_commit, push_head};
+
+/// Default memory budget for the commit cache and its path entries (in MB)
+pub const DEFAULT_CACHE_MAX_MB: usize = 512;
+
+/// Deduplicates strings shared across commits while the cache is built
+///
+/// Authors and committers repeat across thousands of commits, and every
+/// commit's OID also appears in its children's parent lists.
+#[derive(Default)]
+pub struct StringInterner {
+    strings: HashSet<Arc<str>>,
+    /// Heap bytes held by the distinct strings
+    size_bytes: usize,
+}
+
+impl StringInterner {
+    pub fn intern(&mut self, s: &str) -> Arc<str> {
+        if let Some(existing) = self.strings.get(s) {
+            return existing.clone();
+        }
+
+        let interned: Arc<str> = Arc::from(s);
+        // Arc allocation: strong + weak counts followed by the bytes
+        self.size_bytes += 2 * std::mem::size_of::<usize>() + s.len();
+        self.strings.insert(interned.clone());
+        interned
+    }
+
+    pub fn size_bytes(&self) -> usize {
+        self.size_bytes
+    }
+}
 
 /// Cached commit data - stores all info needed for API responses
 #[derive(Debug, Clone)]
 pub struct CachedCommit {
-    pub oid: String,
+    pub oid: Arc<str>,
     pub message: String,
-    pub author_name: String,
-    pub author_email: String,
-    pub committer_name: String,
-    pub committer_email: String,
+    pub author_name: Arc<str>,
+    pub author_email: Arc<str>,
+    pub committer_name: Arc<str>,
+    pub committer_email: Arc<str>,
     pub timestamp: i64,
+    /// Author time as wall-clock seconds in the author's own timezone
+    /// (author timestamp plus UTC offset), for time-of-day statistics
+    pub author_local_time: i64,
     pub parent_count: usize,
-    pub parents: Vec<String>,
+    pub parents: Vec<Arc<str>>,
+    /// Lowercased Conventional Commits type (`feat`, `fix`, ...), for filtering
+    pub commit_type: Option<Arc<str>>,
 }
 
 impl CachedCommit {
+    /// Convert a single commit without sharing strings with any others
+    pub fn from_commit(commit: &git2::Commit, mailmap: Option<&Mailmap>) -> Self {
+        Self::from_commit_interned(commit, &mut StringInterner::default(), mailmap)
+    }
+
+    /// Identities are stored as mapped by `mailmap`
+    pub fn from_commit_interned(
+        commit: &git2::Commit,
+        interner: &mut StringInterner,
+        mailmap: Option<&Mailmap>,
+    ) -> Self {
+        let author = mailmap::author(commit, mailmap);
+        let committer = mailmap::committer(commit, mailmap);
+        let message = commit.message().unwrap_or("").trim().to_string();
+
+        CachedCommit {
+            oid: interner.intern(&commit.id().to_string()),
+            commit_type: conventional::commit_type(&message).map(|t| interner.intern(&t.to_ascii_lowercase())),
+            message,
+            author_name: interner.intern(author.name().unwrap_or("Unknown")),
+            author_email: interner.intern(author.email().unwrap_or("")),
+            committer_name: interner.intern(committer.name().unwrap_or("Unknown")),
+            committer_email: interner.intern(committer.email().unwrap_or("")),
+            timestamp: commit.time().seconds(),
+            author_local_time: local_time(commit.author().when()),
+            parent_count: commit.parent_count(),
+            parents: commit.parent_ids().map(|id| interner.intern(&id.to_string())).collect(),
+        }
+    }
+
+    /// Approximate memory footprint excluding interned strings, used for budget
+    /// accounting (the interner's own size is added once per cache)
+    pub fn size_bytes(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.message.len()
+            + self.parents.capacity() * std::mem::size_of::<Arc<str>>()
+    }
+
+    /// Convert to the short form used for latest/first commit summaries
+    pub fn to_commit_info(&self) -> CommitInfo {
+        CommitInfo {
+            oid: self.oid.to_string(),
+            message: self.message.clone(),
+            author: self.author_name.to_string(),
+            timestamp: self.timestamp,
+            relative_time: format_relative_time(self.timestamp),
+        }
+    }
+
     /// Convert to API response format
     pub fn to_commit_detail(&self) -> CommitDetail {
         CommitDetail {
-            oid: self.oid.clone(),
+            oid: self.oid.to_string(),
             message: self.message.clone(),
             author: AuthorInfo {
-                name: self.author_name.clone(),
-                email: self.author_email.clone(),
+                name: self.author_name.to_string(),
+                email: self.author_email.to_string(),
             },
             committer: AuthorInfo {
-                name: self.committer_name.clone(),
-                email: self.committer_email.clone(),
+                name: self.committer_name.to_string(),
+                email: self.committer_email.to_string(),
             },
             timestamp: self.timestamp,
             relative_time: format_relative_time(self.timestamp),
             parent_count: self.parent_count,
-            parents: self.parents.clone(),
+            parents: self.parents.iter().map(|p| p.to_string()).collect(),
+            signature: None,
+            verification: None,
+            note: None,
+            conventional: conventional::parse(&self.message),
         }
     }
 }
 
+/// Wall-clock seconds of a git time in its own timezone
+pub fn local_time(time: git2::Time) -> i64 {
+    time.seconds() + i64::from(time.offset_minutes()) * 60
+}
+
 /// Cached path data - indices into all_commits plus contributor info
 #[derive(Debug, Clone)]
 pub struct PathCache {
@@ -63,6 +182,39 @@ pub struct PathCache {
     pub commit_indices: Vec<usize>,
     /// Contributors for this path, sorted by commit count
     pub contributors: Vec<ContributorInfo>,
+    /// Approximate heap footprint of this entry (including its key)
+    pub size_bytes: usize,
+    /// Access tick of the last lookup, used for LRU eviction
+    pub last_used: u64,
+}
+
+impl PathCache {
+    fn new(path: &str, commit_indices: Vec<usize>, contributors: Vec<ContributorInfo>) -> Self {
+        let size_bytes = path.len()
+            + commit_indices.capacity() * std::mem::size_of::<usize>()
+            + contributors
+                .iter()
+                .map(|c| std::mem::size_of::<ContributorInfo>() + c.name.len() + c.email.len())
+                .sum::<usize>();
+
+        Self {
+            commit_indices,
+            contributors,
+            size_bytes,
+            last_used: 0,
+        }
+    }
+}
+
+/// Per-path history summary (directory info)
+#[derive(Debug, Clone)]
+pub struct PathSummary {
+    /// Contributors sorted by commit count
+    pub contributors: Vec<ContributorInfo>,
+    /// Newest commit touching the path
+    pub latest: Option<CachedCommit>,
+    /// Oldest commit touching the path
+    pub first: Option<CachedCommit>,
 }
 
 /// Main commit cache structure
@@ -70,10 +222,51 @@ pub struct CommitCache {
     /// All commits in time order (newest first)
     pub all_commits: Vec<CachedCommit>,
 
-    /// path -> cached data (lazily populated)
-    /// Empty string "" key stores root path (all commits)
+    /// path -> cached data (lazily populated, LRU-evicted)
+    /// Empty string "" key stores root path (all commits) and is never evicted;
+    /// keys starting with NUL hold pickaxe search results
     pub path_cache: HashMap<String, PathCache>,
 
+    /// Approximate size of `all_commits` plus the root path entry
+    pub commits_bytes: usize,
+
+    /// Total size of non-root path cache entries
+    pub path_cache_bytes: usize,
+
+    /// Byte budget for non-root path cache entries (whatever the commits and
+    /// changed-file lists leave over)
+    pub path_cache_max_bytes: usize,
+
+    /// Files changed by each commit (parallel to `all_commits`), built on the
+    /// first glob query
+    pub changed_files: Option<Vec<Vec<String>>>,
+
+    /// Approximate size of `changed_files`
+    pub changed_files_bytes: usize,
+
+    /// Children of each commit (indices into `all_commits`, keyed by parent
+    /// OID), built on the first children query
+    pub children: Option<HashMap<Arc<str>, Vec<usize>>>,
+
+    /// Approximate size of `children`
+    pub children_bytes: usize,
+
+    /// Monotonic counter bumped on every path lookup
+    access_tick: u64,
+
+    /// On-disk path index; `None` with `--read-only` or if it could not be
+    /// opened (e.g. a read-only file system)
+    path_index: Option<PathIndex>,
+
+    /// Where path cache and changed-file builds are timed
+    op_log: Arc<OpLog>,
+
+    /// Signature status per commit OID, filled lazily as pages are requested
+    pub signatures: HashMap<Oid, SignatureStatus>,
+
+    /// Approximate size of `signatures`
+    pub signatures_bytes: usize,
+
     /// HEAD commit OID when cache was built
     pub head_oid: Oid,
 
@@ -83,47 +276,110 @@ pub struct CommitCache {
 
 impl CommitCache {
     /// Build initial cache by walking all commits (metadata only, no path computation)
-    pub fn build(repo: &Repository) -> Result<Self> {
-        let head = repo.head()?;
-        let head_oid = head.peel_to_commit()?.id();
+    ///
+    /// Bytes are counted as commits are read, and the build stops with `None`
+    /// as soon as they pass `max_bytes`, so an oversized repository never has
+    /// more than the budget's worth of commits in memory.
+    ///
+    /// With a commit-graph the walk order comes from it, and commit objects are
+    /// only parsed for the cached metadata. `progress` is told how many
+    /// commits there are and counts them as they are read. `read_only` skips
+    /// the on-disk path index, which would be created in the git directory.
+    pub fn build(
+        repo: &Repository,
+        graph: Option<&CommitGraph>,
+        max_bytes: usize,
+        read_only: bool,
+        op_log: Arc<OpLog>,
+        progress: Option<&CacheBuild>,
+    ) -> Result<Option<Self>> {
+        // An unborn HEAD gives an empty cache, marked with the zero OID
+        let head_oid = head_commit(repo)?.map_or_else(Oid::zero, |c| c.id());
+
+        let from_graph = match graph {
+            Some(graph) if !head_oid.is_zero() => graph.walk_by_time(repo, head_oid),
+            _ => None,
+        };
+        let oids = match from_graph {
+            Some(oids) => oids,
+            None => {
+                let mut revwalk = repo.revwalk()?;
+                revwalk.set_sorting(Sort::TIME)?;
+                push_head(&mut revwalk, repo)?;
+                revwalk.collect::<std::result::Result<Vec<_>, _>>()?
+            }
+        };
 
-        let mut revwalk = repo.revwalk()?;
-        revwalk.set_sorting(Sort::TIME)?;
-        revwalk.push_head()?;
+        if let Some(progress) = progress {
+            progress.set_total(oids.len());
+        }
 
-        let mut all_commits = Vec::new();
+        let mut all_commits = Vec::with_capacity(oids.len());
+        let mut commits_bytes = 0;
+        let mut interner = StringInterner::default();
+        let mailmap = mailmap::load(repo);
 
-        for oid_result in revwalk {
-            let oid = oid_result?;
+        let total = oids.len();
+        let over_budget = |read: usize| {
+            tracing::warn!(
+                "Commit cache passed its budget of {} MB after {} of {} commits, serving uncached",
+                max_bytes / (1024 * 1024),
+                read,
+                total
+            );
+            Ok(None)
+        };
+        for oid in oids {
             let commit = repo.find_commit(oid)?;
 
-            let author = commit.author();
-            let committer = commit.committer();
-
-            all_commits.push(CachedCommit {
-                oid: commit.id().to_string(),
-                message: commit.message().unwrap_or("").trim().to_string(),
-                author_name: author.name().unwrap_or("Unknown").to_string(),
-                author_email: author.email().unwrap_or("").to_string(),
-                committer_name: committer.name().unwrap_or("Unknown").to_string(),
-                committer_email: committer.email().unwrap_or("").to_string(),
-                timestamp: commit.time().seconds(),
-                parent_count: commit.parent_count(),
-                parents: commit.parent_ids().map(|id| id.to_string()).collect(),
-            });
+            let cached = CachedCommit::from_commit_interned(&commit, &mut interner, mailmap.as_ref());
+            commits_bytes += cached.size_bytes();
+            all_commits.push(cached);
+            if commits_bytes + interner.size_bytes() > max_bytes {
+                return over_budget(all_commits.len());
+            }
+            if let Some(progress) = progress {
+                progress.advance();
+            }
         }
+        commits_bytes += interner.size_bytes();
 
         // Pre-populate root path cache (all commits, no filtering needed)
         let mut path_cache = HashMap::new();
         let root_cache = Self::build_root_path_cache(&all_commits);
+        commits_bytes += root_cache.size_bytes;
+        if commits_bytes > max_bytes {
+            return over_budget(total);
+        }
         path_cache.insert(String::new(), root_cache);
 
-        Ok(Self {
+        // `--read-only` leaves the repository's files alone
+        let path_index = if read_only {
+            None
+        } else {
+            PathIndex::open(repo)
+                .inspect_err(|e| tracing::warn!("Persistent path index unavailable: {}", e))
+                .ok()
+        };
+
+        Ok(Some(Self {
             all_commits,
             path_cache,
+            commits_bytes,
+            path_cache_bytes: 0,
+            path_cache_max_bytes: max_bytes.saturating_sub(commits_bytes),
+            changed_files: None,
+            changed_files_bytes: 0,
+            children: None,
+            children_bytes: 0,
+            access_tick: 0,
+            path_index,
+            op_log,
+            signatures: HashMap::new(),
+            signatures_bytes: 0,
             head_oid,
             created_at: Instant::now(),
-        })
+        }))
     }
 
     /// Build cache entry for root path (all commits)
@@ -131,7 +387,7 @@ impl CommitCache {
         let commit_indices: Vec<usize> = (0..all_commits.len()).collect();
 
         // Build contributor map
-        let mut contributor_map: HashMap<String, (String, usize)> = HashMap::new();
+        let mut contributor_map: HashMap<Arc<str>, (Arc<str>, usize)> = HashMap::new();
         for commit in all_commits {
             contributor_map
                 .entry(commit.author_email.clone())
@@ -142,23 +398,44 @@ impl CommitCache {
         let mut contributors: Vec<ContributorInfo> = contributor_map
             .into_iter()
             .map(|(email, (name, count))| ContributorInfo {
-                name,
-                email,
+                name: name.to_string(),
+                email: email.to_string(),
                 commit_count: count,
             })
             .collect();
-        contributors.sort_by(|a, b| b.commit_count.cmp(&a.commit_count));
+        contributors.sort_by_key(|c| std::cmp::Reverse(c.commit_count));
 
-        PathCache {
-            commit_indices,
-            contributors,
+        PathCache::new("", commit_indices, contributors)
+    }
+
+    /// Signature status for a commit, memoized across requests
+    ///
+    /// Memoized statuses are charged to the path cache budget, like the
+    /// changed-file lists; once it is used up, statuses are read uncached.
+    pub fn signature_status(&mut self, repo: &Repository, oid: &str) -> SignatureStatus {
+        let Ok(oid) = Oid::from_str(oid) else {
+            return SignatureStatus::Unknown;
+        };
+        if let Some(status) = self.signatures.get(&oid) {
+            return *status;
         }
+
+        let status = read_signature_status(repo, oid);
+        let bytes = std::mem::size_of::<(Oid, SignatureStatus)>();
+        if bytes <= self.path_cache_max_bytes {
+            self.path_cache_max_bytes -= bytes;
+            let evicted = self.evict_to_fit(0);
+            self.log_eviction(evicted);
+            self.signatures_bytes += bytes;
+            self.signatures.insert(oid, status);
+        }
+        status
     }
 
     /// Check if cache is still valid
     pub fn is_valid(&self, repo: &Repository) -> bool {
-        match repo.head().and_then(|h| h.peel_to_commit()) {
-            Ok(head_commit) => head_commit.id() == self.head_oid,
+        match head_commit(repo) {
+            Ok(head) => head.map_or_else(Oid::zero, |c| c.id()) == self.head_oid,
             Err(_) => false,
         }
     }
@@ -173,37 +450,311 @@ impl CommitCache {
         path: &str,
         limit: usize,
         offset: usize,
-        exclude_authors: Option<&[String]>,
+        filters: CommitFilters,
+    ) -> Result<CommitListResponse> {
+        // Entries too large for the budget come back by value instead of being stored
+        if let Some(uncached) = self.ensure_path_cache(repo, path)? {
+            return Ok(self.query_commits(&uncached, limit, offset, filters));
+        }
+
+        let path_cache = self.path_cache.get(path).unwrap();
+        Ok(self.query_commits(path_cache, limit, offset, filters))
+    }
+
+    /// Contributors and newest/oldest commit for a path, from the same entry the
+    /// commit list uses
+    ///
+    /// A bounded `window` limits the contributors to those with commits in it;
+    /// newest/oldest commit still cover all of the path's history.
+    pub fn get_path_summary(&mut self, repo: &Repository, path: &str, window: TimeWindow) -> Result<PathSummary> {
+        let uncached = self.ensure_path_cache(repo, path)?;
+        let path_cache = match &uncached {
+            Some(entry) => entry,
+            None => &self.path_cache[path],
+        };
+        let contributors = if window.is_unbounded() {
+            path_cache.contributors.clone()
+        } else {
+            self.contributors_for(&self.indices_within(&path_cache.commit_indices, window))
+        };
+
+        Ok(PathSummary {
+            contributors,
+            latest: path_cache.commit_indices.first().map(|&idx| self.all_commits[idx].clone()),
+            first: path_cache.commit_indices.last().map(|&idx| self.all_commits[idx].clone()),
+        })
+    }
+
+    /// Indices into `all_commits` of the commits touching `path` (every commit
+    /// for ""), newest first, from the same entry the commit list uses
+    pub fn get_path_commit_indices(&mut self, repo: &Repository, path: &str) -> Result<Vec<usize>> {
+        Ok(match self.ensure_path_cache(repo, path)? {
+            Some(uncached) => uncached.commit_indices,
+            None => self.path_cache[path].commit_indices.clone(),
+        })
+    }
+
+    /// Query commits touching several paths, combined per `mode`
+    ///
+    /// Each path is cached individually, so changing the combination (or adding
+    /// one more path) only builds entries for paths not seen before.
+    pub fn get_commits_for_paths(
+        &mut self,
+        repo: &Repository,
+        paths: &[String],
+        mode: PathMode,
+        limit: usize,
+        offset: usize,
+        filters: CommitFilters,
     ) -> Result<CommitListResponse> {
-        // Build path cache if needed
-        if !self.path_cache.contains_key(path) {
-            tracing::info!("Building path cache for: {}", if path.is_empty() { "(root)" } else { path });
+        if let [path] = paths {
+            return self.get_commits_for_path(repo, path, limit, offset, filters);
+        }
+        if paths.is_empty() {
+            return self.get_commits_for_path(repo, "", limit, offset, filters);
+        }
+
+        let mut combined: Option<Vec<usize>> = None;
+        for path in paths {
+            let indices = match self.ensure_path_cache(repo, path)? {
+                Some(uncached) => uncached.commit_indices,
+                None => self.path_cache[path.as_str()].commit_indices.clone(),
+            };
+            combined = Some(match combined {
+                None => indices,
+                Some(acc) => merge_indices(&acc, &indices, mode),
+            });
+        }
+
+        let commit_indices = combined.unwrap_or_default();
+        let contributors = self.contributors_for(&commit_indices);
+        let merged = PathCache::new("", commit_indices, contributors);
+        Ok(self.query_commits(&merged, limit, offset, filters))
+    }
+
+    /// Get or build the entry for a pickaxe search, then query it like a path
+    ///
+    /// `key` identifies the search (see `Pickaxe::cache_key`); `matches` decides
+    /// per commit and only runs when the search is not cached yet.
+    pub fn get_commits_matching<F>(
+        &mut self,
+        repo: &Repository,
+        key: &str,
+        matches: F,
+        limit: usize,
+        offset: usize,
+        filters: CommitFilters,
+    ) -> Result<CommitListResponse>
+    where
+        F: FnMut(&git2::Commit) -> Result<bool>,
+    {
+        let uncached = self.ensure_entry(key, "pickaxe_build", |cache| cache.build_matching_cache(repo, key, matches))?;
+        if let Some(uncached) = uncached {
+            return Ok(self.query_commits(&uncached, limit, offset, filters));
+        }
+        Ok(self.query_commits(&self.path_cache[key], limit, offset, filters))
+    }
+
+    /// Make sure a path entry exists and mark it as used
+    ///
+    /// Returns the entry by value (without storing it) when it can never fit the
+    /// path budget; otherwise it is in `path_cache` afterwards.
+    fn ensure_path_cache(&mut self, repo: &Repository, path: &str) -> Result<Option<PathCache>> {
+        self.ensure_entry(path, "path_cache_build", |cache| {
+            if is_glob(path) {
+                let matched = cache.build_glob_path_cache(repo, path)?;
+                if !matched.commit_indices.is_empty() {
+                    return Ok(matched);
+                }
+            }
+            cache.build_path_cache(repo, path)
+        })
+    }
+
+    /// Build the entry under `key` with `build` if missing, then mark it as used
+    fn ensure_entry<F>(&mut self, key: &str, operation: &'static str, build: F) -> Result<Option<PathCache>>
+    where
+        F: FnOnce(&mut Self) -> Result<PathCache>,
+    {
+        // Keys are shown without the NUL that marks pickaxe entries
+        let label = if key.is_empty() { "(root)" } else { key.trim_start_matches('\0') };
+
+        if !self.path_cache.contains_key(key) {
+            tracing::info!("Building path cache for: {}", label);
             let start = std::time::Instant::now();
-            let path_cache = self.build_path_cache(repo, path)?;
+            let op_log = self.op_log.clone();
+            let _op = op_log.start(operation, format!("path={}", label));
+            let path_cache = build(self)?;
             tracing::info!(
                 "Path cache built: {} commits in {:?}",
                 path_cache.commit_indices.len(),
                 start.elapsed()
             );
-            self.path_cache.insert(path.to_string(), path_cache);
+
+            // Entries that can never fit are served once and dropped
+            if path_cache.size_bytes > self.path_cache_max_bytes {
+                tracing::info!(
+                    "Path cache for {} exceeds budget ({} KB), not caching",
+                    label,
+                    path_cache.size_bytes / 1024
+                );
+                return Ok(Some(path_cache));
+            }
+
+            self.insert_path_cache(key, path_cache);
         }
 
-        // Now we can safely borrow immutably for the query
-        let path_cache = self.path_cache.get(path).unwrap();
-        Ok(self.query_commits(path_cache, limit, offset, exclude_authors))
+        self.access_tick += 1;
+        let tick = self.access_tick;
+
+        if let Some(entry) = self.path_cache.get_mut(key) {
+            entry.last_used = tick;
+        }
+
+        Ok(None)
+    }
+
+    /// The indices whose commits fall within `window`, order kept
+    fn indices_within(&self, commit_indices: &[usize], window: TimeWindow) -> Vec<usize> {
+        commit_indices
+            .iter()
+            .copied()
+            .filter(|&idx| window.contains(self.all_commits[idx].timestamp))
+            .collect()
+    }
+
+    /// Contributors for a set of commits, sorted by commit count
+    fn contributors_for(&self, commit_indices: &[usize]) -> Vec<ContributorInfo> {
+        let mut contributor_map: HashMap<Arc<str>, (Arc<str>, usize)> = HashMap::new();
+        for &idx in commit_indices {
+            let commit = &self.all_commits[idx];
+            contributor_map
+                .entry(commit.author_email.clone())
+                .and_modify(|(_, count)| *count += 1)
+                .or_insert((commit.author_name.clone(), 1));
+        }
+
+        let mut contributors: Vec<ContributorInfo> = contributor_map
+            .into_iter()
+            .map(|(email, (name, count))| ContributorInfo {
+                name: name.to_string(),
+                email: email.to_string(),
+                commit_count: count,
+            })
+            .collect();
+        contributors.sort_by_key(|c| std::cmp::Reverse(c.commit_count));
+        contributors
+    }
+
+    /// Insert a path cache entry, evicting least recently used entries to stay within budget.
+    ///
+    /// The root entry is accounted for in `commits_bytes`, not the path budget.
+    fn insert_path_cache(&mut self, path: &str, entry: PathCache) {
+        if path.is_empty() {
+            self.path_cache.insert(String::new(), entry);
+            return;
+        }
+
+        let evicted = self.evict_to_fit(entry.size_bytes);
+
+        self.path_cache_bytes += entry.size_bytes;
+        if let Some(replaced) = self.path_cache.insert(path.to_string(), entry) {
+            self.path_cache_bytes -= replaced.size_bytes;
+        }
+
+        self.log_eviction(evicted);
+    }
+
+    /// Evict least recently used non-root entries until `incoming` more bytes fit
+    ///
+    /// Entries are ordered by last use once per call, not rescanned per eviction.
+    fn evict_to_fit(&mut self, incoming: usize) -> usize {
+        if self.path_cache_bytes + incoming <= self.path_cache_max_bytes {
+            return 0;
+        }
+
+        let mut by_age: Vec<(u64, String)> = self.path_cache
+            .iter()
+            .filter(|(key, _)| !key.is_empty())
+            .map(|(key, cached)| (cached.last_used, key.clone()))
+            .collect();
+        by_age.sort_unstable();
+
+        let mut evicted = 0;
+        for (_, key) in by_age {
+            if self.path_cache_bytes + incoming <= self.path_cache_max_bytes {
+                break;
+            }
+            if let Some(removed) = self.path_cache.remove(&key) {
+                self.path_cache_bytes -= removed.size_bytes;
+                evicted += 1;
+            }
+        }
+        evicted
+    }
+
+    fn log_eviction(&self, evicted: usize) {
+        if evicted > 0 {
+            let stats = self.stats();
+            tracing::info!(
+                "Evicted {} path cache entries ({} paths cached, {} KB of {} KB budget, {} commits in {} KB, cache age {}s)",
+                evicted,
+                stats.cached_paths,
+                stats.path_cache_bytes / 1024,
+                self.path_cache_max_bytes / 1024,
+                stats.total_commits,
+                stats.commits_bytes / 1024,
+                stats.age_secs
+            );
+        }
     }
 
     /// Build cache entry for a specific path (expensive - calls git diff for each commit)
-    fn build_path_cache(&self, repo: &Repository, path: &str) -> Result<PathCache> {
+    ///
+    /// Commits already covered by the persistent index are answered from it;
+    /// only commits added since the indexed HEAD are diffed.
+    fn build_path_cache(&mut self, repo: &Repository, path: &str) -> Result<PathCache> {
+        let indexed = self.path_index.as_ref().and_then(|index| {
+            index.lookup(path).unwrap_or_else(|e| {
+                tracing::warn!("Path index lookup failed for {}: {}", path, e);
+                None
+            })
+        });
+
+        // Known matches, plus the commits that still need diffing (`None` = all)
+        let (known, pending) = match indexed {
+            Some((indexed_head, oids)) => match commits_since(repo, self.head_oid, indexed_head) {
+                Ok(new_commits) => (oids, Some(new_commits)),
+                Err(_) => (HashSet::new(), None),
+            },
+            None => (HashSet::new(), None),
+        };
+        if let Some(pending) = &pending {
+            tracing::info!("Path index hit for {}: {} new commits to check", path, pending.len());
+        }
+
         let mut commit_indices = Vec::new();
-        let mut contributor_map: HashMap<String, (String, usize)> = HashMap::new();
+        let mut contributor_map: HashMap<Arc<str>, (Arc<str>, usize)> = HashMap::new();
+        let mut added = Vec::new();
 
         for (idx, cached_commit) in self.all_commits.iter().enumerate() {
             // Check if this commit touches the path
+            deadline::check()?;
             let oid = Oid::from_str(&cached_commit.oid)?;
-            let commit = repo.find_commit(oid)?;
 
-            if commit_touches_path(repo, &commit, path)? {
+            let touches = match &pending {
+                Some(pending) if !pending.contains(&oid) => known.contains(&oid),
+                _ => {
+                    let commit = repo.find_commit(oid)?;
+                    let touches = commit_touches_path(&commit, path)?;
+                    if touches {
+                        added.push(oid);
+                    }
+                    touches
+                }
+            };
+
+            if touches {
                 commit_indices.push(idx);
 
                 contributor_map
@@ -216,44 +767,219 @@ impl CommitCache {
         let mut contributors: Vec<ContributorInfo> = contributor_map
             .into_iter()
             .map(|(email, (name, count))| ContributorInfo {
-                name,
-                email,
+                name: name.to_string(),
+                email: email.to_string(),
                 commit_count: count,
             })
             .collect();
-        contributors.sort_by(|a, b| b.commit_count.cmp(&a.commit_count));
+        contributors.sort_by_key(|c| std::cmp::Reverse(c.commit_count));
 
-        Ok(PathCache {
-            commit_indices,
-            contributors,
-        })
+        // Nothing to persist before the first commit
+        if let Some(index) = &mut self.path_index
+            && !self.head_oid.is_zero()
+            && let Err(e) = index.store(path, self.head_oid, &added)
+        {
+            tracing::warn!("Failed to persist path index for {}: {}", path, e);
+        }
+
+        Ok(PathCache::new(path, commit_indices, contributors))
+    }
+
+    /// Build cache entry for a glob pattern by matching each commit's changed files
+    ///
+    /// The changed-file lists are computed on first use and retained if they fit
+    /// the path budget (shrinking it accordingly), so later globs are in-memory only.
+    fn build_glob_path_cache(&mut self, repo: &Repository, pattern: &str) -> Result<PathCache> {
+        let glob = compile_glob(pattern)?;
+
+        let computed;
+        let changed_files = match &self.changed_files {
+            Some(files) => files,
+            None => {
+                let start = std::time::Instant::now();
+                let op_log = self.op_log.clone();
+                let op = op_log.start("changed_files_build", format!("commits={}", self.all_commits.len()));
+                let mut files = Vec::with_capacity(self.all_commits.len());
+                let mut bytes = 0;
+                for cached_commit in &self.all_commits {
+                    deadline::check()?;
+                    let commit = repo.find_commit(Oid::from_str(&cached_commit.oid)?)?;
+                    let paths = commit_changed_files(repo, &commit)?;
+                    bytes += std::mem::size_of::<Vec<String>>()
+                        + paths.iter().map(|p| std::mem::size_of::<String>() + p.capacity()).sum::<usize>();
+                    files.push(paths);
+                }
+                drop(op);
+                tracing::info!(
+                    "Changed-file lists built for {} commits ({} KB) in {:?}",
+                    files.len(),
+                    bytes / 1024,
+                    start.elapsed()
+                );
+
+                if bytes <= self.path_cache_max_bytes {
+                    self.path_cache_max_bytes -= bytes;
+                    let evicted = self.evict_to_fit(0);
+                    self.log_eviction(evicted);
+                    self.changed_files_bytes = bytes;
+                    self.changed_files.insert(files)
+                } else {
+                    tracing::info!("Changed-file lists exceed budget, not caching");
+                    computed = files;
+                    &computed
+                }
+            }
+        };
+
+        let mut commit_indices = Vec::new();
+        let mut contributor_map: HashMap<Arc<str>, (Arc<str>, usize)> = HashMap::new();
+
+        for (idx, files) in changed_files.iter().enumerate() {
+            if files.iter().any(|f| glob.is_match(f)) {
+                let cached_commit = &self.all_commits[idx];
+                commit_indices.push(idx);
+
+                contributor_map
+                    .entry(cached_commit.author_email.clone())
+                    .and_modify(|(_, count)| *count += 1)
+                    .or_insert((cached_commit.author_name.clone(), 1));
+            }
+        }
+
+        let mut contributors: Vec<ContributorInfo> = contributor_map
+            .into_iter()
+            .map(|(email, (name, count))| ContributorInfo {
+                name: name.to_string(),
+                email: email.to_string(),
+                commit_count: count,
+            })
+            .collect();
+        contributors.sort_by_key(|c| std::cmp::Reverse(c.commit_count));
+
+        Ok(PathCache::new(pattern, commit_indices, contributors))
+    }
+
+    /// Build an entry from every commit `matches` accepts (one git lookup per commit)
+    fn build_matching_cache<F>(&self, repo: &Repository, key: &str, mut matches: F) -> Result<PathCache>
+    where
+        F: FnMut(&git2::Commit) -> Result<bool>,
+    {
+        let mut commit_indices = Vec::new();
+        for (idx, cached_commit) in self.all_commits.iter().enumerate() {
+            deadline::check()?;
+            let commit = repo.find_commit(Oid::from_str(&cached_commit.oid)?)?;
+            if matches(&commit)? {
+                commit_indices.push(idx);
+            }
+        }
+
+        let contributors = self.contributors_for(&commit_indices);
+        Ok(PathCache::new(key, commit_indices, contributors))
+    }
+
+    /// Commits that have `oid` as a parent, newest first
+    ///
+    /// Only commits reachable from HEAD are known to the cache, so children on
+    /// other branches are not included.
+    pub fn get_children(&mut self, oid: &str) -> Vec<CommitDetail> {
+        let computed;
+        let children = match &self.children {
+            Some(children) => children,
+            None => {
+                let start = std::time::Instant::now();
+                let mut children: HashMap<Arc<str>, Vec<usize>> = HashMap::new();
+                for (idx, commit) in self.all_commits.iter().enumerate() {
+                    for parent in &commit.parents {
+                        children.entry(parent.clone()).or_default().push(idx);
+                    }
+                }
+
+                // Keys share the interned parent strings, so only the map and vectors count
+                let bytes = children.capacity()
+                    * (std::mem::size_of::<Arc<str>>() + std::mem::size_of::<Vec<usize>>())
+                    + children.values().map(|v| v.capacity() * std::mem::size_of::<usize>()).sum::<usize>();
+                tracing::info!(
+                    "Children index built for {} commits ({} KB) in {:?}",
+                    self.all_commits.len(),
+                    bytes / 1024,
+                    start.elapsed()
+                );
+
+                if bytes <= self.path_cache_max_bytes {
+                    self.path_cache_max_bytes -= bytes;
+                    let evicted = self.evict_to_fit(0);
+                    self.log_eviction(evicted);
+                    self.children_bytes = bytes;
+                    self.children.insert(children)
+                } else {
+                    tracing::info!("Children index exceeds budget, not caching");
+                    computed = children;
+                    &computed
+                }
+            }
+        };
+
+        children
+            .get(oid)
+            .map(|indices| indices.iter().map(|&idx| self.all_commits[idx].to_commit_detail()).collect())
+            .unwrap_or_default()
     }
 
     /// Query commits with filtering and pagination (fast - all in-memory)
+    ///
+    /// A search in `filters` narrows the commits left after author exclusion and
+    /// counts which fields matched.
     pub fn query_commits(
         &self,
         path_cache: &PathCache,
         limit: usize,
         offset: usize,
-        exclude_authors: Option<&[String]>,
+        filters: CommitFilters,
     ) -> CommitListResponse {
-        let exclude_set: std::collections::HashSet<&str> = exclude_authors
+        let exclude_set: std::collections::HashSet<&str> = filters.exclude_authors
             .map(|authors| authors.iter().map(|s| s.as_str()).collect())
             .unwrap_or_default();
 
-        let total = path_cache.commit_indices.len();
+        // A time window narrows what counts as the path's history, contributors included
+        let windowed;
+        let (commit_indices, windowed_contributors) = if filters.window.is_unbounded() {
+            (&path_cache.commit_indices, None)
+        } else {
+            windowed = self.indices_within(&path_cache.commit_indices, filters.window);
+            (&windowed, Some(self.contributors_for(&windowed)))
+        };
+        let total = commit_indices.len();
 
-        // Filter by author if needed
-        let filtered_indices: Vec<usize> = if exclude_set.is_empty() {
-            path_cache.commit_indices.clone()
+        // Filter by author and conventional type if needed
+        let filtered_indices: Vec<usize> = if exclude_set.is_empty() && filters.types.is_none() {
+            commit_indices.clone()
         } else {
-            path_cache.commit_indices
+            commit_indices
                 .iter()
-                .filter(|&&idx| !exclude_set.contains(self.all_commits[idx].author_email.as_str()))
+                .filter(|&&idx| {
+                    let commit = &self.all_commits[idx];
+                    !exclude_set.contains(&*commit.author_email) && filters.keeps_type(commit.commit_type.as_deref())
+                })
                 .copied()
                 .collect()
         };
 
+        // Filter by search query if needed
+        let (filtered_indices, match_counts) = match filters.search {
+            None => (filtered_indices, None),
+            Some(search) => {
+                let mut counts = QueryMatchCounts::default();
+                let matching: Vec<usize> = filtered_indices
+                    .into_iter()
+                    .filter(|&idx| {
+                        let commit = &self.all_commits[idx];
+                        search.count_matches(&commit.message, &commit.author_name, &commit.author_email, &mut counts)
+                    })
+                    .collect();
+                (matching, Some(counts))
+            }
+        };
+
         let filtered_total = filtered_indices.len();
 
         // Apply pagination
@@ -265,7 +991,9 @@ impl CommitCache {
             .collect();
 
         // Get contributors (convert from ContributorInfo to AuthorInfo for response)
-        let contributors: Vec<AuthorInfo> = path_cache.contributors
+        let contributors: Vec<AuthorInfo> = windowed_contributors
+            .as_ref()
+            .unwrap_or(&path_cache.contributors)
             .iter()
             .map(|c| AuthorInfo {
                 name: c.name.clone(),
@@ -279,46 +1007,556 @@ impl CommitCache {
             filtered_total,
             has_more: filtered_total > offset + limit,
             contributors,
+            match_counts,
         }
     }
 
-    /// Get cache statistics for debugging
+    /// Get cache statistics for debugging and the health endpoint
     pub fn stats(&self) -> CacheStats {
         CacheStats {
+            head_oid: self.head_oid.to_string(),
             total_commits: self.all_commits.len(),
             cached_paths: self.path_cache.len(),
+            path_cache_bytes: self.path_cache_bytes,
+            commits_bytes: self.commits_bytes,
             age_secs: self.created_at.elapsed().as_secs(),
         }
     }
 }
 
-#[derive(Debug)]
-pub struct CacheStats {
-    pub total_commits: usize,
-    pub cached_paths: usize,
-    pub age_secs: u64,
+/// Answer a commits query by walking history directly, without building a cache.
+///
+/// Used when the repository is too large for the configured cache budget. Only
+/// the requested page is materialized; totals and contributors are counted on the fly.
+/// `matches` selects commits, e.g. `PathFilter::matches` or a pickaxe search.
+pub fn query_commits_uncached<F>(
+    repo: &Repository,
+    mut matches: F,
+    limit: usize,
+    offset: usize,
+    filters: CommitFilters,
+) -> Result<CommitListResponse>
+where
+    F: FnMut(&git2::Commit) -> Result<bool>,
+{
+    let exclude_set: std::collections::HashSet<&str> = filters.exclude_authors
+        .map(|authors| authors.iter().map(|s| s.as_str()).collect())
+        .unwrap_or_default();
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TIME)?;
+    push_head(&mut revwalk, repo)?;
+    let mailmap = mailmap::load(repo);
+
+    let mut total = 0;
+    let mut filtered_total = 0;
+    let mut commits = Vec::new();
+    let mut contributor_map: HashMap<String, (String, usize)> = HashMap::new();
+    let mut match_counts = filters.search.map(|_| QueryMatchCounts::default());
+
+    for oid_result in revwalk {
+        deadline::check()?;
+        let commit = repo.find_commit(oid_result?)?;
+
+        if !filters.window.contains(commit.time().seconds()) || !matches(&commit)? {
+            continue;
+        }
+        total += 1;
+
+        let author = mailmap::author(&commit, mailmap.as_ref());
+        let email = author.email().unwrap_or("");
+        contributor_map
+            .entry(email.to_string())
+            .and_modify(|(_, count)| *count += 1)
+            .or_insert((author.name().unwrap_or("Unknown").to_string(), 1));
+
+        if exclude_set.contains(email) {
+            continue;
+        }
+
+        let message = commit.message().unwrap_or("").trim();
+        let commit_type = conventional::commit_type(message).map(|t| t.to_ascii_lowercase());
+        if !filters.keeps_type(commit_type.as_deref()) {
+            continue;
+        }
+
+        if let (Some(search), Some(counts)) = (filters.search, match_counts.as_mut())
+            && !search.count_matches(message, author.name().unwrap_or("Unknown"), email, counts)
+        {
+            continue;
+        }
+
+        if filtered_total >= offset && commits.len() < limit {
+            commits.push(CachedCommit::from_commit(&commit, mailmap.as_ref()).to_commit_detail());
+        }
+        filtered_total += 1;
+    }
+
+    let mut contributors: Vec<(String, (String, usize))> = contributor_map.into_iter().collect();
+    contributors.sort_by_key(|(_, (_, count))| std::cmp::Reverse(*count));
+
+    Ok(CommitListResponse {
+        commits,
+        total,
+        filtered_total,
+        has_more: filtered_total > offset + limit,
+        contributors: contributors
+            .into_iter()
+            .map(|(email, (name, _))| AuthorInfo { name, email })
+            .collect(),
+        match_counts,
+    })
 }
 
-/// Check if a commit touches the given path (copied from history.rs to avoid circular dep)
-fn commit_touches_path(repo: &Repository, commit: &git2::Commit, path: &str) -> Result<bool> {
-    use git2::DiffOptions;
+/// Check whether a commit object carries a signature header (gpgsig)
+///
+/// This only detects presence; it does not verify the signature.
+pub fn read_signature_status(repo: &Repository, oid: Oid) -> SignatureStatus {
+    match repo.extract_signature(&oid, None) {
+        Ok(_) => SignatureStatus::Signed,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => SignatureStatus::Unsigned,
+        Err(_) => SignatureStatus::Unknown,
+    }
+}
 
+/// Whether a `path=` filter value is a glob rather than a plain path
+///
+/// Only `*` makes one: `?`, `[` and `{` are common in real file names
+/// (`[id].tsx`, `{{name}}.txt`). A glob that selects no commits is taken as a
+/// literal path instead (see `PathFilter::literal`).
+pub fn is_glob(path: &str) -> bool {
+    path.contains('*')
+}
+
+/// Compile a path glob; `*` stays within one directory, `**` crosses directories
+pub fn compile_glob(pattern: &str) -> Result<GlobMatcher> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| AppError::BadRequest(format!("Invalid path glob '{}': {}", pattern, e)))
+}
+
+/// Filters applied to a commit list after commits are selected by path (or search)
+#[derive(Clone, Copy, Default)]
+pub struct CommitFilters<'a> {
+    /// Author emails to leave out
+    pub exclude_authors: Option<&'a [String]>,
+    pub search: Option<&'a CommitSearch>,
+    /// Conventional Commits types to keep (lowercase), e.g. `feat` and `fix`
+    pub types: Option<&'a [String]>,
+    /// Commit times to keep; also narrows totals and contributors
+    pub window: TimeWindow,
+}
+
+/// Commit time bounds in Unix seconds, both inclusive; `None` leaves a side open
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimeWindow {
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, timestamp: i64) -> bool {
+        self.since.is_none_or(|since| timestamp >= since) && self.until.is_none_or(|until| timestamp <= until)
+    }
+
+    pub fn is_unbounded(&self) -> bool {
+        self.since.is_none() && self.until.is_none()
+    }
+}
+
+impl CommitFilters<'_> {
+    /// Whether a commit's conventional type passes the `types` filter
+    fn keeps_type(&self, commit_type: Option<&str>) -> bool {
+        match self.types {
+            None => true,
+            Some(types) => commit_type.is_some_and(|t| types.iter().any(|wanted| wanted == t)),
+        }
+    }
+}
+
+/// Commit list `query=`: matched against message, author name and author email
+pub struct CommitSearch {
+    regex: Regex,
+}
+
+impl CommitSearch {
+    /// Plain queries match as case-insensitive substrings; `is_regex` takes the
+    /// query as a regular expression (use `(?i)` for case-insensitivity)
+    pub fn new(query: &str, is_regex: bool) -> Result<Self> {
+        let regex = if is_regex {
+            Regex::new(query).map_err(|e| AppError::BadRequest(format!("Invalid regex '{}': {}", query, e)))?
+        } else {
+            RegexBuilder::new(&regex::escape(query))
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| AppError::Internal(e.to_string()))?
+        };
+        Ok(Self { regex })
+    }
+
+    /// Whether any field matches, adding each matching field to `counts`
+    pub fn count_matches(&self, message: &str, name: &str, email: &str, counts: &mut QueryMatchCounts) -> bool {
+        let in_message = self.regex.is_match(message);
+        let in_name = self.regex.is_match(name);
+        let in_email = self.regex.is_match(email);
+
+        counts.message += usize::from(in_message);
+        counts.author_name += usize::from(in_name);
+        counts.author_email += usize::from(in_email);
+        in_message || in_name || in_email
+    }
+}
+
+/// How `path=` filters select commits when walking history directly
+#[derive(Clone)]
+pub enum PathFilter {
+    All,
+    Path(String),
+    Glob(GlobMatcher),
+    /// Several filters combined per `PathMode`
+    Combined(Vec<PathFilter>, PathMode),
+}
+
+impl PathFilter {
+    pub fn parse(path: &str) -> Result<Self> {
+        Ok(if path.is_empty() {
+            PathFilter::All
+        } else if is_glob(path) {
+            PathFilter::Glob(compile_glob(path)?)
+        } else {
+            PathFilter::Path(path.to_string())
+        })
+    }
+
+    pub fn parse_many(paths: &[String], mode: PathMode) -> Result<Self> {
+        match paths {
+            [] => Ok(PathFilter::All),
+            [path] => Self::parse(path),
+            _ => Ok(PathFilter::Combined(
+                paths.iter().map(|p| Self::parse(p)).collect::<Result<_>>()?,
+                mode,
+            )),
+        }
+    }
+
+    /// The same filter with its globs taken as literal paths, for a walk in
+    /// which the globs selected nothing; `None` when there are no globs
+    pub fn literal(&self) -> Option<PathFilter> {
+        match self {
+            PathFilter::All | PathFilter::Path(_) => None,
+            PathFilter::Glob(glob) => Some(PathFilter::Path(glob.glob().glob().to_string())),
+            PathFilter::Combined(filters, mode) => {
+                let literals: Vec<Option<PathFilter>> = filters.iter().map(PathFilter::literal).collect();
+                if literals.iter().all(Option::is_none) {
+                    return None;
+                }
+                let combined = filters
+                    .iter()
+                    .zip(literals)
+                    .map(|(filter, literal)| literal.unwrap_or_else(|| filter.clone()))
+                    .collect();
+                Some(PathFilter::Combined(combined, *mode))
+            }
+        }
+    }
+
+    /// Whether a changed file is selected: the path itself or a file below
+    /// it, or a file matching the glob
+    pub fn covers_file(&self, file: &str) -> bool {
+        match self {
+            PathFilter::All => true,
+            PathFilter::Path(path) => {
+                let path = path.trim_end_matches('/');
+                file.strip_prefix(path).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            }
+            PathFilter::Glob(glob) => glob.is_match(file),
+            PathFilter::Combined(filters, PathMode::Union) => filters.iter().any(|f| f.covers_file(file)),
+            PathFilter::Combined(filters, PathMode::Intersect) => filters.iter().all(|f| f.covers_file(file)),
+        }
+    }
+
+    pub fn matches(&self, repo: &Repository, commit: &git2::Commit) -> Result<bool> {
+        match self {
+            PathFilter::All => Ok(true),
+            PathFilter::Path(path) => commit_touches_path(commit, path),
+            PathFilter::Glob(glob) => Ok(commit_changed_files(repo, commit)?
+                .iter()
+                .any(|f| glob.is_match(f))),
+            PathFilter::Combined(filters, PathMode::Union) => {
+                for filter in filters {
+                    if filter.matches(repo, commit)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            PathFilter::Combined(filters, PathMode::Intersect) => {
+                for filter in filters {
+                    if !filter.matches(repo, commit)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// Union or intersection of two ascending index lists, keeping ascending order
+fn merge_indices(a: &[usize], b: &[usize], mode: PathMode) -> Vec<usize> {
+    let mut merged = Vec::with_capacity(match mode {
+        PathMode::Union => a.len() + b.len(),
+        PathMode::Intersect => a.len().min(b.len()),
+    });
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Equal => {
+                merged.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+            std::cmp::Ordering::Less => {
+                if mode == PathMode::Union {
+                    merged.push(a[i]);
+                }
+                i += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                if mode == PathMode::Union {
+                    merged.push(b[j]);
+                }
+                j += 1;
+            }
+        }
+    }
+    if mode == PathMode::Union {
+        merged.extend_from_slice(&a[i..]);
+        merged.extend_from_slice(&b[j..]);
+    }
+    merged
+}
+
+/// Paths of all files a commit changed relative to its first parent
+pub fn commit_changed_files(repo: &Repository, commit: &git2::Commit) -> Result<Vec<String>> {
     let tree = commit.tree()?;
+    let parent_tree = if commit.parent_count() > 0 {
+        Some(commit.parent(0)?.tree()?)
+    } else {
+        None
+    };
+
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+
+    let mut files = Vec::with_capacity(diff.deltas().len());
+    for delta in diff.deltas() {
+        let old_path = delta.old_file().path();
+        let new_path = delta.new_file().path();
+        if let Some(p) = new_path {
+            files.push(p.to_string_lossy().to_string());
+        }
+        if let Some(p) = old_path
+            && old_path != new_path
+        {
+            files.push(p.to_string_lossy().to_string());
+        }
+    }
+    Ok(files)
+}
 
+/// Check if a commit touches the given path (compared to its first parent)
+fn commit_touches_path(commit: &git2::Commit, path: &str) -> Result<bool> {
+    let tree = commit.tree()?;
     let parent_tree = if commit.parent_count() > 0 {
         Some(commit.parent(0)?.tree()?)
     } else {
         None
     };
+    Ok(path_changed(&tree, parent_tree.as_ref(), path))
+}
+
+/// Whether `path` differs between `tree` and its `parent` tree; without a
+/// parent, whether it exists at all
+///
+/// Git stores identical content under identical OIDs, so comparing the
+/// entries' OIDs and modes answers this without diffing, and a directory's
+/// subtree OID covers everything below it.
+pub fn path_changed(tree: &git2::Tree, parent: Option<&git2::Tree>, path: &str) -> bool {
+    let entry = path_entry(tree, path);
+    match parent {
+        Some(parent) => entry != path_entry(parent, path),
+        None => entry.is_some(),
+    }
+}
+
+/// OID and file mode of `path` in `tree`; the tree itself for the root (`None` if empty)
+fn path_entry(tree: &git2::Tree, path: &str) -> Option<(Oid, i32)> {
+    let path = path.trim_matches('/');
+    if path.is_empty() {
+        return (!tree.is_empty()).then(|| (tree.id(), 0o040000));
+    }
+    tree.get_path(std::path::Path::new(path))
+        .ok()
+        .map(|entry| (entry.id(), entry.filemode()))
+}
 
-    let mut opts = DiffOptions::new();
-    opts.pathspec(path);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo::TestRepo;
 
-    let diff = repo.diff_tree_to_tree(
-        parent_tree.as_ref(),
-        Some(&tree),
-        Some(&mut opts),
-    )?;
+    const AUTHORS: [(&str, &str); 3] = [
+        ("Ada Lovelace", "ada@example.com"),
+        ("Grace Hopper", "grace@example.com"),
+        ("Alan Turing", "alan@example.com"),
+    ];
 
-    Ok(diff.deltas().len() > 0)
+    /// Every shared string of the commits: identities, OIDs, parents and types
+    fn shared_strings(commits: &[CachedCommit]) -> impl Iterator<Item = &Arc<str>> {
+        commits.iter().flat_map(|c| {
+            [&c.oid, &c.author_name, &c.author_email, &c.committer_name, &c.committer_email]
+                .into_iter()
+                .chain(&c.parents)
+                .chain(&c.commit_type)
+        })
+    }
+
+    #[test]
+    fn interning_shares_author_and_parent_strings() {
+        let mut test = TestRepo::new();
+        for i in 0..60 {
+            test.commit(AUTHORS[i % AUTHORS.len()], "file.txt", &format!("version {}\n", i), &format!("Change {}", i));
+        }
+
+        let cache = CommitCache::build(&test.repo, None, usize::MAX, true, Arc::new(OpLog::default()), None)
+            .unwrap()
+            .expect("cache within budget");
+        let commits = &cache.all_commits;
+        assert_eq!(commits.len(), 60);
+
+        // Newest first: each commit's parent is the next one in the list
+        for pair in commits.windows(2) {
+            assert!(Arc::ptr_eq(&pair[0].parents[0], &pair[1].oid));
+        }
+        // Commits by the same author share one allocation per string
+        for pair in commits.iter().zip(&commits[AUTHORS.len()..]) {
+            assert!(Arc::ptr_eq(&pair.0.author_name, &pair.1.author_name));
+            assert!(Arc::ptr_eq(&pair.0.author_email, &pair.1.author_email));
+            assert!(Arc::ptr_eq(&pair.0.author_email, &pair.1.committer_email));
+        }
+
+        // Counted from the allocations themselves: each distinct pointer once,
+        // against every field owning its own copy
+        let mut distinct: HashMap<*const u8, usize> = HashMap::new();
+        for s in shared_strings(commits) {
+            distinct.insert(s.as_ptr(), s.len());
+        }
+        let interned: usize = distinct.values().sum();
+        let raw: usize = shared_strings(commits).map(|s| s.len()).sum();
+        // One allocation per commit OID plus one per author name and email
+        assert_eq!(distinct.len(), 60 + 2 * AUTHORS.len());
+        let identities: usize = AUTHORS.iter().map(|(name, email)| name.len() + email.len()).sum();
+        assert_eq!(interned, 60 * 40 + identities);
+        assert!(raw > 3 * interned, "interned {} bytes, raw {}", interned, raw);
+
+        // The same history converted commit by commit shares nothing across
+        // commits: an OID, name, email and parent OID each (the committer is the author)
+        let unshared: Vec<CachedCommit> = commits
+            .iter()
+            .map(|c| CachedCommit::from_commit(&test.repo.find_commit(Oid::from_str(&c.oid).unwrap()).unwrap(), None))
+            .collect();
+        let unshared_distinct: HashSet<*const u8> = shared_strings(&unshared).map(|s| s.as_ptr()).collect();
+        assert_eq!(unshared_distinct.len(), 60 * 3 + 59);
+    }
+
+    #[test]
+    fn memoized_signature_statuses_are_charged_to_the_path_budget() {
+        let mut test = TestRepo::new();
+        for i in 0..3 {
+            test.commit(AUTHORS[0], "file.txt", &format!("version {}\n", i), &format!("Change {}", i));
+        }
+        let mut cache = CommitCache::build(&test.repo, None, usize::MAX, true, Arc::new(OpLog::default()), None)
+            .unwrap()
+            .expect("cache within budget");
+
+        // Room for two statuses; the third is read but not memoized
+        let entry_bytes = std::mem::size_of::<(Oid, SignatureStatus)>();
+        cache.path_cache_max_bytes = 2 * entry_bytes;
+        let oids: Vec<String> = cache.all_commits.iter().map(|c| c.oid.to_string()).collect();
+        for oid in &oids {
+            assert_eq!(cache.signature_status(&test.repo, oid), SignatureStatus::Unsigned);
+        }
+
+        assert_eq!(cache.signatures.len(), 2);
+        assert_eq!(cache.signatures_bytes, 2 * entry_bytes);
+        assert_eq!(cache.path_cache_max_bytes, 0);
+        assert!(cache.signatures.contains_key(&Oid::from_str(&oids[0]).unwrap()));
+    }
+
+    #[test]
+    fn glob_and_pickaxe_builds_stop_at_the_deadline_with_504() {
+        use axum::http::StatusCode;
+        use axum::response::IntoResponse;
+        use std::time::Duration;
+
+        let mut test = TestRepo::new();
+        for i in 0..5 {
+            test.commit(AUTHORS[0], &format!("file{}.rs", i), "fn main() {}\n", &format!("Change {}", i));
+        }
+        let mut cache = CommitCache::build(&test.repo, None, usize::MAX, true, Arc::new(OpLog::default()), None)
+            .unwrap()
+            .expect("cache within budget");
+
+        let deadline = deadline::Deadline::new("Search", Duration::from_secs(30));
+        deadline.cancel();
+        let mut matched = 0;
+        let (glob, pickaxe) = deadline.scope(|| {
+            let glob = cache.get_path_commit_indices(&test.repo, "*.rs").map(|_| ());
+            let pickaxe = cache
+                .get_commits_matching(&test.repo, "\0main", |_| { matched += 1; Ok(true) }, 10, 0, CommitFilters::default())
+                .map(|_| ());
+            (glob, pickaxe)
+        });
+
+        for result in [glob, pickaxe] {
+            let error = result.expect_err("stopped by the deadline");
+            assert!(matches!(error, AppError::Timeout(_)), "{:?}", error);
+            assert_eq!(error.into_response().status(), StatusCode::GATEWAY_TIMEOUT);
+        }
+        assert_eq!(matched, 0);
+        assert!(cache.changed_files.is_none());
+        assert_eq!(cache.path_cache.len(), 1, "only the root entry is cached");
+    }
+
+    #[test]
+    fn path_entries_past_the_budget_evict_the_least_recently_used() {
+        let mut test = TestRepo::new();
+        for (i, file) in ["a.txt", "b.txt", "c.txt", "d.txt"].into_iter().enumerate() {
+            test.commit(AUTHORS[0], file, "content\n", &format!("Change {}", i));
+        }
+        let mut cache = CommitCache::build(&test.repo, None, usize::MAX, true, Arc::new(OpLog::default()), None)
+            .unwrap()
+            .expect("cache within budget");
+
+        cache.get_path_commit_indices(&test.repo, "a.txt").unwrap();
+        let entry_bytes = cache.path_cache["a.txt"].size_bytes;
+        cache.path_cache_max_bytes = 2 * entry_bytes;
+
+        cache.get_path_commit_indices(&test.repo, "b.txt").unwrap();
+        cache.get_path_commit_indices(&test.repo, "a.txt").unwrap();
+        cache.get_path_commit_indices(&test.repo, "c.txt").unwrap();
+
+        let mut cached: Vec<&str> = cache.path_cache.keys().map(String::as_str).collect();
+        cached.sort_unstable();
+        assert_eq!(cached, ["", "a.txt", "c.txt"], "b.txt was used least recently");
+        assert_eq!(cache.path_cache_bytes, 2 * entry_bytes);
+
+        // Shrinking the budget evicts down to it at once, oldest first
+        cache.path_cache_max_bytes = entry_bytes;
+        assert_eq!(cache.evict_to_fit(0), 1);
+        assert!(cache.path_cache.contains_key("c.txt"));
+        assert!(cache.path_cache_bytes <= cache.path_cache_max_bytes);
+    }
 }
@@ -0,0 +1,230 @@
+//! Building the commit cache in the background.
+//!
+//! Reading every commit takes seconds on large repositories. Instead of doing
+//! it inside whichever request first needs history, holding the repository
+//! lock all the while, `with_cache()` starts a build on a thread of its own
+//! and answers `202` (`AppError::Warming`) until the cache is ready. The
+//! frontend retries after `Retry-After`.
+//!
+//! - `CacheBuild`: State and progress of the running (or last) build
+//! - `start_cache_build()`: Start a build for the current HEAD unless one is running
+//! - `wait_for_cache()`: Block until the cache is ready (`--prewarm`)
+//! - `cache_status()`: Progress and ETA for GET /api/v1/cache/status
+//!
+//! The build opens its own handle on the repository, so requests that don't
+//! need history are served while it runs.
+
+use git2::Repository;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::{AppError, Result};
+use crate::git::cache::CommitCache;
+use crate::git::repository::{open_handle, GitRepository};
+use crate::models::{CacheBuildState, CacheStatus};
+
+/// How often `wait_for_cache()` checks whether the build has finished
+const WAIT_POLL: Duration = Duration::from_millis(100);
+
+#[derive(Default)]
+pub struct CacheBuild {
+    running: AtomicBool,
+    /// Commits read by the running build
+    read: AtomicUsize,
+    progress: Mutex<Progress>,
+}
+
+#[derive(Default)]
+struct Progress {
+    started: Option<Instant>,
+    /// Commits the build has to read, and when the walk listing them finished
+    total: Option<(usize, Instant)>,
+    /// How long the last finished build took
+    last_duration: Option<Duration>,
+    /// Why the last build failed; reported to one request, then cleared
+    error: Option<String>,
+}
+
+impl CacheBuild {
+    /// The walk listed the commits; reading them starts now
+    pub fn set_total(&self, total: usize) {
+        if let Ok(mut progress) = self.progress.lock() {
+            progress.total = Some((total, Instant::now()));
+        }
+    }
+
+    /// One more commit read
+    pub fn advance(&self) {
+        self.read.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    fn begin(&self) {
+        self.read.store(0, Ordering::Relaxed);
+        if let Ok(mut progress) = self.progress.lock() {
+            progress.started = Some(Instant::now());
+            progress.total = None;
+            progress.error = None;
+        }
+    }
+
+    fn finish(&self, error: Option<String>) {
+        if let Ok(mut progress) = self.progress.lock() {
+            progress.last_duration = progress.started.take().map(|started| started.elapsed());
+            progress.total = None;
+            progress.error = error;
+        }
+        self.running.store(false, Ordering::Release);
+    }
+
+    fn take_error(&self) -> Option<String> {
+        self.progress.lock().ok()?.error.take()
+    }
+
+    /// `(read, total, elapsed, eta)` of the running build
+    fn snapshot(&self) -> (usize, Option<usize>, Option<Duration>, Option<Duration>) {
+        let read = self.read.load(Ordering::Relaxed);
+        let Ok(progress) = self.progress.lock() else {
+            return (read, None, None, None);
+        };
+        let elapsed = progress.started.map(|started| started.elapsed());
+        // Extrapolated from the reading rate so far; the walk before it isn't counted
+        let eta = progress.total.and_then(|(total, reading_since)| {
+            (read > 0).then(|| reading_since.elapsed().mul_f64(total.saturating_sub(read) as f64 / read as f64))
+        });
+        (read, progress.total.map(|(total, _)| total), elapsed, eta)
+    }
+
+    /// The `202` answer for a request that needs the cache
+    fn warming(&self) -> AppError {
+        let message = match self.snapshot() {
+            (read, Some(total), _, _) => format!("Building the commit cache: {} of {} commits read", read, total),
+            _ => "Building the commit cache: walking history".to_string(),
+        };
+        AppError::Warming(message)
+    }
+}
+
+impl GitRepository {
+    /// Start building the commit cache for the current HEAD on a background
+    /// thread, unless a build is already running, and return the error to
+    /// answer the request with
+    ///
+    /// A failed build is reported to one request; the next one starts over.
+    pub(crate) fn start_cache_build(&self, repo: &Repository) -> AppError {
+        if let Some(error) = self.cache_build.take_error() {
+            return AppError::Internal(format!("Building the commit cache failed: {}", error));
+        }
+        if self.cache_build.running.swap(true, Ordering::AcqRel) {
+            return self.cache_build.warming();
+        }
+
+        let graph = match self.commit_graph(repo) {
+            Ok(graph) => graph,
+            Err(e) => {
+                self.cache_build.running.store(false, Ordering::Release);
+                return e;
+            }
+        };
+        let git_dir = repo.path().to_path_buf();
+        let workdir = repo.workdir().map(Path::to_path_buf);
+        let slot = self.cache.clone();
+        let over_budget = self.cache_over_budget.clone();
+        let build = self.cache_build.clone();
+        let op_log = self.op_log.clone();
+        let max_bytes = self.commit_cache_budget();
+        let read_only = self.read_only;
+
+        build.begin();
+        let spawned = std::thread::Builder::new().name("cache-build".to_string()).spawn(move || {
+            tracing::info!("Building commit cache...");
+            let start = Instant::now();
+            let built = open_handle(&git_dir, workdir.as_deref()).and_then(|repo| {
+                let _op = op_log.start("commit_cache_build", format!("budget_mb={}", max_bytes / (1024 * 1024)));
+                CommitCache::build(&repo, graph.as_deref(), max_bytes, read_only, op_log.clone(), Some(&build))
+            });
+
+            match built {
+                Ok(Some(cache)) => {
+                    tracing::info!(
+                        "Cache built: {} commits ({} KB) in {:?}",
+                        cache.all_commits.len(),
+                        cache.commits_bytes / 1024,
+                        start.elapsed()
+                    );
+                    if let Ok(mut slot) = slot.lock() {
+                        *slot = Some(cache);
+                    }
+                    build.finish(None);
+                }
+                Ok(None) => {
+                    over_budget.store(true, Ordering::Relaxed);
+                    build.finish(None);
+                }
+                Err(e) => {
+                    tracing::warn!("Building commit cache failed: {}", e);
+                    build.finish(Some(e.to_string()));
+                }
+            }
+        });
+
+        match spawned {
+            Ok(_) => self.cache_build.warming(),
+            Err(e) => {
+                self.cache_build.finish(None);
+                AppError::Internal(format!("Failed to start cache build: {}", e))
+            }
+        }
+    }
+
+    /// Start a cache build if needed and block until the cache is ready, or
+    /// known to be over budget
+    pub fn wait_for_cache(&self) -> Result<()> {
+        loop {
+            if self.is_cache_over_budget() {
+                return Ok(());
+            }
+            match self.with_cache(|_, _| Ok(())) {
+                Err(AppError::Warming(_)) => std::thread::sleep(WAIT_POLL),
+                result => return result,
+            }
+        }
+    }
+
+    /// State of the commit cache and the progress of a running build, without starting one
+    pub fn cache_status(&self) -> Result<CacheStatus> {
+        let cache = self.cache_stats()?;
+        let building = self.cache_build.is_running();
+        let progress = building.then(|| self.cache_build.snapshot());
+
+        let state = if building {
+            CacheBuildState::Building
+        } else if self.is_cache_over_budget() {
+            CacheBuildState::OverBudget
+        } else if cache.is_some() {
+            CacheBuildState::Ready
+        } else {
+            CacheBuildState::Cold
+        };
+        let last_build_ms = self.cache_build.progress
+            .lock()
+            .ok()
+            .and_then(|progress| progress.last_duration)
+            .map(|d| d.as_millis() as u64);
+
+        Ok(CacheStatus {
+            state,
+            commits_read: progress.map(|(read, _, _, _)| read),
+            total_commits: progress.and_then(|(_, total, _, _)| total),
+            elapsed_ms: progress.and_then(|(_, _, elapsed, _)| elapsed).map(|d| d.as_millis() as u64),
+            eta_ms: progress.and_then(|(_, _, _, eta)| eta).map(|d| d.as_millis() as u64),
+            last_build_ms,
+            cache,
+        })
+    }
+}
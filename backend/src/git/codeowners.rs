@@ -0,0 +1,171 @@
+//! CODEOWNERS lookup: who owns a file or directory.
+//!
+//! The file is read from the commit being viewed, at the first of
+//! `.github/CODEOWNERS`, `CODEOWNERS` and `docs/CODEOWNERS` that exists (GitHub's
+//! order). Patterns follow gitignore rules as GitHub applies them: a leading or
+//! inner `/` anchors to the repository root, otherwise the pattern matches at
+//! any depth; a match on a directory covers everything below it; the last
+//! matching line wins, and a line without owners leaves the path unowned.
+//! GitLab section headers (`[Section]`) are skipped.
+
+use git2::{Repository, Tree};
+use globset::GlobMatcher;
+use std::path::Path;
+
+use crate::error::{AppError, Result};
+use crate::git::cache::compile_glob;
+use crate::git::repository::{resolve_tree_or_head, GitRepository};
+use crate::models::CodeOwnersMatch;
+
+/// Where CODEOWNERS may live, in lookup order
+const CODEOWNERS_LOCATIONS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+pub struct CodeOwners {
+    /// Repository path of the CODEOWNERS file the rules came from
+    file: String,
+    rules: Vec<Rule>,
+}
+
+struct Rule {
+    pattern: String,
+    /// 1-based line in the CODEOWNERS file
+    line: usize,
+    owners: Vec<String>,
+    /// The path itself
+    itself: GlobMatcher,
+    /// Everything below a matching directory
+    contents: GlobMatcher,
+    /// Trailing `/`: the pattern itself only matches directories
+    dir_only: bool,
+}
+
+impl Rule {
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        self.contents.is_match(path) || ((is_dir || !self.dir_only) && self.itself.is_match(path))
+    }
+}
+
+impl CodeOwners {
+    /// Rules from the CODEOWNERS file in `tree`, `None` if it has none
+    pub fn load(repo: &Repository, tree: &Tree) -> Option<Self> {
+        CODEOWNERS_LOCATIONS.iter().find_map(|location| {
+            let entry = tree.get_path(Path::new(location)).ok()?;
+            let blob = repo.find_blob(entry.id()).ok()?;
+            Some(Self::parse(location, &String::from_utf8_lossy(blob.content())))
+        })
+    }
+
+    /// Rules of a CODEOWNERS file; lines with invalid patterns are skipped
+    pub fn parse(file: &str, contents: &str) -> Self {
+        let rules = contents
+            .lines()
+            .enumerate()
+            .filter_map(|(idx, line)| {
+                let line_text = line.trim();
+                if line_text.is_empty() || line_text.starts_with('#') || is_section_header(line_text) {
+                    return None;
+                }
+                let mut fields = line_text.split_whitespace();
+                let pattern = fields.next()?;
+                let owners = fields
+                    .take_while(|field| !field.starts_with('#'))
+                    .map(str::to_string)
+                    .collect();
+                match compile_rule(pattern) {
+                    Ok((itself, contents, dir_only)) => Some(Rule {
+                        pattern: pattern.to_string(),
+                        line: idx + 1,
+                        owners,
+                        itself,
+                        contents,
+                        dir_only,
+                    }),
+                    Err(e) => {
+                        tracing::warn!("Skipping {} line {}: {}", file, idx + 1, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self { file: file.to_string(), rules }
+    }
+
+    /// Owners of `path` per the last matching rule; empty when no rule
+    /// matches or the matching rule lists no owners
+    pub fn owners_of(&self, path: &str, is_dir: bool) -> Vec<String> {
+        self.matching_rule(path, is_dir)
+            .map(|rule| rule.owners.clone())
+            .unwrap_or_default()
+    }
+
+    fn matching_rule(&self, path: &str, is_dir: bool) -> Option<&Rule> {
+        let path = path.trim_matches('/');
+        if path.is_empty() {
+            // Only catch-all patterns speak for the repository as a whole
+            return self.rules.iter().rev().find(|rule| matches!(rule.pattern.trim_matches('/'), "*" | "**"));
+        }
+        self.rules.iter().rev().find(|rule| rule.matches(path, is_dir))
+    }
+
+    /// Full match details for the codeowners endpoint
+    pub fn lookup(&self, path: &str, is_dir: bool) -> CodeOwnersMatch {
+        let rule = self.matching_rule(path, is_dir);
+        CodeOwnersMatch {
+            path: path.to_string(),
+            codeowners_file: Some(self.file.clone()),
+            pattern: rule.map(|r| r.pattern.clone()),
+            line: rule.map(|r| r.line),
+            owners: rule.map(|r| r.owners.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+/// `[Section]`, `^[Optional section]` or `[Section][2]` with default owners
+fn is_section_header(line: &str) -> bool {
+    line.trim_start_matches('^').starts_with('[')
+}
+
+/// Matchers for the path itself and for its contents, and whether the
+/// pattern names directories only (trailing `/`)
+fn compile_rule(pattern: &str) -> Result<(GlobMatcher, GlobMatcher, bool)> {
+    let dir_only = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    // A slash other than a trailing one anchors the pattern at the root
+    let anchored = trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+    if trimmed.is_empty() {
+        return Err(AppError::BadRequest(format!("Empty pattern '{}'", pattern)));
+    }
+    let base = if anchored || trimmed.starts_with("**") {
+        trimmed.to_string()
+    } else {
+        format!("**/{}", trimmed)
+    };
+
+    let itself = compile_glob(&base)?;
+    let contents = compile_glob(&format!("{}/**", base))?;
+    Ok((itself, contents, dir_only))
+}
+
+impl GitRepository {
+    /// Code owners of `path` in `rev` (HEAD when `None`); a repository without
+    /// CODEOWNERS reports no file and no owners
+    pub fn get_code_owners(&self, path: &str, rev: Option<&str>) -> Result<CodeOwnersMatch> {
+        self.with_repo(|repo| {
+            let tree = resolve_tree_or_head(repo, rev)?;
+            let path = path.trim_matches('/');
+            let is_dir = path.is_empty()
+                || tree.get_path(Path::new(path)).is_ok_and(|e| e.kind() == Some(git2::ObjectType::Tree));
+            Ok(match CodeOwners::load(repo, &tree) {
+                Some(owners) => owners.lookup(path, is_dir),
+                None => CodeOwnersMatch {
+                    path: path.to_string(),
+                    codeowners_file: None,
+                    pattern: None,
+                    line: None,
+                    owners: Vec::new(),
+                },
+            })
+        })
+    }
+}
@@ -0,0 +1,102 @@
+//! Creating commits from the staged index.
+//!
+//! - `create_commit()`: Commit whatever is staged, like `git commit` without `-a`
+//! - `user_signature()`: The configured identity, shared with stashing
+//!
+//! The author and committer come from the repository's `user.name` /
+//! `user.email` configuration. Hooks are not run (libgit2 doesn't support
+//! them). When a merge is in progress, the merge heads become additional
+//! parents and the merge state is cleared.
+//!
+//! Supports frontend: StatusTab commit box
+
+use git2::{Oid, Repository, RepositoryState, Signature};
+
+use crate::error::{AppError, Result};
+use crate::git::cache::CachedCommit;
+use crate::git::mailmap;
+use crate::git::repository::GitRepository;
+use crate::models::CommitDetail;
+
+impl GitRepository {
+    /// Commit the staged index on top of HEAD (or as the first commit of an unborn branch)
+    ///
+    /// HEAD moves, so the commit cache rebuilds on the next history query.
+    pub fn create_commit(&self, message: &str) -> Result<CommitDetail> {
+        let _op = self.op_log.start("commit", String::new());
+        self.with_repo(|repo| {
+            if repo.is_bare() {
+                return Err(AppError::BadRequest("Cannot commit in a bare repository".to_string()));
+            }
+
+            // Same cleanup as `git commit`: strip comments and trailing whitespace
+            let message = git2::message_prettify(message, Some(b'#'))?;
+            if message.trim().is_empty() {
+                return Err(AppError::BadRequest("Commit message is empty".to_string()));
+            }
+
+            let signature = user_signature(repo)?;
+
+            // The handle is long-lived, so pick up whatever was staged since it was opened
+            let mut index = repo.index()?;
+            index.read(false)?;
+            if index.has_conflicts() {
+                return Err(AppError::BadRequest("Cannot commit: the index has unresolved conflicts".to_string()));
+            }
+            let tree = repo.find_tree(index.write_tree()?)?;
+
+            let head = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+            let merge_heads = merge_heads(repo)?;
+            if merge_heads.is_empty() {
+                let unchanged = match &head {
+                    Some(head) => head.tree_id() == tree.id(),
+                    None => tree.is_empty(),
+                };
+                if unchanged {
+                    return Err(AppError::BadRequest("Nothing to commit: no changes are staged".to_string()));
+                }
+            }
+
+            let mut parents = Vec::new();
+            parents.extend(head);
+            for oid in &merge_heads {
+                parents.push(repo.find_commit(*oid)?);
+            }
+            let parent_refs: Vec<&git2::Commit> = parents.iter().collect();
+
+            let oid = repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &parent_refs)?;
+            if !merge_heads.is_empty() {
+                repo.cleanup_state()?;
+            }
+
+            let commit = repo.find_commit(oid)?;
+            tracing::info!("Created commit {} ({})", oid, commit.summary().unwrap_or(""));
+
+            let mailmap = mailmap::load(repo);
+            Ok(CachedCommit::from_commit(&commit, mailmap.as_ref()).to_commit_detail())
+        })
+    }
+}
+
+/// The configured `user.name` / `user.email`, timestamped now
+pub fn user_signature(repo: &Repository) -> Result<Signature<'static>> {
+    repo.signature().map_err(|_| {
+        AppError::BadRequest("No user identity: set user.name and user.email in the git config".to_string())
+    })
+}
+
+/// Commits being merged in (`MERGE_HEAD`), empty unless a merge is in progress
+///
+/// Read from the file directly: `Repository::mergehead_foreach` needs `&mut`.
+fn merge_heads(repo: &Repository) -> Result<Vec<Oid>> {
+    if repo.state() != RepositoryState::Merge {
+        return Ok(Vec::new());
+    }
+    let contents = std::fs::read_to_string(repo.path().join("MERGE_HEAD"))
+        .map_err(|e| AppError::Internal(format!("Failed to read MERGE_HEAD: {}", e)))?;
+    let mut heads = Vec::new();
+    for line in contents.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        heads.push(Oid::from_str(line)?);
+    }
+    Ok(heads)
+}
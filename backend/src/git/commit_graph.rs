@@ -0,0 +1,401 @@
+//! Reading git's commit-graph for history walks and ahead/behind counts.
+//!
+//! `git commit-graph write` and `git maintenance` store every commit's
+//! parents, commit time and generation number (topological level) in
+//! `objects/info/commit-graph`, or as a chain of split files under
+//! `objects/info/commit-graphs/`, which is what `git maintenance` writes.
+//! libgit2 only reads the single file, so repositories kept up by
+//! maintenance got none of the benefit. This reader handles both layouts:
+//! - `walk_by_time()`: HEAD's history newest first (as `Sort::TIME`), without
+//!   parsing commit objects for the walk itself
+//! - `ahead_behind()`: Divergence of two commits, walking by generation so it
+//!   stops as soon as nothing left to visit can differ
+//!
+//! Commits newer than the graph are read from the object database, so a stale
+//! graph only costs speed. Without a graph, with `core.commitGraph=false`, or
+//! with one this reader doesn't understand (SHA-256, corrupt), callers use
+//! libgit2's walks instead.
+//!
+//! Used by: the commit cache build, branch upstream status, `ahead_behind()`
+
+use git2::{Oid, Repository};
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::error::{AppError, Result};
+use crate::git::freshness::hash_file;
+use crate::git::repository::GitRepository;
+
+const SIGNATURE: &[u8] = b"CGPH";
+
+/// Only SHA-1 graphs (hash version 1) are read
+const HASH_LEN: usize = 20;
+
+/// Tree OID, two parent positions, generation and commit time
+const CDAT_ENTRY_LEN: usize = HASH_LEN + 16;
+
+/// Parent position meaning "no parent"
+const NO_PARENT: u32 = 0x7000_0000;
+
+/// Set on the second parent when parents 2.. are listed in the EDGE chunk
+const EXTRA_EDGES: u32 = 0x8000_0000;
+
+/// Set on the last EDGE entry of a commit
+const LAST_EDGE: u32 = 0x8000_0000;
+
+const FLAG_LOCAL: u8 = 1;
+const FLAG_UPSTREAM: u8 = 2;
+const FLAG_BOTH: u8 = FLAG_LOCAL | FLAG_UPSTREAM;
+
+/// One graph file; positions in it start at `base`
+struct Layer {
+    data: Vec<u8>,
+    base: u32,
+    count: u32,
+    fanout: Range<usize>,
+    oids: Range<usize>,
+    cdat: Range<usize>,
+    edges: Option<Range<usize>>,
+}
+
+pub struct CommitGraph {
+    /// Base layer first
+    layers: Vec<Layer>,
+}
+
+/// A commit as the walks see it: in the graph by position, or read from the object database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Node {
+    Graph(u32),
+    Loose(Oid),
+}
+
+struct NodeData {
+    oid: Oid,
+    /// `u32::MAX` outside the graph: newer than everything in it
+    generation: u32,
+    time: i64,
+    parents: Vec<Node>,
+}
+
+impl CommitGraph {
+    /// Load the repository's commit-graph; `None` if there is none or it can't be used
+    pub fn load(repo: &Repository) -> Option<Self> {
+        if repo.config().and_then(|c| c.get_bool("core.commitGraph")).ok() == Some(false) {
+            return None;
+        }
+
+        let files = graph_files(repo)?;
+        let mut layers = Vec::with_capacity(files.len());
+        let mut base = 0u32;
+        for file in &files {
+            let data = std::fs::read(file).ok()?;
+            let layer = Layer::parse(data, base);
+            let Some(layer) = layer else {
+                tracing::warn!("Ignoring unreadable commit-graph {}", file.display());
+                return None;
+            };
+            base = base.checked_add(layer.count)?;
+            layers.push(layer);
+        }
+        tracing::info!("Using commit-graph: {} commits in {} file(s)", base, layers.len());
+        Some(Self { layers })
+    }
+
+    /// Commits reachable from `start`, newest commit time first
+    ///
+    /// Like libgit2's `Sort::TIME`, the order is by commit time alone, so a
+    /// parent with a clock-skewed later time comes before its child. Equal
+    /// times keep the order commits were reached in, children first.
+    /// `None` if the graph turns out to be inconsistent, so the caller can
+    /// walk with libgit2 instead.
+    pub fn walk_by_time(&self, repo: &Repository, start: Oid) -> Option<Vec<Oid>> {
+        let start = self.node(start);
+        let mut seen = HashSet::from([start]);
+        let mut reached = vec![(self.data(repo, start)?.time, start)];
+        let mut oids = Vec::new();
+
+        let mut next = 0;
+        while let Some(&(_, node)) = reached.get(next) {
+            next += 1;
+            let data = self.data(repo, node)?;
+            oids.push(data.oid);
+            for parent in data.parents {
+                if seen.insert(parent) {
+                    reached.push((self.data(repo, parent)?.time, parent));
+                }
+            }
+        }
+
+        let mut order: Vec<usize> = (0..reached.len()).collect();
+        order.sort_by_key(|&i| Reverse(reached[i].0));
+        Some(order.into_iter().map(|i| oids[i]).collect())
+    }
+
+    /// Commits reachable from `local` but not `upstream`, and the reverse
+    ///
+    /// Commits are visited highest generation first, so each one's flags are
+    /// final when it is counted, and the walk ends once every queued commit
+    /// is reachable from both sides. `None` if the graph is inconsistent or
+    /// has no generation numbers.
+    pub fn ahead_behind(&self, repo: &Repository, local: Oid, upstream: Oid) -> Option<(usize, usize)> {
+        if local == upstream {
+            return Some((0, 0));
+        }
+
+        let mut flags: HashMap<Node, u8> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        // Queued commits not yet reachable from both sides
+        let mut pending = 0usize;
+
+        for (oid, flag) in [(local, FLAG_LOCAL), (upstream, FLAG_UPSTREAM)] {
+            let node = self.node(oid);
+            let data = self.data(repo, node)?;
+            flags.insert(node, flag);
+            queue.push((data.generation, data.time, node));
+            pending += 1;
+        }
+
+        let (mut ahead, mut behind) = (0, 0);
+        while pending > 0 {
+            let (_, _, node) = queue.pop()?;
+            let node_flags = flags[&node];
+            match node_flags {
+                FLAG_LOCAL => ahead += 1,
+                FLAG_UPSTREAM => behind += 1,
+                _ => {}
+            }
+            if node_flags != FLAG_BOTH {
+                pending -= 1;
+            }
+
+            for parent in self.data(repo, node)?.parents {
+                match flags.get(&parent).copied() {
+                    None => {
+                        let data = self.data(repo, parent)?;
+                        if data.generation == 0 {
+                            // Written by an old git without generation numbers
+                            return None;
+                        }
+                        flags.insert(parent, node_flags);
+                        queue.push((data.generation, data.time, parent));
+                        if node_flags != FLAG_BOTH {
+                            pending += 1;
+                        }
+                    }
+                    Some(old) if old | node_flags != old => {
+                        flags.insert(parent, old | node_flags);
+                        if old | node_flags == FLAG_BOTH {
+                            pending -= 1;
+                        }
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        Some((ahead, behind))
+    }
+
+    fn node(&self, oid: Oid) -> Node {
+        self.layers
+            .iter()
+            .find_map(|layer| layer.find(oid.as_bytes()).map(|i| Node::Graph(layer.base + i)))
+            .unwrap_or(Node::Loose(oid))
+    }
+
+    fn data(&self, repo: &Repository, node: Node) -> Option<NodeData> {
+        match node {
+            Node::Graph(position) => {
+                let layer = self.layers.iter().find(|l| position >= l.base && position - l.base < l.count)?;
+                layer.commit(position - layer.base)
+            }
+            Node::Loose(oid) => {
+                let commit = repo.find_commit(oid).ok()?;
+                Some(NodeData {
+                    oid,
+                    generation: u32::MAX,
+                    time: commit.time().seconds(),
+                    parents: commit.parent_ids().map(|parent| self.node(parent)).collect(),
+                })
+            }
+        }
+    }
+}
+
+impl Layer {
+    fn parse(data: Vec<u8>, base: u32) -> Option<Self> {
+        // Version 1, SHA-1
+        if data.get(..4)? != SIGNATURE || data.get(4..6)? != [1, 1] {
+            return None;
+        }
+
+        // Chunk table: (id, offset) pairs, each chunk ending where the next starts
+        let chunk_count = *data.get(6)? as usize;
+        let mut chunks: HashMap<[u8; 4], Range<usize>> = HashMap::new();
+        for i in 0..chunk_count {
+            let entry = 8 + i * 12;
+            let id: [u8; 4] = data.get(entry..entry + 4)?.try_into().ok()?;
+            let start = usize::try_from(read_u64(&data, entry + 4)?).ok()?;
+            let end = usize::try_from(read_u64(&data, entry + 16)?).ok()?;
+            if start > end || end > data.len() {
+                return None;
+            }
+            chunks.insert(id, start..end);
+        }
+
+        let fanout = chunks.get(b"OIDF")?.clone();
+        if fanout.len() != 256 * 4 {
+            return None;
+        }
+        let count = read_u32(&data, fanout.start + 255 * 4)?;
+        let oids = chunks.get(b"OIDL")?.clone();
+        let cdat = chunks.get(b"CDAT")?.clone();
+        if oids.len() < count as usize * HASH_LEN || cdat.len() < count as usize * CDAT_ENTRY_LEN {
+            return None;
+        }
+        let edges = chunks.get(b"EDGE").cloned();
+
+        Some(Self { data, base, count, fanout, oids, cdat, edges })
+    }
+
+    /// Position of `oid` within this layer
+    fn find(&self, oid: &[u8]) -> Option<u32> {
+        let first = *oid.first()? as usize;
+        let mut low = match first {
+            0 => 0,
+            _ => read_u32(&self.data, self.fanout.start + (first - 1) * 4)?,
+        };
+        let mut high = read_u32(&self.data, self.fanout.start + first * 4)?;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let at = self.oids.start + mid as usize * HASH_LEN;
+            match self.data.get(at..at + HASH_LEN)?.cmp(oid) {
+                std::cmp::Ordering::Equal => return Some(mid),
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+            }
+        }
+        None
+    }
+
+    fn commit(&self, index: u32) -> Option<NodeData> {
+        let oid_at = self.oids.start + index as usize * HASH_LEN;
+        let oid = Oid::from_bytes(self.data.get(oid_at..oid_at + HASH_LEN)?).ok()?;
+
+        let entry = self.cdat.start + index as usize * CDAT_ENTRY_LEN + HASH_LEN;
+        let first_parent = read_u32(&self.data, entry)?;
+        let second_parent = read_u32(&self.data, entry + 4)?;
+        let high = read_u32(&self.data, entry + 8)?;
+        let low = read_u32(&self.data, entry + 12)?;
+
+        let mut parents = Vec::new();
+        if first_parent != NO_PARENT {
+            parents.push(Node::Graph(first_parent));
+        }
+        if second_parent != NO_PARENT {
+            if second_parent & EXTRA_EDGES == 0 {
+                parents.push(Node::Graph(second_parent));
+            } else {
+                let edges = self.edges.as_ref()?;
+                let mut at = edges.start + (second_parent & !EXTRA_EDGES) as usize * 4;
+                loop {
+                    if at + 4 > edges.end {
+                        return None;
+                    }
+                    let edge = read_u32(&self.data, at)?;
+                    parents.push(Node::Graph(edge & !LAST_EDGE));
+                    if edge & LAST_EDGE != 0 {
+                        break;
+                    }
+                    at += 4;
+                }
+            }
+        }
+        // Parents live in this layer or below it
+        if parents.iter().any(|p| matches!(p, Node::Graph(pos) if *pos >= self.base + self.count)) {
+            return None;
+        }
+
+        Some(NodeData {
+            oid,
+            generation: high >> 2,
+            time: (((high & 0b11) as i64) << 32) | low as i64,
+            parents,
+        })
+    }
+}
+
+impl GitRepository {
+    /// The commit-graph, reloaded when `git commit-graph write` or maintenance replaced it
+    pub fn commit_graph(&self, repo: &Repository) -> Result<Option<Arc<CommitGraph>>> {
+        let stamp = graph_stamp(repo);
+        let mut loaded = self.commit_graph
+            .lock()
+            .map_err(|_| AppError::Internal("Commit-graph lock poisoned".to_string()))?;
+        match loaded.as_ref() {
+            Some((known, graph)) if *known == stamp => Ok(graph.clone()),
+            _ => {
+                let graph = CommitGraph::load(repo).map(Arc::new);
+                *loaded = Some((stamp, graph.clone()));
+                Ok(graph)
+            }
+        }
+    }
+}
+
+/// Commits reachable from `local` but not `upstream`, and the reverse; from
+/// the commit-graph when there is a usable one, otherwise from libgit2
+pub fn ahead_behind(repo: &Repository, graph: Option<&CommitGraph>, local: Oid, upstream: Oid) -> Result<(usize, usize)> {
+    if let Some(counts) = graph.and_then(|g| g.ahead_behind(repo, local, upstream)) {
+        return Ok(counts);
+    }
+    Ok(repo.graph_ahead_behind(local, upstream)?)
+}
+
+fn info_dir(repo: &Repository) -> PathBuf {
+    repo.commondir().join("objects").join("info")
+}
+
+/// Graph files, base layer first; like git, a single `commit-graph` file wins over a chain
+fn graph_files(repo: &Repository) -> Option<Vec<PathBuf>> {
+    let info = info_dir(repo);
+    let single = info.join("commit-graph");
+    if single.is_file() {
+        return Some(vec![single]);
+    }
+
+    let chain_dir = info.join("commit-graphs");
+    let chain = std::fs::read_to_string(chain_dir.join("commit-graph-chain")).ok()?;
+    let files: Vec<PathBuf> = chain
+        .lines()
+        .map(str::trim)
+        .filter(|hash| !hash.is_empty())
+        .map(|hash| chain_dir.join(format!("graph-{}.graph", hash)))
+        .collect();
+    (!files.is_empty()).then_some(files)
+}
+
+/// Changes whenever a graph file or the chain is rewritten (split layers are
+/// named by their hash, so the chain file changes with them)
+fn graph_stamp(repo: &Repository) -> u64 {
+    let info = info_dir(repo);
+    let mut hasher = DefaultHasher::new();
+    for file in [Path::new("commit-graph"), Path::new("commit-graphs/commit-graph-chain")] {
+        file.hash(&mut hasher);
+        hash_file(&info.join(file), &mut hasher);
+    }
+    hasher.finish()
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn read_u64(data: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(at..at + 8)?.try_into().ok()?))
+}
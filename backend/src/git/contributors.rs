@@ -0,0 +1,120 @@
+//! Per-contributor views.
+//!
+//! Builds a "person page" for one author email from the commit cache: their
+//! commits, the directories they work in and their activity over time.
+//! Authors are matched by email, case-insensitively.
+//!
+//! Supports: GET /api/v1/repository/contributors/{email}
+
+use std::collections::{HashMap, HashSet};
+
+use git2::{Oid, Repository, Sort};
+
+use crate::error::{AppError, Result};
+use crate::git::cache::{commit_changed_files, CachedCommit};
+use crate::git::mailmap;
+use crate::git::repository::{push_head, GitRepository};
+use crate::git::stats::activity_buckets;
+use crate::models::{ActivityGranularity, ContributorProfile, DirectoryActivity};
+
+/// Most directories returned for one contributor
+const MAX_DIRECTORIES: usize = 50;
+
+impl GitRepository {
+    pub fn get_contributor(&self, email: &str, limit: usize, offset: usize) -> Result<ContributorProfile> {
+        let _op = self.op_log.start("contributor", format!("email={} limit={} offset={}", email, limit, offset));
+        if self.is_cache_over_budget() {
+            return self.with_repo(|repo| {
+                let mut revwalk = repo.revwalk()?;
+                revwalk.set_sorting(Sort::TIME)?;
+                push_head(&mut revwalk, repo)?;
+                let mailmap = mailmap::load(repo);
+
+                let mut commits = Vec::new();
+                for oid_result in revwalk {
+                    let commit = repo.find_commit(oid_result?)?;
+                    let author = mailmap::author(&commit, mailmap.as_ref());
+                    if author.email().is_some_and(|e| e.eq_ignore_ascii_case(email)) {
+                        commits.push(CachedCommit::from_commit(&commit, mailmap.as_ref()));
+                    }
+                }
+                build_profile(repo, email, &commits, None, limit, offset)
+            });
+        }
+
+        self.with_cache(|cache, repo| {
+            let indices: Vec<usize> = cache.all_commits
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.author_email.eq_ignore_ascii_case(email))
+                .map(|(idx, _)| idx)
+                .collect();
+
+            let commits: Vec<CachedCommit> = indices.iter().map(|&idx| cache.all_commits[idx].clone()).collect();
+            // Reuse changed-file lists if a glob query already built them
+            let files = cache.changed_files
+                .as_ref()
+                .map(|files| indices.iter().map(|&idx| files[idx].as_slice()).collect::<Vec<_>>());
+
+            build_profile(repo, email, &commits, files.as_deref(), limit, offset)
+        })
+    }
+}
+
+/// Assemble the profile from the author's commits (newest first)
+///
+/// `changed_files` runs parallel to `commits` when already known; otherwise each
+/// commit is diffed against its first parent.
+fn build_profile(
+    repo: &Repository,
+    email: &str,
+    commits: &[CachedCommit],
+    changed_files: Option<&[&[String]]>,
+    limit: usize,
+    offset: usize,
+) -> Result<ContributorProfile> {
+    let (Some(newest), Some(oldest)) = (commits.first(), commits.last()) else {
+        return Err(AppError::AuthorNotFound(email.to_string()));
+    };
+
+    // Count each directory at most once per commit
+    let mut directory_counts: HashMap<String, usize> = HashMap::new();
+    for (i, commit) in commits.iter().enumerate() {
+        let computed;
+        let files: &[String] = match changed_files {
+            Some(files) => files[i],
+            None => {
+                let git_commit = repo.find_commit(Oid::from_str(&commit.oid)?)?;
+                computed = commit_changed_files(repo, &git_commit)?;
+                &computed
+            }
+        };
+
+        let directories: HashSet<&str> = files
+            .iter()
+            .map(|f| f.rsplit_once('/').map(|(dir, _)| dir).unwrap_or(""))
+            .collect();
+        for dir in directories {
+            *directory_counts.entry(dir.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    let mut directories: Vec<DirectoryActivity> = directory_counts
+        .into_iter()
+        .map(|(path, commit_count)| DirectoryActivity { path, commit_count })
+        .collect();
+    directories.sort_by(|a, b| b.commit_count.cmp(&a.commit_count).then_with(|| a.path.cmp(&b.path)));
+    directories.truncate(MAX_DIRECTORIES);
+
+    Ok(ContributorProfile {
+        name: newest.author_name.to_string(),
+        email: newest.author_email.to_string(),
+        commit_count: commits.len(),
+        first_commit_timestamp: oldest.timestamp,
+        last_commit_timestamp: newest.timestamp,
+        commits: commits.iter().skip(offset).take(limit).map(|c| c.to_commit_detail()).collect(),
+        has_more: commits.len() > offset + limit,
+        directories,
+        activity: activity_buckets(commits.iter().map(|c| c.timestamp), ActivityGranularity::Month),
+    })
+}
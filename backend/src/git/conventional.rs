@@ -0,0 +1,111 @@
+//! Conventional Commits parsing (`feat(parser)!: subject`).
+//!
+//! Splits a message into type, scope, breaking flag, subject, body and the
+//! trailing block of git trailers (`Signed-off-by: ...`, `Refs #123`,
+//! `BREAKING CHANGE: ...`). Messages whose first line isn't a conventional
+//! header are left unparsed.
+//!
+//! Used by: commit list type filter, changelog-style grouping
+
+use crate::models::{ConventionalCommit, Trailer};
+
+/// Trailer keys that mark a breaking change, per the specification
+const BREAKING_KEYS: [&str; 2] = ["BREAKING CHANGE", "BREAKING-CHANGE"];
+
+/// Structured fields of a conventional commit message, `None` if the first
+/// line isn't `type(scope)!: subject`
+pub fn parse(message: &str) -> Option<ConventionalCommit> {
+    let mut lines = message.lines();
+    let header = parse_header(lines.next()?)?;
+    let rest: Vec<&str> = lines.collect();
+
+    // The last paragraph holds the trailers, if every line in it is one
+    let last_break = rest.iter().rposition(|line| line.trim().is_empty());
+    let last_paragraph = &rest[last_break.map_or(0, |i| i + 1)..];
+    let (body_lines, trailers) = match parse_trailers(last_paragraph) {
+        Some(trailers) => (&rest[..last_break.unwrap_or(0)], trailers),
+        None => (&rest[..], Vec::new()),
+    };
+    let body = body_lines.join("\n").trim().to_string();
+
+    Some(ConventionalCommit {
+        commit_type: header.commit_type.to_string(),
+        scope: header.scope.map(str::to_string),
+        breaking: header.breaking || trailers.iter().any(|t| BREAKING_KEYS.contains(&t.key.as_str())),
+        subject: header.subject.to_string(),
+        body: (!body.is_empty()).then_some(body),
+        trailers,
+    })
+}
+
+/// Just the type of a conventional commit message (as written, e.g. `feat`)
+pub fn commit_type(message: &str) -> Option<&str> {
+    parse_header(message.lines().next()?).map(|header| header.commit_type)
+}
+
+struct Header<'a> {
+    commit_type: &'a str,
+    scope: Option<&'a str>,
+    breaking: bool,
+    subject: &'a str,
+}
+
+fn parse_header(line: &str) -> Option<Header<'_>> {
+    let (prefix, subject) = line.split_once(": ")?;
+    let (prefix, breaking) = match prefix.strip_suffix('!') {
+        Some(prefix) => (prefix, true),
+        None => (prefix, false),
+    };
+    let (commit_type, scope) = match prefix.split_once('(') {
+        Some((commit_type, scope)) => {
+            let scope = scope.strip_suffix(')')?;
+            if scope.is_empty() || scope.contains(['(', ')']) {
+                return None;
+            }
+            (commit_type, Some(scope))
+        }
+        None => (prefix, None),
+    };
+
+    let valid_type = commit_type.starts_with(|c: char| c.is_ascii_alphabetic())
+        && commit_type.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    let subject = subject.trim();
+    if !valid_type || subject.is_empty() {
+        return None;
+    }
+    Some(Header { commit_type, scope, breaking, subject })
+}
+
+/// Trailers of a paragraph, `None` unless it consists only of trailers and
+/// their indented continuation lines
+fn parse_trailers(paragraph: &[&str]) -> Option<Vec<Trailer>> {
+    let mut trailers: Vec<Trailer> = Vec::new();
+    for line in paragraph {
+        if line.starts_with([' ', '\t']) {
+            let last = trailers.last_mut()?;
+            last.value.push('\n');
+            last.value.push_str(line.trim());
+            continue;
+        }
+        let (key, value) = trailer_line(line)?;
+        trailers.push(Trailer { key: key.to_string(), value: value.trim().to_string() });
+    }
+    (!trailers.is_empty()).then_some(trailers)
+}
+
+/// `Key: value` or `Key #value` (e.g. `Refs #123`); keys are word characters
+/// and dashes, except the spaced `BREAKING CHANGE`
+fn trailer_line(line: &str) -> Option<(&str, &str)> {
+    if let Some(value) = line.strip_prefix("BREAKING CHANGE: ") {
+        return Some(("BREAKING CHANGE", value));
+    }
+    let split = line.find([':', ' '])?;
+    let key = &line[..split];
+    let value = match &line[split..] {
+        rest if rest.starts_with(": ") => &rest[2..],
+        rest if rest.starts_with(" #") => &rest[1..],
+        _ => return None,
+    };
+    let valid_key = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid_key.then_some((key, value))
+}
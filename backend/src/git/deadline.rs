@@ -0,0 +1,92 @@
+//! Time limits and cancellation for expensive git work.
+//!
+//! - `Deadline`: The time budget of one request's git work, cancellable from
+//!   outside (timeout, client disconnect)
+//! - `check()`: Called between the steps of long operations; fails with
+//!   `AppError::Timeout` (504) once the running operation's deadline passed
+//!   or it was cancelled
+//!
+//! The deadline is installed for the thread doing the work (`Deadline::scope`),
+//! so the git code checks it without it being passed through every call;
+//! outside a scope, `check()` never fails. Checks sit between diffed files,
+//! walked commits and before taking the repository lock, so a stopped
+//! operation releases the lock after at most one step. libgit2 calls
+//! themselves can't be interrupted, which is why large blames run on a
+//! repository handle of their own, outside the lock.
+//!
+//! Used by: `routes::timeout::limited()` for blame, diff, compare, directory info, pickaxe and file content
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::error::{AppError, Result};
+
+/// Default for `--request-timeout` (seconds)
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Deadline>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Clone)]
+pub struct Deadline {
+    /// What is being limited, for the error message, e.g. "blame of src/big.rs"
+    operation: String,
+    limit: Duration,
+    started: Instant,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Deadline {
+    pub fn new(operation: impl Into<String>, limit: Duration) -> Self {
+        Self {
+            operation: operation.into(),
+            limit,
+            started: Instant::now(),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Stop the operation at its next check
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Run `f` with this deadline checked by `check()` on the current thread
+    pub fn scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        struct Reset(Option<Deadline>);
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                let previous = self.0.take();
+                CURRENT.with(|current| *current.borrow_mut() = previous);
+            }
+        }
+
+        let _reset = Reset(CURRENT.with(|current| current.borrow_mut().replace(self.clone())));
+        f()
+    }
+
+    /// The error a request past its deadline is answered with
+    pub fn timeout_error(&self) -> AppError {
+        AppError::Timeout(format!(
+            "{} took longer than {}s and was stopped; narrow it down (a path, a line range, a smaller \
+             commit range) or raise --request-timeout",
+            self.operation,
+            self.limit.as_secs()
+        ))
+    }
+
+    fn is_over(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed) || self.started.elapsed() > self.limit
+    }
+}
+
+/// Fail with `AppError::Timeout` if the current thread's operation is past its deadline or cancelled
+pub fn check() -> Result<()> {
+    CURRENT.with(|current| match current.borrow().as_ref() {
+        Some(deadline) if deadline.is_over() => Err(deadline.timeout_error()),
+        _ => Ok(()),
+    })
+}
@@ -5,19 +5,68 @@
 //! - Hunks with line-by-line additions/deletions
 //! - Full file contents (old and new) for side-by-side view
 //! - Author attribution per file (who touched each file between commits)
+//! - Image metadata (blob OIDs, mime, dimensions) for image files, so both
+//!   versions can be shown side by side
+//! - Blob OIDs, sizes and size change for binary files, which have no hunks
+//! - Git LFS pointers (content OID and real size) instead of the pointer text
+//!   when either side of a file is stored in LFS
+//! - `.gitattributes` overrides: `binary`/`-diff` files are diffed as binary
+//!   and `linguist-generated` files are flagged and come without contents
+//! - Optional per-line blame for context/deletion lines (`include_blame`), from
+//!   one blame of each file's old side
+//! - Word-level change spans on paired deletion/addition lines (see word_diff.rs)
+//! - For the working tree, untracked files in their own `untracked` section
+//!   with size, binary detection and (for text under a size limit) contents
+//!
+//! `compare_file()` diffs one file between two refs without diffing the rest
+//! of the tree.
+//!
+//! `range_diff()` pairs the commits of two ranges like `git range-diff`: each
+//! commit becomes its message plus patch (without line numbers or blob OIDs),
+//! pairing costs are the size of the diff between those texts, and the cheapest
+//! overall pairing is found with the Hungarian algorithm. Leaving a commit
+//! unpaired costs `creation_factor` percent of its own size.
+//!
+//! `get_diff_tree_summary()` rolls per-file line counts up into every ancestor
+//! directory, without loading file contents.
 //!
 //! `get_file_authors_between_commits()` walks intermediate commits to track
 //! which authors modified each file, enabling contributor filtering in diff view.
 //!
 //! Supports frontend: DiffViewer modal with split/unified view, author badges
 
-use git2::{Delta, DiffOptions, Repository, Sort};
+use encoding_rs::{Encoding, UTF_8};
+use git2::{Delta, DiffFormat, DiffOptions, Repository, Sort};
 use std::collections::HashMap;
 use std::path::Path;
 
 use crate::error::{AppError, Result};
-use crate::git::repository::GitRepository;
-use crate::models::{AuthorInfo, DiffHunk, DiffLine, DiffResponse, DiffStats, DiffStatus, FileAuthorInfo, FileDiff, LineType, WorkingTreeStatus};
+use crate::git::attributes::diff_attributes;
+use crate::git::codeowners::CodeOwners;
+use crate::git::deadline;
+use crate::git::encoding;
+use crate::git::lfs::{blob_pointer, workdir_pointer};
+use crate::git::mailmap;
+use crate::git::objects::{image_dimensions, sniff_mime};
+use crate::git::repository::{commit_to_info, head_commit, resolve_commit, resolve_tree_or_head, GitRepository};
+use crate::git::word_diff::mark_changed_spans;
+use crate::models::{
+    AuthorInfo, BinaryDiff, BinaryVersion, DiffHunk, DiffLine, DiffResponse, DiffStats, DiffStatus, DiffTreeSummary, DirectoryDiffSummary,
+    FileAuthorInfo, FileCompareResponse, FileDiff, ImageDiff, ImageVersion, LfsDiff, LineBlame, LineType, ModeChange, RangeDiffEntry,
+    RangeDiffResponse, RangeDiffStatus, UntrackedFile,
+};
+
+/// Most commits accepted per range; range-diff pairing is cubic in the number of commits
+const MAX_RANGE_DIFF_COMMITS: usize = 200;
+
+/// Untracked files larger than this come without contents
+const MAX_UNTRACKED_CONTENT_BYTES: u64 = 1024 * 1024;
+
+/// Unchanged lines around each change unless the request asks for more or fewer
+pub const DEFAULT_CONTEXT_LINES: u32 = 3;
+
+/// Context large enough that each file comes back as one hunk spanning the whole file
+pub const FULL_FILE_CONTEXT: u32 = i32::MAX as u32;
 
 impl GitRepository {
     pub fn get_diff(
@@ -25,38 +74,45 @@ impl GitRepository {
         from_commit: Option<&str>,
         to_commit: &str,
         path: Option<&str>,
+        include_contents: bool,
+        include_blame: bool,
+        context_lines: u32,
     ) -> Result<DiffResponse> {
-        // Convert to owned strings for the closure
-        let from_commit_owned = from_commit.map(|s| s.to_string());
-        let to_commit_owned = to_commit.to_string();
+        let _op = self.op_log.start(
+            "diff",
+            format!("from={} to={} path={} contents={} blame={} context={}", from_commit.unwrap_or("parent"), to_commit, path.unwrap_or(""), include_contents, include_blame, context_lines),
+        );
         let path_owned = path.map(|s| s.to_string());
 
         self.with_repo(|repo| {
-            let to_oid = git2::Oid::from_str(&to_commit_owned)
-                .map_err(|_| AppError::CommitNotFound(to_commit_owned.clone()))?;
-            let to = repo.find_commit(to_oid)
-                .map_err(|_| AppError::CommitNotFound(to_commit_owned.clone()))?;
+            let to = resolve_commit(repo, to_commit)?;
+            let to_oid = to.id();
             let to_tree = to.tree()?;
+            let codeowners = CodeOwners::load(repo, &to_tree);
 
-            let from_tree = if let Some(ref from_oid_str) = from_commit_owned {
-                let from_oid = git2::Oid::from_str(from_oid_str)
-                    .map_err(|_| AppError::CommitNotFound(from_oid_str.clone()))?;
-                let from = repo.find_commit(from_oid)
-                    .map_err(|_| AppError::CommitNotFound(from_oid_str.clone()))?;
-                Some(from.tree()?)
+            // An explicit `from` is reported back as its OID; the implicit parent is not
+            let explicit_from = from_commit.map(|spec| resolve_commit(repo, spec)).transpose()?;
+            let explicit_from_id = explicit_from.as_ref().map(|c| c.id());
+            let from = if let Some(from) = explicit_from {
+                Some(from)
             } else if to.parent_count() > 0 {
-                Some(to.parent(0)?.tree()?)
+                Some(to.parent(0)?)
             } else {
                 None
             };
+            let from_tree = match &from {
+                Some(commit) => Some(commit.tree()?),
+                None => None,
+            };
+            let from_id = from.as_ref().map(|c| c.id());
 
             let mut opts = DiffOptions::new();
-            opts.context_lines(3);
+            opts.context_lines(context_lines).include_typechange(true);
 
-            if let Some(ref p) = path_owned {
-                if !p.is_empty() {
-                    opts.pathspec(p);
-                }
+            if let Some(ref p) = path_owned
+                && !p.is_empty()
+            {
+                opts.pathspec(p);
             }
 
             let diff = repo.diff_tree_to_tree(
@@ -69,23 +125,24 @@ impl GitRepository {
             let mut stats = DiffStats::default();
 
             for (delta_idx, delta) in diff.deltas().enumerate() {
-                let status = match delta.status() {
-                    Delta::Added => DiffStatus::Added,
-                    Delta::Deleted => DiffStatus::Deleted,
-                    Delta::Modified => DiffStatus::Modified,
-                    Delta::Renamed => DiffStatus::Renamed,
-                    Delta::Copied => DiffStatus::Copied,
-                    Delta::Typechange => DiffStatus::TypeChanged,
-                    _ => DiffStatus::Unmodified,
-                };
+                deadline::check()?;
+                let status = delta_status(delta.status());
 
                 let old_path = delta.old_file().path().map(|p| p.to_string_lossy().to_string());
                 let new_path = delta.new_file().path().map(|p| p.to_string_lossy().to_string());
 
-                let is_binary = delta.flags().is_binary();
+                // libgit2 only flags binary files once the blobs are loaded
+                let patch = git2::Patch::from_diff(&diff, delta_idx)?;
+                let attrs = diff_attributes(repo, new_path.as_deref().or(old_path.as_deref()));
+                let is_binary = attrs.binary || patch_is_binary(patch.as_ref(), &delta);
+                // Pointer text is never shown, so LFS files go without contents or hunks
+                let lfs = lfs_diff(repo, &delta, None);
+                let has_text = !is_binary && lfs.is_none();
+                let include_contents = include_contents && !attrs.generated;
+                let encoding = if has_text { delta_encoding(repo, &delta, None) } else { UTF_8 };
 
                 // Get file contents
-                let old_content = if !is_binary {
+                let old_content = if include_contents && has_text {
                     old_path.as_ref().and_then(|p| {
                         from_tree.as_ref().and_then(|tree| {
                             get_blob_content(repo, tree, p).ok()
@@ -95,7 +152,7 @@ impl GitRepository {
                     None
                 };
 
-                let new_content = if !is_binary {
+                let new_content = if include_contents && has_text {
                     new_path.as_ref().and_then(|p| {
                         get_blob_content(repo, &to_tree, p).ok()
                     })
@@ -103,52 +160,18 @@ impl GitRepository {
                     None
                 };
 
-                // Get hunks
-                let mut hunks: Vec<DiffHunk> = Vec::new();
-                let patch = git2::Patch::from_diff(&diff, delta_idx)?;
+                let old_blame = if include_blame && has_text && status != DiffStatus::Added {
+                    from_id.zip(old_path.as_ref()).and_then(|(id, p)| blame_file_at(repo, p, id))
+                } else {
+                    None
+                };
 
-                if let Some(patch) = patch {
-                    for hunk_idx in 0..patch.num_hunks() {
-                        let (hunk, _) = patch.hunk(hunk_idx)?;
-
-                        let mut lines: Vec<DiffLine> = Vec::new();
-
-                        for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
-                            let line = patch.line_in_hunk(hunk_idx, line_idx)?;
-
-                            let line_type = match line.origin() {
-                                '+' => {
-                                    stats.insertions += 1;
-                                    LineType::Addition
-                                }
-                                '-' => {
-                                    stats.deletions += 1;
-                                    LineType::Deletion
-                                }
-                                ' ' => LineType::Context,
-                                _ => LineType::Header,
-                            };
-
-                            let content = String::from_utf8_lossy(line.content()).to_string();
-
-                            lines.push(DiffLine {
-                                line_type,
-                                old_lineno: line.old_lineno(),
-                                new_lineno: line.new_lineno(),
-                                content,
-                            });
-                        }
-
-                        hunks.push(DiffHunk {
-                            old_start: hunk.old_start(),
-                            old_lines: hunk.old_lines(),
-                            new_start: hunk.new_start(),
-                            new_lines: hunk.new_lines(),
-                            header: String::from_utf8_lossy(hunk.header()).to_string(),
-                            lines,
-                        });
-                    }
-                }
+                let hunks = if has_text {
+                    patch_hunks(repo, patch.as_ref(), context_lines, &mut stats, old_blame.as_ref(), encoding)?
+                } else {
+                    Vec::new()
+                };
+                let owners = path_owners(codeowners.as_ref(), new_path.as_deref().or(old_path.as_deref()));
 
                 files.push(FileDiff {
                     old_path,
@@ -157,21 +180,27 @@ impl GitRepository {
                     hunks,
                     old_content,
                     new_content,
+                    encoding: non_utf8(encoding),
                     is_binary,
+                    is_generated: attrs.generated,
                     authors: Vec::new(),
                     biggest_change_author: None,
+                    image: image_diff(repo, &delta),
+                    binary: is_binary.then(|| binary_diff(repo, patch.as_ref(), &delta, None)),
+                    lfs,
+                    old_mode: file_mode(&delta.old_file()),
+                    new_mode: file_mode(&delta.new_file()),
+                    mode_change: mode_change(&delta),
+                    owners,
                 });
 
                 stats.files_changed += 1;
             }
 
             // Get author information for files between the commits
-            let from_oid = from_commit_owned.as_ref()
-                .and_then(|s| git2::Oid::from_str(s).ok());
-
             let file_authors = get_file_authors_between_commits(
                 repo,
-                from_oid,
+                explicit_from_id,
                 to_oid,
                 path_owned.as_deref(),
             )?;
@@ -184,82 +213,365 @@ impl GitRepository {
                 let file_path = file.new_path.as_ref()
                     .or(file.old_path.as_ref());
 
-                if let Some(path) = file_path {
-                    if let Some(authors) = file_authors.get(path) {
-                        file.authors = authors.clone();
-                        file.biggest_change_author = authors.first().map(|a| a.email.clone());
-
-                        // Add to contributors list
-                        for author in authors {
-                            all_contributors.entry(author.email.clone()).or_insert_with(|| AuthorInfo {
-                                name: author.name.clone(),
-                                email: author.email.clone(),
-                            });
-                        }
+                if let Some(path) = file_path
+                    && let Some(authors) = file_authors.get(path)
+                {
+                    file.authors = authors.clone();
+                    file.biggest_change_author = authors.first().map(|a| a.email.clone());
+
+                    // Add to contributors list
+                    for author in authors {
+                        all_contributors.entry(author.email.clone()).or_insert_with(|| AuthorInfo {
+                            name: author.name.clone(),
+                            email: author.email.clone(),
+                        });
                     }
                 }
             }
 
             // Sort contributors by name
             let mut contributors: Vec<AuthorInfo> = all_contributors.into_values().collect();
-            contributors.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+            contributors.sort_by_key(|a| a.name.to_lowercase());
 
             let total_files = files.len();
 
             Ok(DiffResponse {
-                from_commit: from_commit_owned,
-                to_commit: to_commit_owned,
+                from_commit: explicit_from_id.map(|oid| oid.to_string()),
+                to_commit: to_oid.to_string(),
                 path: path_owned,
                 files,
                 stats,
                 contributors,
                 total_files,
                 filtered_files: total_files,
+                untracked: Vec::new(),
             })
         })
     }
 
-    pub fn get_diff_between_commits(
+    /// Aggregate changes between two commits per directory
+    ///
+    /// Each changed file counts towards its own directory and every ancestor,
+    /// so the root entry ("") holds the overall totals.
+    pub fn get_diff_tree_summary(
         &self,
-        from_commit: &str,
+        from_commit: Option<&str>,
         to_commit: &str,
         path: Option<&str>,
-    ) -> Result<DiffResponse> {
-        self.get_diff(Some(from_commit), to_commit, path)
+    ) -> Result<DiffTreeSummary> {
+        let _op = self.op_log.start("diff_tree_summary", format!("from={} to={} path={}", from_commit.unwrap_or("parent"), to_commit, path.unwrap_or("")));
+        self.with_repo(|repo| {
+            let to = resolve_commit(repo, to_commit)?;
+            let to_tree = to.tree()?;
+
+            let from = match from_commit {
+                Some(spec) => Some(resolve_commit(repo, spec)?),
+                None if to.parent_count() > 0 => Some(to.parent(0)?),
+                None => None,
+            };
+            let from_tree = match &from {
+                Some(commit) => Some(commit.tree()?),
+                None => None,
+            };
+
+            let mut opts = DiffOptions::new();
+            if let Some(p) = path
+                && !p.is_empty()
+            {
+                opts.pathspec(p);
+            }
+
+            let diff = repo.diff_tree_to_tree(from_tree.as_ref(), Some(&to_tree), Some(&mut opts))?;
+
+            let mut directories: HashMap<String, DirectoryDiffSummary> = HashMap::new();
+            let mut stats = DiffStats::default();
+
+            for (delta_idx, delta) in diff.deltas().enumerate() {
+                deadline::check()?;
+                let file_path = delta.new_file().path()
+                    .or_else(|| delta.old_file().path())
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_default();
+
+                let (insertions, deletions) = match git2::Patch::from_diff(&diff, delta_idx)? {
+                    Some(patch) => {
+                        let (_, additions, deletions) = patch.line_stats()?;
+                        (additions, deletions)
+                    }
+                    None => (0, 0),
+                };
+
+                stats.files_changed += 1;
+                stats.insertions += insertions;
+                stats.deletions += deletions;
+
+                for dir in ancestor_directories(&file_path) {
+                    let entry = directories.entry(dir.to_string()).or_insert_with(|| DirectoryDiffSummary {
+                        path: dir.to_string(),
+                        files_changed: 0,
+                        insertions: 0,
+                        deletions: 0,
+                    });
+                    entry.files_changed += 1;
+                    entry.insertions += insertions;
+                    entry.deletions += deletions;
+                }
+            }
+
+            let mut directories: Vec<DirectoryDiffSummary> = directories.into_values().collect();
+            directories.sort_by(|a, b| a.path.cmp(&b.path));
+
+            Ok(DiffTreeSummary {
+                from_commit: from.map(|c| c.id().to_string()),
+                to_commit: to.id().to_string(),
+                path: path.map(|s| s.to_string()),
+                directories,
+                stats,
+            })
+        })
     }
 
-    pub fn get_working_tree_status(&self, path: Option<&str>) -> Result<WorkingTreeStatus> {
+    /// Diff a single file between two refs
+    ///
+    /// The path is matched exactly, so only that file is diffed no matter how
+    /// large the overall change between the refs is. A file identical on both
+    /// sides comes back as `unmodified` with no hunks.
+    pub fn compare_file(&self, path: &str, base: &str, head: &str, context_lines: u32) -> Result<FileCompareResponse> {
+        let _op = self.op_log.start("compare_file", format!("path={} base={} head={}", path, base, head));
         self.with_repo(|repo| {
-            // Bare or empty repos have no working tree
-            if repo.is_bare() || repo.head().is_err() {
-                return Ok(WorkingTreeStatus {
-                    has_changes: false,
-                    files_changed: 0,
-                });
+            let base_commit = resolve_commit(repo, base)?;
+            let head_commit = resolve_commit(repo, head)?;
+            let base_tree = base_commit.tree()?;
+            let head_tree = head_commit.tree()?;
+
+            let kind_in = |tree: &git2::Tree| tree.get_path(Path::new(path)).ok().and_then(|e| e.kind());
+            let (base_kind, head_kind) = (kind_in(&base_tree), kind_in(&head_tree));
+            if base_kind.is_none() && head_kind.is_none() {
+                return Err(AppError::PathNotFound(path.to_string()));
+            }
+            if [base_kind, head_kind].contains(&Some(git2::ObjectType::Tree)) {
+                return Err(AppError::InvalidPath(format!("{} is not a file", path)));
             }
+            let (in_base, in_head) = (base_kind.is_some(), head_kind.is_some());
+            let owners = path_owners(CodeOwners::load(repo, &head_tree).as_ref(), Some(path));
+            let attrs = diff_attributes(repo, Some(path));
+
+            let mut opts = DiffOptions::new();
+            opts.context_lines(context_lines)
+                .include_typechange(true)
+                .pathspec(path)
+                .disable_pathspec_match(true);
+
+            let diff = repo.diff_tree_to_tree(Some(&base_tree), Some(&head_tree), Some(&mut opts))?;
 
-            let mut opts = git2::StatusOptions::new();
-            opts.include_untracked(true)
-                .recurse_untracked_dirs(true)
-                .include_ignored(false);
+            let mut stats = DiffStats::default();
+            let file = match diff.deltas().next() {
+                Some(delta) => {
+                    let patch = git2::Patch::from_diff(&diff, 0)?;
+                    let is_binary = attrs.binary || patch_is_binary(patch.as_ref(), &delta);
+                    let lfs = lfs_diff(repo, &delta, None);
+                    let has_text = !is_binary && lfs.is_none();
+                    let encoding = if has_text { delta_encoding(repo, &delta, None) } else { UTF_8 };
+                    let hunks = if has_text {
+                        patch_hunks(repo, patch.as_ref(), context_lines, &mut stats, None, encoding)?
+                    } else {
+                        Vec::new()
+                    };
+                    let load_contents = has_text && !attrs.generated;
+                    stats.files_changed = 1;
+
+                    FileDiff {
+                        old_path: in_base.then(|| path.to_string()),
+                        new_path: in_head.then(|| path.to_string()),
+                        status: delta_status(delta.status()),
+                        hunks,
+                        old_content: if load_contents { get_blob_content(repo, &base_tree, path).ok() } else { None },
+                        new_content: if load_contents { get_blob_content(repo, &head_tree, path).ok() } else { None },
+                        encoding: non_utf8(encoding),
+                        is_binary,
+                        is_generated: attrs.generated,
+                        authors: Vec::new(),
+                        biggest_change_author: None,
+                        image: image_diff(repo, &delta),
+                        binary: is_binary.then(|| binary_diff(repo, patch.as_ref(), &delta, None)),
+                        lfs,
+                        old_mode: file_mode(&delta.old_file()),
+                        new_mode: file_mode(&delta.new_file()),
+                        mode_change: mode_change(&delta),
+                        owners,
+                    }
+                }
+                None => {
+                    let entry = head_tree.get_path(Path::new(path)).ok();
+                    let pointer = entry.as_ref().and_then(|entry| blob_pointer(repo, entry.id()));
+                    let decoded = if attrs.binary || pointer.is_some() {
+                        None
+                    } else {
+                        entry.as_ref()
+                            .and_then(|entry| repo.find_blob(entry.id()).ok())
+                            .and_then(|blob| encoding::decode(blob.content()))
+                    };
+                    let (content, encoding) = decoded.unzip();
+                    let is_binary = content.is_none() && pointer.is_none();
+                    let content = content.filter(|_| !attrs.generated);
+                    let image = entry.as_ref()
+                        .and_then(|entry| image_version(repo, Path::new(path), entry.id()))
+                        .map(|version| ImageDiff { old: Some(version.clone()), new: Some(version) });
+                    let mode = entry.map(|entry| format!("{:06o}", entry.filemode()));
+                    FileDiff {
+                        old_path: Some(path.to_string()),
+                        new_path: Some(path.to_string()),
+                        status: DiffStatus::Unmodified,
+                        hunks: Vec::new(),
+                        old_content: content.clone(),
+                        new_content: content,
+                        encoding: encoding.and_then(non_utf8),
+                        is_binary,
+                        is_generated: attrs.generated,
+                        authors: Vec::new(),
+                        biggest_change_author: None,
+                        image,
+                        binary: None,
+                        lfs: pointer.map(|pointer| LfsDiff { old: Some(pointer.clone()), new: Some(pointer), size_delta: 0 }),
+                        old_mode: mode.clone(),
+                        new_mode: mode,
+                        mode_change: None,
+                        owners,
+                    }
+                }
+            };
 
-            if let Some(p) = path {
-                if !p.is_empty() {
-                    opts.pathspec(p);
+            Ok(FileCompareResponse {
+                path: path.to_string(),
+                base_commit: base_commit.id().to_string(),
+                head_commit: head_commit.id().to_string(),
+                file,
+                stats,
+            })
+        })
+    }
+
+    /// Pair the commits of `old_range` and `new_range` (`A..B` each), like `git range-diff`
+    ///
+    /// Merge commits are skipped. `creation_factor` (percent, git's default is
+    /// 60) sets how different two commits may be before they are reported as
+    /// removed and added rather than modified.
+    pub fn range_diff(&self, old_range: &str, new_range: &str, creation_factor: u32) -> Result<RangeDiffResponse> {
+        let _op = self.op_log.start(
+            "range_diff",
+            format!("old={} new={} creation_factor={}", old_range, new_range, creation_factor),
+        );
+        self.with_repo(|repo| {
+            let old_commits = range_commits(repo, old_range)?;
+            let new_commits = range_commits(repo, new_range)?;
+            let old_texts = old_commits.iter().map(|c| commit_patch_text(repo, c)).collect::<Result<Vec<_>>>()?;
+            let new_texts = new_commits.iter().map(|c| commit_patch_text(repo, c)).collect::<Result<Vec<_>>>()?;
+            let mailmap = mailmap::load(repo);
+
+            let (n, m) = (old_commits.len(), new_commits.len());
+            let unpaired_cost = |text: &str| text.lines().count() as i64 * i64::from(creation_factor) / 100;
+
+            // Square matrix as in git: old x new pairings, old x dummy (removed),
+            // dummy x new (added), dummy x dummy free
+            let mut cost = vec![vec![0i64; n + m]; n + m];
+            for (row, old_text) in cost.iter_mut().zip(&old_texts) {
+                deadline::check()?;
+                for (cell, new_text) in row.iter_mut().zip(&new_texts) {
+                    *cell = text_diff_size(old_text, new_text)?;
+                }
+                row[m..].fill(unpaired_cost(old_text));
+            }
+            for row in cost.iter_mut().skip(n) {
+                for (cell, new_text) in row.iter_mut().zip(&new_texts) {
+                    *cell = unpaired_cost(new_text);
                 }
             }
 
-            let statuses = repo.statuses(Some(&mut opts))?;
-            let files_changed = statuses.len();
+            let assignment = min_cost_assignment(&cost);
+            let paired_old: Vec<Option<usize>> = (0..m)
+                .map(|j| (0..n).find(|&i| assignment[i] == j))
+                .collect();
+
+            let removed_entry = |i: usize| RangeDiffEntry {
+                status: RangeDiffStatus::Removed,
+                old_position: Some(i + 1),
+                old_commit: Some(commit_to_info(&old_commits[i], mailmap.as_ref())),
+                new_position: None,
+                new_commit: None,
+                interdiff: Vec::new(),
+            };
+
+            let mut entries = Vec::with_capacity(n + m);
+            let mut next_old = 0;
+            for (j, new_commit) in new_commits.iter().enumerate() {
+                let Some(i) = paired_old[j] else {
+                    entries.push(RangeDiffEntry {
+                        status: RangeDiffStatus::Added,
+                        old_position: None,
+                        old_commit: None,
+                        new_position: Some(j + 1),
+                        new_commit: Some(commit_to_info(new_commit, mailmap.as_ref())),
+                        interdiff: Vec::new(),
+                    });
+                    continue;
+                };
+
+                // Removed commits that came before this one in the old range
+                while next_old < i {
+                    if assignment[next_old] >= m {
+                        entries.push(removed_entry(next_old));
+                    }
+                    next_old += 1;
+                }
+                next_old = next_old.max(i + 1);
 
-            Ok(WorkingTreeStatus {
-                has_changes: files_changed > 0,
-                files_changed,
+                let (status, interdiff) = if old_texts[i] == new_texts[j] {
+                    (RangeDiffStatus::Equal, Vec::new())
+                } else {
+                    let patch = git2::Patch::from_buffers(
+                        old_texts[i].as_bytes(),
+                        None,
+                        new_texts[j].as_bytes(),
+                        None,
+                        None,
+                    )?;
+                    (RangeDiffStatus::Modified, hunks_of(&patch, &mut DiffStats::default(), None, UTF_8)?)
+                };
+
+                entries.push(RangeDiffEntry {
+                    status,
+                    old_position: Some(i + 1),
+                    old_commit: Some(commit_to_info(&old_commits[i], mailmap.as_ref())),
+                    new_position: Some(j + 1),
+                    new_commit: Some(commit_to_info(new_commit, mailmap.as_ref())),
+                    interdiff,
+                });
+            }
+            for (i, &col) in assignment.iter().enumerate().take(n).skip(next_old) {
+                if col >= m {
+                    entries.push(removed_entry(i));
+                }
+            }
+
+            Ok(RangeDiffResponse {
+                old_range: old_range.to_string(),
+                new_range: new_range.to_string(),
+                entries,
             })
         })
     }
 
-    pub fn get_working_tree_diff(&self, path: Option<&str>) -> Result<DiffResponse> {
+    pub fn get_working_tree_diff(
+        &self,
+        path: Option<&str>,
+        include_contents: bool,
+        include_blame: bool,
+        context_lines: u32,
+    ) -> Result<DiffResponse> {
+        let _op = self.op_log.start(
+            "working_tree_diff",
+            format!("path={} contents={} blame={} context={}", path.unwrap_or(""), include_contents, include_blame, context_lines),
+        );
         let path_owned = path.map(|s| s.to_string());
 
         self.with_repo(|repo| {
@@ -268,22 +580,21 @@ impl GitRepository {
                 .ok_or_else(|| AppError::Internal("Repository has no working directory".to_string()))?
                 .to_path_buf();
 
-            let head_commit = repo.head()
-                .map_err(|_| AppError::Internal("No HEAD found".to_string()))?
-                .peel_to_commit()
-                .map_err(|_| AppError::Internal("Cannot resolve HEAD to commit".to_string()))?;
-            let head_tree = head_commit.tree()?;
-            let head_oid = head_commit.id().to_string();
+            // Before the first commit, everything is compared against the empty tree
+            let head_tree = resolve_tree_or_head(repo, None)?;
+            let head = head_commit(repo)?.map(|c| c.id());
+            let codeowners = CodeOwners::load(repo, &head_tree);
 
             let mut opts = DiffOptions::new();
-            opts.context_lines(3)
+            opts.context_lines(context_lines)
+                .include_typechange(true)
                 .include_untracked(true)
                 .recurse_untracked_dirs(true);
 
-            if let Some(ref p) = path_owned {
-                if !p.is_empty() {
-                    opts.pathspec(p);
-                }
+            if let Some(ref p) = path_owned
+                && !p.is_empty()
+            {
+                opts.pathspec(p);
             }
 
             let diff = repo.diff_tree_to_workdir_with_index(
@@ -292,26 +603,36 @@ impl GitRepository {
             )?;
 
             let mut files: Vec<FileDiff> = Vec::new();
+            let mut untracked: Vec<UntrackedFile> = Vec::new();
             let mut stats = DiffStats::default();
 
             for (delta_idx, delta) in diff.deltas().enumerate() {
-                let status = match delta.status() {
-                    Delta::Added => DiffStatus::Added,
-                    Delta::Deleted => DiffStatus::Deleted,
-                    Delta::Modified => DiffStatus::Modified,
-                    Delta::Renamed => DiffStatus::Renamed,
-                    Delta::Copied => DiffStatus::Copied,
-                    Delta::Typechange => DiffStatus::TypeChanged,
-                    _ => DiffStatus::Unmodified,
-                };
+                deadline::check()?;
+                if delta.status() == Delta::Untracked {
+                    if let Some(path) = delta.new_file().path() {
+                        let path = path.to_string_lossy().to_string();
+                        let owners = path_owners(codeowners.as_ref(), Some(&path));
+                        untracked.push(untracked_file(repo, &workdir, path, include_contents, owners));
+                    }
+                    continue;
+                }
+
+                let status = delta_status(delta.status());
 
                 let old_path = delta.old_file().path().map(|p| p.to_string_lossy().to_string());
                 let new_path = delta.new_file().path().map(|p| p.to_string_lossy().to_string());
 
-                let is_binary = delta.flags().is_binary();
+                // libgit2 only flags binary files once the blobs are loaded
+                let patch = git2::Patch::from_diff(&diff, delta_idx)?;
+                let attrs = diff_attributes(repo, new_path.as_deref().or(old_path.as_deref()));
+                let is_binary = attrs.binary || patch_is_binary(patch.as_ref(), &delta);
+                let lfs = lfs_diff(repo, &delta, Some(&workdir));
+                let has_text = !is_binary && lfs.is_none();
+                let include_contents = include_contents && !attrs.generated;
+                let encoding = if has_text { delta_encoding(repo, &delta, Some(&workdir)) } else { UTF_8 };
 
                 // Old content from HEAD tree
-                let old_content = if !is_binary {
+                let old_content = if include_contents && has_text {
                     old_path.as_ref().and_then(|p| {
                         get_blob_content(repo, &head_tree, p).ok()
                     })
@@ -320,61 +641,27 @@ impl GitRepository {
                 };
 
                 // New content from working directory
-                let new_content = if !is_binary {
+                let new_content = if include_contents && has_text {
                     new_path.as_ref().and_then(|p| {
-                        let full_path = workdir.join(p);
-                        std::fs::read_to_string(&full_path).ok()
+                        let bytes = std::fs::read(workdir.join(p)).ok()?;
+                        encoding::decode(&bytes).map(|(text, _)| text)
                     })
                 } else {
                     None
                 };
 
-                // Get hunks
-                let mut hunks: Vec<DiffHunk> = Vec::new();
-                let patch = git2::Patch::from_diff(&diff, delta_idx)?;
+                let old_blame = if include_blame && has_text && status != DiffStatus::Added {
+                    head.zip(old_path.as_ref()).and_then(|(head, p)| blame_file_at(repo, p, head))
+                } else {
+                    None
+                };
 
-                if let Some(patch) = patch {
-                    for hunk_idx in 0..patch.num_hunks() {
-                        let (hunk, _) = patch.hunk(hunk_idx)?;
-
-                        let mut lines: Vec<DiffLine> = Vec::new();
-
-                        for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
-                            let line = patch.line_in_hunk(hunk_idx, line_idx)?;
-
-                            let line_type = match line.origin() {
-                                '+' => {
-                                    stats.insertions += 1;
-                                    LineType::Addition
-                                }
-                                '-' => {
-                                    stats.deletions += 1;
-                                    LineType::Deletion
-                                }
-                                ' ' => LineType::Context,
-                                _ => LineType::Header,
-                            };
-
-                            let content = String::from_utf8_lossy(line.content()).to_string();
-
-                            lines.push(DiffLine {
-                                line_type,
-                                old_lineno: line.old_lineno(),
-                                new_lineno: line.new_lineno(),
-                                content,
-                            });
-                        }
-
-                        hunks.push(DiffHunk {
-                            old_start: hunk.old_start(),
-                            old_lines: hunk.old_lines(),
-                            new_start: hunk.new_start(),
-                            new_lines: hunk.new_lines(),
-                            header: String::from_utf8_lossy(hunk.header()).to_string(),
-                            lines,
-                        });
-                    }
-                }
+                let hunks = if has_text {
+                    patch_hunks(repo, patch.as_ref(), context_lines, &mut stats, old_blame.as_ref(), encoding)?
+                } else {
+                    Vec::new()
+                };
+                let owners = path_owners(codeowners.as_ref(), new_path.as_deref().or(old_path.as_deref()));
 
                 files.push(FileDiff {
                     old_path,
@@ -383,9 +670,18 @@ impl GitRepository {
                     hunks,
                     old_content,
                     new_content,
+                    encoding: non_utf8(encoding),
                     is_binary,
+                    is_generated: attrs.generated,
                     authors: Vec::new(),
                     biggest_change_author: None,
+                    image: image_diff(repo, &delta),
+                    binary: is_binary.then(|| binary_diff(repo, patch.as_ref(), &delta, Some(&workdir))),
+                    lfs,
+                    old_mode: file_mode(&delta.old_file()),
+                    new_mode: file_mode(&delta.new_file()),
+                    mode_change: mode_change(&delta),
+                    owners,
                 });
 
                 stats.files_changed += 1;
@@ -394,7 +690,7 @@ impl GitRepository {
             let total_files = files.len();
 
             Ok(DiffResponse {
-                from_commit: Some(head_oid),
+                from_commit: head.map(|oid| oid.to_string()),
                 to_commit: "WORKING_TREE".to_string(),
                 path: path_owned,
                 files,
@@ -402,11 +698,531 @@ impl GitRepository {
                 contributors: Vec::new(),
                 total_files,
                 filtered_files: total_files,
+                untracked,
             })
         })
     }
 }
 
+/// Metadata and, if wanted and textual, contents of an untracked file
+///
+/// Never fails: a file that vanished or can't be read is reported with what
+/// is known about it and no contents.
+fn untracked_file(repo: &Repository, workdir: &Path, path: String, include_contents: bool, owners: Vec<String>) -> UntrackedFile {
+    let full_path = workdir.join(&path);
+    let attrs = diff_attributes(repo, Some(&path));
+    let mut file = UntrackedFile {
+        path,
+        size: 0,
+        is_binary: attrs.binary,
+        is_generated: attrs.generated,
+        is_symlink: false,
+        mime: None,
+        line_count: None,
+        content: None,
+        encoding: None,
+        too_large: false,
+        owners,
+    };
+
+    let Ok(metadata) = std::fs::symlink_metadata(&full_path) else {
+        return file;
+    };
+
+    // A symlink's "content" is its target, as git stores it
+    if metadata.file_type().is_symlink() {
+        file.is_symlink = true;
+        if let Ok(target) = std::fs::read_link(&full_path) {
+            let target = target.to_string_lossy().to_string();
+            file.size = target.len() as u64;
+            file.line_count = Some(1);
+            if include_contents {
+                file.content = Some(target);
+            }
+        }
+        return file;
+    }
+
+    file.size = metadata.len();
+    file.too_large = file.size > MAX_UNTRACKED_CONTENT_BYTES;
+
+    // Oversized files are only probed, enough to tell text from binary
+    let limit = if file.too_large { 8000 } else { MAX_UNTRACKED_CONTENT_BYTES };
+    let mut bytes = Vec::new();
+    let read = std::fs::File::open(&full_path).and_then(|f| {
+        use std::io::Read;
+        f.take(limit).read_to_end(&mut bytes)
+    });
+    if read.is_err() {
+        return file;
+    }
+
+    file.mime = sniff_mime(&bytes).map(|m| m.to_string());
+    // Bytes invalid in their detected encoding count as binary too
+    let decoded = encoding::decode_part(&bytes, false, file.too_large);
+    file.is_binary = file.is_binary || decoded.is_none();
+    let Some((text, text_encoding, _)) = decoded.filter(|_| !file.is_binary && !file.too_large) else {
+        return file;
+    };
+
+    file.line_count = Some(text.lines().count());
+    file.encoding = non_utf8(text_encoding);
+    if include_contents && !file.is_generated {
+        file.content = Some(text);
+    }
+    file
+}
+
+fn delta_status(delta: Delta) -> DiffStatus {
+    match delta {
+        Delta::Added => DiffStatus::Added,
+        Delta::Deleted => DiffStatus::Deleted,
+        Delta::Modified => DiffStatus::Modified,
+        Delta::Renamed => DiffStatus::Renamed,
+        Delta::Copied => DiffStatus::Copied,
+        Delta::Typechange => DiffStatus::TypeChanged,
+        _ => DiffStatus::Unmodified,
+    }
+}
+
+/// Hunks of one delta, adding its line counts to `stats`
+///
+/// libgit2 produces no text for typechanges (file <-> symlink), so those are
+/// diffed from the two blobs; a side only in the working tree has no blob and
+/// leaves the typechange without hunks.
+fn patch_hunks(
+    repo: &Repository,
+    patch: Option<&git2::Patch>,
+    context_lines: u32,
+    stats: &mut DiffStats,
+    old_blame: Option<&git2::Blame>,
+    encoding: &'static Encoding,
+) -> Result<Vec<DiffHunk>> {
+    let Some(patch) = patch else {
+        return Ok(Vec::new());
+    };
+    let delta = patch.delta();
+    if delta.status() != Delta::Typechange {
+        return hunks_of(patch, stats, old_blame, encoding);
+    }
+
+    let blob = |file: &git2::DiffFile| repo.find_blob(file.id()).ok();
+    let (old_file, new_file) = (delta.old_file(), delta.new_file());
+    let (Some(old_blob), Some(new_blob)) = (blob(&old_file), blob(&new_file)) else {
+        return Ok(Vec::new());
+    };
+    let mut opts = DiffOptions::new();
+    opts.context_lines(context_lines);
+    let blob_patch = git2::Patch::from_blobs(
+        &old_blob,
+        old_file.path(),
+        &new_blob,
+        new_file.path(),
+        Some(&mut opts),
+    )?;
+    hunks_of(&blob_patch, stats, old_blame, encoding)
+}
+
+/// Whether a delta is binary; the patch's copy of the delta carries the flag
+/// libgit2 sets while loading the blobs
+fn patch_is_binary(patch: Option<&git2::Patch>, delta: &git2::DiffDelta) -> bool {
+    patch.map_or(delta.flags().is_binary(), |p| p.delta().flags().is_binary())
+}
+
+/// Hunks of a patch, adding its line counts to `stats`; line text is
+/// transcoded from `encoding`
+fn hunks_of(
+    patch: &git2::Patch,
+    stats: &mut DiffStats,
+    old_blame: Option<&git2::Blame>,
+    encoding: &'static Encoding,
+) -> Result<Vec<DiffHunk>> {
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+
+    for hunk_idx in 0..patch.num_hunks() {
+        deadline::check()?;
+        let (hunk, _) = patch.hunk(hunk_idx)?;
+
+        let mut lines: Vec<DiffLine> = Vec::new();
+
+        for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
+            let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+
+            let line_type = match line.origin() {
+                '+' => {
+                    stats.insertions += 1;
+                    LineType::Addition
+                }
+                '-' => {
+                    stats.deletions += 1;
+                    LineType::Deletion
+                }
+                ' ' => LineType::Context,
+                _ => LineType::Header,
+            };
+
+            let content = encoding::decode_with(line.content(), encoding).into_owned();
+
+            lines.push(DiffLine {
+                line_type,
+                old_lineno: line.old_lineno(),
+                new_lineno: line.new_lineno(),
+                content,
+                blame: line_blame(old_blame, &line),
+                spans: Vec::new(),
+            });
+        }
+        mark_changed_spans(&mut lines);
+
+        hunks.push(DiffHunk {
+            old_start: hunk.old_start(),
+            old_lines: hunk.old_lines(),
+            new_start: hunk.new_start(),
+            new_lines: hunk.new_lines(),
+            header: encoding::decode_with(hunk.header(), encoding).into_owned(),
+            lines,
+        });
+    }
+
+    Ok(hunks)
+}
+
+/// Non-merge commits of an `A..B` range, oldest first
+pub fn range_commits<'r>(repo: &'r Repository, range: &str) -> Result<Vec<git2::Commit<'r>>> {
+    let (from, to) = range
+        .split_once("..")
+        .filter(|(_, to)| !to.starts_with('.'))
+        .ok_or_else(|| AppError::BadRequest(format!("Expected a range like main..feature, got '{}'", range)))?;
+    let from = resolve_commit(repo, if from.is_empty() { "HEAD" } else { from })?;
+    let to = resolve_commit(repo, if to.is_empty() { "HEAD" } else { to })?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+    revwalk.push(to.id())?;
+    revwalk.hide(from.id())?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        if commits.len() == MAX_RANGE_DIFF_COMMITS {
+            return Err(AppError::BadRequest(format!(
+                "Range {} has more than {} commits",
+                range, MAX_RANGE_DIFF_COMMITS
+            )));
+        }
+        commits.push(commit);
+    }
+    Ok(commits)
+}
+
+/// A commit's message and patch as compared by range-diff
+///
+/// Blob OIDs and hunk line numbers are left out, so a commit that was only
+/// rebased onto a different base compares equal to its old version.
+fn commit_patch_text(repo: &Repository, commit: &git2::Commit) -> Result<String> {
+    let mut text = String::new();
+    for line in commit.message().unwrap_or("").trim().lines() {
+        text.push_str("    ");
+        text.push_str(line);
+        text.push('\n');
+    }
+    text.push('\n');
+
+    let tree = commit.tree()?;
+    let parent_tree = match commit.parent_count() {
+        0 => None,
+        _ => Some(commit.parent(0)?.tree()?),
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        let content = String::from_utf8_lossy(line.content());
+        match line.origin() {
+            'F' => {
+                for header in content.lines().filter(|l| !l.starts_with("index ")) {
+                    text.push_str(header);
+                    text.push('\n');
+                }
+            }
+            'H' => {
+                // "@@ -12,7 +12,8 @@ fn name" -> "@@ fn name"
+                let section = content.splitn(3, "@@").nth(2).unwrap_or("").trim_end();
+                text.push_str("@@");
+                text.push_str(section);
+                text.push('\n');
+            }
+            origin @ ('+' | '-' | ' ') => {
+                text.push(origin);
+                text.push_str(&content);
+            }
+            _ => text.push_str(&content),
+        }
+        true
+    })?;
+    Ok(text)
+}
+
+/// Number of added plus removed lines between two texts
+fn text_diff_size(old: &str, new: &str) -> Result<i64> {
+    if old == new {
+        return Ok(0);
+    }
+    let patch = git2::Patch::from_buffers(old.as_bytes(), None, new.as_bytes(), None, None)?;
+    let (_, additions, deletions) = patch.line_stats()?;
+    Ok((additions + deletions) as i64)
+}
+
+/// Column assigned to each row of a square cost matrix, minimizing the total
+/// cost (Hungarian algorithm with potentials, O(n^3))
+fn min_cost_assignment(cost: &[Vec<i64>]) -> Vec<usize> {
+    let n = cost.len();
+    // 1-based internally; row 0 / column 0 are the virtual start
+    let mut u = vec![0i64; n + 1];
+    let mut v = vec![0i64; n + 1];
+    let mut row_of = vec![0usize; n + 1];
+    let mut way = vec![0usize; n + 1];
+
+    for row in 1..=n {
+        row_of[0] = row;
+        let mut col0 = 0;
+        let mut min_slack = vec![i64::MAX; n + 1];
+        let mut used = vec![false; n + 1];
+        loop {
+            used[col0] = true;
+            let r = row_of[col0];
+            let mut delta = i64::MAX;
+            let mut col1 = 0;
+            for col in 1..=n {
+                if used[col] {
+                    continue;
+                }
+                let reduced = cost[r - 1][col - 1] - u[r] - v[col];
+                if reduced < min_slack[col] {
+                    min_slack[col] = reduced;
+                    way[col] = col0;
+                }
+                if min_slack[col] < delta {
+                    delta = min_slack[col];
+                    col1 = col;
+                }
+            }
+            for col in 0..=n {
+                if used[col] {
+                    u[row_of[col]] += delta;
+                    v[col] -= delta;
+                } else {
+                    min_slack[col] -= delta;
+                }
+            }
+            col0 = col1;
+            if row_of[col0] == 0 {
+                break;
+            }
+        }
+        // Flip the augmenting path
+        loop {
+            let prev = way[col0];
+            row_of[col0] = row_of[prev];
+            col0 = prev;
+            if col0 == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut assignment = vec![0; n];
+    for col in 1..=n {
+        if row_of[col] > 0 {
+            assignment[row_of[col] - 1] = col - 1;
+        }
+    }
+    assignment
+}
+
+/// CODEOWNERS owners of a changed file, empty without a CODEOWNERS file
+fn path_owners(codeowners: Option<&CodeOwners>, path: Option<&str>) -> Vec<String> {
+    codeowners
+        .zip(path)
+        .map(|(codeowners, path)| codeowners.owners_of(path, false))
+        .unwrap_or_default()
+}
+
+/// Octal mode of one side of a delta ("100644", "100755", "120000"), `None`
+/// when the file doesn't exist on that side
+fn file_mode(file: &git2::DiffFile) -> Option<String> {
+    file.exists().then(|| format!("{:06o}", u32::from(file.mode())))
+}
+
+/// How the mode changed between two existing sides, `None` if it didn't
+fn mode_change(delta: &git2::DiffDelta) -> Option<ModeChange> {
+    use git2::FileMode;
+
+    let (old_file, new_file) = (delta.old_file(), delta.new_file());
+    if !old_file.exists() || !new_file.exists() {
+        return None;
+    }
+    let (old, new) = (old_file.mode(), new_file.mode());
+    let is_executable = |mode| mode == FileMode::BlobExecutable;
+    let is_regular = |mode| matches!(mode, FileMode::Blob | FileMode::BlobGroupWritable);
+    Some(match (old, new) {
+        _ if old == new => return None,
+        (_, FileMode::Link) => ModeChange::ToSymlink,
+        (FileMode::Link, _) => ModeChange::FromSymlink,
+        _ if is_regular(old) && is_executable(new) => ModeChange::ExecutableAdded,
+        _ if is_executable(old) && is_regular(new) => ModeChange::ExecutableRemoved,
+        // Regular -> group-writable is the same file to git; anything else
+        // (e.g. a file replaced by a submodule) has no dedicated flag
+        _ if is_regular(old) && is_regular(new) => return None,
+        _ => ModeChange::Other,
+    })
+}
+
+/// Old/new image metadata for a delta, or `None` if neither side is an image
+fn image_diff(repo: &Repository, delta: &git2::DiffDelta) -> Option<ImageDiff> {
+    let side = |file: git2::DiffFile| file.path().and_then(|p| image_version(repo, p, file.id()));
+    let old = side(delta.old_file());
+    let new = side(delta.new_file());
+    (old.is_some() || new.is_some()).then_some(ImageDiff { old, new })
+}
+
+/// Blob OIDs and sizes of both sides of a binary delta
+///
+/// Working tree files (`workdir` given) may not be hashed by the diff yet, so
+/// their OID and size are taken from the file itself.
+fn binary_diff(
+    repo: &Repository,
+    patch: Option<&git2::Patch>,
+    delta: &git2::DiffDelta,
+    workdir: Option<&Path>,
+) -> BinaryDiff {
+    let patched = patch.map(|p| p.delta());
+    let delta = patched.as_ref().unwrap_or(delta);
+    let side = |file: git2::DiffFile| -> Option<BinaryVersion> {
+        if !file.exists() {
+            return None;
+        }
+        if !file.id().is_zero()
+            && let Ok((size, _)) = repo.odb().and_then(|odb| odb.read_header(file.id()))
+        {
+            return Some(BinaryVersion { oid: file.id().to_string(), size: size as u64 });
+        }
+        let full_path = workdir?.join(file.path()?);
+        let oid = git2::Oid::hash_file(git2::ObjectType::Blob, &full_path).ok()?;
+        let size = std::fs::metadata(&full_path).ok()?.len();
+        Some(BinaryVersion { oid: oid.to_string(), size })
+    };
+
+    let old = side(delta.old_file());
+    let new = side(delta.new_file());
+    let size_of = |v: &Option<BinaryVersion>| v.as_ref().map_or(0, |v| v.size as i64);
+    BinaryDiff {
+        size_delta: size_of(&new) - size_of(&old),
+        old,
+        new,
+    }
+}
+
+/// LFS pointers on each side of a delta, `None` unless at least one side is one
+///
+/// `workdir` is set when the new side is the working tree, whose file holds
+/// the pointer only if LFS never replaced it with the content.
+fn lfs_diff(repo: &Repository, delta: &git2::DiffDelta, workdir: Option<&Path>) -> Option<LfsDiff> {
+    let old = blob_pointer(repo, delta.old_file().id());
+    let new = match workdir {
+        Some(workdir) => delta.new_file().path().and_then(|p| workdir_pointer(repo, &workdir.join(p))),
+        None => blob_pointer(repo, delta.new_file().id()),
+    };
+    if old.is_none() && new.is_none() {
+        return None;
+    }
+
+    // A side that isn't a pointer counts with its own size
+    let size = |file: git2::DiffFile, workdir: Option<&Path>| -> i64 {
+        if !file.exists() {
+            return 0;
+        }
+        match workdir {
+            Some(workdir) => file.path()
+                .and_then(|p| std::fs::metadata(workdir.join(p)).ok())
+                .map_or(0, |m| m.len() as i64),
+            None => repo.odb()
+                .and_then(|odb| odb.read_header(file.id()))
+                .map_or(0, |(size, _)| size as i64),
+        }
+    };
+    let old_size = old.as_ref().map_or_else(|| size(delta.old_file(), None), |p| p.size as i64);
+    let new_size = new.as_ref().map_or_else(|| size(delta.new_file(), workdir), |p| p.size as i64);
+
+    Some(LfsDiff {
+        old,
+        new,
+        size_delta: new_size - old_size,
+    })
+}
+
+/// Image metadata for a blob whose path has an image extension
+///
+/// Returns `None` for non-images and for blobs not in the object database
+/// (the missing side of an add/delete, or a working tree file).
+fn image_version(repo: &Repository, path: &Path, oid: git2::Oid) -> Option<ImageVersion> {
+    let guessed = mime_guess::from_path(path).first()?;
+    if guessed.type_() != mime_guess::mime::IMAGE || oid.is_zero() {
+        return None;
+    }
+
+    let blob = repo.find_blob(oid).ok()?;
+    let content = blob.content();
+    // Trust the bytes over the extension when they identify an image
+    let mime = sniff_mime(content)
+        .filter(|m| m.starts_with("image/"))
+        .map(str::to_string)
+        .unwrap_or_else(|| guessed.to_string());
+    let dimensions = image_dimensions(content);
+
+    Some(ImageVersion {
+        oid: oid.to_string(),
+        mime,
+        size: blob.size() as u64,
+        width: dimensions.map(|(w, _)| w),
+        height: dimensions.map(|(_, h)| h),
+    })
+}
+
+/// All directories containing `file_path`, from the root ("") down to its parent
+fn ancestor_directories(file_path: &str) -> Vec<&str> {
+    let mut dirs = vec![""];
+    for (idx, _) in file_path.match_indices('/') {
+        dirs.push(&file_path[..idx]);
+    }
+    dirs
+}
+
+/// Blame a file as of `commit`, or `None` if it can't be blamed there
+fn blame_file_at<'r>(repo: &'r Repository, path: &str, commit: git2::Oid) -> Option<git2::Blame<'r>> {
+    let mut opts = git2::BlameOptions::new();
+    opts.newest_commit(commit).use_mailmap(true);
+    repo.blame_file(Path::new(path), Some(&mut opts)).ok()
+}
+
+/// Blame data for a context or deletion line, looked up by its old line number
+fn line_blame(blame: Option<&git2::Blame>, line: &git2::DiffLine) -> Option<LineBlame> {
+    if !matches!(line.origin(), ' ' | '-') {
+        return None;
+    }
+    let hunk = blame?.get_line(line.old_lineno()? as usize)?;
+    let sig = hunk.final_signature();
+
+    Some(LineBlame {
+        commit_oid: hunk.final_commit_id().to_string(),
+        author_name: sig.name().unwrap_or("Unknown").to_string(),
+        author_email: sig.email().unwrap_or("").to_string(),
+        timestamp: sig.when().seconds(),
+    })
+}
+
 fn get_blob_content(repo: &Repository, tree: &git2::Tree, path: &str) -> Result<String> {
     let entry = tree.get_path(Path::new(path))
         .map_err(|_| AppError::PathNotFound(path.to_string()))?;
@@ -415,8 +1231,31 @@ fn get_blob_content(repo: &Repository, tree: &git2::Tree, path: &str) -> Result<
     let blob = obj.as_blob()
         .ok_or_else(|| AppError::InvalidPath(format!("{} is not a file", path)))?;
 
-    String::from_utf8(blob.content().to_vec())
-        .map_err(|_| AppError::Internal("File is not valid UTF-8".to_string()))
+    encoding::decode(blob.content())
+        .map(|(text, _)| text)
+        .ok_or_else(|| AppError::InvalidPath(format!("{} is not a text file", path)))
+}
+
+/// Encoding of a text delta's lines, detected from its new side (the old one
+/// for deletions); `workdir` is set when the new side is the working tree
+fn delta_encoding(repo: &Repository, delta: &git2::DiffDelta, workdir: Option<&Path>) -> &'static Encoding {
+    let blob_encoding = |file: git2::DiffFile| repo.find_blob(file.id()).ok().map(|blob| encoding::detect(blob.content()));
+    let new_file = delta.new_file();
+    let detected = if !new_file.exists() {
+        blob_encoding(delta.old_file())
+    } else if let Some(workdir) = workdir {
+        new_file.path()
+            .and_then(|p| std::fs::read(workdir.join(p)).ok())
+            .map(|bytes| encoding::detect(&bytes))
+    } else {
+        blob_encoding(new_file)
+    };
+    detected.unwrap_or(UTF_8)
+}
+
+/// `FileDiff::encoding` / `UntrackedFile::encoding`: the encoding's name unless it is UTF-8
+fn non_utf8(encoding: &'static Encoding) -> Option<String> {
+    (encoding != UTF_8).then(|| encoding.name().to_string())
 }
 
 /// Track author info for a specific file during intermediate commits analysis
@@ -445,13 +1284,15 @@ fn get_file_authors_between_commits(
     if let Some(from) = from_oid {
         revwalk.hide(from)?;
     }
+    let mailmap = mailmap::load(repo);
 
     for oid_result in revwalk {
+        deadline::check()?;
         let oid = oid_result?;
         let commit = repo.find_commit(oid)?;
 
         // Get author info
-        let author = commit.author();
+        let author = mailmap::author(&commit, mailmap.as_ref());
         let author_email = author.email().unwrap_or("").to_string();
         let author_name = author.name().unwrap_or("Unknown").to_string();
         let timestamp = commit.time().seconds();
@@ -467,10 +1308,10 @@ fn get_file_authors_between_commits(
 
         // Diff this commit against its parent
         let mut diff_opts = DiffOptions::new();
-        if let Some(p) = path_filter {
-            if !p.is_empty() {
-                diff_opts.pathspec(p);
-            }
+        if let Some(p) = path_filter
+            && !p.is_empty()
+        {
+            diff_opts.pathspec(p);
         }
 
         let diff = repo.diff_tree_to_tree(
@@ -486,7 +1327,7 @@ fn get_file_authors_between_commits(
                 .map(|p| p.to_string_lossy().to_string());
 
             if let Some(path) = file_path {
-                let author_map = file_authors.entry(path).or_insert_with(HashMap::new);
+                let author_map = file_authors.entry(path).or_default();
 
                 let entry = author_map.entry(author_email.clone()).or_insert_with(|| AuthorCommitInfo {
                     email: author_email.clone(),
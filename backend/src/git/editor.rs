@@ -0,0 +1,95 @@
+//! Launching the user's local editor on a working tree file.
+//!
+//! The editor command comes from `$GIT_VIEWER_EDITOR`, `$VISUAL` or `$EDITOR`
+//! (falling back to VS Code's `code`). Known editors get their own "jump to
+//! line" syntax; unknown ones are just handed the file.
+//!
+//! Supports: POST /api/v1/repository/open-in-editor (opt-in via --open-in-editor)
+
+use std::path::{Component, Path};
+use std::process::{Command, Stdio};
+
+use crate::error::{AppError, Result};
+use crate::git::repository::GitRepository;
+use crate::models::OpenInEditorResponse;
+
+/// Resolve the editor command from the environment
+pub fn default_editor() -> String {
+    ["GIT_VIEWER_EDITOR", "VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "code".to_string())
+}
+
+impl GitRepository {
+    /// Open a working tree file in the configured editor, optionally at a line
+    pub fn open_in_editor(&self, path: &str, line: Option<u32>) -> Result<OpenInEditorResponse> {
+        let editor = self.editor.as_deref().ok_or_else(|| {
+            AppError::Forbidden("Opening files in an editor is disabled (start with --open-in-editor)".to_string())
+        })?;
+
+        let relative = Path::new(path);
+        if path.is_empty()
+            || relative.components().any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(AppError::InvalidPath(path.to_string()));
+        }
+
+        let full_path = self.with_repo(|repo| {
+            repo.workdir()
+                .map(|workdir| workdir.join(relative))
+                .ok_or_else(|| AppError::BadRequest("Repository has no working tree".to_string()))
+        })?;
+        if !full_path.is_file() {
+            return Err(AppError::PathNotFound(path.to_string()));
+        }
+
+        let mut parts = editor.split_whitespace();
+        let program = parts.next().ok_or_else(|| AppError::Internal("Empty editor command".to_string()))?;
+        let mut command = Command::new(program);
+        command.args(parts);
+        command.args(editor_args(program, &full_path.to_string_lossy(), line));
+
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| AppError::Internal(format!("Failed to launch editor '{}': {}", program, e)))?;
+
+        // Reap the editor when it exits so it doesn't linger as a zombie
+        std::thread::spawn(move || {
+            let _ = child.wait();
+        });
+
+        Ok(OpenInEditorResponse {
+            path: path.to_string(),
+            line,
+            editor: editor.to_string(),
+        })
+    }
+}
+
+/// Arguments that open `file` at `line` for the given editor program
+fn editor_args(program: &str, file: &str, line: Option<u32>) -> Vec<String> {
+    let Some(line) = line else {
+        return vec![file.to_string()];
+    };
+
+    let name = Path::new(program)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match name.as_str() {
+        "code" | "code-insiders" | "codium" | "cursor" => {
+            vec!["--goto".to_string(), format!("{}:{}", file, line)]
+        }
+        "subl" | "zed" | "hx" | "helix" => vec![format!("{}:{}", file, line)],
+        "vi" | "vim" | "nvim" | "nano" | "emacs" | "emacsclient" | "micro" | "kak" | "gedit" => {
+            vec![format!("+{}", line), file.to_string()]
+        }
+        _ => vec![file.to_string()],
+    }
+}
@@ -0,0 +1,101 @@
+//! Text encodings of files that aren't UTF-8.
+//!
+//! Repositories hold Latin-1, Windows-1252, Shift-JIS and other legacy
+//! encoded files. Rather than refusing them, their encoding is detected (a
+//! byte order mark, else `chardetng`'s guess from the bytes, the way browsers
+//! guess for unlabeled pages) and they are transcoded to UTF-8.
+//!
+//! - `detect()`: Encoding of some bytes: UTF-8 when valid, else by BOM or guess
+//! - `decode()`: Bytes as text in their detected encoding; `None` for binary content
+//! - `decode_part()`: Same for bytes cut out of a longer file
+//! - `decode_with()`: Bytes as text in a given encoding, invalid sequences replaced
+//!
+//! Encodings are named by their WHATWG labels (`Shift_JIS`, `windows-1252`),
+//! which the browser's `TextDecoder` accepts as well.
+//!
+//! Used by: file content (base64 for binary files) and diff contents and hunks
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+use std::borrow::Cow;
+
+/// Leading bytes looked at to detect the encoding, and to find NUL bytes
+/// marking binary content, as git does
+const PROBE_LEN: usize = 8000;
+
+/// `FileContent::encoding` of binary content, which is sent base64-encoded
+pub const BASE64: &str = "base64";
+
+/// Longest character in any encoding detected, in bytes (GB18030)
+const MAX_CHAR_LEN: usize = 4;
+
+/// Encoding of `bytes`: UTF-8 when they are valid UTF-8 (a character cut off
+/// at the end included), else the one named by a byte order mark or guessed
+pub fn detect(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => UTF_8,
+        Err(e) if e.error_len().is_none() => UTF_8,
+        Err(_) => {
+            let mut detector = EncodingDetector::new();
+            let probe = &bytes[..bytes.len().min(PROBE_LEN)];
+            detector.feed(probe, probe.len() == bytes.len());
+            detector.guess(None, false)
+        }
+    }
+}
+
+/// `bytes` as text in their detected encoding, with the encoding
+///
+/// `None` for binary content: NUL bytes outside UTF-16, or bytes invalid in
+/// the encoding guessed. A byte order mark is dropped.
+pub fn decode(bytes: &[u8]) -> Option<(String, &'static Encoding)> {
+    decode_part(bytes, false, false).map(|(text, encoding, _)| (text, encoding))
+}
+
+/// `decode()` for bytes cut out of a longer file, at the front with
+/// `cut_front` and at the back with `cut_back`; characters split by a cut
+/// edge are dropped
+///
+/// Returns the text, its encoding and how many bytes were dropped at the front
+/// and back.
+pub fn decode_part(bytes: &[u8], cut_front: bool, cut_back: bool) -> Option<(String, &'static Encoding, (usize, usize))> {
+    let encoding = detect(bytes);
+    if is_binary(bytes, encoding) {
+        return None;
+    }
+
+    // Only multi-byte encodings can split a character; try the edges with up
+    // to one character's worth of bytes dropped
+    let max_drop = if encoding.is_single_byte() { 0 } else { MAX_CHAR_LEN - 1 };
+    for front in 0..=if cut_front { max_drop } else { 0 } {
+        for back in 0..=if cut_back { max_drop } else { 0 } {
+            let Some(part) = bytes.get(front..bytes.len().saturating_sub(back)) else {
+                continue;
+            };
+            let decoded = match (front, Encoding::for_bom(part)) {
+                (0, Some((bom_encoding, bom_len))) if bom_encoding == encoding => {
+                    encoding.decode_without_bom_handling_and_without_replacement(&part[bom_len..])
+                }
+                _ => encoding.decode_without_bom_handling_and_without_replacement(part),
+            };
+            if let Some(text) = decoded {
+                return Some((text.into_owned(), encoding, (front, back)));
+            }
+        }
+    }
+    None
+}
+
+/// `bytes` as text in `encoding`, invalid sequences replaced with U+FFFD
+pub fn decode_with<'a>(bytes: &'a [u8], encoding: &'static Encoding) -> Cow<'a, str> {
+    encoding.decode_without_bom_handling(bytes).0
+}
+
+/// NUL bytes in the probe, which UTF-16 text has plenty of
+fn is_binary(bytes: &[u8], encoding: &'static Encoding) -> bool {
+    let utf16 = encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE;
+    !utf16 && bytes[..bytes.len().min(PROBE_LEN)].contains(&0)
+}
@@ -0,0 +1,215 @@
+//! Noticing repository changes made outside the viewer.
+//!
+//! - `RepoStamp`: File stamps of HEAD, the refs (loose and packed) and the index
+//! - `LooseRefs`: The loose refs' stamp, kept between requests
+//! - `sync_external_changes()`: Compare with the last stamp and drop what went stale
+//!
+//! A fetch, a branch created in a terminal or a file staged in an IDE only
+//! shows up on disk, and the long-lived repository handle and the caches
+//! built from it don't see it on their own. Every such write replaces one of
+//! these files, so stat-ing them (size, modification time) is enough to
+//! notice; nothing is read unless a stamp moved. Loose refs are found by
+//! walking `refs/`, but only when a directory below it has a new
+//! modification time: git writes a ref by renaming a lock file over it, which
+//! touches the directory holding it, so the walk is skipped while nothing
+//! changed.
+//!
+//! What goes stale per change:
+//! - HEAD or refs: the commit cache, when HEAD no longer resolves to the
+//!   commit it was built from (a fetch alone leaves it valid)
+//! - index: the handle's in-memory index, reloaded from disk
+//!
+//! Supports frontend: history, branches and status reflecting outside changes without a restart
+
+use git2::Repository;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::error::{AppError, Result};
+use crate::git::repository::GitRepository;
+use crate::models::ChangeKind;
+
+/// Fingerprints of the files git rewrites when HEAD, refs or the index change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepoStamp {
+    pub head: u64,
+    /// `packed-refs` and every loose ref under `refs/`
+    pub refs: u64,
+    pub index: u64,
+}
+
+impl RepoStamp {
+    pub fn read(repo: &Repository, loose_refs: &mut LooseRefs) -> Self {
+        // Linked worktrees have their own HEAD and index but share the refs
+        let git_dir = repo.path();
+        let common_dir = repo.commondir();
+
+        let mut refs = DefaultHasher::new();
+        hash_file(&common_dir.join("packed-refs"), &mut refs);
+        loose_refs.stamp(&common_dir.join("refs")).hash(&mut refs);
+
+        Self {
+            head: file_stamp(&git_dir.join("HEAD")),
+            refs: refs.finish(),
+            index: file_stamp(&git_dir.join("index")),
+        }
+    }
+
+    /// What changed between `previous` and this stamp
+    pub fn changes_since(&self, previous: &RepoStamp) -> Vec<ChangeKind> {
+        let mut kinds = Vec::new();
+        if self.head != previous.head {
+            kinds.push(ChangeKind::Head);
+        }
+        if self.refs != previous.refs {
+            kinds.push(ChangeKind::Refs);
+        }
+        if self.index != previous.index {
+            kinds.push(ChangeKind::Index);
+        }
+        kinds
+    }
+}
+
+impl GitRepository {
+    /// Stat the repository and invalidate whatever changed since the last call
+    ///
+    /// Called with the repository lock held, before anything is read from the
+    /// handle or the caches. The first call only records the stamp.
+    pub fn sync_external_changes(&self, repo: &Repository) -> Result<Vec<ChangeKind>> {
+        let stamp = {
+            let mut loose_refs = self.loose_refs
+                .lock()
+                .map_err(|_| AppError::Internal("Loose refs lock poisoned".to_string()))?;
+            RepoStamp::read(repo, &mut loose_refs)
+        };
+        let previous = self.stamp
+            .lock()
+            .map_err(|_| AppError::Internal("Stamp lock poisoned".to_string()))?
+            .replace(stamp);
+        let Some(previous) = previous else {
+            return Ok(Vec::new());
+        };
+
+        let changes = stamp.changes_since(&previous);
+        if changes.is_empty() {
+            return Ok(changes);
+        }
+        tracing::debug!("Repository changed on disk: {:?}", changes);
+
+        if changes.contains(&ChangeKind::Index) {
+            repo.index()?.read(false)?;
+        }
+
+        if changes.contains(&ChangeKind::Head) || changes.contains(&ChangeKind::Refs) {
+            let mut cache = self.cache.lock().map_err(|_| AppError::Internal("Cache lock poisoned".to_string()))?;
+            if cache.as_ref().is_some_and(|c| !c.is_valid(repo)) {
+                tracing::info!("HEAD moved on disk, dropping commit cache");
+                *cache = None;
+            }
+        }
+
+        Ok(changes)
+    }
+}
+
+/// Size and modification time of `path`; a missing file has a stamp of its own
+fn file_stamp(path: &Path) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hash_file(path, &mut hasher);
+    hasher.finish()
+}
+
+/// Feed `path`'s size and modification time into `hasher`
+pub fn hash_file(path: &Path, hasher: &mut DefaultHasher) {
+    if let Ok(metadata) = std::fs::metadata(path) {
+        metadata.len().hash(hasher);
+        metadata.modified().ok().hash(hasher);
+    }
+}
+
+/// Loose refs as of the last walk of `refs/`
+#[derive(Debug, Default)]
+pub struct LooseRefs {
+    /// Every directory walked, with its modification time before the walk read it
+    dirs: Vec<(PathBuf, Option<SystemTime>)>,
+    stamp: u64,
+}
+
+impl LooseRefs {
+    /// Stamp of the loose refs below `root`, walking them again only if a
+    /// directory was modified since the last walk
+    pub fn stamp(&mut self, root: &Path) -> u64 {
+        let unchanged = !self.dirs.is_empty() && self.dirs.iter().all(|(dir, modified)| dir_modified(dir) == *modified);
+        if !unchanged {
+            self.dirs.clear();
+            let mut hasher = DefaultHasher::new();
+            hash_loose_refs(root, &mut hasher, &mut self.dirs);
+            self.stamp = hasher.finish();
+        }
+        self.stamp
+    }
+}
+
+fn dir_modified(dir: &Path) -> Option<SystemTime> {
+    std::fs::metadata(dir).and_then(|m| m.modified()).ok()
+}
+
+/// Stamps of all loose refs below `dir`, by path, recording each directory
+/// in `dirs`; `.lock` files of writes in progress are skipped
+fn hash_loose_refs(dir: &Path, hasher: &mut DefaultHasher, dirs: &mut Vec<(PathBuf, Option<SystemTime>)>) {
+    // Taken before reading, so a write during the walk shows up next time
+    dirs.push((dir.to_path_buf(), dir_modified(dir)));
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<_> = entries.filter_map(|e| e.ok()).collect();
+    // Directory order isn't stable across writes
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            entry.file_name().hash(hasher);
+            hash_loose_refs(&path, hasher, dirs);
+        } else if path.extension().is_none_or(|ext| ext != "lock") {
+            entry.file_name().hash(hasher);
+            hash_file(&path, hasher);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo::TestRepo;
+
+    #[test]
+    fn loose_ref_stamp_moves_only_when_a_ref_is_written() {
+        let mut test = TestRepo::new();
+        let first = test.commit(("Ada", "ada@example.com"), "a.txt", "a\n", "first");
+        let refs = test.repo.path().join("refs");
+        let mut loose_refs = LooseRefs::default();
+
+        let initial = loose_refs.stamp(&refs);
+        assert_eq!(loose_refs.stamp(&refs), initial);
+
+        // A ref in a new directory touches its parent
+        test.repo.branch("feature/login", &test.repo.find_commit(first).unwrap(), false).unwrap();
+        let branched = loose_refs.stamp(&refs);
+        assert_ne!(branched, initial);
+        assert!(loose_refs.dirs.iter().any(|(dir, _)| dir.ends_with("heads/feature")));
+
+        // Moving an existing ref renames a lock file over it
+        let second = test.commit(("Ada", "ada@example.com"), "a.txt", "b\n", "second");
+        test.repo.find_branch("feature/login", git2::BranchType::Local).unwrap()
+            .get_mut()
+            .set_target(second, "move")
+            .unwrap();
+        let moved = loose_refs.stamp(&refs);
+        assert_ne!(moved, branched);
+        assert_eq!(loose_refs.stamp(&refs), moved);
+    }
+}
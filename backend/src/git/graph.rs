@@ -0,0 +1,221 @@
+//! Commit graph export.
+//!
+//! Walks the commit DAG for a ref range (`to` reachable, `from` hidden) and
+//! either renders it as Graphviz DOT (one node per commit, edges from child to
+//! parent, branch/tag names attached to the commits they point at) or lays it
+//! out for a gitk-style graph.
+//!
+//! Layout: commits come in topological order, one row each. Every lane holds
+//! the commit it is waiting for, and no two lanes wait for the same one, so a
+//! commit always lands in the single lane expecting it (or a free lane for a
+//! branch tip). Its first parent takes over its lane unless another lane
+//! already expects that parent; further parents join the lane expecting them
+//! or open a new one. Each row lists the line segments down to the next row,
+//! including lanes passing by. Parents outside the walked range get no lane.
+//!
+//! Supports: documentation and teaching about branching models (`dot -Tsvg`),
+//! the branch graph next to the commit list
+
+use git2::{Commit, Oid, Repository, Sort};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::error::Result;
+use crate::git::mailmap;
+use crate::git::repository::{head_commit, resolve_commit, GitRepository};
+use crate::models::{GraphCommit, GraphEdge, GraphResponse};
+
+impl GitRepository {
+    /// Render the commit DAG between `from` (exclusive) and `to` (inclusive, default HEAD) as DOT
+    pub fn get_graph_dot(&self, from: Option<&str>, to: Option<&str>, limit: usize) -> Result<String> {
+        self.with_repo(|repo| {
+            let commits = walk_graph(repo, from, to, limit)?;
+            let included: HashSet<Oid> = commits.iter().map(|c| c.id()).collect();
+
+            let mut dot = String::new();
+            let _ = writeln!(dot, "digraph commits {{");
+            let _ = writeln!(dot, "  rankdir=BT;");
+            let _ = writeln!(dot, "  node [shape=box, fontname=\"monospace\", fontsize=10];");
+
+            for commit in &commits {
+                let summary = commit.summary().unwrap_or("");
+                let _ = writeln!(
+                    dot,
+                    "  \"{}\" [label=\"{}\\n{}\"];",
+                    commit.id(),
+                    short_oid(commit.id()),
+                    escape_label(summary)
+                );
+            }
+
+            for commit in &commits {
+                for parent_id in commit.parent_ids() {
+                    if included.contains(&parent_id) {
+                        let _ = writeln!(dot, "  \"{}\" -> \"{}\";", commit.id(), parent_id);
+                    }
+                }
+            }
+
+            for (name, target) in ref_labels(repo, &included)? {
+                let _ = writeln!(
+                    dot,
+                    "  \"ref:{}\" [shape=note, style=filled, fillcolor=lightyellow, label=\"{}\"];",
+                    escape_label(&name),
+                    escape_label(&name)
+                );
+                let _ = writeln!(dot, "  \"ref:{}\" -> \"{}\" [style=dashed, arrowhead=none];", escape_label(&name), target);
+            }
+
+            let _ = writeln!(dot, "}}");
+            Ok(dot)
+        })
+    }
+
+    /// Commits between `from` (exclusive) and `to` (inclusive, default HEAD)
+    /// in topological order, with lane assignments for drawing the graph
+    pub fn get_graph_layout(&self, from: Option<&str>, to: Option<&str>, limit: usize) -> Result<GraphResponse> {
+        let _op = self.op_log.start(
+            "graph_layout",
+            format!("from={} to={} limit={}", from.unwrap_or(""), to.unwrap_or("HEAD"), limit),
+        );
+        self.with_repo(|repo| {
+            let mut commits = walk_graph(repo, from, to, limit.saturating_add(1))?;
+            let has_more = commits.len() > limit;
+            commits.truncate(limit);
+
+            let included: HashSet<Oid> = commits.iter().map(|c| c.id()).collect();
+            let mut refs: HashMap<Oid, Vec<String>> = HashMap::new();
+            for (name, target) in ref_labels(repo, &included)? {
+                refs.entry(target).or_default().push(name);
+            }
+            let mailmap = mailmap::load(repo);
+
+            // The commit each lane waits for; `None` marks a free lane
+            let mut lanes: Vec<Option<Oid>> = Vec::new();
+            let mut lane_count = 0;
+            let mut rows = Vec::with_capacity(commits.len());
+
+            for commit in &commits {
+                let oid = commit.id();
+                let lane = match lanes.iter().position(|l| *l == Some(oid)) {
+                    Some(lane) => lane,
+                    None => free_lane(&mut lanes),
+                };
+                let before = lanes.clone();
+                lanes[lane] = None;
+
+                let mut edges = Vec::new();
+                for (idx, parent) in commit.parent_ids().enumerate() {
+                    if !included.contains(&parent) {
+                        continue;
+                    }
+                    let target = match lanes.iter().position(|l| *l == Some(parent)) {
+                        Some(existing) => existing,
+                        // The first parent continues straight down
+                        None if idx == 0 => lane,
+                        None => free_lane(&mut lanes),
+                    };
+                    lanes[target] = Some(parent);
+                    edges.push(GraphEdge { from_lane: lane, to_lane: target });
+                }
+                // Lanes waiting for other commits pass by this row
+                for (other, waiting) in before.iter().enumerate() {
+                    if other != lane && waiting.is_some() && lanes[other] == *waiting {
+                        edges.push(GraphEdge { from_lane: other, to_lane: other });
+                    }
+                }
+                edges.sort_by_key(|e| (e.from_lane, e.to_lane));
+
+                while lanes.last() == Some(&None) {
+                    lanes.pop();
+                }
+                lane_count = lane_count.max(before.len()).max(lanes.len());
+
+                let author = mailmap::author(commit, mailmap.as_ref());
+                rows.push(GraphCommit {
+                    oid: oid.to_string(),
+                    parents: commit.parent_ids().map(|p| p.to_string()).collect(),
+                    summary: commit.summary().unwrap_or("").to_string(),
+                    author: author.name().unwrap_or("Unknown").to_string(),
+                    timestamp: commit.time().seconds(),
+                    lane,
+                    edges,
+                    refs: refs.remove(&oid).unwrap_or_default(),
+                });
+            }
+
+            Ok(GraphResponse { commits: rows, lane_count, has_more })
+        })
+    }
+}
+
+/// Up to `limit` commits reachable from `to` (default HEAD) but not from
+/// `from`, children before parents
+fn walk_graph<'r>(repo: &'r Repository, from: Option<&str>, to: Option<&str>, limit: usize) -> Result<Vec<Commit<'r>>> {
+    let to_commit = match to {
+        Some(to) => resolve_commit(repo, to)?,
+        None => match head_commit(repo)? {
+            Some(head) => head,
+            // Nothing committed yet
+            None => return Ok(Vec::new()),
+        },
+    };
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    revwalk.push(to_commit.id())?;
+    if let Some(from) = from {
+        revwalk.hide(resolve_commit(repo, from)?.id())?;
+    }
+
+    let mut commits = Vec::new();
+    for oid in revwalk.take(limit) {
+        commits.push(repo.find_commit(oid?)?);
+    }
+    Ok(commits)
+}
+
+/// Index of the leftmost free lane, opening a new one if all are taken
+fn free_lane(lanes: &mut Vec<Option<Oid>>) -> usize {
+    match lanes.iter().position(Option::is_none) {
+        Some(lane) => lane,
+        None => {
+            lanes.push(None);
+            lanes.len() - 1
+        }
+    }
+}
+
+/// Branch and tag names pointing at commits in the graph
+fn ref_labels(repo: &Repository, included: &HashSet<Oid>) -> Result<Vec<(String, Oid)>> {
+    let mut labels: HashMap<String, Oid> = HashMap::new();
+
+    for reference in repo.references()? {
+        let reference = reference?;
+        if !(reference.is_branch() || reference.is_tag() || reference.is_remote()) {
+            continue;
+        }
+
+        let Some(name) = reference.shorthand().map(|s| s.to_string()) else {
+            continue;
+        };
+
+        if let Ok(commit) = reference.peel_to_commit()
+            && included.contains(&commit.id())
+        {
+            labels.insert(name, commit.id());
+        }
+    }
+
+    let mut labels: Vec<(String, Oid)> = labels.into_iter().collect();
+    labels.sort();
+    Ok(labels)
+}
+
+fn short_oid(oid: Oid) -> String {
+    oid.to_string()[..7].to_string()
+}
+
+fn escape_label(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
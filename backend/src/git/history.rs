@@ -1,8 +1,11 @@
 //! Commit history operations.
 //!
 //! Provides:
-//! - `get_commits()`: Paginated commit list with author filtering (uses cache)
-//! - `get_directory_info()`: Directory statistics (file count, size, contributors)
+//! - `get_commits()`: Paginated commit list with author and date filtering (uses cache)
+//! - `stream_commits()`: Commits emitted one by one as the revwalk progresses
+//! - `get_children()`: Commits that have a given commit as parent (forward navigation)
+//! - `get_directory_info()`: Directory statistics (file count, size, contributors,
+//!   optionally only those active in a date range)
 //! - `get_last_commits_for_paths()`: Batch fetch last commit info for multiple paths
 //!
 //! The main `get_commits()` uses the commit cache for fast repeated queries.
@@ -10,47 +13,40 @@
 //!
 //! Supports frontend: HistoryTab commit list, contributor filter, directory info
 
-use git2::{DiffOptions, Repository, Sort};
+use git2::{Oid, Repository, Sort};
 use std::collections::{HashMap, HashSet};
 
 use crate::error::Result;
-use crate::git::repository::{commit_to_info, GitRepository};
-use crate::models::{CommitInfo, CommitListResponse, ContributorInfo, DirectoryInfo};
-
-pub fn get_last_commit_for_path(repo: &Repository, path: &str) -> Result<CommitInfo> {
-    let mut revwalk = repo.revwalk()?;
-    revwalk.set_sorting(Sort::TIME)?;
-    revwalk.push_head()?;
-
-    for oid in revwalk {
-        let oid = oid?;
-        let commit = repo.find_commit(oid)?;
-
-        // Check if this commit modified the path
-        if commit_touches_path(repo, &commit, path)? {
-            return Ok(commit_to_info(&commit));
-        }
-    }
-
-    // Fallback: return the head commit
-    let head = repo.head()?;
-    let commit = head.peel_to_commit()?;
-    Ok(commit_to_info(&commit))
-}
-
-/// Get last commit info for multiple paths in a single history walk.
-/// Much more efficient than calling get_last_commit_for_path for each path.
-pub fn get_last_commits_for_paths(repo: &Repository, paths: &[String]) -> Result<HashMap<String, CommitInfo>> {
+use crate::git::cache::{
+    path_changed, query_commits_uncached, read_signature_status, CachedCommit, CommitFilters, PathFilter, PathSummary,
+    TimeWindow,
+};
+use crate::git::codeowners::CodeOwners;
+use crate::git::deadline;
+use crate::git::mailmap;
+use crate::git::repository::{
+    commit_to_info, head_commit, push_head, resolve_commit, resolve_tree_or_head, GitRepository, RepoLocation,
+};
+use crate::models::{CommitDetail, CommitInfo, CommitListResponse, ContributorInfo, DirectoryInfo, PathMode};
+
+/// Get last commit info for multiple paths in a single history walk from `start`.
+/// Much more efficient than walking history separately for each path.
+pub fn get_last_commits_for_paths(
+    repo: &Repository,
+    start: &git2::Commit,
+    paths: &[String],
+) -> Result<HashMap<String, CommitInfo>> {
     if paths.is_empty() {
         return Ok(HashMap::new());
     }
 
     let mut results: HashMap<String, CommitInfo> = HashMap::new();
     let mut remaining: HashSet<&str> = paths.iter().map(|s| s.as_str()).collect();
+    let mailmap = mailmap::load(repo);
 
     let mut revwalk = repo.revwalk()?;
     revwalk.set_sorting(Sort::TIME)?;
-    revwalk.push_head()?;
+    revwalk.push(start.id())?;
 
     for oid in revwalk {
         if remaining.is_empty() {
@@ -61,20 +57,18 @@ pub fn get_last_commits_for_paths(repo: &Repository, paths: &[String]) -> Result
         let commit = repo.find_commit(oid)?;
 
         // Check which remaining paths this commit touches
-        let touched = get_touched_paths(repo, &commit, &remaining)?;
+        let touched = get_touched_paths(&commit, &remaining)?;
 
         for path in touched {
             if remaining.remove(path.as_str()) {
-                results.insert(path, commit_to_info(&commit));
+                results.insert(path, commit_to_info(&commit, mailmap.as_ref()));
             }
         }
     }
 
-    // For any paths not found, use HEAD commit as fallback
+    // For any paths not found, use the starting commit as fallback
     if !remaining.is_empty() {
-        let head = repo.head()?;
-        let commit = head.peel_to_commit()?;
-        let fallback_info = commit_to_info(&commit);
+        let fallback_info = commit_to_info(start, mailmap.as_ref());
 
         for path in remaining {
             results.insert(path.to_string(), fallback_info.clone());
@@ -84,154 +78,216 @@ pub fn get_last_commits_for_paths(repo: &Repository, paths: &[String]) -> Result
     Ok(results)
 }
 
-/// Check which of the given paths are touched by this commit.
-fn get_touched_paths(repo: &Repository, commit: &git2::Commit, paths: &HashSet<&str>) -> Result<Vec<String>> {
+/// Check which of the given paths are touched by this commit (compared to its first parent)
+fn get_touched_paths(commit: &git2::Commit, paths: &HashSet<&str>) -> Result<Vec<String>> {
     let tree = commit.tree()?;
-
     let parent_tree = if commit.parent_count() > 0 {
         Some(commit.parent(0)?.tree()?)
     } else {
         None
     };
-
-    let diff = repo.diff_tree_to_tree(
-        parent_tree.as_ref(),
-        Some(&tree),
-        None, // No pathspec filter - we'll check manually
-    )?;
-
-    let mut touched = Vec::new();
-
-    for delta in diff.deltas() {
-        // Check both old and new paths (for renames)
-        if let Some(path) = delta.new_file().path().and_then(|p| p.to_str()) {
-            // Check if this path or any parent directory matches our targets
-            for &target in paths {
-                if path == target || path.starts_with(&format!("{}/", target)) || target.starts_with(&format!("{}/", path)) {
-                    if !touched.contains(&target.to_string()) {
-                        touched.push(target.to_string());
-                    }
-                }
-            }
-        }
-        if let Some(path) = delta.old_file().path().and_then(|p| p.to_str()) {
-            for &target in paths {
-                if path == target || path.starts_with(&format!("{}/", target)) || target.starts_with(&format!("{}/", path)) {
-                    if !touched.contains(&target.to_string()) {
-                        touched.push(target.to_string());
-                    }
-                }
-            }
-        }
+    // Nothing below the root changed (e.g. an empty commit)
+    if parent_tree.as_ref().is_some_and(|parent| parent.id() == tree.id()) {
+        return Ok(Vec::new());
     }
 
-    Ok(touched)
-}
-
-fn commit_touches_path(repo: &Repository, commit: &git2::Commit, path: &str) -> Result<bool> {
-    let tree = commit.tree()?;
-
-    let parent_tree = if commit.parent_count() > 0 {
-        Some(commit.parent(0)?.tree()?)
-    } else {
-        None
-    };
-
-    let mut opts = DiffOptions::new();
-    opts.pathspec(path);
-
-    let diff = repo.diff_tree_to_tree(
-        parent_tree.as_ref(),
-        Some(&tree),
-        Some(&mut opts),
-    )?;
-
-    Ok(diff.deltas().len() > 0)
+    Ok(paths
+        .iter()
+        .filter(|path| path_changed(&tree, parent_tree.as_ref(), path))
+        .map(|path| path.to_string())
+        .collect())
 }
 
 impl GitRepository {
     /// Get commits using the cache for fast repeated queries
+    ///
+    /// Falls back to a direct history walk when the repository exceeds the cache budget.
+    /// With `include_signatures`, each returned commit is annotated with its signature
+    /// status (memoized in the cache, so only new pages pay for the lookup).
+    /// A search in `filters` lists only commits whose message or author matches.
     pub fn get_commits(
         &self,
-        path: Option<&str>,
+        paths: &[String],
+        path_mode: PathMode,
         limit: usize,
         offset: usize,
-        exclude_authors: Option<&[String]>,
+        filters: CommitFilters,
+        include_signatures: bool,
     ) -> Result<CommitListResponse> {
+        let _op = self.op_log.start(
+            "commits",
+            format!("paths={} mode={:?} limit={} offset={} signatures={}", paths.join(","), path_mode, limit, offset, include_signatures),
+        );
+        if self.is_cache_over_budget() {
+            return self.with_repo(|repo| {
+                let filter = PathFilter::parse_many(paths, path_mode)?;
+                let mut response = query_commits_uncached(repo, |c| filter.matches(repo, c), limit, offset, filters)?;
+                if response.total == 0
+                    && let Some(literal) = filter.literal()
+                {
+                    response = query_commits_uncached(repo, |c| literal.matches(repo, c), limit, offset, filters)?;
+                }
+                if include_signatures {
+                    for commit in &mut response.commits {
+                        commit.signature = Some(read_signature_status(repo, Oid::from_str(&commit.oid)?));
+                    }
+                }
+                Ok(response)
+            });
+        }
+
         self.with_cache(|cache, repo| {
-            let path_key = path.unwrap_or("");
-            cache.get_commits_for_path(repo, path_key, limit, offset, exclude_authors)
+            let mut response = cache.get_commits_for_paths(repo, paths, path_mode, limit, offset, filters)?;
+            if include_signatures {
+                for commit in &mut response.commits {
+                    commit.signature = Some(cache.signature_status(repo, &commit.oid));
+                }
+            }
+            Ok(response)
         })
     }
 
-    pub fn get_directory_info(&self, path: Option<&str>) -> Result<DirectoryInfo> {
-        self.with_repo(|repo| {
-            let head = repo.head()?;
-            let commit = head.peel_to_commit()?;
-            let tree = commit.tree()?;
-
-            let target_tree = if let Some(p) = path {
-                if p.is_empty() || p == "/" {
-                    tree.clone()
-                } else {
-                    let entry = tree.get_path(std::path::Path::new(p))?;
-                    let obj = entry.to_object(repo)?;
-                    obj.peel_to_tree()?
+    /// Commits whose parent is `spec` (an OID or ref), newest first
+    ///
+    /// Only children reachable from HEAD are found.
+    pub fn get_children(&self, spec: &str) -> Result<Vec<CommitDetail>> {
+        let _op = self.op_log.start("children", format!("commit={}", spec));
+        let oid = self.with_repo(|repo| Ok(resolve_commit(repo, spec)?.id()))?;
+
+        if self.is_cache_over_budget() {
+            return self.with_repo(|repo| {
+                // Children descend from the commit, so its own ancestry can be skipped
+                let mut revwalk = repo.revwalk()?;
+                revwalk.set_sorting(Sort::TIME)?;
+                push_head(&mut revwalk, repo)?;
+                revwalk.hide(oid)?;
+                let mailmap = mailmap::load(repo);
+
+                let mut children = Vec::new();
+                for child_oid in revwalk {
+                    let commit = repo.find_commit(child_oid?)?;
+                    if commit.parent_ids().any(|p| p == oid) {
+                        children.push(CachedCommit::from_commit(&commit, mailmap.as_ref()).to_commit_detail());
+                    }
                 }
+                Ok(children)
+            });
+        }
+
+        self.with_cache(|cache, _| Ok(cache.get_children(&oid.to_string())))
+    }
+
+    /// Prepare a history walk that hands each matching commit to `emit` as soon as it is found.
+    ///
+    /// Only HEAD is resolved here, under the repository lock; the walk itself
+    /// runs on a handle of its own (see `CommitStream::run`), so a slow client
+    /// holding it up doesn't hold up other requests or a repository switch.
+    pub fn stream_commits(
+        &self,
+        paths: &[String],
+        path_mode: PathMode,
+        limit: Option<usize>,
+        exclude_authors: Option<&[String]>,
+    ) -> Result<CommitStream> {
+        let filter = PathFilter::parse_many(paths, path_mode)?;
+        let (location, head) =
+            self.with_repo(|repo| Ok((RepoLocation::of(repo), head_commit(repo)?.map(|c| c.id()))))?;
+
+        Ok(CommitStream {
+            location,
+            head,
+            filter,
+            limit,
+            exclude_authors: exclude_authors.map(|authors| authors.iter().cloned().collect()).unwrap_or_default(),
+        })
+    }
+
+    pub fn get_directory_info(&self, path: Option<&str>, window: TimeWindow) -> Result<DirectoryInfo> {
+        let _op = self.op_log.start("directory_info", format!("path={}", path.unwrap_or("")));
+        // "" and "/" both mean the repository root
+        let history_path = path.filter(|p| !p.is_empty() && *p != "/").unwrap_or("");
+
+        let (file_count, directory_count, total_size, owners) = self.with_repo(|repo| {
+            let tree = resolve_tree_or_head(repo, None)?;
+            let owners = CodeOwners::load(repo, &tree)
+                .map(|codeowners| codeowners.owners_of(history_path, true))
+                .unwrap_or_default();
+
+            let target_tree = if history_path.is_empty() {
+                tree
             } else {
-                tree.clone()
+                let entry = tree.get_path(std::path::Path::new(history_path))?;
+                let obj = entry.to_object(repo)?;
+                obj.peel_to_tree()?
             };
 
             // Count files and directories, calculate total size
-            let (file_count, directory_count, total_size) = count_entries(repo, &target_tree);
-
-            // Get contributors
-            let contributors = get_contributors_internal(repo, path)?;
-
-            // Get latest commit
-            let latest_commit = get_latest_commit_internal(repo, path)?;
-
-            // Get first commit (oldest)
-            let first_commit = get_first_commit_internal(repo, path)?;
-
-            Ok(DirectoryInfo {
-                path: path.unwrap_or("").to_string(),
-                file_count,
-                directory_count,
-                total_size,
-                contributors,
-                first_commit,
-                latest_commit,
-            })
+            let (files, dirs, size) = count_entries(repo, &target_tree);
+            Ok((files, dirs, size, owners))
+        })?;
+
+        // Contributors and first/latest commit come from the same path entry the
+        // HistoryTab uses, so a second view of one path costs no history walk
+        let summary = if self.is_cache_over_budget() {
+            self.with_repo(|repo| path_summary_uncached(repo, history_path, window))?
+        } else {
+            self.with_cache(|cache, repo| cache.get_path_summary(repo, history_path, window))?
+        };
+
+        Ok(DirectoryInfo {
+            path: path.unwrap_or("").to_string(),
+            file_count,
+            directory_count,
+            total_size,
+            contributors: summary.contributors,
+            first_commit: summary.first.map(|c| c.to_commit_info()),
+            latest_commit: summary.latest.map(|c| c.to_commit_info()),
+            owners,
         })
     }
 }
 
-fn get_contributors_internal(repo: &Repository, path: Option<&str>) -> Result<Vec<ContributorInfo>> {
+/// Single history walk collecting what `CommitCache::get_path_summary` returns,
+/// for repositories too large for the cache budget
+fn path_summary_uncached(repo: &Repository, path: &str, window: TimeWindow) -> Result<PathSummary> {
+    let filter = PathFilter::parse(path)?;
+    let summary = filtered_path_summary(repo, &filter, window)?;
+    match filter.literal() {
+        Some(literal) if summary.latest.is_none() => filtered_path_summary(repo, &literal, window),
+        _ => Ok(summary),
+    }
+}
+
+fn filtered_path_summary(repo: &Repository, filter: &PathFilter, window: TimeWindow) -> Result<PathSummary> {
     let mut revwalk = repo.revwalk()?;
     revwalk.set_sorting(Sort::TIME)?;
-    revwalk.push_head()?;
+    push_head(&mut revwalk, repo)?;
 
+    let mailmap = mailmap::load(repo);
     let mut contributor_map: HashMap<String, (String, usize)> = HashMap::new();
+    let mut latest = None;
+    let mut first = None;
 
     for oid in revwalk {
-        let oid = oid?;
-        let commit = repo.find_commit(oid)?;
+        deadline::check()?;
+        let commit = repo.find_commit(oid?)?;
 
-        if let Some(p) = path {
-            if !p.is_empty() && !commit_touches_path(repo, &commit, p)? {
-                continue;
-            }
+        if !filter.matches(repo, &commit)? {
+            continue;
         }
 
-        let author = commit.author();
-        let email = author.email().unwrap_or("").to_string();
-        let name = author.name().unwrap_or("Unknown").to_string();
+        let cached = CachedCommit::from_commit(&commit, mailmap.as_ref());
+        if window.contains(cached.timestamp) {
+            contributor_map
+                .entry(cached.author_email.to_string())
+                .and_modify(|(_, count)| *count += 1)
+                .or_insert((cached.author_name.to_string(), 1));
+        }
 
-        contributor_map
-            .entry(email.clone())
-            .and_modify(|(_, count)| *count += 1)
-            .or_insert((name, 1));
+        if latest.is_none() {
+            latest = Some(cached.clone());
+        }
+        first = Some(cached);
     }
 
     let mut contributors: Vec<ContributorInfo> = contributor_map
@@ -243,51 +299,13 @@ fn get_contributors_internal(repo: &Repository, path: Option<&str>) -> Result<Ve
         })
         .collect();
 
-    contributors.sort_by(|a, b| b.commit_count.cmp(&a.commit_count));
+    contributors.sort_by_key(|c| std::cmp::Reverse(c.commit_count));
 
-    Ok(contributors)
-}
-
-fn get_latest_commit_internal(repo: &Repository, path: Option<&str>) -> Result<Option<CommitInfo>> {
-    let mut revwalk = repo.revwalk()?;
-    revwalk.set_sorting(Sort::TIME)?;
-    revwalk.push_head()?;
-
-    for oid in revwalk {
-        let oid = oid?;
-        let commit = repo.find_commit(oid)?;
-
-        if let Some(p) = path {
-            if !p.is_empty() && !commit_touches_path(repo, &commit, p)? {
-                continue;
-            }
-        }
-
-        return Ok(Some(commit_to_info(&commit)));
-    }
-
-    Ok(None)
-}
-
-fn get_first_commit_internal(repo: &Repository, path: Option<&str>) -> Result<Option<CommitInfo>> {
-    let mut revwalk = repo.revwalk()?;
-    revwalk.set_sorting(Sort::TIME | Sort::REVERSE)?;
-    revwalk.push_head()?;
-
-    for oid in revwalk {
-        let oid = oid?;
-        let commit = repo.find_commit(oid)?;
-
-        if let Some(p) = path {
-            if !p.is_empty() && !commit_touches_path(repo, &commit, p)? {
-                continue;
-            }
-        }
-
-        return Ok(Some(commit_to_info(&commit)));
-    }
-
-    Ok(None)
+    Ok(PathSummary {
+        contributors,
+        latest,
+        first,
+    })
 }
 
 fn count_entries(repo: &Repository, tree: &git2::Tree) -> (usize, usize, u64) {
@@ -299,21 +317,21 @@ fn count_entries(repo: &Repository, tree: &git2::Tree) -> (usize, usize, u64) {
         match entry.kind() {
             Some(git2::ObjectType::Blob) => {
                 file_count += 1;
-                if let Ok(obj) = entry.to_object(repo) {
-                    if let Some(blob) = obj.as_blob() {
-                        total_size += blob.size() as u64;
-                    }
+                if let Ok(obj) = entry.to_object(repo)
+                    && let Some(blob) = obj.as_blob()
+                {
+                    total_size += blob.size() as u64;
                 }
             }
             Some(git2::ObjectType::Tree) => {
                 dir_count += 1;
-                if let Ok(obj) = entry.to_object(repo) {
-                    if let Some(subtree) = obj.as_tree() {
-                        let (fc, dc, ts) = count_entries(repo, subtree);
-                        file_count += fc;
-                        dir_count += dc;
-                        total_size += ts;
-                    }
+                if let Ok(obj) = entry.to_object(repo)
+                    && let Some(subtree) = obj.as_tree()
+                {
+                    let (fc, dc, ts) = count_entries(repo, subtree);
+                    file_count += fc;
+                    dir_count += dc;
+                    total_size += ts;
                 }
             }
             _ => {}
@@ -322,3 +340,79 @@ fn count_entries(repo: &Repository, tree: &git2::Tree) -> (usize, usize, u64) {
 
     (file_count, dir_count, total_size)
 }
+
+/// A history walk prepared by `GitRepository::stream_commits`
+pub struct CommitStream {
+    location: RepoLocation,
+    head: Option<Oid>,
+    filter: PathFilter,
+    limit: Option<usize>,
+    exclude_authors: HashSet<String>,
+}
+
+impl CommitStream {
+    /// Walk history and hand each matching commit to `emit`
+    ///
+    /// Does not touch the commit cache, so the first commits are available
+    /// immediately even on huge repositories. Stops early when `emit` returns false
+    /// (e.g. the client went away) or after `limit` commits.
+    ///
+    /// A glob that selects nothing is walked again as a literal path.
+    pub fn run<F>(self, mut emit: F) -> Result<()>
+    where
+        F: FnMut(CommitDetail) -> bool,
+    {
+        let Some(head) = self.head else {
+            return Ok(());
+        };
+        let repo = self.location.open()?;
+
+        let found = self.walk(&repo, head, &self.filter, &mut emit)?;
+        if found == Some(0)
+            && let Some(literal) = self.filter.literal()
+        {
+            self.walk(&repo, head, &literal, &mut emit)?;
+        }
+        Ok(())
+    }
+
+    /// Commits matched by `filter` (excluded authors included); `None` if
+    /// `emit` stopped the walk
+    fn walk<F>(&self, repo: &Repository, head: Oid, filter: &PathFilter, emit: &mut F) -> Result<Option<usize>>
+    where
+        F: FnMut(CommitDetail) -> bool,
+    {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(Sort::TIME)?;
+        revwalk.push(head)?;
+
+        let mailmap = mailmap::load(repo);
+
+        let mut matched = 0;
+        let mut sent = 0;
+        for oid in revwalk {
+            if self.limit.is_some_and(|l| sent >= l) {
+                break;
+            }
+
+            let commit = repo.find_commit(oid?)?;
+
+            if !filter.matches(repo, &commit)? {
+                continue;
+            }
+            matched += 1;
+
+            let cached = CachedCommit::from_commit(&commit, mailmap.as_ref());
+            if self.exclude_authors.contains(&*cached.author_email) {
+                continue;
+            }
+
+            if !emit(cached.to_commit_detail()) {
+                return Ok(None);
+            }
+            sent += 1;
+        }
+
+        Ok(Some(matched))
+    }
+}
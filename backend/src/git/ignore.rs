@@ -0,0 +1,317 @@
+//! Ignored files and the rules that ignore them.
+//!
+//! - `list_ignored()`: Ignored files and directories under a path, like `git status --ignored`
+//! - `check_ignore()`: Whether a path is ignored and by which line, like `git check-ignore -v`
+//!
+//! libgit2 can tell whether a path is ignored but not why, so the rules are
+//! also read here, in git's precedence order: the `.gitignore` files from the
+//! path's directory up to the root (deeper files win), then
+//! `$GIT_DIR/info/exclude`, then `core.excludesFile`. Within a file the last
+//! matching line wins; a `!` line re-includes the path. A path inside an
+//! ignored directory is explained by the directory's rule, as git never
+//! looks into ignored directories.
+//!
+//! When a rule matches, its verdict wins over libgit2's: libgit2 misses a `!`
+//! in a nested `.gitignore` re-including what a parent's pattern ignores.
+//!
+//! Supports frontend: file tree "why is this file missing" explanation
+
+use git2::{Repository, StatusOptions};
+use globset::GlobMatcher;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::{AppError, Result};
+use crate::git::cache::compile_glob;
+use crate::git::repository::GitRepository;
+use crate::models::{IgnoreCheck, IgnoreRule, IgnoredEntry, IgnoredFiles};
+
+/// Most entries a listing returns when the request doesn't say
+pub const DEFAULT_IGNORED_LIMIT: usize = 1000;
+
+/// Upper bound on `limit`, so a `node_modules`-style tree can't produce a huge response
+pub const MAX_IGNORED_LIMIT: usize = 10_000;
+
+impl GitRepository {
+    /// Ignored paths under `path`, with the rule behind each
+    ///
+    /// An ignored directory is listed once (`is_dir`), not file by file.
+    pub fn list_ignored(&self, path: Option<&str>, limit: usize) -> Result<IgnoredFiles> {
+        self.with_repo(|repo| {
+            let workdir = repo.workdir()
+                .ok_or_else(|| AppError::BadRequest("Repository has no working tree".to_string()))?
+                .to_path_buf();
+            let prefix = path.map(|p| p.trim_matches('/')).filter(|p| !p.is_empty());
+
+            let mut opts = StatusOptions::new();
+            opts.include_ignored(true)
+                .include_untracked(true)
+                .recurse_untracked_dirs(true)
+                .recurse_ignored_dirs(false);
+            if let Some(prefix) = prefix {
+                opts.pathspec(prefix);
+            }
+
+            let mut rules = IgnoreRules::load(repo, workdir);
+            let mut entries = Vec::new();
+            let mut truncated = false;
+            for entry in repo.statuses(Some(&mut opts))?.iter() {
+                if !entry.status().is_ignored() {
+                    continue;
+                }
+                let Some(entry_path) = entry.path() else {
+                    continue;
+                };
+                // libgit2 marks directories with a trailing slash
+                let is_dir = entry_path.ends_with('/');
+                let entry_path = entry_path.trim_end_matches('/').to_string();
+                let rule = rules.explain(&entry_path, is_dir);
+                if rule.as_ref().is_some_and(|r| r.negated) {
+                    continue;
+                }
+                if entries.len() == limit {
+                    truncated = true;
+                    break;
+                }
+                entries.push(IgnoredEntry { path: entry_path, is_dir, rule });
+            }
+
+            Ok(IgnoredFiles {
+                path: prefix.map(str::to_string),
+                entries,
+                truncated,
+            })
+        })
+    }
+
+    /// Whether `path` is ignored, and the last rule that matches it
+    ///
+    /// Tracked files are never ignored, but the rule that would ignore them
+    /// is still reported. A matching `!` rule means the path is re-included.
+    pub fn check_ignore(&self, path: &str) -> Result<IgnoreCheck> {
+        let path = path.trim_matches('/');
+        if path.is_empty() || Path::new(path).components().any(|c| !matches!(c, Component::Normal(_))) {
+            return Err(AppError::InvalidPath(path.to_string()));
+        }
+
+        self.with_repo(|repo| {
+            let workdir = repo.workdir()
+                .ok_or_else(|| AppError::BadRequest("Repository has no working tree".to_string()))?
+                .to_path_buf();
+            let full_path = workdir.join(path);
+            let exists = full_path.symlink_metadata().is_ok();
+            let is_dir = full_path.is_dir();
+
+            // The handle is long-lived, so pick up whatever was staged since it was opened
+            let mut index = repo.index()?;
+            index.read(false)?;
+            let tracked = index.get_path(Path::new(path), 0).is_some();
+
+            let rule = IgnoreRules::load(repo, workdir).explain(path, is_dir);
+            let ignored = !tracked
+                && match &rule {
+                    Some(rule) => !rule.negated,
+                    None => repo.is_path_ignored(path)?,
+                };
+
+            Ok(IgnoreCheck {
+                path: path.to_string(),
+                ignored,
+                tracked,
+                exists,
+                is_dir,
+                rule,
+            })
+        })
+    }
+}
+
+/// The ignore files that apply to a working tree, read as they are needed
+struct IgnoreRules {
+    workdir: PathBuf,
+    /// `.gitignore` per directory ("" for the root); `None` when it has none
+    per_directory: HashMap<String, Option<IgnoreFile>>,
+    /// `info/exclude`, then `core.excludesFile`
+    global: Vec<IgnoreFile>,
+}
+
+struct IgnoreFile {
+    /// Where the rules came from, as shown to the user
+    source: String,
+    /// Directory the patterns are relative to ("" for the root and global files)
+    base: String,
+    patterns: Vec<Pattern>,
+}
+
+struct Pattern {
+    /// The line as written, without trailing whitespace
+    text: String,
+    /// 1-based line in the ignore file
+    line: usize,
+    negated: bool,
+    /// Trailing `/`: only matches directories
+    dir_only: bool,
+    matcher: GlobMatcher,
+}
+
+impl IgnoreRules {
+    fn load(repo: &Repository, workdir: PathBuf) -> Self {
+        let mut global = Vec::new();
+
+        let exclude = repo.path().join("info").join("exclude");
+        let exclude_source = exclude.strip_prefix(&workdir).unwrap_or(&exclude).to_string_lossy().to_string();
+        global.extend(IgnoreFile::read(&exclude, exclude_source, String::new()));
+
+        if let Some(excludes_file) = excludes_file(repo) {
+            let source = excludes_file.to_string_lossy().to_string();
+            global.extend(IgnoreFile::read(&excludes_file, source, String::new()));
+        }
+
+        Self { workdir, per_directory: HashMap::new(), global }
+    }
+
+    /// The rule deciding `path`, taking ignored parent directories into account
+    fn explain(&mut self, path: &str, is_dir: bool) -> Option<IgnoreRule> {
+        let components: Vec<&str> = path.split('/').collect();
+        for depth in 1..components.len() {
+            let parent = components[..depth].join("/");
+            if let Some(rule) = self.last_match(&parent, true)
+                && !rule.negated
+            {
+                return Some(rule);
+            }
+        }
+        self.last_match(path, is_dir)
+    }
+
+    /// The highest-precedence matching line for `path`, ignoring its parents
+    fn last_match(&mut self, path: &str, is_dir: bool) -> Option<IgnoreRule> {
+        // Directories containing `path`, from the root down
+        let mut directories = vec![String::new()];
+        if let Some((parent, _)) = path.rsplit_once('/') {
+            let mut directory = String::new();
+            for component in parent.split('/') {
+                if !directory.is_empty() {
+                    directory.push('/');
+                }
+                directory.push_str(component);
+                directories.push(directory.clone());
+            }
+        }
+
+        for directory in directories.iter().rev() {
+            if let Some(file) = self.directory_file(directory)
+                && let Some(rule) = file.last_match(path, is_dir)
+            {
+                return Some(rule);
+            }
+        }
+        self.global.iter().find_map(|file| file.last_match(path, is_dir))
+    }
+
+    fn directory_file(&mut self, directory: &str) -> Option<&IgnoreFile> {
+        let workdir = &self.workdir;
+        self.per_directory
+            .entry(directory.to_string())
+            .or_insert_with(|| {
+                let source = if directory.is_empty() {
+                    ".gitignore".to_string()
+                } else {
+                    format!("{}/.gitignore", directory)
+                };
+                IgnoreFile::read(&workdir.join(&source), source, directory.to_string())
+            })
+            .as_ref()
+    }
+}
+
+impl IgnoreFile {
+    /// Patterns of an ignore file, `None` if it can't be read; invalid patterns are skipped
+    fn read(file: &Path, source: String, base: String) -> Option<Self> {
+        let contents = std::fs::read(file).ok()?;
+        let contents = String::from_utf8_lossy(&contents);
+        let patterns = contents
+            .lines()
+            .enumerate()
+            .filter_map(|(idx, line)| match parse_pattern(line, idx + 1) {
+                Ok(pattern) => pattern,
+                Err(e) => {
+                    tracing::warn!("Skipping {} line {}: {}", source, idx + 1, e);
+                    None
+                }
+            })
+            .collect();
+        Some(Self { source, base, patterns })
+    }
+
+    fn last_match(&self, path: &str, is_dir: bool) -> Option<IgnoreRule> {
+        let relative = if self.base.is_empty() {
+            path
+        } else {
+            path.strip_prefix(self.base.as_str())?.strip_prefix('/')?
+        };
+        self.patterns
+            .iter()
+            .rev()
+            .find(|p| (is_dir || !p.dir_only) && p.matcher.is_match(relative))
+            .map(|p| IgnoreRule {
+                source: self.source.clone(),
+                line: p.line,
+                pattern: p.text.clone(),
+                negated: p.negated,
+            })
+    }
+}
+
+/// One ignore file line; `None` for blank lines and comments
+fn parse_pattern(line: &str, line_number: usize) -> Result<Option<Pattern>> {
+    // Trailing spaces don't count unless escaped with a backslash
+    let mut text = line.trim_end_matches(['\r', '\n']);
+    while text.ends_with(' ') && !text.ends_with("\\ ") {
+        text = &text[..text.len() - 1];
+    }
+    if text.is_empty() || text.starts_with('#') {
+        return Ok(None);
+    }
+
+    let (negated, pattern) = match text.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    // `\!` and `\#` start a pattern with a literal `!` or `#`
+    let pattern = pattern.strip_prefix('\\').filter(|p| p.starts_with(['!', '#'])).unwrap_or(pattern);
+
+    let dir_only = pattern.ends_with('/');
+    let pattern = pattern.trim_end_matches('/');
+    // A slash other than a trailing one anchors the pattern to the file's directory
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_start_matches('/');
+    if pattern.is_empty() {
+        return Ok(None);
+    }
+    let glob = if anchored || pattern.starts_with("**") {
+        pattern.to_string()
+    } else {
+        format!("**/{}", pattern)
+    };
+
+    Ok(Some(Pattern {
+        text: text.to_string(),
+        line: line_number,
+        negated,
+        dir_only,
+        matcher: compile_glob(&glob)?,
+    }))
+}
+
+/// `core.excludesFile`, defaulting to `$XDG_CONFIG_HOME/git/ignore` like git
+fn excludes_file(repo: &Repository) -> Option<PathBuf> {
+    if let Ok(path) = repo.config().and_then(|c| c.get_path("core.excludesFile")) {
+        return Some(path);
+    }
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("git").join("ignore"))
+}
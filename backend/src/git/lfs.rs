@@ -0,0 +1,84 @@
+//! Git LFS pointer files.
+//!
+//! A file tracked with LFS is committed as a small text pointer naming the
+//! real content's SHA-256 and size; the content lives on the LFS server and,
+//! once fetched, under `$GIT_DIR/lfs/objects`. libgit2 doesn't run the LFS
+//! filter, so trees and diffs would otherwise show the pointer text.
+//!
+//! - `parse_pointer()`: Pointer fields from a blob's bytes, if it is one
+//! - `blob_pointer()`: Same for a blob by OID, without loading large blobs
+//! - `workdir_pointer()`: Same for a working tree file that was never smudged
+//! - `local_object_path()`: Where fetched content is stored
+//!
+//! Supports frontend: LFS badges in the file list, LFS summary in the diff viewer
+
+use git2::{ObjectType, Oid, Repository};
+use std::path::{Path, PathBuf};
+
+use crate::models::LfsPointer;
+
+/// Pointer files are always smaller than this (LFS spec)
+pub const MAX_POINTER_SIZE: usize = 1024;
+
+/// First line of a pointer: the current spec, and the pre-1.0 one
+const POINTER_VERSIONS: [&str; 2] = [
+    "version https://git-lfs.github.com/spec/v1",
+    "version https://hawser.github.com/spec/v1",
+];
+
+/// The pointer in `content`, `None` for any other file
+pub fn parse_pointer(repo: &Repository, content: &[u8]) -> Option<LfsPointer> {
+    if content.len() >= MAX_POINTER_SIZE {
+        return None;
+    }
+    let text = std::str::from_utf8(content).ok()?;
+    let mut lines = text.lines();
+    if !POINTER_VERSIONS.contains(&lines.next()?.trim_end()) {
+        return None;
+    }
+
+    let (mut oid, mut size) = (None, None);
+    for line in lines.filter(|l| !l.is_empty()) {
+        let (key, value) = line.split_once(' ')?;
+        match key {
+            "oid" => {
+                oid = value
+                    .strip_prefix("sha256:")
+                    .filter(|hex| hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .map(str::to_string);
+            }
+            "size" => size = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    let oid = oid?;
+    let downloaded = local_object_path(repo, &oid).is_file();
+    Some(LfsPointer { oid, size: size?, downloaded })
+}
+
+/// The pointer stored in blob `oid`; larger blobs are ruled out from the header alone
+pub fn blob_pointer(repo: &Repository, oid: Oid) -> Option<LfsPointer> {
+    if oid.is_zero() {
+        return None;
+    }
+    let (size, kind) = repo.odb().ok()?.read_header(oid).ok()?;
+    if kind != ObjectType::Blob || size >= MAX_POINTER_SIZE {
+        return None;
+    }
+    parse_pointer(repo, repo.find_blob(oid).ok()?.content())
+}
+
+/// The pointer a working tree file holds, when LFS hasn't replaced it with the content
+pub fn workdir_pointer(repo: &Repository, path: &Path) -> Option<LfsPointer> {
+    let metadata = std::fs::symlink_metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() >= MAX_POINTER_SIZE as u64 {
+        return None;
+    }
+    parse_pointer(repo, &std::fs::read(path).ok()?)
+}
+
+/// Where `git lfs fetch` keeps the content for `oid` (a SHA-256 in hex)
+pub fn local_object_path(repo: &Repository, oid: &str) -> PathBuf {
+    repo.path().join("lfs").join("objects").join(&oid[..2]).join(&oid[2..4]).join(oid)
+}
@@ -0,0 +1,32 @@
+//! `.mailmap` resolution of author and committer identities.
+//!
+//! The mailmap is read the way git reads it: `.mailmap` at the top of the
+//! working tree (HEAD's for bare repositories) plus the `mailmap.file` and
+//! `mailmap.blob` config. Attribution goes through it everywhere - commit
+//! cache, contributors, blame and diff authors - so someone who committed
+//! under several names or emails counts as one person. Exports
+//! (format-patch, raw objects) keep identities as recorded.
+
+use git2::{Commit, Mailmap, Repository, Signature};
+
+/// The repository's mailmap; `None` (nothing mapped) if it can't be read
+pub fn load(repo: &Repository) -> Option<Mailmap> {
+    repo.mailmap()
+        .inspect_err(|e| tracing::warn!("Ignoring unreadable mailmap: {}", e))
+        .ok()
+}
+
+/// `sig` with the canonical name and email from the mailmap
+pub fn resolve(mailmap: Option<&Mailmap>, sig: &Signature) -> Signature<'static> {
+    mailmap
+        .and_then(|m| m.resolve_signature(sig).ok())
+        .unwrap_or_else(|| sig.to_owned())
+}
+
+pub fn author(commit: &Commit, mailmap: Option<&Mailmap>) -> Signature<'static> {
+    resolve(mailmap, &commit.author())
+}
+
+pub fn committer(commit: &Commit, mailmap: Option<&Mailmap>) -> Signature<'static> {
+    resolve(mailmap, &commit.committer())
+}
@@ -0,0 +1,395 @@
+//! Repository maintenance - object storage statistics and gc-style actions.
+//!
+//! Statistics are gathered by scanning the git directory directly (loose object
+//! fan-out directories, `objects/pack/*.pack`, `logs/`). Actions shell out to the
+//! `git` binary since libgit2 has no gc/repack implementation.
+//!
+//! Only one action may run at a time per repository.
+//!
+//! `list_packs()` reads object counts straight from each `.idx` fan-out table.
+//!
+//! `fsck()` walks every object reachable from refs and reports objects that are
+//! missing or fail to load (libgit2 verifies object hashes on read).
+//!
+//! Supports frontend: maintenance panel (status + "run gc" button)
+
+use git2::{ErrorCode, ObjectType, Oid, Repository};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::error::{AppError, Result};
+use crate::git::repository::{GitRepository, RepoLocation};
+use crate::models::{
+    FsckEvent, FsckProblemKind, MaintenanceAction, MaintenanceResult, MaintenanceStatus, PackInfo,
+    PackListResponse,
+};
+
+/// Git's default `gc.auto` threshold for loose objects
+const GC_AUTO_LOOSE_OBJECTS: usize = 6700;
+/// Git's default `gc.autoPackLimit`
+const GC_AUTO_PACK_LIMIT: usize = 50;
+/// Emit a progress event every this many objects
+const FSCK_PROGRESS_INTERVAL: usize = 1000;
+
+impl GitRepository {
+    /// Directory holding the repository's objects, refs and logs
+    ///
+    /// For linked worktrees this is the shared (common) directory, not the
+    /// per-worktree `.git/worktrees/<name>` directory.
+    pub fn git_dir(&self) -> Result<PathBuf> {
+        self.with_repo(|repo| Ok(repo.commondir().to_path_buf()))
+    }
+
+    pub fn get_maintenance_status(&self) -> Result<MaintenanceStatus> {
+        let git_dir = self.git_dir()?;
+        Ok(scan_maintenance_status(&git_dir))
+    }
+
+    /// List packfiles with sizes, object counts and creation times
+    pub fn list_packs(&self) -> Result<PackListResponse> {
+        let pack_dir = self.git_dir()?.join("objects").join("pack");
+        let mut packs = Vec::new();
+
+        for entry in std::fs::read_dir(&pack_dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "pack") {
+                continue;
+            }
+
+            let metadata = entry.metadata().ok();
+            let created = metadata.as_ref()
+                .and_then(|m| m.created().or_else(|_| m.modified()).ok())
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64);
+
+            let idx_path = path.with_extension("idx");
+
+            packs.push(PackInfo {
+                name: entry.file_name().to_string_lossy().to_string(),
+                size: metadata.map(|m| m.len()).unwrap_or(0),
+                index_size: std::fs::metadata(&idx_path).map(|m| m.len()).unwrap_or(0),
+                object_count: read_idx_object_count(&idx_path),
+                created,
+                has_bitmap: path.with_extension("bitmap").exists(),
+                is_kept: path.with_extension("keep").exists(),
+            });
+        }
+
+        packs.sort_by_key(|p| std::cmp::Reverse(p.size));
+
+        let repack_candidates = packs.iter().filter(|p| !p.is_kept).count();
+
+        Ok(PackListResponse {
+            total_size: packs.iter().map(|p| p.size).sum(),
+            total_objects: packs.iter().filter_map(|p| p.object_count).map(u64::from).sum(),
+            repack_recommended: repack_candidates > 1,
+            packs,
+        })
+    }
+
+    /// Claim the repository for a maintenance action
+    ///
+    /// Fails with a conflict while another action runs. The action itself
+    /// (`Maintenance::run`) needs neither the repository lock nor the
+    /// `SharedRepo` guard, so it can run on a blocking thread of its own.
+    pub fn start_maintenance(&self, action: MaintenanceAction) -> Result<Maintenance> {
+        let git_dir = self.git_dir()?;
+        if self.maintenance_running.swap(true, Ordering::SeqCst) {
+            return Err(AppError::CheckoutConflict(
+                "Another maintenance action is already running".to_string(),
+            ));
+        }
+
+        Ok(Maintenance { action, git_dir, running: self.maintenance_running.clone() })
+    }
+}
+
+/// A maintenance action claimed by `GitRepository::start_maintenance`; the
+/// repository is free for another action once this is dropped
+pub struct Maintenance {
+    action: MaintenanceAction,
+    git_dir: PathBuf,
+    running: Arc<AtomicBool>,
+}
+
+impl Drop for Maintenance {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+impl Maintenance {
+    /// Run the action by shelling out to `git`, blocking until it exits
+    pub fn run(self) -> Result<MaintenanceResult> {
+        let before = scan_maintenance_status(&self.git_dir);
+
+        let args: &[&str] = match self.action {
+            MaintenanceAction::Gc => &["gc", "--quiet"],
+            MaintenanceAction::Repack => &["repack", "-a", "-d", "--quiet"],
+            MaintenanceAction::PrunePacked => &["prune-packed", "--quiet"],
+        };
+
+        tracing::info!("Running maintenance: git {}", args.join(" "));
+        let start = Instant::now();
+
+        let output = Command::new("git")
+            .arg("--git-dir")
+            .arg(&self.git_dir)
+            .args(args)
+            .output()
+            .map_err(|e| AppError::Internal(format!("Failed to run git: {}", e)))?;
+
+        let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+
+        Ok(MaintenanceResult {
+            action: self.action,
+            success: output.status.success(),
+            output: text.trim().to_string(),
+            duration_ms: start.elapsed().as_millis(),
+            before,
+            after: scan_maintenance_status(&self.git_dir),
+        })
+    }
+}
+
+impl GitRepository {
+    /// Prepare a check of every object reachable from refs
+    ///
+    /// The objects are walked on a handle of their own (see `Fsck::run`), so
+    /// the check doesn't hold the repository lock for the whole walk.
+    pub fn fsck(&self) -> Result<Fsck> {
+        let location = self.with_repo(|repo| Ok(RepoLocation::of(repo)))?;
+        Ok(Fsck { location })
+    }
+}
+
+/// An object check prepared by `GitRepository::fsck`
+pub struct Fsck {
+    location: RepoLocation,
+}
+
+impl Fsck {
+    /// Verify every object reachable from refs, streaming events to `emit`
+    ///
+    /// Stops early when `emit` returns false (client disconnected).
+    pub fn run<F>(self, emit: F) -> Result<()>
+    where
+        F: FnMut(FsckEvent) -> bool,
+    {
+        let repo = self.location.open()?;
+        let mut checker = FsckChecker {
+            repo: &repo,
+            odb: repo.odb()?,
+            seen: HashSet::new(),
+            pending: Vec::new(),
+            objects_checked: 0,
+            commits_checked: 0,
+            problems: 0,
+            emit,
+            cancelled: false,
+        };
+        let start = Instant::now();
+
+        for reference in repo.references()? {
+            let reference = reference?;
+            let name = reference.name().unwrap_or("(invalid ref)").to_string();
+            if let Some(oid) = reference.resolve().ok().and_then(|r| r.target()) {
+                checker.pending.push((oid, name));
+            }
+        }
+        checker.run();
+
+        if !checker.cancelled {
+            let done = FsckEvent::Done {
+                objects_checked: checker.objects_checked,
+                commits_checked: checker.commits_checked,
+                problems: checker.problems,
+                duration_ms: start.elapsed().as_millis(),
+            };
+            (checker.emit)(done);
+        }
+
+        Ok(())
+    }
+}
+
+struct FsckChecker<'r, F: FnMut(FsckEvent) -> bool> {
+    repo: &'r Repository,
+    odb: git2::Odb<'r>,
+    seen: HashSet<Oid>,
+    /// Objects still to check, with a description of what referenced them
+    pending: Vec<(Oid, String)>,
+    objects_checked: usize,
+    commits_checked: usize,
+    problems: usize,
+    emit: F,
+    cancelled: bool,
+}
+
+impl<F: FnMut(FsckEvent) -> bool> FsckChecker<'_, F> {
+    fn run(&mut self) {
+        while let Some((oid, referenced_by)) = self.pending.pop() {
+            if self.cancelled {
+                return;
+            }
+            if !self.seen.insert(oid) {
+                continue;
+            }
+
+            self.check(oid, &referenced_by);
+            self.objects_checked += 1;
+
+            if self.objects_checked.is_multiple_of(FSCK_PROGRESS_INTERVAL) {
+                let progress = FsckEvent::Progress {
+                    objects_checked: self.objects_checked,
+                    commits_checked: self.commits_checked,
+                };
+                self.cancelled = !(self.emit)(progress);
+            }
+        }
+    }
+
+    fn check(&mut self, oid: Oid, referenced_by: &str) {
+        let kind = match self.odb.read_header(oid) {
+            Ok((_, kind)) => kind,
+            Err(e) => {
+                let problem = if e.code() == ErrorCode::NotFound {
+                    FsckProblemKind::Missing
+                } else {
+                    FsckProblemKind::Corrupt
+                };
+                self.report(oid, problem, referenced_by, e.message());
+                return;
+            }
+        };
+
+        // Full read inflates the object and verifies its hash
+        let read_error = self.odb.read(oid).err();
+        if let Some(e) = read_error {
+            self.report(oid, FsckProblemKind::Corrupt, referenced_by, e.message());
+            return;
+        }
+
+        let from = oid.to_string();
+        match kind {
+            ObjectType::Commit => match self.repo.find_commit(oid) {
+                Ok(commit) => {
+                    self.commits_checked += 1;
+                    self.pending.push((commit.tree_id(), from.clone()));
+                    for parent in commit.parent_ids() {
+                        self.pending.push((parent, from.clone()));
+                    }
+                }
+                Err(e) => self.report(oid, FsckProblemKind::Corrupt, referenced_by, e.message()),
+            },
+            ObjectType::Tree => match self.repo.find_tree(oid) {
+                Ok(tree) => {
+                    for entry in tree.iter() {
+                        // Submodule commits live in another repository
+                        if entry.kind() != Some(ObjectType::Commit) {
+                            self.pending.push((entry.id(), from.clone()));
+                        }
+                    }
+                }
+                Err(e) => self.report(oid, FsckProblemKind::Corrupt, referenced_by, e.message()),
+            },
+            ObjectType::Tag => match self.repo.find_tag(oid) {
+                Ok(tag) => self.pending.push((tag.target_id(), from)),
+                Err(e) => self.report(oid, FsckProblemKind::Corrupt, referenced_by, e.message()),
+            },
+            _ => {}
+        }
+    }
+
+    fn report(&mut self, oid: Oid, kind: FsckProblemKind, referenced_by: &str, message: &str) {
+        self.problems += 1;
+        let problem = FsckEvent::Problem {
+            oid: oid.to_string(),
+            kind,
+            referenced_by: referenced_by.to_string(),
+            message: message.to_string(),
+        };
+        self.cancelled = !(self.emit)(problem);
+    }
+}
+
+fn scan_maintenance_status(git_dir: &Path) -> MaintenanceStatus {
+    let objects_dir = git_dir.join("objects");
+
+    let mut loose_objects = 0;
+    let mut loose_size = 0;
+    if let Ok(entries) = std::fs::read_dir(&objects_dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.len() != 2 || !name.chars().all(|c| c.is_ascii_hexdigit()) {
+                continue;
+            }
+            for object in std::fs::read_dir(entry.path()).into_iter().flatten().flatten() {
+                loose_objects += 1;
+                loose_size += object.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+        }
+    }
+
+    let mut pack_count = 0;
+    let mut pack_size = 0;
+    for entry in std::fs::read_dir(objects_dir.join("pack")).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "pack") {
+            pack_count += 1;
+            pack_size += entry.metadata().map(|m| m.len()).unwrap_or(0);
+        }
+    }
+
+    let (reflog_entries, reflog_size) = scan_reflogs(&git_dir.join("logs"));
+
+    MaintenanceStatus {
+        loose_objects,
+        loose_size,
+        pack_count,
+        pack_size,
+        reflog_entries,
+        reflog_size,
+        gc_recommended: loose_objects > GC_AUTO_LOOSE_OBJECTS || pack_count > GC_AUTO_PACK_LIMIT,
+    }
+}
+
+/// Read the object count from a pack index (last entry of the 256-entry fan-out table)
+///
+/// Version 2+ indexes start with a `\xfftOc` magic and a version word before the
+/// fan-out; version 1 indexes start with the fan-out directly.
+fn read_idx_object_count(idx_path: &Path) -> Option<u32> {
+    use std::io::Read;
+
+    let mut header = [0u8; 8 + 256 * 4];
+    std::fs::File::open(idx_path).ok()?.read_exact(&mut header).ok()?;
+
+    let fanout_start = if header[..4] == [0xff, b't', b'O', b'c'] { 8 } else { 0 };
+    let last = fanout_start + 255 * 4;
+    Some(u32::from_be_bytes(header[last..last + 4].try_into().ok()?))
+}
+
+/// Count reflog lines and bytes under `logs/` recursively
+fn scan_reflogs(dir: &Path) -> (usize, u64) {
+    let mut entries = 0;
+    let mut size = 0;
+
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            let (e, s) = scan_reflogs(&path);
+            entries += e;
+            size += s;
+        } else if let Ok(content) = std::fs::read(&path) {
+            entries += content.iter().filter(|&&b| b == b'\n').count();
+            size += content.len() as u64;
+        }
+    }
+
+    (entries, size)
+}
@@ -0,0 +1,142 @@
+//! Merging a branch into HEAD.
+//!
+//! - `merge_branch()`: Analyze, then fast-forward or create a merge commit
+//!
+//! The merge is computed in memory first, so nothing is written when it
+//! would conflict: the caller gets the conflicting paths and the working tree
+//! stays as it was. Applying requires a clean working tree, like a checkout.
+//!
+//! Supports frontend: merge action in the branch switcher
+
+use git2::{BranchType, MergeAnalysis as Analysis, Repository, RepositoryState};
+
+use crate::error::{AppError, Result};
+use crate::git::commit::user_signature;
+use crate::git::repository::{resolve_commit, GitRepository};
+use crate::git::status::ensure_clean;
+use crate::models::{MergeAnalysis, MergeResult};
+
+impl GitRepository {
+    /// Merge `branch` (any branch, tag or revision) into HEAD
+    ///
+    /// With `dry_run`, only reports what would happen.
+    pub fn merge_branch(&self, branch: &str, dry_run: bool) -> Result<MergeResult> {
+        let _op = self.op_log.start("merge", format!("branch={} dry_run={}", branch, dry_run));
+        self.with_repo(|repo| {
+            if repo.is_bare() {
+                return Err(AppError::BadRequest("Cannot merge in a bare repository".to_string()));
+            }
+            if repo.state() != RepositoryState::Clean {
+                return Err(AppError::BadRequest(format!(
+                    "Cannot merge: another operation is in progress ({:?})",
+                    repo.state()
+                )));
+            }
+
+            let theirs = resolve_commit(repo, branch)?;
+            let annotated = repo.find_annotated_commit(theirs.id())?;
+            let (analysis, _) = repo.merge_analysis(&[&annotated])?;
+            let head = repo.head().ok().and_then(|h| h.target());
+
+            let mut result = MergeResult {
+                branch: branch.to_string(),
+                analysis: MergeAnalysis::UpToDate,
+                applied: false,
+                head: head.map(|oid| oid.to_string()),
+                merge_commit: None,
+                conflicts: Vec::new(),
+            };
+
+            if analysis.contains(Analysis::ANALYSIS_UP_TO_DATE) {
+                return Ok(result);
+            }
+
+            if analysis.intersects(Analysis::ANALYSIS_FASTFORWARD | Analysis::ANALYSIS_UNBORN) {
+                result.analysis = MergeAnalysis::FastForward;
+                if dry_run {
+                    return Ok(result);
+                }
+                ensure_clean(repo, "merge")?;
+                checkout_tree(repo, &theirs)?;
+                move_head(repo, theirs.id(), &format!("merge {}: Fast-forward", branch))?;
+
+                tracing::info!("Fast-forwarded to {} ({})", theirs.id(), branch);
+                result.applied = true;
+                result.head = Some(theirs.id().to_string());
+                return Ok(result);
+            }
+
+            // Diverged: merge in memory so conflicts leave nothing behind
+            let ours = repo.head()?.peel_to_commit()?;
+            let mut index = repo.merge_commits(&ours, &theirs, None)?;
+            if index.has_conflicts() {
+                result.analysis = MergeAnalysis::Conflicts;
+                for conflict in index.conflicts()? {
+                    let conflict = conflict?;
+                    let entry = conflict.our.or(conflict.their).or(conflict.ancestor);
+                    if let Some(entry) = entry {
+                        result.conflicts.push(String::from_utf8_lossy(&entry.path).into_owned());
+                    }
+                }
+                result.conflicts.sort();
+                result.conflicts.dedup();
+                return Ok(result);
+            }
+
+            result.analysis = MergeAnalysis::Normal;
+            if dry_run {
+                return Ok(result);
+            }
+            ensure_clean(repo, "merge")?;
+
+            let signature = user_signature(repo)?;
+            let tree = repo.find_tree(index.write_tree_to(repo)?)?;
+            let message = format!("{}\n", merge_message(repo, branch));
+            let merge_oid = repo.commit(None, &signature, &signature, &message, &tree, &[&ours, &theirs])?;
+            let merge_commit = repo.find_commit(merge_oid)?;
+
+            checkout_tree(repo, &merge_commit)?;
+            move_head(repo, merge_oid, &format!("merge {}: Merge made by libgit2", branch))?;
+
+            tracing::info!("Merged {} into HEAD as {}", branch, merge_oid);
+            result.applied = true;
+            result.head = Some(merge_oid.to_string());
+            result.merge_commit = Some(merge_oid.to_string());
+            Ok(result)
+        })
+    }
+}
+
+/// Update the working tree and index to `commit`
+///
+/// Forced, like branch switching: callers check for local changes first.
+fn checkout_tree(repo: &Repository, commit: &git2::Commit) -> Result<()> {
+    let mut checkout_builder = git2::build::CheckoutBuilder::new();
+    checkout_builder.force();
+    repo.checkout_tree(commit.as_object(), Some(&mut checkout_builder))?;
+    Ok(())
+}
+
+/// Point the current branch (or a detached HEAD) at `oid`
+fn move_head(repo: &Repository, oid: git2::Oid, log_message: &str) -> Result<()> {
+    let head = repo.find_reference("HEAD")?;
+    match head.symbolic_target() {
+        // Also covers an unborn branch, whose ref doesn't exist yet
+        Some(branch_ref) => {
+            repo.reference(branch_ref, oid, true, log_message)?;
+        }
+        None => repo.set_head_detached(oid)?,
+    }
+    Ok(())
+}
+
+/// Default merge commit message, worded like git's
+fn merge_message(repo: &Repository, branch: &str) -> String {
+    if repo.find_branch(branch, BranchType::Local).is_ok() {
+        format!("Merge branch '{}'", branch)
+    } else if repo.find_branch(branch, BranchType::Remote).is_ok() {
+        format!("Merge remote-tracking branch '{}'", branch)
+    } else {
+        format!("Merge commit '{}'", branch)
+    }
+}
@@ -3,14 +3,96 @@
 //! Submodules:
 //! - `repository`: Thread-safe git repository wrapper and basic operations
 //! - `cache`: In-memory commit cache for fast history queries
+//! - `cache_build`: Background commit cache builds and their progress
+//! - `commit_graph`: Reader for git's commit-graph files (single or split chain) for walks and ahead/behind
+//! - `path_index`: SQLite-persisted per-path commit indices backing the cache
 //! - `tree`: File tree traversal and content retrieval
+//! - `archive`: zip / tar.gz snapshots of a commit's tree via `git archive`
 //! - `history`: Commit history with path filtering and author attribution
+//! - `blame`: Per-line attribution, looking through ignored (reformatting) commits
+//! - `conventional`: Conventional Commits parsing of commit messages
+//! - `codeowners`: CODEOWNERS rules and the owners of a path
+//! - `contributors`: Per-author profile built from the commit cache
+//! - `mailmap`: `.mailmap` resolution of author and committer identities
+//! - `notes`: Git notes on commits (`refs/notes/*`)
+//! - `pickaxe`: Commits whose diffs add or remove a string or regex (`-S`/`-G`)
 //! - `diff`: Diff generation between commits with author info per file
+//! - `lfs`: Git LFS pointer detection, so trees and diffs show the real file's size and OID
+//! - `attributes`: `.gitattributes` that force binary diffs or mark generated files
+//! - `encoding`: Encoding detection and transcoding of non-UTF-8 file content
+//! - `word_diff`: Intra-line change spans for paired -/+ lines
+//! - `patches`: Commits rendered as mbox patches (`git format-patch`)
+//! - `status`: Working tree status (staged, unstaged, untracked, conflicted)
+//! - `freshness`: Stat-based detection of HEAD, ref and index changes made outside the viewer
+//! - `watcher`: File system watching of HEAD, refs, the index and working tree for live updates
+//! - `ignore`: Ignored files and the .gitignore rule behind each (`git check-ignore -v`)
+//! - `commit`: Committing the staged index
+//! - `stash`: Stash push and pop
+//! - `merge`: Fast-forward and merge-commit merges into HEAD
+//! - `reset`: Soft/mixed/hard resets with a dry-run summary
+//! - `graph`: Commit DAG export (Graphviz DOT) and lane layout for graph views
+//! - `objects`: Raw object access by OID, content sniffing, image dimensions
+//! - `permalinks`: Web links to commits, files and lines on the remote's forge
+//! - `paths`: Case- and normalization-insensitive `path=` resolution
+//! - `refs`: Unified listing of all references
+//! - `signatures`: GPG/SSH signature verification for commits and tags
+//! - `branches`: Branch creation, renaming and deletion
+//! - `tags`: Tag lifecycle operations
+//! - `submodules`: Submodule listing with pinned and checked-out commits
+//! - `maintenance`: Object storage statistics and gc/repack actions
+//! - `editor`: Launching the local editor on a working tree file
+//! - `stats`: Commit activity over time, punch card, per-author line counts and file hotspots
+//! - `profiling`: Timing ring buffer for slow-operation diagnostics
+//! - `deadline`: Time limits and cancellation checked between steps of expensive operations
+//! - `bots`: Bot author patterns excluded from contributor lists
+//! - `test_repo`: Scratch repositories for unit tests
 
+pub mod archive;
+pub mod attributes;
+pub mod blame;
+pub mod bots;
+pub mod branches;
 pub mod cache;
+pub mod cache_build;
+pub mod codeowners;
+pub mod commit;
+pub mod commit_graph;
+pub mod contributors;
+pub mod conventional;
+pub mod deadline;
 pub mod diff;
+pub mod editor;
+pub mod encoding;
+pub mod freshness;
+pub mod graph;
 pub mod history;
+pub mod ignore;
+pub mod lfs;
+pub mod mailmap;
+pub mod maintenance;
+pub mod merge;
+pub mod notes;
+pub mod objects;
+pub mod path_index;
+pub mod patches;
+pub mod paths;
+pub mod permalinks;
+pub mod pickaxe;
+pub mod profiling;
+pub mod refs;
 pub mod repository;
+pub mod reset;
+pub mod signatures;
+pub mod stats;
+pub mod stash;
+pub mod status;
+pub mod submodules;
+pub mod tags;
 pub mod tree;
+pub mod watcher;
+pub mod word_diff;
+
+#[cfg(test)]
+pub mod test_repo;
 
 pub use repository::{GitRepository, SharedRepo};
@@ -0,0 +1,84 @@
+//! Git notes.
+//!
+//! Notes attach text to commits without rewriting them (review metadata, build
+//! IDs, ...). `attach_notes()` fills `CommitDetail::note` from the default
+//! notes ref for a page of commits; `get_notes()` reads every `refs/notes/*`
+//! ref for one commit.
+//!
+//! Supports: commit list `include_notes`, GET /api/v1/repository/commits/{oid}/notes
+
+use git2::{Oid, Repository};
+
+use crate::error::Result;
+use crate::git::repository::{resolve_commit, GitRepository};
+use crate::models::{AuthorInfo, CommitDetail, CommitNotes, NoteInfo};
+
+/// Notes ref git reads and writes by default
+const DEFAULT_NOTES_REF: &str = "refs/notes/commits";
+
+impl GitRepository {
+    /// Set `note` on each commit from `refs/notes/commits`
+    ///
+    /// Notes can change without HEAD moving, so they are read on every call
+    /// rather than cached with the commits.
+    pub fn attach_notes(&self, commits: &mut [CommitDetail]) -> Result<()> {
+        self.with_repo(|repo| {
+            if repo.find_reference(DEFAULT_NOTES_REF).is_err() {
+                return Ok(());
+            }
+            for commit in commits.iter_mut() {
+                let Ok(oid) = Oid::from_str(&commit.oid) else {
+                    continue;
+                };
+                commit.note = repo
+                    .find_note(Some(DEFAULT_NOTES_REF), oid)
+                    .ok()
+                    .and_then(|note| note.message().map(|m| m.to_string()));
+            }
+            Ok(())
+        })
+    }
+
+    /// Notes on `spec` (an OID or ref) from every notes ref
+    pub fn get_notes(&self, spec: &str) -> Result<CommitNotes> {
+        self.with_repo(|repo| {
+            let oid = resolve_commit(repo, spec)?.id();
+
+            let mut notes_refs = notes_refs(repo)?;
+            notes_refs.sort_by_key(|name| (name != DEFAULT_NOTES_REF, name.clone()));
+
+            let notes = notes_refs
+                .iter()
+                .filter_map(|notes_ref| {
+                    let note = repo.find_note(Some(notes_ref), oid).ok()?;
+                    let author = note.author();
+                    Some(NoteInfo {
+                        notes_ref: notes_ref.clone(),
+                        message: note.message().unwrap_or("").to_string(),
+                        author: AuthorInfo {
+                            name: author.name().unwrap_or("Unknown").to_string(),
+                            email: author.email().unwrap_or("").to_string(),
+                        },
+                        timestamp: author.when().seconds(),
+                    })
+                })
+                .collect();
+
+            Ok(CommitNotes {
+                commit: oid.to_string(),
+                notes,
+            })
+        })
+    }
+}
+
+/// Names of all refs under `refs/notes/`
+fn notes_refs(repo: &Repository) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for reference in repo.references_glob("refs/notes/*")? {
+        if let Some(name) = reference?.name() {
+            names.push(name.to_string());
+        }
+    }
+    Ok(names)
+}
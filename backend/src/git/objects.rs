@@ -0,0 +1,281 @@
+//! Raw object access by OID.
+//!
+//! Provides:
+//! - `get_object()`: Any object by full or abbreviated OID; blobs as bytes plus a
+//!   sniffed content type, trees/commits/tags as structured data
+//! - `sniff_mime()`: Content type from magic bytes (images, audio, video, PDF, UTF-8 text)
+//! - `raw_content_type()`: Content type for a file served raw, from its bytes and extension
+//! - `image_dimensions()`: Width/height read from PNG, GIF, JPEG, WebP and BMP headers
+//!
+//! Objects are immutable, so responses built from them can be cached forever.
+//!
+//! Supports frontend: DiffViewer image comparison (old/new images by OID), permalinks
+
+use git2::{ObjectType, Repository, Signature};
+
+use crate::error::{AppError, Result};
+use crate::git::repository::GitRepository;
+use crate::models::{CommitObject, GitObject, ObjectSignature, ObjectTreeEntry, TagObject, TreeObject};
+
+/// Shortest abbreviated OID accepted (same minimum as git)
+const MIN_OID_PREFIX: usize = 4;
+
+/// A blob's content and detected content type
+pub struct BlobData {
+    pub content: Vec<u8>,
+    pub mime: &'static str,
+}
+
+/// An object as served by the objects endpoint
+pub enum ObjectContent {
+    Blob(BlobData),
+    Structured(GitObject),
+}
+
+impl GitRepository {
+    pub fn get_object(&self, oid: &str) -> Result<ObjectContent> {
+        self.with_repo(|repo| {
+            let object = find_object(repo, oid)?;
+            let id = object.id().to_string();
+
+            let content = match object.kind() {
+                Some(ObjectType::Blob) => {
+                    let blob = object.peel_to_blob()?;
+                    let content = blob.content().to_vec();
+                    ObjectContent::Blob(BlobData {
+                        mime: sniff_mime(&content).unwrap_or("application/octet-stream"),
+                        content,
+                    })
+                }
+                Some(ObjectType::Tree) => {
+                    let tree = object.peel_to_tree()?;
+                    let entries = tree
+                        .iter()
+                        .map(|entry| ObjectTreeEntry {
+                            name: entry.name().unwrap_or("").to_string(),
+                            mode: format!("{:06o}", entry.filemode()),
+                            kind: entry.kind().map(|k| k.str()).unwrap_or("unknown").to_string(),
+                            oid: entry.id().to_string(),
+                        })
+                        .collect();
+                    ObjectContent::Structured(GitObject::Tree(TreeObject { oid: id, entries }))
+                }
+                Some(ObjectType::Commit) => {
+                    let commit = object.peel_to_commit()?;
+                    ObjectContent::Structured(GitObject::Commit(CommitObject {
+                        oid: id,
+                        tree: commit.tree_id().to_string(),
+                        parents: commit.parent_ids().map(|p| p.to_string()).collect(),
+                        author: object_signature(&commit.author()),
+                        committer: object_signature(&commit.committer()),
+                        message: String::from_utf8_lossy(commit.message_bytes()).to_string(),
+                    }))
+                }
+                Some(ObjectType::Tag) => {
+                    let tag = object.as_tag()
+                        .ok_or_else(|| AppError::Internal(format!("{} is not a tag", id)))?;
+                    ObjectContent::Structured(GitObject::Tag(TagObject {
+                        oid: id,
+                        name: String::from_utf8_lossy(tag.name_bytes()).to_string(),
+                        target: tag.target_id().to_string(),
+                        target_type: tag.target_type().map(|k| k.str()).unwrap_or("unknown").to_string(),
+                        tagger: tag.tagger().map(|s| object_signature(&s)),
+                        message: tag.message_bytes().map(|m| String::from_utf8_lossy(m).to_string()),
+                    }))
+                }
+                _ => return Err(AppError::BadRequest(format!("Unsupported object type for {}", id))),
+            };
+            Ok(content)
+        })
+    }
+}
+
+/// Look up an object by full OID or unambiguous prefix
+fn find_object<'r>(repo: &'r Repository, oid: &str) -> Result<git2::Object<'r>> {
+    let not_found = || AppError::ObjectNotFound(oid.to_string());
+
+    if oid.len() < MIN_OID_PREFIX || !oid.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(not_found());
+    }
+
+    match repo.find_object_by_prefix(oid, None) {
+        Ok(object) => Ok(object),
+        Err(e) if e.code() == git2::ErrorCode::Ambiguous => {
+            Err(AppError::BadRequest(format!("Ambiguous object prefix: {}", oid)))
+        }
+        Err(_) => Err(not_found()),
+    }
+}
+
+fn object_signature(sig: &Signature) -> ObjectSignature {
+    ObjectSignature {
+        name: String::from_utf8_lossy(sig.name_bytes()).to_string(),
+        email: String::from_utf8_lossy(sig.email_bytes()).to_string(),
+        timestamp: sig.when().seconds(),
+        offset_minutes: sig.when().offset_minutes(),
+    }
+}
+
+/// Detect a content type from the first bytes of `content`
+///
+/// Returns `None` for binary data that matches no known signature.
+pub fn sniff_mime(content: &[u8]) -> Option<&'static str> {
+    let mime = if content.starts_with(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if content.starts_with(b"GIF87a") || content.starts_with(b"GIF89a") {
+        "image/gif"
+    } else if content.starts_with(b"\xff\xd8\xff") {
+        "image/jpeg"
+    } else if content.len() >= 12 && &content[..4] == b"RIFF" && &content[8..12] == b"WEBP" {
+        "image/webp"
+    } else if content.starts_with(b"BM") && content.len() >= 26 {
+        "image/bmp"
+    } else if content.starts_with(b"\x00\x00\x01\x00") {
+        "image/x-icon"
+    } else if content.starts_with(b"%PDF-") {
+        "application/pdf"
+    } else if let Some(media) = sniff_media(content) {
+        media
+    } else if looks_like_text(content) {
+        if is_svg(content) {
+            "image/svg+xml"
+        } else {
+            "text/plain; charset=utf-8"
+        }
+    } else {
+        return None;
+    };
+    Some(mime)
+}
+
+/// Audio and video containers (and the image formats sharing their container)
+fn sniff_media(content: &[u8]) -> Option<&'static str> {
+    let head = &content[..content.len().min(64)];
+    let contains = |needle: &[u8]| head.windows(needle.len()).any(|w| w == needle);
+
+    // ISO base media (MP4, QuickTime, AVIF): a `ftyp` box naming the brand
+    if content.get(4..8) == Some(b"ftyp") {
+        return Some(match content.get(8..12)? {
+            b"M4A " | b"M4B " => "audio/mp4",
+            b"qt  " => "video/quicktime",
+            b"avif" | b"avis" => "image/avif",
+            b"heic" | b"heix" => "image/heic",
+            _ => "video/mp4",
+        });
+    }
+    if content.len() >= 12 && &content[..4] == b"RIFF" {
+        return match &content[8..12] {
+            b"WAVE" => Some("audio/wav"),
+            b"AVI " => Some("video/x-msvideo"),
+            _ => None,
+        };
+    }
+    let mime = if content.starts_with(b"\x1a\x45\xdf\xa3") {
+        if contains(b"webm") { "video/webm" } else { "video/x-matroska" }
+    } else if content.starts_with(b"OggS") {
+        if contains(b"theora") { "video/ogg" } else { "audio/ogg" }
+    } else if content.starts_with(b"fLaC") {
+        "audio/flac"
+    } else if content.starts_with(b"ID3") {
+        "audio/mpeg"
+    } else if content.starts_with(b"MThd") {
+        "audio/midi"
+    } else if content.len() >= 2 && content[0] == 0xff && content[1] & 0xf6 == 0xf0 {
+        // ADTS frame header: sync bits, then layer 0
+        "audio/aac"
+    } else if content.len() >= 2 && content[0] == 0xff && content[1] & 0xe6 == 0xe2 && content[1] & 0x18 != 0x08 {
+        // MP3 frame header without an ID3 tag: sync bits, a valid version, layer III
+        "audio/mpeg"
+    } else {
+        return None;
+    };
+    Some(mime)
+}
+
+/// Content type for serving a file raw, from its first bytes and its path
+///
+/// What the bytes say wins (an image is an image whatever its name, and text is
+/// served as plain text so it displays instead of downloading); the extension
+/// only names binary formats without a recognized signature (`.zip`, `.woff2`).
+pub fn raw_content_type(path: &str, head: &[u8]) -> String {
+    match sniff_mime(head) {
+        Some(mime) => mime.to_string(),
+        None => mime_guess::from_path(path)
+            .first()
+            .filter(|m| m.type_() != mime_guess::mime::TEXT)
+            .map(|m| m.essence_str().to_string())
+            .unwrap_or_else(|| "application/octet-stream".to_string()),
+    }
+}
+
+/// UTF-8 without NUL bytes in the first 8000 bytes
+pub fn looks_like_text(content: &[u8]) -> bool {
+    let probe = &content[..content.len().min(8000)];
+    if probe.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(probe) {
+        Ok(_) => true,
+        // A multi-byte character cut off at the probe boundary still counts as text
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+fn is_svg(content: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&content[..content.len().min(1024)]);
+    head.contains("<svg")
+}
+
+/// Pixel dimensions from an image header, if the format is recognized
+pub fn image_dimensions(content: &[u8]) -> Option<(u32, u32)> {
+    let be32 = |at: usize| content.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+    let le16 = |at: usize| content.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as u32);
+    let le24 = |at: usize| content.get(at..at + 3).map(|b| u32::from_le_bytes([b[0], b[1], b[2], 0]));
+    let le32 = |at: usize| content.get(at..at + 4).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+
+    match sniff_mime(content)? {
+        // IHDR is always the first chunk
+        "image/png" => Some((be32(16)?, be32(20)?)),
+        "image/gif" => Some((le16(6)?, le16(8)?)),
+        // Height is negative for top-down bitmaps
+        "image/bmp" => Some((le32(18)?.unsigned_abs(), le32(22)?.unsigned_abs())),
+        "image/webp" => match content.get(12..16)? {
+            b"VP8 " => Some((le16(26)? & 0x3fff, le16(28)? & 0x3fff)),
+            b"VP8L" => {
+                let bits = u32::from_le_bytes(content.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+            }
+            b"VP8X" => Some((le24(24)? + 1, le24(27)? + 1)),
+            _ => None,
+        },
+        "image/jpeg" => jpeg_dimensions(content),
+        _ => None,
+    }
+}
+
+/// Walk JPEG segments until the start-of-frame marker that carries the size
+fn jpeg_dimensions(content: &[u8]) -> Option<(u32, u32)> {
+    let mut pos = 2;
+    loop {
+        // Markers may be padded with any number of 0xff bytes
+        while *content.get(pos)? == 0xff {
+            pos += 1;
+        }
+        let marker = *content.get(pos)?;
+        pos += 1;
+
+        // Standalone markers carry no length
+        if marker == 0x01 || (0xd0..=0xd7).contains(&marker) {
+            continue;
+        }
+
+        let len = u16::from_be_bytes(content.get(pos..pos + 2)?.try_into().ok()?) as usize;
+        let is_sof = (0xc0..=0xcf).contains(&marker) && !matches!(marker, 0xc4 | 0xc8 | 0xcc);
+        if is_sof {
+            let height = u16::from_be_bytes(content.get(pos + 3..pos + 5)?.try_into().ok()?);
+            let width = u16::from_be_bytes(content.get(pos + 5..pos + 7)?.try_into().ok()?);
+            return Some((width as u32, height as u32));
+        }
+        pos += len;
+    }
+}
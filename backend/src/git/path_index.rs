@@ -0,0 +1,202 @@
+//! Persistent per-path commit index stored in SQLite.
+//!
+//! Lives at `<git common dir>/git-viewer/path-index.sqlite` and records, for
+//! each path queried so far, the OIDs of commits touching it plus the HEAD it
+//! was computed at. When HEAD moves, only commits reachable from the new HEAD
+//! but not the indexed one are diffed, so `build_path_cache` pays the full
+//! history walk once per repository rather than once per process.
+//!
+//! Entries are never pruned after history rewrites; OIDs that are no longer
+//! reachable simply never match a cached commit. An index written with another
+//! schema version (`PRAGMA user_version`) is dropped and rebuilt.
+//!
+//! With `--read-only` the index is not used at all, so nothing is written to
+//! the repository.
+//!
+//! Used by: `CommitCache::build_path_cache()` in cache.rs
+
+use git2::{Oid, Repository};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashSet;
+
+use crate::error::{AppError, Result};
+
+const INDEX_DIR: &str = "git-viewer";
+const INDEX_FILE: &str = "path-index.sqlite";
+
+/// Schema version kept in `PRAGMA user_version`; an index written with any
+/// other version is dropped and rebuilt
+const SCHEMA_VERSION: i32 = 1;
+
+pub struct PathIndex {
+    conn: Connection,
+}
+
+impl PathIndex {
+    /// Open (creating if needed) the index for this repository
+    pub fn open(repo: &Repository) -> Result<Self> {
+        let dir = repo.commondir().join(INDEX_DIR);
+        std::fs::create_dir_all(&dir).map_err(|e| AppError::Internal(e.to_string()))?;
+
+        let mut conn = Connection::open(dir.join(INDEX_FILE)).map_err(index_error)?;
+        conn.execute_batch("PRAGMA journal_mode = WAL;").map_err(index_error)?;
+
+        let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).map_err(index_error)?;
+        if version != SCHEMA_VERSION {
+            if version != 0 {
+                tracing::info!("Path index has schema version {}, rebuilding it", version);
+            }
+            let tx = conn.transaction().map_err(index_error)?;
+            tx.execute_batch(&format!(
+                "DROP TABLE IF EXISTS paths;
+                 DROP TABLE IF EXISTS path_commits;
+                 CREATE TABLE paths (
+                     path TEXT PRIMARY KEY,
+                     head_oid BLOB NOT NULL
+                 );
+                 CREATE TABLE path_commits (
+                     path TEXT NOT NULL,
+                     oid BLOB NOT NULL,
+                     PRIMARY KEY (path, oid)
+                 ) WITHOUT ROWID;
+                 PRAGMA user_version = {};",
+                SCHEMA_VERSION
+            ))
+            .map_err(index_error)?;
+            tx.commit().map_err(index_error)?;
+        }
+
+        Ok(Self { conn })
+    }
+
+    /// HEAD the path was last indexed at, and the commits found touching it
+    pub fn lookup(&self, path: &str) -> Result<Option<(Oid, HashSet<Oid>)>> {
+        let head: Option<Vec<u8>> = self.conn
+            .query_row("SELECT head_oid FROM paths WHERE path = ?1", params![path], |row| row.get(0))
+            .optional()
+            .map_err(index_error)?;
+        let Some(head) = head else {
+            return Ok(None);
+        };
+        let head = Oid::from_bytes(&head)?;
+
+        let mut stmt = self.conn
+            .prepare_cached("SELECT oid FROM path_commits WHERE path = ?1")
+            .map_err(index_error)?;
+        let rows = stmt
+            .query_map(params![path], |row| row.get::<_, Vec<u8>>(0))
+            .map_err(index_error)?;
+
+        let mut oids = HashSet::new();
+        for row in rows {
+            oids.insert(Oid::from_bytes(&row.map_err(index_error)?)?);
+        }
+
+        Ok(Some((head, oids)))
+    }
+
+    /// Record newly found commits for a path and advance its indexed HEAD
+    pub fn store(&mut self, path: &str, head: Oid, added: &[Oid]) -> Result<()> {
+        let tx = self.conn.transaction().map_err(index_error)?;
+        {
+            let mut insert = tx
+                .prepare_cached("INSERT OR IGNORE INTO path_commits (path, oid) VALUES (?1, ?2)")
+                .map_err(index_error)?;
+            for oid in added {
+                insert.execute(params![path, oid.as_bytes()]).map_err(index_error)?;
+            }
+        }
+        tx.execute(
+            "INSERT INTO paths (path, head_oid) VALUES (?1, ?2)
+             ON CONFLICT(path) DO UPDATE SET head_oid = excluded.head_oid",
+            params![path, head.as_bytes()],
+        )
+        .map_err(index_error)?;
+        tx.commit().map_err(index_error)
+    }
+}
+
+/// Commits reachable from `head` but not from `indexed_head`
+///
+/// Fails if `indexed_head` no longer exists, in which case callers rescan.
+pub fn commits_since(repo: &Repository, head: Oid, indexed_head: Oid) -> Result<HashSet<Oid>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(head)?;
+    revwalk.hide(indexed_head)?;
+
+    let mut oids = HashSet::new();
+    for oid in revwalk {
+        oids.insert(oid?);
+    }
+    Ok(oids)
+}
+
+fn index_error(e: rusqlite::Error) -> AppError {
+    AppError::Internal(format!("Path index error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo::TestRepo;
+    use crate::git::GitRepository;
+
+    const ADA: (&str, &str) = ("Ada", "ada@example.com");
+
+    /// How many commits touch `path`, asked of a freshly opened repository as a restarted server would
+    fn history_len(test: &TestRepo, path: &str) -> usize {
+        let repo = GitRepository::open(test.repo.workdir().unwrap()).unwrap();
+        repo.wait_for_cache().unwrap();
+        repo.with_cache(|cache, r| cache.get_path_commit_indices(r, path)).unwrap().len()
+    }
+
+    #[test]
+    fn a_restarted_server_answers_indexed_commits_from_disk_and_diffs_only_new_ones() {
+        let mut test = TestRepo::new();
+        let first = test.commit(ADA, "a.txt", "a\n", "first");
+        let unrelated = test.commit(ADA, "b.txt", "b\n", "second");
+        assert_eq!(history_len(&test, "a.txt"), 1);
+
+        let index = PathIndex::open(&test.repo).unwrap();
+        let (head, oids) = index.lookup("a.txt").unwrap().unwrap();
+        assert_eq!(head, unrelated);
+        assert_eq!(oids, HashSet::from([first]));
+        drop(index);
+
+        // A commit planted in the index is believed, so nothing indexed is diffed again
+        PathIndex::open(&test.repo).unwrap().store("a.txt", unrelated, &[unrelated]).unwrap();
+        assert_eq!(history_len(&test, "a.txt"), 2);
+
+        let third = test.commit(ADA, "a.txt", "a2\n", "third");
+        assert_eq!(history_len(&test, "a.txt"), 3);
+        let (head, oids) = PathIndex::open(&test.repo).unwrap().lookup("a.txt").unwrap().unwrap();
+        assert_eq!(head, third);
+        assert_eq!(oids, HashSet::from([first, unrelated, third]));
+    }
+
+    #[test]
+    fn an_index_with_another_schema_version_is_rebuilt() {
+        let mut test = TestRepo::new();
+        let first = test.commit(ADA, "a.txt", "a\n", "first");
+        PathIndex::open(&test.repo).unwrap().store("a.txt", first, &[first]).unwrap();
+
+        let file = test.repo.commondir().join(INDEX_DIR).join(INDEX_FILE);
+        Connection::open(&file).unwrap().execute_batch("PRAGMA user_version = 99;").unwrap();
+
+        let index = PathIndex::open(&test.repo).unwrap();
+        assert!(index.lookup("a.txt").unwrap().is_none());
+        assert_eq!(history_len(&test, "a.txt"), 1);
+    }
+
+    #[test]
+    fn commits_since_lists_only_what_the_indexed_head_lacks() {
+        let mut test = TestRepo::new();
+        let first = test.commit(ADA, "a.txt", "a\n", "first");
+        let second = test.commit(ADA, "a.txt", "b\n", "second");
+        let third = test.commit(ADA, "a.txt", "c\n", "third");
+
+        assert_eq!(commits_since(&test.repo, third, first).unwrap(), HashSet::from([second, third]));
+        assert!(commits_since(&test.repo, third, third).unwrap().is_empty());
+        assert!(commits_since(&test.repo, third, Oid::from_bytes(&[7; 20]).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+//! Lenient resolution of `path=` parameters.
+//!
+//! On macOS the file system is case-insensitive and hands out decomposed (NFD)
+//! Unicode names, while git stores whatever bytes were committed (usually NFC).
+//! A path copied from Finder or a terminal can therefore differ from the git
+//! path only in case or normalization form. With `--canonicalize-paths`, such
+//! paths are matched against the HEAD tree component by component and replaced
+//! by the path as git stores it, so responses carry the canonical path.
+//!
+//! Exact matches always win; paths that match nothing are passed through
+//! unchanged so the usual 404 still happens.
+//!
+//! Supports: tree, file, blame, commits, diff and directory-info `path` parameters
+
+use git2::{ObjectType, Repository, Tree};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::git::cache::is_glob;
+use crate::git::repository::GitRepository;
+
+impl GitRepository {
+    /// Enable case-insensitive, normalization-insensitive path resolution
+    pub fn with_path_canonicalization(mut self, enabled: bool) -> Self {
+        self.canonicalize_paths = enabled;
+        self
+    }
+
+    /// The git path a `path=` parameter refers to
+    ///
+    /// Returns the input unchanged when canonicalization is off, for globs, and
+    /// when nothing in the HEAD tree matches.
+    pub fn canonical_path(&self, path: &str) -> String {
+        if !self.canonicalize_paths || path.is_empty() || is_glob(path) {
+            return path.to_string();
+        }
+
+        self.with_repo(|repo| Ok(resolve_in_head(repo, path)))
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| path.to_string())
+    }
+
+    pub fn canonical_path_opt(&self, path: Option<&str>) -> Option<String> {
+        path.map(|p| self.canonical_path(p))
+    }
+}
+
+fn resolve_in_head(repo: &Repository, path: &str) -> Option<String> {
+    let tree = repo.head().ok()?.peel_to_tree().ok()?;
+    resolve_in_tree(repo, &tree, path)
+}
+
+/// Walk `path` down from `tree`, picking the stored name for each component
+fn resolve_in_tree(repo: &Repository, tree: &Tree, path: &str) -> Option<String> {
+    let components: Vec<&str> = path.trim_matches('/').split('/').filter(|c| !c.is_empty()).collect();
+    let mut resolved: Vec<String> = Vec::with_capacity(components.len());
+    let mut current = tree.clone();
+
+    for (i, component) in components.iter().enumerate() {
+        let entry = match current.get_name(component) {
+            Some(entry) => entry,
+            None => {
+                let wanted = fold(component);
+                current.iter().find(|e| e.name().is_some_and(|name| fold(name) == wanted))?
+            }
+        };
+        resolved.push(entry.name()?.to_string());
+        let (kind, id) = (entry.kind(), entry.id());
+        drop(entry);
+
+        if i + 1 < components.len() {
+            if kind != Some(ObjectType::Tree) {
+                return None;
+            }
+            current = repo.find_tree(id).ok()?;
+        }
+    }
+
+    Some(resolved.join("/"))
+}
+
+/// Comparison key: NFC-normalized and lowercased
+fn fold(name: &str) -> String {
+    name.nfc().collect::<String>().to_lowercase()
+}
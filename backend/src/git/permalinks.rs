@@ -0,0 +1,176 @@
+//! Web permalinks to commits, files and line ranges on the remote's forge.
+//!
+//! The remote URL (`git@github.com:owner/repo.git`, `ssh://git@host:2222/group/sub/repo`,
+//! `https://bitbucket.org/team/repo.git`, ...) is turned into the project's web
+//! page, and the forge is recognized from the host name to pick the URL
+//! layout. Hosts that don't name their software are assumed to follow
+//! GitHub's layout (GitHub Enterprise). Links are pinned to full commit OIDs.
+
+use std::path::Path;
+
+use crate::error::{AppError, Result};
+use crate::git::repository::{resolve_commit_or_head, GitRepository};
+use crate::models::{ForgeKind, Permalink};
+
+/// Lines a permalink highlights, `start..=end` (1-based)
+#[derive(Debug, Clone, Copy)]
+pub struct LineRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl GitRepository {
+    /// Permalink on `remote` for `rev` (HEAD when `None`), its file or
+    /// directory `path`, and optionally `lines` of that file
+    pub fn permalink(
+        &self,
+        remote: &str,
+        rev: Option<&str>,
+        path: Option<&str>,
+        lines: Option<LineRange>,
+    ) -> Result<Permalink> {
+        self.with_repo(|repo| {
+            let remote_url = repo
+                .find_remote(remote)
+                .map_err(|_| AppError::BadRequest(format!("No remote named '{}'", remote)))?
+                .url()
+                .map(str::to_string)
+                .ok_or_else(|| AppError::BadRequest(format!("Remote '{}' has no URL", remote)))?;
+            let (web_url, forge) = web_remote(&remote_url).ok_or_else(|| {
+                AppError::BadRequest(format!("Remote '{}' ({}) is not a hosted repository", remote, remote_url))
+            })?;
+
+            let commit = resolve_commit_or_head(repo, rev)?;
+            let oid = commit.id().to_string();
+
+            let url = match path.filter(|p| !p.is_empty()) {
+                None => {
+                    if lines.is_some() {
+                        return Err(AppError::BadRequest("Line ranges need a file path".to_string()));
+                    }
+                    commit_url(&web_url, forge, &oid)
+                }
+                Some(path) => {
+                    let entry = commit.tree()?.get_path(Path::new(path))
+                        .map_err(|_| AppError::PathNotFound(path.to_string()))?;
+                    let is_dir = entry.kind() == Some(git2::ObjectType::Tree);
+                    if is_dir && lines.is_some() {
+                        return Err(AppError::BadRequest(format!("{} is a directory, not a file", path)));
+                    }
+                    if let Some(range) = lines
+                        && (range.start == 0 || range.end < range.start)
+                    {
+                        return Err(AppError::BadRequest(format!(
+                            "Invalid line range {}-{}", range.start, range.end
+                        )));
+                    }
+                    file_url(&web_url, forge, &oid, path, is_dir, lines)
+                }
+            };
+
+            Ok(Permalink {
+                remote: remote.to_string(),
+                web_url,
+                forge,
+                commit: oid,
+                path: path.filter(|p| !p.is_empty()).map(str::to_string),
+                url,
+            })
+        })
+    }
+}
+
+/// Project web page and forge for a remote URL, `None` for local paths and
+/// schemes with no web counterpart
+pub fn web_remote(url: &str) -> Option<(String, ForgeKind)> {
+    let url = url.trim();
+    let (scheme, host, repo_path) = match url.split_once("://") {
+        Some((scheme, rest)) => {
+            let web_scheme = match scheme {
+                "http" | "https" => scheme,
+                "ssh" | "git" | "git+ssh" | "ssh+git" => "https",
+                _ => return None,
+            };
+            let (authority, repo_path) = rest.split_once('/')?;
+            let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+            // An SSH port says nothing about the web server's
+            let host = if web_scheme == scheme { host } else { host.split(':').next()? };
+            (web_scheme, host, repo_path)
+        }
+        None => {
+            // scp-like `user@host:owner/repo`; a '/' before the ':' (or a
+            // drive letter) means a local path
+            let (authority, repo_path) = url.split_once(':')?;
+            if authority.contains(['/', '\\']) || authority.len() == 1 {
+                return None;
+            }
+            let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+            ("https", host, repo_path)
+        }
+    };
+
+    let repo_path = repo_path.trim_matches('/');
+    let repo_path = repo_path.strip_suffix(".git").unwrap_or(repo_path);
+    if host.is_empty() || repo_path.is_empty() {
+        return None;
+    }
+    Some((format!("{}://{}/{}", scheme, host, repo_path), forge_of(host)))
+}
+
+fn forge_of(host: &str) -> ForgeKind {
+    let host = host.to_ascii_lowercase();
+    if host.contains("gitlab") {
+        ForgeKind::GitLab
+    } else if host.contains("bitbucket") {
+        ForgeKind::Bitbucket
+    } else if ["gitea", "forgejo", "codeberg"].iter().any(|name| host.contains(name)) {
+        ForgeKind::Gitea
+    } else {
+        ForgeKind::GitHub
+    }
+}
+
+fn commit_url(web_url: &str, forge: ForgeKind, oid: &str) -> String {
+    match forge {
+        ForgeKind::GitHub | ForgeKind::Gitea => format!("{}/commit/{}", web_url, oid),
+        ForgeKind::GitLab => format!("{}/-/commit/{}", web_url, oid),
+        ForgeKind::Bitbucket => format!("{}/commits/{}", web_url, oid),
+    }
+}
+
+fn file_url(web_url: &str, forge: ForgeKind, oid: &str, path: &str, is_dir: bool, lines: Option<LineRange>) -> String {
+    let path = encode_path(path);
+    let base = match (forge, is_dir) {
+        (ForgeKind::GitHub, false) => format!("{}/blob/{}/{}", web_url, oid, path),
+        (ForgeKind::GitHub, true) => format!("{}/tree/{}/{}", web_url, oid, path),
+        (ForgeKind::GitLab, false) => format!("{}/-/blob/{}/{}", web_url, oid, path),
+        (ForgeKind::GitLab, true) => format!("{}/-/tree/{}/{}", web_url, oid, path),
+        (ForgeKind::Bitbucket, _) => format!("{}/src/{}/{}", web_url, oid, path),
+        (ForgeKind::Gitea, _) => format!("{}/src/commit/{}/{}", web_url, oid, path),
+    };
+    let Some(LineRange { start, end }) = lines else {
+        return base;
+    };
+    let anchor = match forge {
+        ForgeKind::GitHub | ForgeKind::Gitea if start == end => format!("L{}", start),
+        ForgeKind::GitHub | ForgeKind::Gitea => format!("L{}-L{}", start, end),
+        ForgeKind::GitLab if start == end => format!("L{}", start),
+        ForgeKind::GitLab => format!("L{}-{}", start, end),
+        ForgeKind::Bitbucket if start == end => format!("lines-{}", start),
+        ForgeKind::Bitbucket => format!("lines-{}:{}", start, end),
+    };
+    format!("{}#{}", base, anchor)
+}
+
+/// Percent-encode a repository path for a URL, keeping the `/` separators
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
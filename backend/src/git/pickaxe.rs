@@ -0,0 +1,172 @@
+//! Pickaxe search - commits whose diffs add or remove a string or regex.
+//!
+//! Mirrors `git log -S` / `git log -G`:
+//! - `string`: the commit changes how often the string occurs in some file
+//!   (so moving a line within a file does not count)
+//! - `regex`: an added or removed line matches the regex
+//!
+//! Each commit is compared with its first parent; binary files are skipped. An
+//! optional path limits the search to files under it.
+//!
+//! Results are kept in the commit cache like path entries, so repeating a search
+//! (paging, author filters) is in-memory. Over-budget repositories walk history
+//! for every request.
+//!
+//! Supports: GET /api/v1/repository/commits/pickaxe
+
+use git2::{DiffOptions, Oid, Patch, Repository};
+use regex::Regex;
+
+use crate::error::{AppError, Result};
+use crate::git::cache::{query_commits_uncached, CommitFilters};
+use crate::git::deadline;
+use crate::git::repository::GitRepository;
+use crate::models::{CommitListResponse, PickaxeMode};
+
+/// A compiled pickaxe search
+pub struct Pickaxe {
+    needle: String,
+    regex: Option<Regex>,
+    path: Option<String>,
+}
+
+impl Pickaxe {
+    pub fn new(pattern: &str, mode: PickaxeMode, path: Option<&str>) -> Result<Self> {
+        if pattern.is_empty() {
+            return Err(AppError::BadRequest("Search pattern must not be empty".to_string()));
+        }
+
+        let regex = match mode {
+            PickaxeMode::String => None,
+            PickaxeMode::Regex => Some(
+                Regex::new(pattern)
+                    .map_err(|e| AppError::BadRequest(format!("Invalid regex '{}': {}", pattern, e)))?,
+            ),
+        };
+
+        Ok(Self {
+            needle: pattern.to_string(),
+            regex,
+            path: path.filter(|p| !p.is_empty()).map(|p| p.to_string()),
+        })
+    }
+
+    /// Commit cache key; the leading NUL keeps it apart from real paths
+    pub fn cache_key(&self) -> String {
+        let flag = if self.regex.is_some() { "-G" } else { "-S" };
+        match &self.path {
+            Some(path) => format!("\0{} {} -- {}", flag, self.needle, path),
+            None => format!("\0{} {}", flag, self.needle),
+        }
+    }
+
+    /// Whether the commit's diff against its first parent matches
+    pub fn matches(&self, repo: &Repository, commit: &git2::Commit) -> Result<bool> {
+        deadline::check()?;
+        let tree = commit.tree()?;
+        let parent_tree = if commit.parent_count() > 0 {
+            Some(commit.parent(0)?.tree()?)
+        } else {
+            None
+        };
+
+        let mut opts = DiffOptions::new();
+        if let Some(path) = &self.path {
+            opts.pathspec(path);
+        }
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts))?;
+
+        match &self.regex {
+            None => {
+                for delta in diff.deltas() {
+                    let old = self.occurrences(repo, delta.old_file().id())?;
+                    let new = self.occurrences(repo, delta.new_file().id())?;
+                    if old != new {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Some(regex) => {
+                for idx in 0..diff.deltas().len() {
+                    let Some(patch) = Patch::from_diff(&diff, idx)? else {
+                        continue;
+                    };
+                    for hunk_idx in 0..patch.num_hunks() {
+                        for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
+                            let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+                            if matches!(line.origin(), '+' | '-')
+                                && regex.is_match(&String::from_utf8_lossy(line.content()))
+                            {
+                                return Ok(true);
+                            }
+                        }
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    /// Non-overlapping occurrences of the needle in a blob (0 for missing or binary blobs)
+    fn occurrences(&self, repo: &Repository, oid: Oid) -> Result<usize> {
+        if oid.is_zero() {
+            return Ok(0);
+        }
+        let blob = repo.find_blob(oid)?;
+        if blob.is_binary() {
+            return Ok(0);
+        }
+
+        let haystack = blob.content();
+        let needle = self.needle.as_bytes();
+        let mut count = 0;
+        let mut pos = 0;
+        while pos + needle.len() <= haystack.len() {
+            if &haystack[pos..pos + needle.len()] == needle {
+                count += 1;
+                pos += needle.len();
+            } else {
+                pos += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+impl GitRepository {
+    /// Commits whose diffs add or remove `pattern`, newest first, paginated like `get_commits`
+    pub fn search_diffs(
+        &self,
+        pattern: &str,
+        mode: PickaxeMode,
+        path: Option<&str>,
+        limit: usize,
+        offset: usize,
+        exclude_authors: Option<&[String]>,
+    ) -> Result<CommitListResponse> {
+        let pickaxe = Pickaxe::new(pattern, mode, path)?;
+        let filters = CommitFilters { exclude_authors, ..Default::default() };
+        let _op = self.op_log.start(
+            "pickaxe",
+            format!("pattern={} mode={:?} path={} limit={} offset={}", pattern, mode, path.unwrap_or(""), limit, offset),
+        );
+
+        if self.is_cache_over_budget() {
+            return self.with_repo(|repo| {
+                query_commits_uncached(repo, |c| pickaxe.matches(repo, c), limit, offset, filters)
+            });
+        }
+
+        self.with_cache(|cache, repo| {
+            cache.get_commits_matching(
+                repo,
+                &pickaxe.cache_key(),
+                |c| pickaxe.matches(repo, c),
+                limit,
+                offset,
+                filters,
+            )
+        })
+    }
+}
@@ -0,0 +1,87 @@
+//! Timing records for git operations.
+//!
+//! Expensive operations (commit and path cache builds, diffs, blames, tree
+//! walks, history queries) hold an `OpTimer` for their duration; when it is
+//! dropped the elapsed time and the operation's parameters are appended to a
+//! fixed-size ring buffer. The log is shared across repository switches.
+//!
+//! Supports: GET /api/v1/debug/slow-ops
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::models::OperationTiming;
+
+/// Most recent operations kept in the ring buffer
+pub const OP_LOG_CAPACITY: usize = 500;
+
+#[derive(Debug, Default)]
+pub struct OpLog {
+    records: Mutex<VecDeque<OperationTiming>>,
+    /// Operations recorded since startup, including ones already dropped
+    total: AtomicUsize,
+}
+
+impl OpLog {
+    /// Start timing an operation; the record is written when the timer drops
+    pub fn start(&self, operation: &'static str, params: String) -> OpTimer<'_> {
+        OpTimer {
+            log: self,
+            operation,
+            params,
+            started: Instant::now(),
+        }
+    }
+
+    fn record(&self, timing: OperationTiming) {
+        self.total.fetch_add(1, Ordering::Relaxed);
+        // Diagnostics must never take the server down; a poisoned log just stops recording
+        if let Ok(mut records) = self.records.lock() {
+            if records.len() == OP_LOG_CAPACITY {
+                records.pop_front();
+            }
+            records.push_back(timing);
+        }
+    }
+
+    /// Recorded operations slower than `min_ms`, slowest first
+    pub fn slowest(&self, limit: usize, min_ms: f64) -> Vec<OperationTiming> {
+        let Ok(records) = self.records.lock() else {
+            return Vec::new();
+        };
+        let mut slowest: Vec<OperationTiming> = records
+            .iter()
+            .filter(|r| r.duration_ms >= min_ms)
+            .cloned()
+            .collect();
+        slowest.sort_by(|a, b| b.duration_ms.total_cmp(&a.duration_ms));
+        slowest.truncate(limit);
+        slowest
+    }
+
+    pub fn total(&self) -> usize {
+        self.total.load(Ordering::Relaxed)
+    }
+}
+
+/// Records the time between its creation and drop, including early returns on error
+pub struct OpTimer<'a> {
+    log: &'a OpLog,
+    operation: &'static str,
+    params: String,
+    started: Instant,
+}
+
+impl Drop for OpTimer<'_> {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed();
+        self.log.record(OperationTiming {
+            operation: self.operation,
+            params: std::mem::take(&mut self.params),
+            duration_ms: elapsed.as_secs_f64() * 1000.0,
+            timestamp: chrono::Utc::now().timestamp(),
+        });
+    }
+}
@@ -0,0 +1,181 @@
+//! Reference listing.
+//!
+//! Provides `list_refs()`: every ref in the repository (branches, remotes,
+//! tags, notes, the stash and anything else under `refs/`) with its direct
+//! target and peeled commit. Stash entries beyond the newest are read from
+//! the stash reflog and listed as `stash@{n}`.
+//!
+//! `get_head_state()` is the cheap "has anything changed?" probe: branch, HEAD
+//! OID, the index file's checksum and the refs stamp, without walking any history.
+//!
+//! `get_reflog()` lists where a ref (default HEAD) pointed over time, so a
+//! checkout or reset made by mistake can be undone.
+//!
+//! Supports frontend: unified ref picker, compare view
+
+use git2::{ObjectType, Repository};
+
+use crate::error::{AppError, Result};
+use crate::git::repository::{format_relative_time, GitRepository};
+use crate::models::{HeadState, RefInfo, RefKind, ReflogEntry, ReflogResponse};
+
+const STASH_REF: &str = "refs/stash";
+
+impl GitRepository {
+    pub fn list_refs(&self) -> Result<Vec<RefInfo>> {
+        self.with_repo(|repo| {
+            let head_ref = repo.head().ok().and_then(|h| h.name().map(|n| n.to_string()));
+
+            let mut refs = Vec::new();
+            for reference in repo.references()? {
+                let reference = reference?;
+                let Some(name) = reference.name().map(|n| n.to_string()) else {
+                    continue;
+                };
+
+                if name == STASH_REF {
+                    refs.extend(stash_entries(repo)?);
+                    continue;
+                }
+
+                let (kind, short_name) = classify(&name);
+                let commit = reference.peel(ObjectType::Commit).ok().map(|c| c.id().to_string());
+
+                refs.push(RefInfo {
+                    is_head: head_ref.as_deref() == Some(name.as_str()),
+                    short_name: short_name.to_string(),
+                    kind,
+                    target: reference.target().map(|oid| oid.to_string()),
+                    symbolic_target: reference.symbolic_target().map(|s| s.to_string()),
+                    commit,
+                    name,
+                });
+            }
+
+            refs.sort_by(|a, b| (a.kind as u8, &a.name).cmp(&(b.kind as u8, &b.name)));
+            Ok(refs)
+        })
+    }
+}
+
+impl GitRepository {
+    pub fn get_head_state(&self) -> Result<HeadState> {
+        self.with_repo(|repo| {
+            let head = repo.head().ok();
+            let branch = match &head {
+                Some(h) if h.is_branch() => h.shorthand().map(|s| s.to_string()),
+                // Unborn branch: HEAD names a branch that has no commits yet
+                None => repo.find_reference("HEAD").ok()
+                    .and_then(|r| r.symbolic_target().map(|t| t.trim_start_matches("refs/heads/").to_string())),
+                _ => None,
+            };
+            let oid = head.and_then(|h| h.target()).map(|oid| oid.to_string());
+            // `with_repo` has just taken the stamp
+            let refs_stamp = self.stamp
+                .lock()
+                .map_err(|_| AppError::Internal("Stamp lock poisoned".to_string()))?
+                .map_or(0, |stamp| stamp.refs);
+
+            Ok(HeadState {
+                branch,
+                oid,
+                index_checksum: read_index_checksum(repo),
+                refs_stamp: format!("{:016x}", refs_stamp),
+            })
+        })
+    }
+}
+
+impl GitRepository {
+    /// Newest `limit` reflog entries of `name` (`HEAD`, a branch, or any ref)
+    pub fn get_reflog(&self, name: &str, limit: usize) -> Result<ReflogResponse> {
+        self.with_repo(|repo| {
+            // libgit2 hands out an empty reflog for unknown names, so resolve first
+            let ref_name = if name == "HEAD" {
+                name.to_string()
+            } else {
+                repo.resolve_reference_from_short_name(name)
+                    .ok()
+                    .and_then(|r| r.name().map(|n| n.to_string()))
+                    .ok_or_else(|| AppError::CommitNotFound(name.to_string()))?
+            };
+
+            let reflog = repo.reflog(&ref_name)?;
+            let entries = reflog
+                .iter()
+                .take(limit)
+                .enumerate()
+                .map(|(idx, entry)| {
+                    let committer = entry.committer();
+                    let timestamp = committer.when().seconds();
+                    ReflogEntry {
+                        selector: format!("{}@{{{}}}", name, idx),
+                        old_oid: Some(entry.id_old()).filter(|oid| !oid.is_zero()).map(|oid| oid.to_string()),
+                        new_oid: entry.id_new().to_string(),
+                        message: entry.message().unwrap_or("").to_string(),
+                        committer_name: committer.name().unwrap_or("Unknown").to_string(),
+                        committer_email: committer.email().unwrap_or("").to_string(),
+                        timestamp,
+                        relative_time: format_relative_time(timestamp),
+                    }
+                })
+                .collect();
+
+            Ok(ReflogResponse {
+                ref_name,
+                total: reflog.len(),
+                entries,
+            })
+        })
+    }
+}
+
+/// The SHA-1 trailer of the index file (per worktree), without parsing the index
+fn read_index_checksum(repo: &Repository) -> Option<String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = std::fs::File::open(repo.path().join("index")).ok()?;
+    file.seek(SeekFrom::End(-20)).ok()?;
+    let mut trailer = [0u8; 20];
+    file.read_exact(&mut trailer).ok()?;
+    Some(trailer.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Kind and display name for a full ref name
+fn classify(name: &str) -> (RefKind, &str) {
+    let prefixes = [
+        ("refs/heads/", RefKind::Branch),
+        ("refs/remotes/", RefKind::Remote),
+        ("refs/tags/", RefKind::Tag),
+        ("refs/notes/", RefKind::Note),
+    ];
+
+    for (prefix, kind) in prefixes {
+        if let Some(short) = name.strip_prefix(prefix) {
+            return (kind, short);
+        }
+    }
+    (RefKind::Other, name.strip_prefix("refs/").unwrap_or(name))
+}
+
+/// One entry per stash, newest first, from the `refs/stash` reflog
+fn stash_entries(repo: &Repository) -> Result<Vec<RefInfo>> {
+    let reflog = repo.reflog(STASH_REF)?;
+
+    Ok(reflog
+        .iter()
+        .enumerate()
+        .map(|(idx, entry)| {
+            let oid = entry.id_new().to_string();
+            RefInfo {
+                name: format!("stash@{{{}}}", idx),
+                short_name: entry.message().unwrap_or("").to_string(),
+                kind: RefKind::Stash,
+                target: Some(oid.clone()),
+                symbolic_target: None,
+                commit: Some(oid),
+                is_head: false,
+            }
+        })
+        .collect())
+}
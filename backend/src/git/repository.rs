@@ -1,25 +1,104 @@
 //! Thread-safe git repository wrapper.
 //!
 //! Provides `GitRepository` struct that wraps libgit2's Repository with:
-//! - Mutex for thread-safe access (libgit2 Repository is not thread-safe)
-//! - Commit cache for fast history queries (lazily initialized)
+//! - Mutex for thread-safe access (libgit2 Repository is not thread-safe);
+//!   methods block, so handlers call them on blocking threads
+//!   (`routes::blocking`)
+//! - Commit cache for fast history queries (lazily initialized, memory-budgeted)
 //! - Helper methods for common operations
 //!
+//! Opening handles regular checkouts, bare repos, linked worktrees (`.git` is a
+//! file pointing at the real gitdir) and `GIT_DIR`/`GIT_WORK_TREE` layouts.
+//!
 //! Used by: All route handlers via `SharedRepo` (Arc<RwLock<GitRepository>>)
 
-use git2::Repository;
-use std::path::Path;
+use git2::{Mailmap, Oid, Repository};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 
 use crate::error::{AppError, Result};
-use crate::git::cache::CommitCache;
-use crate::models::{BlameLine, BlameResponse, BranchInfo, CommitInfo, RepositoryInfo};
+use crate::git::bots::default_bot_patterns;
+use crate::git::cache::{CommitCache, DEFAULT_CACHE_MAX_MB};
+use crate::git::cache_build::CacheBuild;
+use crate::git::commit_graph::{self, CommitGraph};
+use crate::git::deadline;
+use crate::git::freshness::{LooseRefs, RepoStamp};
+use crate::git::mailmap;
+use crate::git::profiling::OpLog;
+use crate::git::stats::{LineCountMemo, LINE_COUNT_BUDGET_DIVISOR};
+use crate::git::status::ensure_clean_for_checkout;
+use crate::git::watcher::{ChangeNotifier, RepoWatcher};
+use crate::models::{
+    AheadBehind, Bootstrap, BranchInfo, CacheStats, CommitInfo, DetachedHead, EntryType, RepositoryInfo, UpstreamStatus,
+};
+
+/// The tree with no entries; libgit2 knows it without it being in the object database
+const EMPTY_TREE_OID: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Top-level directories whose path caches `prewarm` builds
+const PREWARM_MAX_DIRECTORIES: usize = 20;
+
+/// Memoized ahead/behind pairs kept before the map is cleared and refilled
+const MAX_AHEAD_BEHIND_ENTRIES: usize = 10_000;
 
 pub struct GitRepository {
     pub repo: Mutex<Repository>,
     pub path: String,
     /// Commit cache for fast history queries (lazily initialized)
-    pub cache: Mutex<Option<CommitCache>>,
+    pub cache: Arc<Mutex<Option<CommitCache>>>,
+    /// Memory budget for the commit cache and derived caches
+    pub cache_max_bytes: usize,
+    /// Set once a cache build exceeded the budget; queries then bypass the cache
+    pub cache_over_budget: Arc<AtomicBool>,
+    /// Progress of the background cache build (see cache_build.rs)
+    pub cache_build: Arc<CacheBuild>,
+    /// Set while a gc/repack runs (see maintenance.rs); one at a time per repository
+    pub maintenance_running: Arc<AtomicBool>,
+    /// Editor command for open-in-editor; `None` keeps the endpoint disabled
+    pub editor: Option<String>,
+    /// Where the UI opens on load; set from `--path` / `--ref`, and not
+    /// carried over to a repository switched to
+    pub bootstrap: Bootstrap,
+    /// How long blame, diff and other expensive requests may run before they
+    /// are stopped with `504` (see deadline.rs); `None` for no limit
+    pub request_timeout: Option<Duration>,
+    /// Canonical directory the repository switcher may browse and open
+    /// repositories in; `None` allows the whole file system
+    pub browse_root: Option<PathBuf>,
+    /// Author name/email patterns treated as bots (see bots.rs)
+    pub bot_patterns: Vec<String>,
+    /// Resolve `path=` parameters ignoring case and Unicode normalization (see paths.rs)
+    pub canonicalize_paths: bool,
+    /// Served with `--read-only`: nothing is written to the repository, not
+    /// even the persistent path index
+    pub read_only: bool,
+    /// Timings of recent git operations, kept across repository switches
+    pub op_log: Arc<OpLog>,
+    /// Recursive (files, directories) totals per tree OID; trees are immutable,
+    /// so entries stay valid across commits and branch switches
+    pub tree_counts: Mutex<HashMap<Oid, (u32, u32)>>,
+    /// (ahead, behind) per (branch tip, upstream tip); like tree counts these
+    /// depend only on the OIDs, so the branch list only walks pairs that moved
+    pub ahead_behind_counts: Mutex<HashMap<(Oid, Oid), (usize, usize)>>,
+    /// Per-file lines added and removed by each commit against its first
+    /// parent, so author and hotspot statistics only diff commits they haven't
+    /// seen before; budgeted out of `cache_max_bytes`
+    pub line_counts: Mutex<LineCountMemo>,
+    /// Live update subscribers, kept across repository switches
+    pub changes: Arc<ChangeNotifier>,
+    /// File system watcher feeding `changes`; started by the first subscriber
+    pub watcher: Mutex<Option<RepoWatcher>>,
+    /// HEAD, refs and index stamps as of the last request (see freshness.rs)
+    pub stamp: Mutex<Option<RepoStamp>>,
+    /// Directories of the last walk over the loose refs, so it's only
+    /// repeated when one of them changed
+    pub loose_refs: Mutex<LooseRefs>,
+    /// Commit-graph as loaded for the stamp of its files (see commit_graph.rs);
+    /// the inner `None` means there is no usable graph
+    pub commit_graph: Mutex<Option<(u64, Option<Arc<CommitGraph>>)>>,
 }
 
 impl GitRepository {
@@ -27,41 +106,182 @@ impl GitRepository {
         let path_str = path.as_ref().to_string_lossy().to_string();
         let repo = Repository::discover(&path).map_err(|_| AppError::RepoNotFound(path_str.clone()))?;
 
-        Ok(Self {
+        Ok(Self::from_repository(repo, path_str))
+    }
+
+    /// Open the repository described by `GIT_DIR`, `GIT_WORK_TREE` and friends
+    pub fn open_from_env() -> Result<Self> {
+        let git_dir = std::env::var("GIT_DIR").unwrap_or_default();
+        let repo = Repository::open_from_env().map_err(|_| AppError::RepoNotFound(git_dir))?;
+
+        // Prefer the working tree for display; fall back to the git dir for bare layouts
+        let path = repo.workdir()
+            .unwrap_or_else(|| repo.path())
+            .to_string_lossy()
+            .trim_end_matches('/')
+            .to_string();
+
+        Ok(Self::from_repository(repo, path))
+    }
+
+    /// Open an explicit git directory, optionally paired with a separate work tree
+    ///
+    /// Unlike `open`, no discovery happens: `git_dir` must be the git directory itself.
+    pub fn open_separated(git_dir: &Path, work_tree: Option<&Path>) -> Result<Self> {
+        let git_dir_str = git_dir.to_string_lossy().to_string();
+        let repo = Repository::open(git_dir).map_err(|_| AppError::RepoNotFound(git_dir_str.clone()))?;
+
+        let path = match work_tree {
+            Some(work_tree) => {
+                if !work_tree.is_dir() {
+                    return Err(AppError::PathNotFound(work_tree.to_string_lossy().to_string()));
+                }
+                repo.set_workdir(work_tree, false)?;
+                work_tree.to_string_lossy().to_string()
+            }
+            None => git_dir_str,
+        };
+
+        Ok(Self::from_repository(repo, path))
+    }
+
+    fn from_repository(repo: Repository, path: String) -> Self {
+        Self {
             repo: Mutex::new(repo),
-            path: path_str,
-            cache: Mutex::new(None),
-        })
+            path,
+            cache: Arc::new(Mutex::new(None)),
+            cache_max_bytes: DEFAULT_CACHE_MAX_MB * 1024 * 1024,
+            cache_over_budget: Arc::new(AtomicBool::new(false)),
+            cache_build: Arc::new(CacheBuild::default()),
+            maintenance_running: Arc::new(AtomicBool::new(false)),
+            editor: None,
+            bootstrap: Bootstrap::default(),
+            request_timeout: None,
+            browse_root: None,
+            bot_patterns: default_bot_patterns(),
+            canonicalize_paths: false,
+            read_only: false,
+            op_log: Arc::new(OpLog::default()),
+            tree_counts: Mutex::new(HashMap::new()),
+            ahead_behind_counts: Mutex::new(HashMap::new()),
+            line_counts: Mutex::new(LineCountMemo::new(DEFAULT_CACHE_MAX_MB * 1024 * 1024 / LINE_COUNT_BUDGET_DIVISOR)),
+            changes: Arc::new(ChangeNotifier::default()),
+            watcher: Mutex::new(None),
+            stamp: Mutex::new(None),
+            loose_refs: Mutex::new(LooseRefs::default()),
+            commit_graph: Mutex::new(None),
+        }
+    }
+
+    /// Set the memory budget for the commit cache and derived caches
+    pub fn with_cache_budget(mut self, max_bytes: usize) -> Self {
+        self.cache_max_bytes = max_bytes;
+        self.line_counts = Mutex::new(LineCountMemo::new(max_bytes / LINE_COUNT_BUDGET_DIVISOR));
+        self
+    }
+
+    /// The part of the memory budget left to the commit cache once line
+    /// counts have their share
+    pub fn commit_cache_budget(&self) -> usize {
+        self.cache_max_bytes - self.cache_max_bytes / LINE_COUNT_BUDGET_DIVISOR
+    }
+
+    /// Enable open-in-editor with the given editor command
+    pub fn with_editor(mut self, editor: Option<String>) -> Self {
+        self.editor = editor;
+        self
+    }
+
+    /// Stop expensive requests after `timeout`; `None` lets them run to completion
+    pub fn with_request_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Keep the repository's files untouched (`--read-only`)
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Confine filesystem browsing and repository switching to `root` (canonical)
+    pub fn with_browse_root(mut self, root: Option<PathBuf>) -> Self {
+        self.browse_root = root;
+        self
+    }
+
+    /// Carry server-level settings over from the previously open repository
+    pub fn with_settings_from(self, previous: &GitRepository) -> Self {
+        let mut repo = self.with_cache_budget(previous.cache_max_bytes)
+            .with_editor(previous.editor.clone())
+            .with_browse_root(previous.browse_root.clone())
+            .with_request_timeout(previous.request_timeout)
+            .with_read_only(previous.read_only)
+            .with_path_canonicalization(previous.canonicalize_paths);
+        repo.op_log = previous.op_log.clone();
+        repo.changes = previous.changes.clone();
+        repo.bot_patterns = previous.bot_patterns.clone();
+        repo
+    }
+
+    /// Whether the repository is too large to keep the commit cache in memory
+    pub fn is_cache_over_budget(&self) -> bool {
+        self.cache_over_budget.load(Ordering::Relaxed)
+    }
+
+    /// Statistics of the commit cache as it is, without building it
+    pub fn cache_stats(&self) -> Result<Option<CacheStats>> {
+        let cache = self.cache.lock().map_err(|_| AppError::Internal("Cache lock poisoned".to_string()))?;
+        Ok(cache.as_ref().map(CommitCache::stats))
+    }
+
+    /// Build the commit cache before the first request needs it (`--prewarm`)
+    ///
+    /// With `paths`, also the path caches of the top-level directories (what a
+    /// click in the file list shows history for) and the root listing with
+    /// last commits and counts. Each step takes the repository lock on its
+    /// own, so requests arriving meanwhile are served between steps.
+    pub fn prewarm(&self, paths: bool) -> Result<()> {
+        let start = std::time::Instant::now();
+        let _op = self.op_log.start("prewarm", format!("paths={}", paths));
+        self.wait_for_cache()?;
+
+        if paths && !self.is_cache_over_budget() {
+            let root = self.get_tree_entries(None, None, true, true, 0, None)?;
+            for dir in root.entries.iter().filter(|e| e.entry_type == EntryType::Directory).take(PREWARM_MAX_DIRECTORIES) {
+                self.with_cache(|cache, repo| cache.get_path_commit_indices(repo, &dir.path).map(|_| ()))?;
+            }
+        }
+
+        tracing::info!("Caches prewarmed in {:?}", start.elapsed());
+        Ok(())
     }
 
-    /// Get or initialize the commit cache, rebuilding if HEAD has changed
+    /// Run `f` on the commit cache, which must be built for the current HEAD
+    /// (HEAD moves with commits, checkouts and resets, including ones made
+    /// outside the viewer)
+    ///
+    /// A missing or stale cache is rebuilt in the background (see
+    /// cache_build.rs) and the call fails with `AppError::Warming` meanwhile.
+    /// Once a build has exceeded the memory budget, `is_cache_over_budget()`
+    /// reports true and callers query uncached; one that checked just before
+    /// gets `Warming` too, and queries uncached when retried.
     pub fn with_cache<F, T>(&self, f: F) -> Result<T>
     where
         F: FnOnce(&mut CommitCache, &Repository) -> Result<T>,
     {
         let repo = self.repo.lock().map_err(|_| AppError::Internal("Repo lock poisoned".to_string()))?;
+        deadline::check()?;
+        self.sync_external_changes(&repo)?;
         let mut cache_guard = self.cache.lock().map_err(|_| AppError::Internal("Cache lock poisoned".to_string()))?;
 
-        // Check if we need to (re)build the cache
-        let needs_rebuild = match cache_guard.as_ref() {
-            None => true,
-            Some(cache) => !cache.is_valid(&repo),
-        };
-
-        if needs_rebuild {
-            tracing::info!("Building commit cache...");
-            let start = std::time::Instant::now();
-            let new_cache = CommitCache::build(&repo)?;
-            tracing::info!(
-                "Cache built: {} commits in {:?}",
-                new_cache.all_commits.len(),
-                start.elapsed()
-            );
-            *cache_guard = Some(new_cache);
+        if let Some(cache) = cache_guard.as_mut().filter(|cache| cache.is_valid(&repo)) {
+            return f(cache, &repo);
         }
-
-        let cache = cache_guard.as_mut().unwrap();
-        f(cache, &repo)
+        if !self.is_cache_over_budget() {
+            return Err(self.start_cache_build(&repo));
+        }
+        Err(AppError::Warming("The commit cache is over its memory budget; retry to query without it".to_string()))
     }
 
     pub fn info(&self) -> Result<RepositoryInfo> {
@@ -80,15 +300,32 @@ impl GitRepository {
             }
         });
 
-        let head_commit = repo.head().ok().and_then(|h| {
-            h.peel_to_commit().ok().map(|c| commit_to_info(&c))
-        });
+        let mailmap = mailmap::load(&repo);
+        let head = head_commit(&repo)?;
+
+        let detached_head = match &head {
+            Some(commit) if repo.head_detached().unwrap_or(false) => Some(DetachedHead {
+                oid: commit.id().to_string(),
+                describe: describe_commit(commit),
+            }),
+            _ => None,
+        };
+
+        // HEAD names a branch that doesn't exist yet: empty repository or fresh orphan branch
+        let unborn_branch = match head {
+            Some(_) => None,
+            None => repo.find_reference("HEAD").ok().and_then(|h| {
+                h.symbolic_target().map(|t| t.strip_prefix("refs/heads/").unwrap_or(t).to_string())
+            }),
+        };
 
         Ok(RepositoryInfo {
             name,
             path: self.path.clone(),
             head_branch,
-            head_commit,
+            head_commit: head.map(|c| commit_to_info(&c, mailmap.as_ref())),
+            detached_head,
+            unborn_branch,
             is_bare: repo.is_bare(),
             is_empty: repo.is_empty().unwrap_or(true),
         })
@@ -99,12 +336,29 @@ impl GitRepository {
         F: FnOnce(&Repository) -> Result<T>,
     {
         let repo = self.repo.lock().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
+        // A request that timed out waiting for the lock stops here
+        deadline::check()?;
+        self.sync_external_changes(&repo)?;
         f(&repo)
     }
 
-    /// List all local and remote branches in the repository
+    /// All branches, local ones with ahead/behind counts against their upstream
+    ///
+    /// Upstreams are resolved from one config snapshot (`Branch::upstream()`
+    /// re-reads the config per call). Counts are memoized per (tip, upstream
+    /// tip) pair, and branches level with their upstream need no walk, so
+    /// repositories with hundreds of branches only pay for the ones that
+    /// changed since the last listing.
     pub fn list_branches(&self) -> Result<Vec<BranchInfo>> {
+        let _op = self.op_log.start("branches", String::new());
         let repo = self.repo.lock().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
+        self.sync_external_changes(&repo)?;
+        let mut counts = self.ahead_behind_counts
+            .lock()
+            .map_err(|_| AppError::Internal("Ahead/behind lock poisoned".to_string()))?;
+        if counts.len() > MAX_AHEAD_BEHIND_ENTRIES {
+            counts.clear();
+        }
 
         let head = repo.head().ok();
         let current_branch = head.as_ref().and_then(|h| {
@@ -115,8 +369,11 @@ impl GitRepository {
             }
         });
 
+        let mailmap = mailmap::load(&repo);
         let mut local_branches = Vec::new();
         let mut remote_branches = Vec::new();
+        let mut upstreams = UpstreamResolver::new(&repo)?;
+        let graph = self.commit_graph(&repo)?;
 
         // List local branches
         for branch_result in repo.branches(Some(git2::BranchType::Local))? {
@@ -124,13 +381,16 @@ impl GitRepository {
             let name = branch.name()?.unwrap_or("").to_string();
             let is_current = current_branch.as_ref() == Some(&name);
 
-            let last_commit = branch.get().peel_to_commit().ok().map(|c| commit_to_info(&c));
+            let last_commit = branch.get().peel_to_commit().ok().map(|c| commit_to_info(&c, mailmap.as_ref()));
+            let upstream = branch.get().target()
+                .and_then(|tip| upstream_status(&repo, graph.as_deref(), &mut upstreams, &name, tip, &mut counts));
 
             local_branches.push(BranchInfo {
                 name: name.clone(),
                 is_current,
                 is_remote: false,
                 last_commit,
+                upstream,
             });
         }
 
@@ -139,13 +399,14 @@ impl GitRepository {
             let (branch, _) = branch_result?;
             let name = branch.name()?.unwrap_or("").to_string();
 
-            let last_commit = branch.get().peel_to_commit().ok().map(|c| commit_to_info(&c));
+            let last_commit = branch.get().peel_to_commit().ok().map(|c| commit_to_info(&c, mailmap.as_ref()));
 
             remote_branches.push(BranchInfo {
                 name: name.clone(),
                 is_current: false,
                 is_remote: true,
                 last_commit,
+                upstream: None,
             });
         }
 
@@ -159,7 +420,7 @@ impl GitRepository {
         });
 
         // Sort remote branches alphabetically
-        remote_branches.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        remote_branches.sort_by_key(|a| a.name.to_lowercase());
 
         // Combine: local first, then remote
         let mut branches = local_branches;
@@ -168,6 +429,25 @@ impl GitRepository {
         Ok(branches)
     }
 
+    /// Commits `head` has that `base` lacks and vice versa (any two refs or OIDs)
+    pub fn ahead_behind(&self, base: &str, head: &str) -> Result<AheadBehind> {
+        self.with_repo(|repo| {
+            let base_commit = resolve_commit(repo, base)?.id();
+            let head_commit = resolve_commit(repo, head)?.id();
+            let graph = self.commit_graph(repo)?;
+            let (ahead, behind) = commit_graph::ahead_behind(repo, graph.as_deref(), head_commit, base_commit)?;
+
+            Ok(AheadBehind {
+                base: base.to_string(),
+                head: head.to_string(),
+                base_commit: base_commit.to_string(),
+                head_commit: head_commit.to_string(),
+                ahead,
+                behind,
+            })
+        })
+    }
+
     /// Checkout a branch by name
     pub fn checkout_branch(&self, branch_name: &str) -> Result<()> {
         let repo = self.repo.lock().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
@@ -180,57 +460,10 @@ impl GitRepository {
             .ok_or_else(|| AppError::Internal("Invalid branch reference".to_string()))?;
 
         // Check for uncommitted changes before attempting checkout
-        let statuses = repo.statuses(Some(
-            git2::StatusOptions::new()
-                .include_untracked(false)
-                .include_ignored(false)
-        ))?;
-
-        let dirty_files: Vec<String> = statuses
-            .iter()
-            .filter(|s| {
-                let status = s.status();
-                status.intersects(
-                    git2::Status::INDEX_NEW
-                        | git2::Status::INDEX_MODIFIED
-                        | git2::Status::INDEX_DELETED
-                        | git2::Status::INDEX_RENAMED
-                        | git2::Status::INDEX_TYPECHANGE
-                        | git2::Status::WT_MODIFIED
-                        | git2::Status::WT_DELETED
-                        | git2::Status::WT_RENAMED
-                        | git2::Status::WT_TYPECHANGE
-                )
-            })
-            .filter_map(|s| s.path().map(|p| p.to_string()))
-            .take(5) // Limit to first 5 files
-            .collect();
-
-        if !dirty_files.is_empty() {
-            let file_list = dirty_files.join(", ");
-            let more = if statuses.len() > 5 {
-                format!(" and {} more", statuses.len() - 5)
-            } else {
-                String::new()
-            };
-            return Err(AppError::CheckoutConflict(format!(
-                "Cannot switch branches: you have uncommitted changes in: {}{}",
-                file_list, more
-            )));
-        }
+        ensure_clean_for_checkout(&repo)?;
 
-        // Checkout the tree to update working directory
-        // We use force() here because we've already verified there are no uncommitted changes above
         let commit = branch.get().peel_to_commit()?;
-        let tree = commit.tree()?;
-
-        let mut checkout_builder = git2::build::CheckoutBuilder::new();
-        checkout_builder.force(); // Force update since we verified no uncommitted changes
-
-        repo.checkout_tree(tree.as_object(), Some(&mut checkout_builder))?;
-
-        // Set HEAD to the branch after successful checkout
-        repo.set_head(refname)?;
+        switch_to(&repo, refname, &commit)?;
 
         tracing::info!("Checked out branch: {}", branch_name);
 
@@ -242,44 +475,7 @@ impl GitRepository {
         let repo = self.repo.lock().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
 
         // Check for uncommitted changes before attempting checkout
-        let statuses = repo.statuses(Some(
-            git2::StatusOptions::new()
-                .include_untracked(false)
-                .include_ignored(false)
-        ))?;
-
-        let dirty_files: Vec<String> = statuses
-            .iter()
-            .filter(|s| {
-                let status = s.status();
-                status.intersects(
-                    git2::Status::INDEX_NEW
-                        | git2::Status::INDEX_MODIFIED
-                        | git2::Status::INDEX_DELETED
-                        | git2::Status::INDEX_RENAMED
-                        | git2::Status::INDEX_TYPECHANGE
-                        | git2::Status::WT_MODIFIED
-                        | git2::Status::WT_DELETED
-                        | git2::Status::WT_RENAMED
-                        | git2::Status::WT_TYPECHANGE
-                )
-            })
-            .filter_map(|s| s.path().map(|p| p.to_string()))
-            .take(5)
-            .collect();
-
-        if !dirty_files.is_empty() {
-            let file_list = dirty_files.join(", ");
-            let more = if statuses.len() > 5 {
-                format!(" and {} more", statuses.len() - 5)
-            } else {
-                String::new()
-            };
-            return Err(AppError::CheckoutConflict(format!(
-                "Cannot switch branches: you have uncommitted changes in: {}{}",
-                file_list, more
-            )));
-        }
+        ensure_clean_for_checkout(&repo)?;
 
         // Check if local branch already exists
         if repo.find_branch(local_name, git2::BranchType::Local).is_ok() {
@@ -306,93 +502,197 @@ impl GitRepository {
             .ok_or_else(|| AppError::Internal("Invalid branch reference".to_string()))?
             .to_string();
 
-        // Checkout the tree first, then set HEAD
-        // We use force() because we've already verified there are no uncommitted changes
-        let tree = commit.tree()?;
-        let mut checkout_builder = git2::build::CheckoutBuilder::new();
-        checkout_builder.force();
-
-        repo.checkout_tree(tree.as_object(), Some(&mut checkout_builder))?;
-
-        // Set HEAD to the new local branch after successful checkout
-        repo.set_head(&refname)?;
+        switch_to(&repo, &refname, &commit)?;
 
         tracing::info!("Created and checked out local branch '{}' tracking '{}'", local_name, remote_branch);
 
         Ok(())
     }
+}
 
-    /// Get blame information for a file at a specific commit
-    pub fn get_blame(&self, path: &str, commit_oid: Option<&str>) -> Result<BlameResponse> {
-        let repo = self.repo.lock().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
+/// Whether `path` itself is a repository root: a checkout with a `.git` directory,
+/// a linked worktree/submodule with a `.git` file, or a bare repository
+pub fn is_git_repository(path: &Path) -> bool {
+    Repository::open_ext(path, git2::RepositoryOpenFlags::NO_SEARCH, std::iter::empty::<&std::ffi::OsStr>()).is_ok()
+}
 
-        // Determine the commit to blame at
-        let commit_id = if let Some(oid_str) = commit_oid {
-            git2::Oid::from_str(oid_str)
-                .map_err(|_| AppError::PathNotFound(format!("Invalid commit OID: {}", oid_str)))?
-        } else {
-            // Default to HEAD
-            repo.head()
-                .map_err(|_| AppError::PathNotFound("No HEAD found".to_string()))?
-                .peel_to_commit()
-                .map_err(|_| AppError::PathNotFound("Cannot resolve HEAD to commit".to_string()))?
-                .id()
-        };
+/// Where a repository lives, so work that outlasts the repository lock (and
+/// the `SharedRepo` guard) can open a handle of its own later
+#[derive(Debug, Clone)]
+pub(crate) struct RepoLocation {
+    git_dir: PathBuf,
+    workdir: Option<PathBuf>,
+}
 
-        let commit = repo.find_commit(commit_id)
-            .map_err(|_| AppError::PathNotFound(format!("Commit not found: {}", commit_id)))?;
-
-        // Set up blame options to stop at the specific commit
-        let mut blame_opts = git2::BlameOptions::new();
-        blame_opts.newest_commit(commit_id);
-
-        // Get blame for the file
-        let blame = repo.blame_file(std::path::Path::new(path), Some(&mut blame_opts))
-            .map_err(|e| AppError::PathNotFound(format!("Cannot blame file '{}': {}", path, e)))?;
-
-        // Convert blame hunks to BlameLine entries
-        let mut lines = Vec::new();
-        for hunk_index in 0..blame.len() {
-            if let Some(hunk) = blame.get_index(hunk_index) {
-                let sig = hunk.final_signature();
-                let author_name = sig.name().unwrap_or("Unknown").to_string();
-                let author_email = sig.email().unwrap_or("").to_string();
-                let hunk_commit_id = hunk.final_commit_id();
-                let timestamp = sig.when().seconds();
-
-                // Each hunk covers multiple lines
-                let start_line = hunk.final_start_line();
-                let line_count = hunk.lines_in_hunk();
-
-                for i in 0..line_count {
-                    lines.push(BlameLine {
-                        line_number: (start_line + i) as u32,
-                        author_name: author_name.clone(),
-                        author_email: author_email.clone(),
-                        commit_oid: hunk_commit_id.to_string(),
-                        timestamp,
-                    });
-                }
-            }
-        }
+impl RepoLocation {
+    pub(crate) fn of(repo: &Repository) -> Self {
+        Self { git_dir: repo.path().to_path_buf(), workdir: repo.workdir().map(Path::to_path_buf) }
+    }
+
+    pub(crate) fn open(&self) -> Result<Repository> {
+        open_handle(&self.git_dir, self.workdir.as_deref())
+    }
+}
 
-        // Sort by line number
-        lines.sort_by_key(|l| l.line_number);
+/// A handle of its own on the repository at `git_dir`, with the same working
+/// tree, for long work done outside the repository lock
+pub(crate) fn open_handle(git_dir: &Path, workdir: Option<&Path>) -> Result<Repository> {
+    let repo = Repository::open(git_dir)?;
+    if let Some(workdir) = workdir
+        && repo.workdir() != Some(workdir)
+    {
+        repo.set_workdir(workdir, false)?;
+    }
+    Ok(repo)
+}
+
+/// Check out `commit`'s tree, then point HEAD at `refname`
+///
+/// Uses a forced checkout, so callers must have run `ensure_clean_for_checkout` first.
+pub fn switch_to(repo: &Repository, refname: &str, commit: &git2::Commit) -> Result<()> {
+    let tree = commit.tree()?;
+    let mut checkout_builder = git2::build::CheckoutBuilder::new();
+    checkout_builder.force();
+
+    repo.checkout_tree(tree.as_object(), Some(&mut checkout_builder))?;
+
+    // Set HEAD only after the working directory was updated successfully
+    repo.set_head(refname)?;
+    Ok(())
+}
+
+/// Resolve a revision expression (full or abbreviated OID, branch, tag,
+/// `HEAD~3`, `main^2`, `v1.0^{commit}`, ...) to a commit
+///
+/// An abbreviated OID matching several objects is a bad request rather than a
+/// missing commit, so the caller can ask for a longer prefix.
+pub fn resolve_commit<'r>(repo: &'r Repository, spec: &str) -> Result<git2::Commit<'r>> {
+    let object = repo.revparse_single(spec).map_err(|e| match e.code() {
+        git2::ErrorCode::Ambiguous => AppError::BadRequest(format!("Ambiguous revision: {}", spec)),
+        _ => AppError::CommitNotFound(spec.to_string()),
+    })?;
+    object.peel_to_commit()
+        .map_err(|_| AppError::CommitNotFound(spec.to_string()))
+}
+
+/// Divergence of a local branch from its upstream; `None` without one (or if it is gone)
+fn upstream_status(
+    repo: &Repository,
+    graph: Option<&CommitGraph>,
+    upstreams: &mut UpstreamResolver,
+    branch: &str,
+    local: Oid,
+    counts: &mut HashMap<(Oid, Oid), (usize, usize)>,
+) -> Option<UpstreamStatus> {
+    let upstream = repo.find_reference(&upstreams.upstream_ref(branch)?).ok()?;
+    let name = upstream.shorthand()?.to_string();
+    let remote = upstream.resolve().ok()?.target()?;
+
+    let (ahead, behind) = if local == remote {
+        (0, 0)
+    } else if let Some(&known) = counts.get(&(local, remote)) {
+        known
+    } else {
+        let computed = commit_graph::ahead_behind(repo, graph, local, remote).ok()?;
+        counts.insert((local, remote), computed);
+        computed
+    };
+    Some(UpstreamStatus { name, ahead, behind })
+}
 
-        Ok(BlameResponse {
-            path: path.to_string(),
-            commit: commit_id.to_string(),
-            lines,
+/// Upstream ref names of local branches, read from one config snapshot
+struct UpstreamResolver<'r> {
+    repo: &'r Repository,
+    config: git2::Config,
+    /// Remotes by name, loaded on first use (`None` if the remote is missing)
+    remotes: HashMap<String, Option<git2::Remote<'r>>>,
+}
+
+impl<'r> UpstreamResolver<'r> {
+    fn new(repo: &'r Repository) -> Result<Self> {
+        Ok(Self {
+            repo,
+            config: repo.config()?.snapshot()?,
+            remotes: HashMap::new(),
         })
     }
+
+    /// Full name of the ref `branch` tracks, e.g. `refs/remotes/origin/main`
+    fn upstream_ref(&mut self, branch: &str) -> Option<String> {
+        let remote_name = self.config.get_string(&format!("branch.{}.remote", branch)).ok()?;
+        let merge = self.config.get_string(&format!("branch.{}.merge", branch)).ok()?;
+        if remote_name == "." {
+            return Some(merge);
+        }
+
+        let repo = self.repo;
+        let remote = self.remotes
+            .entry(remote_name)
+            .or_insert_with_key(|name| repo.find_remote(name).ok())
+            .as_ref()?;
+        remote
+            .refspecs()
+            .filter(|spec| spec.direction() == git2::Direction::Fetch && spec.src_matches(&merge))
+            .find_map(|spec| spec.transform(&merge).ok()?.as_str().map(|s| s.to_string()))
+    }
+}
+
+/// Resolve `spec` like `resolve_commit`, or HEAD when no ref is given
+///
+/// An unborn HEAD (empty repository, fresh orphan branch) is a missing commit.
+pub fn resolve_commit_or_head<'r>(repo: &'r Repository, spec: Option<&str>) -> Result<git2::Commit<'r>> {
+    match spec {
+        Some(spec) => resolve_commit(repo, spec),
+        None => head_commit(repo)?
+            .ok_or_else(|| AppError::CommitNotFound("HEAD (the current branch has no commits yet)".to_string())),
+    }
+}
+
+/// Tree of `spec`, or of HEAD when no ref is given; an unborn HEAD has the empty tree
+pub fn resolve_tree_or_head<'r>(repo: &'r Repository, spec: Option<&str>) -> Result<git2::Tree<'r>> {
+    match spec {
+        Some(spec) => Ok(resolve_commit(repo, spec)?.tree()?),
+        None => match head_commit(repo)? {
+            Some(commit) => Ok(commit.tree()?),
+            None => Ok(repo.find_tree(Oid::from_str(EMPTY_TREE_OID)?)?),
+        },
+    }
+}
+
+/// `git describe --tags --always` for a commit: nearest tag plus distance, or the short OID
+fn describe_commit(commit: &git2::Commit) -> String {
+    let mut options = git2::DescribeOptions::new();
+    options.describe_tags().show_commit_oid_as_fallback(true);
+    commit.as_object()
+        .describe(&options)
+        .and_then(|d| d.format(None))
+        .unwrap_or_else(|_| commit.id().to_string()[..7].to_string())
+}
+
+/// HEAD's commit, or `None` while the current branch has no commits yet
+pub fn head_commit(repo: &Repository) -> Result<Option<git2::Commit<'_>>> {
+    match repo.head() {
+        Ok(head) => Ok(Some(head.peel_to_commit()?)),
+        Err(e) if matches!(e.code(), git2::ErrorCode::UnbornBranch | git2::ErrorCode::NotFound) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Start a revwalk at HEAD; with an unborn HEAD the walk yields nothing
+pub fn push_head(revwalk: &mut git2::Revwalk, repo: &Repository) -> Result<()> {
+    if let Some(head) = head_commit(repo)? {
+        revwalk.push(head.id())?;
+    }
+    Ok(())
 }
 
-pub fn commit_to_info(commit: &git2::Commit) -> CommitInfo {
+/// Short commit summary, with the author's name as mapped by `mailmap`
+pub fn commit_to_info(commit: &git2::Commit, mailmap: Option<&Mailmap>) -> CommitInfo {
     let timestamp = commit.time().seconds();
     CommitInfo {
         oid: commit.id().to_string(),
         message: commit.message().unwrap_or("").trim().to_string(),
-        author: commit.author().name().unwrap_or("Unknown").to_string(),
+        author: mailmap::author(commit, mailmap).name().unwrap_or("Unknown").to_string(),
         timestamp,
         relative_time: format_relative_time(timestamp),
     }
@@ -423,3 +723,67 @@ pub fn format_relative_time(timestamp: i64) -> String {
 }
 
 pub type SharedRepo = Arc<RwLock<GitRepository>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo::TestRepo;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+
+    const AUTHOR: (&str, &str) = ("Ada Lovelace", "ada@example.com");
+
+    fn status_of(error: AppError) -> StatusCode {
+        error.into_response().status()
+    }
+
+    /// Two blobs whose OIDs share their first four hex digits, the shortest
+    /// prefix libgit2 resolves
+    fn ambiguous_prefix(repo: &Repository) -> String {
+        let mut seen = HashMap::new();
+        for i in 0.. {
+            let oid = repo.blob(format!("blob {}", i).as_bytes()).unwrap().to_string();
+            if seen.insert(oid[..4].to_string(), oid.clone()).is_some_and(|other| other != oid) {
+                return oid[..4].to_string();
+            }
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn resolve_commit_accepts_revision_expressions() {
+        let mut test = TestRepo::new();
+        let first = test.commit(AUTHOR, "a.txt", "one\n", "First");
+        let second = test.commit(AUTHOR, "a.txt", "two\n", "Second");
+        let repo = &test.repo;
+
+        let first_commit = repo.find_commit(first).unwrap();
+        repo.tag_lightweight("v1", first_commit.as_object(), false).unwrap();
+        let signature = first_commit.author();
+        repo.tag("v1-annotated", first_commit.as_object(), &signature, "Release", false).unwrap();
+        repo.branch("topic", &first_commit, false).unwrap();
+
+        assert_eq!(resolve_commit(repo, "HEAD").unwrap().id(), second);
+        assert_eq!(resolve_commit(repo, "HEAD~1").unwrap().id(), first);
+        assert_eq!(resolve_commit(repo, "v1").unwrap().id(), first);
+        assert_eq!(resolve_commit(repo, "v1-annotated").unwrap().id(), first);
+        assert_eq!(resolve_commit(repo, "topic").unwrap().id(), first);
+        assert_eq!(resolve_commit(repo, &second.to_string()[..7]).unwrap().id(), second);
+    }
+
+    #[test]
+    fn resolve_commit_rejects_unknown_and_ambiguous_specs() {
+        let mut test = TestRepo::new();
+        test.commit(AUTHOR, "a.txt", "one\n", "First");
+        let repo = &test.repo;
+
+        let unknown = resolve_commit(repo, "no-such-branch").err().unwrap();
+        assert!(matches!(unknown, AppError::CommitNotFound(_)));
+        assert_eq!(status_of(unknown), StatusCode::NOT_FOUND);
+        assert_eq!(status_of(resolve_commit(repo, "HEAD~5").err().unwrap()), StatusCode::NOT_FOUND);
+
+        let ambiguous = resolve_commit(repo, &ambiguous_prefix(repo)).err().unwrap();
+        assert!(matches!(ambiguous, AppError::BadRequest(_)));
+        assert_eq!(status_of(ambiguous), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,123 @@
+//! Moving HEAD to another commit (`git reset`).
+//!
+//! - `reset_head()`: Soft, mixed or hard reset with a dry-run summary
+//!
+//! Guards: the repository must have a commit checked out and no operation
+//! (merge, rebase, ...) in progress; mixed and hard resets need a working
+//! tree; a hard reset that would throw away uncommitted changes is refused
+//! unless forced. Commits dropped from the branch are listed so the caller
+//! can show what an undo would remove.
+//!
+//! Supports frontend: "undo last commit" in the history view
+
+use git2::{Repository, RepositoryState, ResetType, Sort};
+use std::collections::BTreeSet;
+
+use crate::error::{AppError, Result};
+use crate::git::mailmap;
+use crate::git::repository::{commit_to_info, head_commit, resolve_commit, GitRepository};
+use crate::git::status::read_working_tree_status;
+use crate::models::{ResetMode, ResetResult};
+
+/// Removed commits listed in a reset summary; the rest are only counted
+const MAX_RESET_COMMITS_LISTED: usize = 50;
+
+impl GitRepository {
+    /// Reset HEAD (and its branch) to `target`; with `dry_run`, only report what would change
+    pub fn reset_head(&self, target: &str, mode: ResetMode, dry_run: bool, force: bool) -> Result<ResetResult> {
+        let _op = self.op_log.start("reset", format!("target={} mode={:?} dry_run={}", target, mode, dry_run));
+        self.with_repo(|repo| {
+            if repo.is_bare() && mode != ResetMode::Soft {
+                return Err(AppError::BadRequest("Only soft resets are possible in a bare repository".to_string()));
+            }
+            if repo.state() != RepositoryState::Clean {
+                return Err(AppError::BadRequest(format!(
+                    "Cannot reset: another operation is in progress ({:?})",
+                    repo.state()
+                )));
+            }
+
+            let head = head_commit(repo)?
+                .ok_or_else(|| AppError::BadRequest("Cannot reset: the current branch has no commits yet".to_string()))?;
+            let target_commit = resolve_commit(repo, target)?;
+
+            let mut result = summarize(repo, &head, &target_commit, mode)?;
+
+            if mode == ResetMode::Hard && !result.discarded_changes.is_empty() && !force && !dry_run {
+                return Err(AppError::CheckoutConflict(format!(
+                    "Hard reset would discard uncommitted changes in {} file(s): {}; pass force=true to discard them",
+                    result.discarded_changes.len(),
+                    result.discarded_changes.join(", ")
+                )));
+            }
+
+            if dry_run {
+                return Ok(result);
+            }
+
+            let reset_type = match mode {
+                ResetMode::Soft => ResetType::Soft,
+                ResetMode::Mixed => ResetType::Mixed,
+                ResetMode::Hard => ResetType::Hard,
+            };
+            repo.reset(target_commit.as_object(), reset_type, None)?;
+
+            tracing::info!(
+                "Reset ({:?}) HEAD from {} to {} ({} commit(s) removed)",
+                mode,
+                head.id(),
+                target_commit.id(),
+                result.removed_commit_count
+            );
+            result.applied = true;
+            Ok(result)
+        })
+    }
+}
+
+/// What resetting from `head` to `target` changes, before anything is touched
+fn summarize(repo: &Repository, head: &git2::Commit, target: &git2::Commit, mode: ResetMode) -> Result<ResetResult> {
+    let mailmap = mailmap::load(repo);
+
+    // Commits on HEAD but not on the target, newest first
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    revwalk.push(head.id())?;
+    revwalk.hide(target.id())?;
+    let mut removed_commit_count = 0;
+    let mut removed_commits = Vec::new();
+    for oid in revwalk {
+        let oid = oid?;
+        removed_commit_count += 1;
+        if removed_commits.len() < MAX_RESET_COMMITS_LISTED {
+            removed_commits.push(commit_to_info(&repo.find_commit(oid)?, mailmap.as_ref()));
+        }
+    }
+    let (added_commit_count, _) = repo.graph_ahead_behind(target.id(), head.id())?;
+
+    let diff = repo.diff_tree_to_tree(Some(&head.tree()?), Some(&target.tree()?), None)?;
+
+    let discarded_changes = if mode == ResetMode::Hard {
+        let status = read_working_tree_status(repo, None, false)?;
+        let paths: BTreeSet<String> = status.staged.into_iter()
+            .chain(status.unstaged)
+            .map(|entry| entry.path)
+            .chain(status.conflicted)
+            .collect();
+        paths.into_iter().collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok(ResetResult {
+        mode,
+        target: target.id().to_string(),
+        previous_head: head.id().to_string(),
+        applied: false,
+        removed_commit_count,
+        removed_commits,
+        added_commit_count,
+        files_changed: diff.deltas().len(),
+        discarded_changes,
+    })
+}
@@ -0,0 +1,216 @@
+//! Signature verification for commits and tags.
+//!
+//! libgit2 can extract signatures but not check them, so verification runs
+//! `git` itself: it knows how to call gpg, gpgsm or ssh-keygen and honours the
+//! repository's `gpg.*` configuration, including `gpg.ssh.allowedSignersFile`.
+//! - Commits: `git log --no-walk` with the `%G?`/`%GS`/`%GF` placeholders, so
+//!   a whole page of commits costs one process
+//! - Tags: `git verify-tag --raw`, with GPG status lines or ssh-keygen
+//!   messages parsed into the same statuses
+//!
+//! Supports: GET /api/v1/repository/verify/{spec}, commit list `verify_signatures`
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+use git2::{ObjectType, Oid};
+
+use crate::error::{AppError, Result};
+use crate::git::repository::GitRepository;
+use crate::models::{CommitDetail, SignatureFormat, SignatureVerification, VerificationStatus};
+
+/// Field and record separators for the `git log` format
+const FIELD_SEP: char = '\x1f';
+const RECORD_SEP: char = '\x1e';
+
+impl GitRepository {
+    /// Verify the signature of a commit or tag (`spec` is an OID or ref name)
+    pub fn verify_signature(&self, spec: &str) -> Result<SignatureVerification> {
+        let _op = self.op_log.start("verify_signature", format!("spec={}", spec));
+        let (oid, kind, format) = self.with_repo(|repo| {
+            let object = repo
+                .revparse_single(spec)
+                .map_err(|_| AppError::ObjectNotFound(spec.to_string()))?;
+            match object.kind() {
+                Some(ObjectType::Tag) => {
+                    let tag = object.peel_to_tag()?;
+                    Ok((tag.id(), ObjectType::Tag, tag.message().and_then(signature_format)))
+                }
+                _ => {
+                    let commit = object
+                        .peel_to_commit()
+                        .map_err(|_| AppError::BadRequest(format!("{} is not a commit or tag", spec)))?;
+                    let format = repo
+                        .extract_signature(&commit.id(), None)
+                        .ok()
+                        .and_then(|(signature, _)| signature.as_str().and_then(signature_format));
+                    Ok((commit.id(), ObjectType::Commit, format))
+                }
+            }
+        })?;
+
+        let git_dir = self.git_dir()?;
+        let (status, signer, key) = match (kind, format) {
+            (_, None) => (VerificationStatus::Unsigned, None, None),
+            (ObjectType::Tag, Some(_)) => verify_tag(&git_dir, oid)?,
+            (_, Some(_)) => verify_commits(&git_dir, &[oid])?
+                .remove(&oid)
+                .unwrap_or((VerificationStatus::CannotVerify, None, None)),
+        };
+
+        Ok(SignatureVerification {
+            oid: oid.to_string(),
+            object_type: kind.to_string(),
+            format,
+            status,
+            signer,
+            key,
+        })
+    }
+
+    /// Set `verification` on each commit with one `git log` call for the page
+    pub fn attach_verification(&self, commits: &mut [CommitDetail]) -> Result<()> {
+        let oids: Vec<Oid> = commits.iter().filter_map(|c| Oid::from_str(&c.oid).ok()).collect();
+        if oids.is_empty() {
+            return Ok(());
+        }
+        let _op = self.op_log.start("verify_signatures", format!("commits={}", oids.len()));
+
+        let results = verify_commits(&self.git_dir()?, &oids)?;
+        for commit in commits.iter_mut() {
+            commit.verification = Oid::from_str(&commit.oid)
+                .ok()
+                .and_then(|oid| results.get(&oid))
+                .map(|(status, _, _)| *status);
+        }
+        Ok(())
+    }
+}
+
+type Verdict = (VerificationStatus, Option<String>, Option<String>);
+
+/// Signature format from the armor line of a signature block
+fn signature_format(text: &str) -> Option<SignatureFormat> {
+    if text.contains("-----BEGIN PGP SIGNATURE-----") {
+        Some(SignatureFormat::Gpg)
+    } else if text.contains("-----BEGIN SSH SIGNATURE-----") {
+        Some(SignatureFormat::Ssh)
+    } else if text.contains("-----BEGIN SIGNED MESSAGE-----") {
+        Some(SignatureFormat::X509)
+    } else {
+        None
+    }
+}
+
+/// Status, signer and key per commit via `git log --no-walk`
+fn verify_commits(git_dir: &Path, oids: &[Oid]) -> Result<HashMap<Oid, Verdict>> {
+    let output = Command::new("git")
+        .arg("--git-dir")
+        .arg(git_dir)
+        .args(["log", "--no-walk=unsorted"])
+        .arg(format!("--format=%H{0}%G?{0}%GS{0}%GF{1}", FIELD_SEP, RECORD_SEP))
+        .args(oids.iter().map(|oid| oid.to_string()))
+        .output()
+        .map_err(|e| AppError::Internal(format!("Failed to run git: {}", e)))?;
+    if !output.status.success() {
+        return Err(AppError::Internal(format!(
+            "git log failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut results = HashMap::new();
+    for record in text.split(RECORD_SEP) {
+        let mut fields = record.trim_start_matches('\n').split(FIELD_SEP);
+        let (Some(oid), Some(code)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let Ok(oid) = Oid::from_str(oid) else {
+            continue;
+        };
+        let non_empty = |s: Option<&str>| s.map(str::trim).filter(|s| !s.is_empty()).map(|s| s.to_string());
+        let signer = non_empty(fields.next());
+        let key = non_empty(fields.next());
+        results.insert(oid, (status_from_code(code), signer, key));
+    }
+    Ok(results)
+}
+
+/// git's `%G?` letter as a status
+fn status_from_code(code: &str) -> VerificationStatus {
+    match code {
+        "G" => VerificationStatus::Good,
+        "U" => VerificationStatus::Untrusted,
+        "B" => VerificationStatus::Bad,
+        "X" => VerificationStatus::ExpiredSignature,
+        "Y" => VerificationStatus::ExpiredKey,
+        "R" => VerificationStatus::RevokedKey,
+        "N" => VerificationStatus::Unsigned,
+        _ => VerificationStatus::CannotVerify,
+    }
+}
+
+/// Status, signer and key of a signed tag via `git verify-tag --raw`
+fn verify_tag(git_dir: &Path, oid: Oid) -> Result<Verdict> {
+    let output = Command::new("git")
+        .arg("--git-dir")
+        .arg(git_dir)
+        .args(["verify-tag", "--raw"])
+        .arg(oid.to_string())
+        .output()
+        .map_err(|e| AppError::Internal(format!("Failed to run git: {}", e)))?;
+    let text = String::from_utf8_lossy(&output.stderr);
+
+    // GPG: machine-readable status lines
+    let mut status = None;
+    let (mut signer, mut key) = (None, None);
+    let mut trusted = false;
+    for line in text.lines().filter_map(|l| l.strip_prefix("[GNUPG:] ")) {
+        let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let user_id = || rest.split_once(' ').map(|(_, uid)| uid.to_string());
+        match keyword {
+            "GOODSIG" => {
+                status.get_or_insert(VerificationStatus::Untrusted);
+                signer = user_id();
+            }
+            "BADSIG" => {
+                status = Some(VerificationStatus::Bad);
+                signer = user_id();
+            }
+            "EXPSIG" => status = Some(VerificationStatus::ExpiredSignature),
+            "EXPKEYSIG" => status = Some(VerificationStatus::ExpiredKey),
+            "REVKEYSIG" => status = Some(VerificationStatus::RevokedKey),
+            "ERRSIG" | "NO_PUBKEY" => status = Some(VerificationStatus::CannotVerify),
+            "VALIDSIG" => {
+                // VALIDSIG <fpr> ... <primary-key-fpr>
+                key = rest.split_whitespace().last().map(|s| s.to_string());
+            }
+            "TRUST_FULLY" | "TRUST_ULTIMATE" => trusted = true,
+            _ => {}
+        }
+    }
+    if let Some(status) = status {
+        let status = if status == VerificationStatus::Untrusted && trusted {
+            VerificationStatus::Good
+        } else {
+            status
+        };
+        return Ok((status, signer, key));
+    }
+
+    // SSH: ssh-keygen's human-readable verdict
+    // `Good "git" signature for <principal> with <alg> key <fingerprint>`
+    if let Some(good) = text.lines().find_map(|l| l.strip_prefix("Good \"git\" signature ")) {
+        let key = good.split_whitespace().last().map(|s| s.to_string());
+        return Ok(match good.strip_prefix("for ").and_then(|r| r.split_once(" with ")) {
+            Some((principal, _)) => (VerificationStatus::Good, Some(principal.to_string()), key),
+            None => (VerificationStatus::Untrusted, None, key),
+        });
+    }
+    if text.contains("Could not verify signature") || text.contains("Signature verification failed") {
+        return Ok((VerificationStatus::Bad, None, None));
+    }
+    Ok((VerificationStatus::CannotVerify, None, None))
+}
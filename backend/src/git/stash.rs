@@ -0,0 +1,77 @@
+//! Stash operations.
+//!
+//! - `stash_push()`: Save local changes and reset the working tree to HEAD
+//! - `stash_pop()`: Reapply a stash entry and drop it
+//!
+//! Both need `&mut Repository`, so they lock the repository directly instead
+//! of going through `with_repo`.
+//!
+//! Supports frontend: "stash and switch" when a checkout is refused for uncommitted changes
+
+use git2::{ErrorCode, Repository, StashFlags};
+
+use crate::error::{AppError, Result};
+use crate::git::commit::user_signature;
+use crate::git::repository::GitRepository;
+use crate::models::StashEntry;
+
+impl GitRepository {
+    /// Stash tracked changes (and untracked files with `include_untracked`), like `git stash push`
+    pub fn stash_push(&self, message: Option<&str>, include_untracked: bool) -> Result<StashEntry> {
+        let mut repo = self.repo.lock().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
+        if repo.is_bare() {
+            return Err(AppError::BadRequest("Cannot stash in a bare repository".to_string()));
+        }
+
+        let signature = user_signature(&repo)?;
+        let flags = if include_untracked { StashFlags::INCLUDE_UNTRACKED } else { StashFlags::DEFAULT };
+
+        let oid = repo.stash_save2(&signature, message, Some(flags)).map_err(|e| match e.code() {
+            ErrorCode::NotFound => AppError::BadRequest("No local changes to stash".to_string()),
+            _ => AppError::Git(e),
+        })?;
+
+        let entry = stash_entry(&mut repo, 0)?
+            .ok_or_else(|| AppError::Internal("Stash entry missing after save".to_string()))?;
+        tracing::info!("Stashed changes as {} ({})", oid, entry.message);
+        Ok(entry)
+    }
+
+    /// Apply stash entry `index` (0 = most recent) and drop it, like `git stash pop`
+    ///
+    /// If applying would overwrite local changes, nothing is touched and the
+    /// entry stays on the stash.
+    pub fn stash_pop(&self, index: usize) -> Result<StashEntry> {
+        let mut repo = self.repo.lock().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
+        if repo.is_bare() {
+            return Err(AppError::BadRequest("Cannot apply a stash in a bare repository".to_string()));
+        }
+
+        let entry = stash_entry(&mut repo, index)?
+            .ok_or_else(|| AppError::PathNotFound(format!("Stash entry not found: stash@{{{}}}", index)))?;
+
+        repo.stash_pop(index, None).map_err(|e| match e.code() {
+            ErrorCode::Conflict | ErrorCode::MergeConflict => AppError::CheckoutConflict(format!(
+                "Cannot apply stash@{{{}}}: it conflicts with local changes",
+                index
+            )),
+            _ => AppError::Git(e),
+        })?;
+
+        tracing::info!("Popped stash@{{{}}} ({})", index, entry.message);
+        Ok(entry)
+    }
+}
+
+/// The stash entry at `index`, if there is one
+fn stash_entry(repo: &mut Repository, index: usize) -> Result<Option<StashEntry>> {
+    let mut found = None;
+    repo.stash_foreach(|i, message, oid| {
+        if i == index {
+            found = Some(StashEntry { index, message: message.to_string(), oid: oid.to_string() });
+            return false;
+        }
+        true
+    })?;
+    Ok(found)
+}
@@ -0,0 +1,602 @@
+//! Repository statistics computed from the commit cache.
+//!
+//! Activity: commits per day, ISO week or month (UTC), from the first to the
+//! last matching commit with empty periods filled in. Commits can be limited
+//! to a path (or glob), which reuses the path cache entry the commit list
+//! builds, and to one author email (case-insensitive, after mailmap). Bot
+//! authors are left out unless asked for.
+//!
+//! Punch card: commits per day of the week and hour of the day, in each
+//! author's own timezone (from the offset recorded with the author time),
+//! with the same path, author and bot filters.
+//!
+//! Author line statistics: insertions and deletions per author over a ref
+//! range (`v1.0..main`) or a time window.
+//!
+//! Hotspots: files ranked by how many commits changed them and by churn
+//! (lines added plus removed) within a time window, optionally below a path
+//! or matching a glob, whose commits come from the path cache.
+//!
+//! Line counts come from diffing each commit against its first parent, with
+//! rename detection; the per-file counts are memoized per OID, so later
+//! requests (and the same commits after HEAD moves) are served without
+//! diffing. The memo gets an eighth of the cache budget and drops the least
+//! recently used commits past it. Merge commits are left out, like `git log --numstat`.
+//!
+//! Repositories over the cache budget are answered by walking history.
+//!
+//! Supports: GET /api/v1/repository/stats/activity, /stats/punchcard, /stats/authors
+//! and /stats/hotspots
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, Timelike};
+use git2::{Oid, Repository, Sort};
+
+use crate::error::{AppError, Result};
+use crate::git::cache::{local_time, PathFilter, TimeWindow};
+use crate::git::mailmap;
+use crate::git::repository::{push_head, resolve_commit, GitRepository};
+use crate::models::{
+    ActivityBucket, ActivityGranularity, ActivityResponse, AuthorLineStats, AuthorStatsResponse, FileHotspot, HotspotSort,
+    HotspotsResponse, PunchCardCell, PunchCardResponse,
+};
+
+/// Share of the cache budget (`--cache-max-mb`) for memoized line counts
+pub const LINE_COUNT_BUDGET_DIVISOR: usize = 8;
+
+/// Lines one commit added to and removed from one file
+#[derive(Debug, Clone)]
+pub struct FileLineCount {
+    /// New path; the old one for deletions
+    pub path: String,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+/// Per-file line counts memoized per commit OID, within a byte budget
+///
+/// Past the budget, the least recently used commits are dropped in one batch
+/// until a quarter of the budget is free again, so eviction sorts the entries
+/// once per batch rather than scanning them per insert.
+pub struct LineCountMemo {
+    entries: HashMap<Oid, MemoizedLineCounts>,
+    bytes: usize,
+    max_bytes: usize,
+    /// Monotonic counter bumped on every lookup
+    access_tick: u64,
+}
+
+struct MemoizedLineCounts {
+    files: Vec<FileLineCount>,
+    size_bytes: usize,
+    last_used: u64,
+}
+
+impl LineCountMemo {
+    pub fn new(max_bytes: usize) -> Self {
+        Self { entries: HashMap::new(), bytes: 0, max_bytes, access_tick: 0 }
+    }
+
+    /// Per-file line counts of a commit, diffed on first use
+    fn get(&mut self, repo: &Repository, oid: Oid) -> Result<&[FileLineCount]> {
+        self.access_tick += 1;
+        let tick = self.access_tick;
+        if !self.entries.contains_key(&oid) {
+            let files = commit_line_counts(repo, oid)?;
+            let size_bytes = std::mem::size_of::<(Oid, MemoizedLineCounts)>()
+                + files.iter().map(|f| std::mem::size_of::<FileLineCount>() + f.path.len()).sum::<usize>();
+            if self.bytes + size_bytes > self.max_bytes {
+                self.evict(size_bytes);
+            }
+            self.bytes += size_bytes;
+            self.entries.insert(oid, MemoizedLineCounts { files, size_bytes, last_used: tick });
+        }
+        let entry = self.entries.get_mut(&oid).expect("memoized above");
+        entry.last_used = tick;
+        Ok(&entry.files)
+    }
+
+    /// Drop least recently used commits until `incoming` bytes fit with a
+    /// quarter of the budget to spare
+    fn evict(&mut self, incoming: usize) {
+        let target = (self.max_bytes - self.max_bytes / 4).saturating_sub(incoming);
+        let mut by_age: Vec<(u64, Oid)> = self.entries.iter().map(|(oid, e)| (e.last_used, *oid)).collect();
+        by_age.sort_unstable();
+        let before = self.entries.len();
+        for (_, oid) in by_age {
+            if self.bytes <= target {
+                break;
+            }
+            if let Some(removed) = self.entries.remove(&oid) {
+                self.bytes -= removed.size_bytes;
+            }
+        }
+        tracing::debug!(
+            "Evicted {} memoized line counts ({} KB of {} KB budget)",
+            before - self.entries.len(),
+            self.bytes / 1024,
+            self.max_bytes / 1024
+        );
+    }
+}
+
+/// When a commit happened, as the cache records it
+struct CommitTimes {
+    timestamp: i64,
+    /// See `CachedCommit::author_local_time`
+    author_local_time: i64,
+}
+
+/// A non-merge commit counted towards an author
+struct AuthoredCommit {
+    oid: Oid,
+    name: String,
+    email: String,
+    timestamp: i64,
+}
+
+impl GitRepository {
+    /// Commit counts per period, for the whole history or only the commits
+    /// touching `path` and/or authored by `author`; bot commits are left out
+    /// unless `include_bots`
+    pub fn get_activity(
+        &self,
+        path: Option<&str>,
+        author: Option<&str>,
+        granularity: ActivityGranularity,
+        include_bots: bool,
+    ) -> Result<ActivityResponse> {
+        let _op = self.op_log.start(
+            "activity",
+            format!("path={} author={} granularity={:?}", path.unwrap_or(""), author.unwrap_or(""), granularity),
+        );
+        let timestamps: Vec<i64> = self.matching_commit_times(path, author, include_bots)?
+            .into_iter()
+            .map(|times| times.timestamp)
+            .collect();
+
+        Ok(ActivityResponse {
+            granularity,
+            path: path.filter(|p| !p.is_empty()).map(str::to_string),
+            author: author.map(str::to_string),
+            total_commits: timestamps.len(),
+            buckets: activity_buckets(timestamps, granularity),
+        })
+    }
+
+    /// Commits per hour of the week in the author's local time; bot commits
+    /// are left out unless `include_bots`
+    pub fn get_punch_card(&self, path: Option<&str>, author: Option<&str>, include_bots: bool) -> Result<PunchCardResponse> {
+        let _op = self.op_log.start(
+            "punch_card",
+            format!("path={} author={}", path.unwrap_or(""), author.unwrap_or("")),
+        );
+        let times = self.matching_commit_times(path, author, include_bots)?;
+
+        let mut counts = [[0usize; 24]; 7];
+        for commit in &times {
+            let Some(local) = DateTime::from_timestamp(commit.author_local_time, 0) else {
+                continue;
+            };
+            counts[local.weekday().num_days_from_sunday() as usize][local.hour() as usize] += 1;
+        }
+
+        let mut cells = Vec::with_capacity(7 * 24);
+        for (day, hours) in counts.iter().enumerate() {
+            for (hour, &commit_count) in hours.iter().enumerate() {
+                cells.push(PunchCardCell { day: day as u8, hour: hour as u8, commit_count });
+            }
+        }
+
+        Ok(PunchCardResponse {
+            path: path.filter(|p| !p.is_empty()).map(str::to_string),
+            author: author.map(str::to_string),
+            total_commits: times.len(),
+            cells,
+        })
+    }
+
+    /// Times of the commits touching `path` (all commits when `None`) and
+    /// authored by `author` (any author but bots, unless `include_bots`, when
+    /// `None`), newest first
+    fn matching_commit_times(
+        &self,
+        path: Option<&str>,
+        author: Option<&str>,
+        include_bots: bool,
+    ) -> Result<Vec<CommitTimes>> {
+        // A bot asked for by email is counted
+        let by_author = |name: &str, email: &str| match author {
+            Some(author) => author.eq_ignore_ascii_case(email),
+            None => include_bots || !self.is_bot(name, email),
+        };
+
+        if self.is_cache_over_budget() {
+            return self.with_repo(|repo| {
+                let filter = PathFilter::parse(path.unwrap_or(""))?;
+                let mut revwalk = repo.revwalk()?;
+                revwalk.set_sorting(Sort::TIME)?;
+                push_head(&mut revwalk, repo)?;
+                let mailmap = mailmap::load(repo);
+
+                let mut times = Vec::new();
+                for oid_result in revwalk {
+                    let commit = repo.find_commit(oid_result?)?;
+                    let commit_author = mailmap::author(&commit, mailmap.as_ref());
+                    if by_author(commit_author.name().unwrap_or("Unknown"), commit_author.email().unwrap_or(""))
+                        && filter.matches(repo, &commit)?
+                    {
+                        times.push(CommitTimes {
+                            timestamp: commit.time().seconds(),
+                            author_local_time: local_time(commit.author().when()),
+                        });
+                    }
+                }
+                Ok(times)
+            });
+        }
+
+        self.with_cache(|cache, repo| {
+            let indices = cache.get_path_commit_indices(repo, path.unwrap_or(""))?;
+            Ok(indices
+                .into_iter()
+                .map(|idx| &cache.all_commits[idx])
+                .filter(|commit| by_author(&commit.author_name, &commit.author_email))
+                .map(|commit| CommitTimes {
+                    timestamp: commit.timestamp,
+                    author_local_time: commit.author_local_time,
+                })
+                .collect())
+        })
+    }
+
+    /// Insertions and deletions per author over the commits of `range`
+    /// (`from..to`), or of HEAD's history, within `window`
+    pub fn get_author_stats(&self, range: Option<&str>, window: TimeWindow, include_bots: bool) -> Result<AuthorStatsResponse> {
+        let _op = self.op_log.start(
+            "author_stats",
+            format!("range={} since={:?} until={:?}", range.unwrap_or(""), window.since, window.until),
+        );
+
+        let walk_and_count = |repo: &Repository| {
+            let commits = walk_authored_commits(repo, range, window)?;
+            self.count_lines(repo, commits, include_bots)
+        };
+        let authors = if range.is_some() || self.is_cache_over_budget() {
+            self.with_repo(walk_and_count)?
+        } else {
+            self.with_cache(|cache, repo| {
+                let commits = cache.all_commits
+                    .iter()
+                    .filter(|c| c.parent_count <= 1 && window.contains(c.timestamp))
+                    .map(|c| Ok(AuthoredCommit {
+                        oid: Oid::from_str(&c.oid)?,
+                        name: c.author_name.to_string(),
+                        email: c.author_email.to_string(),
+                        timestamp: c.timestamp,
+                    }))
+                    .collect::<Result<Vec<_>>>()?;
+                self.count_lines(repo, commits, include_bots)
+            })?
+        };
+
+        Ok(AuthorStatsResponse {
+            range: range.map(str::to_string),
+            since: window.since,
+            until: window.until,
+            commit_count: authors.iter().map(|a| a.commit_count).sum(),
+            insertions: authors.iter().map(|a| a.insertions).sum(),
+            deletions: authors.iter().map(|a| a.deletions).sum(),
+            authors,
+        })
+    }
+
+    /// Line counts of `commits` summed per author email (case-insensitive),
+    /// most lines changed first
+    fn count_lines(&self, repo: &Repository, commits: Vec<AuthoredCommit>, include_bots: bool) -> Result<Vec<AuthorLineStats>> {
+        let mut line_counts = self.line_counts
+            .lock()
+            .map_err(|_| AppError::Internal("Line count lock poisoned".to_string()))?;
+
+        let mut authors: HashMap<String, AuthorLineStats> = HashMap::new();
+        for commit in commits {
+            if !include_bots && self.is_bot(&commit.name, &commit.email) {
+                continue;
+            }
+            let files = line_counts.get(repo, commit.oid)?;
+            let insertions: usize = files.iter().map(|f| f.insertions).sum();
+            let deletions: usize = files.iter().map(|f| f.deletions).sum();
+            // Commits arrive newest first, so the first name seen is the latest
+            let author = authors.entry(commit.email.to_lowercase()).or_insert_with(|| AuthorLineStats {
+                name: commit.name,
+                email: commit.email,
+                commit_count: 0,
+                insertions: 0,
+                deletions: 0,
+            });
+            author.commit_count += 1;
+            author.insertions += insertions;
+            author.deletions += deletions;
+        }
+
+        let mut authors: Vec<AuthorLineStats> = authors.into_values().collect();
+        authors.sort_by(|a, b| {
+            (b.insertions + b.deletions)
+                .cmp(&(a.insertions + a.deletions))
+                .then_with(|| a.email.cmp(&b.email))
+        });
+        Ok(authors)
+    }
+}
+
+impl GitRepository {
+    /// Files changed by the non-merge commits in `window`, limited to those
+    /// `path` covers (a path, directory or glob; everything when `None`),
+    /// ranked by `sort`
+    pub fn get_hotspots(
+        &self,
+        path: Option<&str>,
+        window: TimeWindow,
+        sort: HotspotSort,
+        limit: usize,
+        include_bots: bool,
+    ) -> Result<HotspotsResponse> {
+        let _op = self.op_log.start(
+            "hotspots",
+            format!("path={} since={:?} sort={:?}", path.unwrap_or(""), window.since, sort),
+        );
+        let filter = PathFilter::parse(path.unwrap_or(""))?;
+
+        let (commit_count, mut files) = if self.is_cache_over_budget() {
+            self.with_repo(|repo| {
+                let commits = walk_authored_commits(repo, None, window)?;
+                self.rank_files(repo, commits, &filter, include_bots)
+            })?
+        } else {
+            self.with_cache(|cache, repo| {
+                // The path's entry already narrows history to commits touching it
+                let indices = cache.get_path_commit_indices(repo, path.unwrap_or(""))?;
+                let commits = indices
+                    .into_iter()
+                    .map(|idx| &cache.all_commits[idx])
+                    .filter(|c| c.parent_count <= 1 && window.contains(c.timestamp))
+                    .map(|c| Ok(AuthoredCommit {
+                        oid: Oid::from_str(&c.oid)?,
+                        name: c.author_name.to_string(),
+                        email: c.author_email.to_string(),
+                        timestamp: c.timestamp,
+                    }))
+                    .collect::<Result<Vec<_>>>()?;
+                self.rank_files(repo, commits, &filter, include_bots)
+            })?
+        };
+
+        let churn = |f: &FileHotspot| f.insertions + f.deletions;
+        files.sort_by(|a, b| {
+            let order = match sort {
+                HotspotSort::Frequency => b.commit_count.cmp(&a.commit_count).then_with(|| churn(b).cmp(&churn(a))),
+                HotspotSort::Churn => churn(b).cmp(&churn(a)).then_with(|| b.commit_count.cmp(&a.commit_count)),
+            };
+            order.then_with(|| a.path.cmp(&b.path))
+        });
+        let total_files = files.len();
+        files.truncate(limit);
+
+        Ok(HotspotsResponse {
+            path: path.filter(|p| !p.is_empty()).map(str::to_string),
+            since: window.since,
+            until: window.until,
+            sort,
+            commit_count,
+            total_files,
+            files,
+        })
+    }
+
+    /// Per-file totals over `commits`, counting only files `filter` covers;
+    /// also returns how many commits changed at least one of them
+    fn rank_files(
+        &self,
+        repo: &Repository,
+        commits: Vec<AuthoredCommit>,
+        filter: &PathFilter,
+        include_bots: bool,
+    ) -> Result<(usize, Vec<FileHotspot>)> {
+        let mut line_counts = self.line_counts
+            .lock()
+            .map_err(|_| AppError::Internal("Line count lock poisoned".to_string()))?;
+
+        let mut commit_count = 0;
+        let mut files: HashMap<String, (FileHotspot, HashSet<String>)> = HashMap::new();
+        for commit in commits {
+            if !include_bots && self.is_bot(&commit.name, &commit.email) {
+                continue;
+            }
+            let mut touched = false;
+            for file in line_counts.get(repo, commit.oid)? {
+                if !filter.covers_file(&file.path) {
+                    continue;
+                }
+                touched = true;
+                let (hotspot, authors) = files.entry(file.path.clone()).or_insert_with(|| {
+                    let hotspot = FileHotspot {
+                        path: file.path.clone(),
+                        commit_count: 0,
+                        insertions: 0,
+                        deletions: 0,
+                        author_count: 0,
+                        last_changed_timestamp: commit.timestamp,
+                    };
+                    (hotspot, HashSet::new())
+                });
+                hotspot.commit_count += 1;
+                hotspot.insertions += file.insertions;
+                hotspot.deletions += file.deletions;
+                hotspot.last_changed_timestamp = hotspot.last_changed_timestamp.max(commit.timestamp);
+                authors.insert(commit.email.to_lowercase());
+            }
+            commit_count += usize::from(touched);
+        }
+
+        let files = files
+            .into_values()
+            .map(|(hotspot, authors)| FileHotspot { author_count: authors.len(), ..hotspot })
+            .collect();
+        Ok((commit_count, files))
+    }
+}
+
+/// Non-merge commits of `range` (HEAD's history without one) within
+/// `window`, newest first, with mailmapped authors
+fn walk_authored_commits(repo: &Repository, range: Option<&str>, window: TimeWindow) -> Result<Vec<AuthoredCommit>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TIME)?;
+    match range {
+        Some(range) => {
+            let (from, to) = range
+                .split_once("..")
+                .filter(|(_, to)| !to.starts_with('.'))
+                .ok_or_else(|| AppError::BadRequest(format!("Expected a range like v1.0..main, got '{}'", range)))?;
+            revwalk.push(resolve_commit(repo, if to.is_empty() { "HEAD" } else { to })?.id())?;
+            if !from.is_empty() {
+                revwalk.hide(resolve_commit(repo, from)?.id())?;
+            }
+        }
+        None => push_head(&mut revwalk, repo)?,
+    }
+    let mailmap = mailmap::load(repo);
+
+    let mut commits = Vec::new();
+    for oid_result in revwalk {
+        let commit = repo.find_commit(oid_result?)?;
+        if commit.parent_count() > 1 || !window.contains(commit.time().seconds()) {
+            continue;
+        }
+        let author = mailmap::author(&commit, mailmap.as_ref());
+        commits.push(AuthoredCommit {
+            oid: commit.id(),
+            name: author.name().unwrap_or("Unknown").to_string(),
+            email: author.email().unwrap_or("").to_string(),
+            timestamp: commit.time().seconds(),
+        });
+    }
+    Ok(commits)
+}
+
+/// Lines added and removed per file by a commit against its first parent,
+/// renames counted as moves rather than as a deletion plus an addition;
+/// binary files count no lines
+fn commit_line_counts(repo: &Repository, oid: Oid) -> Result<Vec<FileLineCount>> {
+    let commit = repo.find_commit(oid)?;
+    let tree = commit.tree()?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+    diff.find_similar(None)?;
+
+    let mut files = Vec::with_capacity(diff.deltas().len());
+    for (idx, delta) in diff.deltas().enumerate() {
+        let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+            continue;
+        };
+        let (_, insertions, deletions) = match git2::Patch::from_diff(&diff, idx)? {
+            Some(patch) => patch.line_stats()?,
+            None => (0, 0, 0),
+        };
+        files.push(FileLineCount {
+            path: path.to_string_lossy().to_string(),
+            insertions,
+            deletions,
+        });
+    }
+    Ok(files)
+}
+
+/// Commits per period from the first to the last timestamp, gaps filled with zero
+pub fn activity_buckets(timestamps: impl IntoIterator<Item = i64>, granularity: ActivityGranularity) -> Vec<ActivityBucket> {
+    let mut counts: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    for timestamp in timestamps {
+        let date = DateTime::from_timestamp(timestamp, 0).unwrap_or_default().date_naive();
+        *counts.entry(period_start(date, granularity)).or_insert(0) += 1;
+    }
+
+    let (Some(&first), Some(&last)) = (counts.keys().next(), counts.keys().next_back()) else {
+        return Vec::new();
+    };
+
+    let mut buckets = Vec::new();
+    let mut period = first;
+    while period <= last {
+        buckets.push(ActivityBucket {
+            period: period_label(period, granularity),
+            commit_count: counts.get(&period).copied().unwrap_or(0),
+        });
+        let next = match granularity {
+            ActivityGranularity::Day => period.checked_add_days(Days::new(1)),
+            ActivityGranularity::Week => period.checked_add_days(Days::new(7)),
+            ActivityGranularity::Month => period.checked_add_months(Months::new(1)),
+        };
+        let Some(next) = next else { break };
+        period = next;
+    }
+    buckets
+}
+
+/// First day of the period containing `date`
+fn period_start(date: NaiveDate, granularity: ActivityGranularity) -> NaiveDate {
+    match granularity {
+        ActivityGranularity::Day => date,
+        ActivityGranularity::Week => date - Days::new(u64::from(date.weekday().num_days_from_monday())),
+        ActivityGranularity::Month => date.with_day(1).unwrap_or(date),
+    }
+}
+
+fn period_label(start: NaiveDate, granularity: ActivityGranularity) -> String {
+    match granularity {
+        ActivityGranularity::Day => start.format("%Y-%m-%d").to_string(),
+        ActivityGranularity::Week => {
+            let week = start.iso_week();
+            format!("{:04}-W{:02}", week.year(), week.week())
+        }
+        ActivityGranularity::Month => start.format("%Y-%m").to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo::TestRepo;
+
+    #[test]
+    fn line_count_memo_evicts_least_recently_used_commits_past_its_budget() {
+        let mut test = TestRepo::new();
+        let author = ("Ada", "ada@example.com");
+        let oids: Vec<Oid> = (0..8)
+            .map(|i| test.commit(author, &format!("file{}.txt", i), "one\ntwo\n", "add file"))
+            .collect();
+
+        let mut unbounded = LineCountMemo::new(usize::MAX);
+        unbounded.get(&test.repo, oids[0]).unwrap();
+        let entry_bytes = unbounded.bytes;
+
+        // Room for four commits: the fifth evicts down to three quarters of the budget
+        let mut memo = LineCountMemo::new(4 * entry_bytes);
+        for &oid in &oids[..4] {
+            memo.get(&test.repo, oid).unwrap();
+        }
+        assert_eq!(memo.entries.len(), 4);
+        memo.get(&test.repo, oids[0]).unwrap();
+        let files = memo.get(&test.repo, oids[4]).unwrap();
+        assert_eq!(files[0].path, "file4.txt");
+        assert_eq!(files[0].insertions, 2);
+
+        assert!(memo.bytes <= 4 * entry_bytes);
+        assert!(memo.entries.contains_key(&oids[0]), "recently used commit was evicted");
+        assert!(memo.entries.contains_key(&oids[4]));
+        assert!(!memo.entries.contains_key(&oids[1]), "least recently used commit was kept");
+        assert_eq!(memo.bytes, memo.entries.len() * entry_bytes);
+    }
+}
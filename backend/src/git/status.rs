@@ -0,0 +1,293 @@
+//! Working tree status.
+//!
+//! Provides:
+//! - `get_working_tree_status()`: Staged/unstaged/untracked/conflicted files
+//! - `read_working_tree_status()`: Same, on an already locked repository
+//! - `get_status_files()`: One entry per changed path with index and working
+//!   tree status side by side, and the kind of each conflict
+//! - `ensure_clean_for_checkout()`: Checkout guard with a per-file error message
+//! - `ensure_clean()`: Same guard for other operations that rewrite the working tree
+//!
+//! Supports frontend: StatusTab, HistoryTab uncommitted changes, Changes panel, branch switching
+
+use git2::{Repository, Status, StatusOptions};
+use std::collections::{HashMap, HashSet};
+
+use crate::error::{AppError, Result};
+use crate::git::repository::GitRepository;
+use crate::models::{ConflictKind, FileStatus, StatusEntry, StatusFile, StatusFiles, WorkingTreeStatus};
+
+/// How many files a checkout error lists before summarizing the rest
+const CHECKOUT_BLOCKERS_SHOWN: usize = 5;
+
+impl GitRepository {
+    pub fn get_working_tree_status(&self, path: Option<&str>) -> Result<WorkingTreeStatus> {
+        self.with_repo(|repo| read_working_tree_status(repo, path, true))
+    }
+
+    /// Every changed path under `path`, with staged and unstaged status per path
+    pub fn get_status_files(&self, path: Option<&str>, include_untracked: bool) -> Result<StatusFiles> {
+        self.with_repo(|repo| {
+            let mut result = StatusFiles {
+                files: Vec::new(),
+                staged_count: 0,
+                unstaged_count: 0,
+                untracked_count: 0,
+                conflicted_count: 0,
+            };
+            if repo.is_bare() {
+                return Ok(result);
+            }
+
+            let mut opts = StatusOptions::new();
+            opts.include_untracked(include_untracked)
+                .recurse_untracked_dirs(true)
+                .include_ignored(false)
+                .renames_head_to_index(true);
+            if let Some(p) = path
+                && !p.is_empty()
+            {
+                opts.pathspec(p);
+            }
+
+            let statuses = repo.statuses(Some(&mut opts))?;
+            let conflicts = conflict_kinds(repo)?;
+
+            for entry in statuses.iter() {
+                let flags = entry.status();
+                let Some(entry_path) = entry.path().map(|p| p.to_string()) else {
+                    continue;
+                };
+
+                if flags.contains(Status::CONFLICTED) {
+                    result.conflicted_count += 1;
+                    result.files.push(StatusFile {
+                        conflict: Some(conflicts.get(&entry_path).copied().unwrap_or(ConflictKind::BothModified)),
+                        path: entry_path,
+                        old_path: None,
+                        index_status: None,
+                        worktree_status: None,
+                        is_untracked: false,
+                    });
+                    continue;
+                }
+
+                let is_untracked = flags.contains(Status::WT_NEW);
+                let index_status = index_status(flags);
+                let worktree_status = worktree_status(flags);
+
+                // For staged renames, `entry.path()` is the old path
+                let delta = entry.head_to_index().filter(|_| index_status.is_some());
+                let new_path = delta.as_ref()
+                    .and_then(|d| d.new_file().path())
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_else(|| entry_path.clone());
+                let old_path = delta.as_ref()
+                    .and_then(|d| d.old_file().path())
+                    .map(|p| p.to_string_lossy().to_string())
+                    .filter(|p| *p != new_path);
+
+                result.staged_count += usize::from(index_status.is_some());
+                result.unstaged_count += usize::from(worktree_status.is_some());
+                result.untracked_count += usize::from(is_untracked);
+                result.files.push(StatusFile {
+                    path: new_path,
+                    old_path,
+                    index_status,
+                    worktree_status,
+                    is_untracked,
+                    conflict: None,
+                });
+            }
+
+            result.files.sort_by(|a, b| a.path.cmp(&b.path));
+            Ok(result)
+        })
+    }
+}
+
+/// Read the working tree status, optionally limited to a path
+pub fn read_working_tree_status(
+    repo: &Repository,
+    path: Option<&str>,
+    include_untracked: bool,
+) -> Result<WorkingTreeStatus> {
+    let mut status = WorkingTreeStatus {
+        has_changes: false,
+        files_changed: 0,
+        staged: Vec::new(),
+        unstaged: Vec::new(),
+        untracked: Vec::new(),
+        conflicted: Vec::new(),
+    };
+
+    // Bare repos have no working tree; before the first commit everything
+    // staged shows as added and everything else as untracked
+    if repo.is_bare() {
+        return Ok(status);
+    }
+
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(include_untracked)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false)
+        .renames_head_to_index(true);
+
+    if let Some(p) = path
+        && !p.is_empty()
+    {
+        opts.pathspec(p);
+    }
+
+    let statuses = repo.statuses(Some(&mut opts))?;
+    let mut changed_paths = HashSet::new();
+
+    for entry in statuses.iter() {
+        let flags = entry.status();
+        let Some(entry_path) = entry.path().map(|p| p.to_string()) else {
+            continue;
+        };
+
+        if flags.contains(Status::CONFLICTED) {
+            changed_paths.insert(entry_path.clone());
+            status.conflicted.push(entry_path);
+            continue;
+        }
+        if flags.contains(Status::WT_NEW) {
+            changed_paths.insert(entry_path.clone());
+            status.untracked.push(entry_path);
+            continue;
+        }
+
+        if let Some(file_status) = index_status(flags) {
+            // For staged renames, `entry.path()` is the old path
+            let delta = entry.head_to_index();
+            let new_path = delta.as_ref()
+                .and_then(|d| d.new_file().path())
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|| entry_path.clone());
+            let old_path = delta.as_ref()
+                .and_then(|d| d.old_file().path())
+                .map(|p| p.to_string_lossy().to_string())
+                .filter(|p| *p != new_path);
+            changed_paths.insert(new_path.clone());
+            status.staged.push(StatusEntry {
+                path: new_path,
+                old_path,
+                status: file_status,
+            });
+        }
+        if let Some(file_status) = worktree_status(flags) {
+            let path = entry.index_to_workdir()
+                .and_then(|d| d.new_file().path().map(|p| p.to_string_lossy().to_string()))
+                .unwrap_or(entry_path);
+            changed_paths.insert(path.clone());
+            status.unstaged.push(StatusEntry {
+                path,
+                old_path: None,
+                status: file_status,
+            });
+        }
+    }
+
+    status.files_changed = changed_paths.len();
+    status.has_changes = status.files_changed > 0;
+    Ok(status)
+}
+
+/// Refuse to switch branches while tracked files have uncommitted changes
+///
+/// Untracked files are ignored, matching `git switch`.
+pub fn ensure_clean_for_checkout(repo: &Repository) -> Result<()> {
+    ensure_clean(repo, "switch branches")
+}
+
+/// Refuse `action` (e.g. "merge") while tracked files have uncommitted changes
+pub fn ensure_clean(repo: &Repository, action: &str) -> Result<()> {
+    let status = read_working_tree_status(repo, None, false)?;
+    if !status.has_changes {
+        return Ok(());
+    }
+
+    let mut blockers: Vec<String> = status.conflicted
+        .iter()
+        .map(|p| format!("{} (conflicted)", p))
+        .collect();
+    for entry in &status.staged {
+        blockers.push(format!("{} (staged, {})", entry.path, entry.status.as_str()));
+    }
+    for entry in &status.unstaged {
+        blockers.push(format!("{} ({})", entry.path, entry.status.as_str()));
+    }
+
+    let shown = blockers.iter().take(CHECKOUT_BLOCKERS_SHOWN).cloned().collect::<Vec<_>>().join(", ");
+    let more = if blockers.len() > CHECKOUT_BLOCKERS_SHOWN {
+        format!(" and {} more", blockers.len() - CHECKOUT_BLOCKERS_SHOWN)
+    } else {
+        String::new()
+    };
+
+    Err(AppError::CheckoutConflict(format!(
+        "Cannot {}: you have uncommitted changes in: {}{}",
+        action, shown, more
+    )))
+}
+
+/// Kind of each conflict in the index, keyed by path
+fn conflict_kinds(repo: &Repository) -> Result<HashMap<String, ConflictKind>> {
+    let mut index = repo.index()?;
+    index.read(false)?;
+
+    let mut kinds = HashMap::new();
+    if !index.has_conflicts() {
+        return Ok(kinds);
+    }
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        let kind = match (conflict.ancestor.is_some(), conflict.our.is_some(), conflict.their.is_some()) {
+            (true, true, true) => ConflictKind::BothModified,
+            (false, true, true) => ConflictKind::BothAdded,
+            (true, false, false) => ConflictKind::BothDeleted,
+            (false, true, false) => ConflictKind::AddedByUs,
+            (false, false, true) => ConflictKind::AddedByThem,
+            (true, false, true) => ConflictKind::DeletedByUs,
+            (true, true, false) => ConflictKind::DeletedByThem,
+            (false, false, false) => continue,
+        };
+        let entry = conflict.our.as_ref().or(conflict.their.as_ref()).or(conflict.ancestor.as_ref());
+        if let Some(entry) = entry {
+            kinds.insert(String::from_utf8_lossy(&entry.path).into_owned(), kind);
+        }
+    }
+    Ok(kinds)
+}
+
+fn index_status(flags: Status) -> Option<FileStatus> {
+    if flags.contains(Status::INDEX_NEW) {
+        Some(FileStatus::Added)
+    } else if flags.contains(Status::INDEX_MODIFIED) {
+        Some(FileStatus::Modified)
+    } else if flags.contains(Status::INDEX_DELETED) {
+        Some(FileStatus::Deleted)
+    } else if flags.contains(Status::INDEX_RENAMED) {
+        Some(FileStatus::Renamed)
+    } else if flags.contains(Status::INDEX_TYPECHANGE) {
+        Some(FileStatus::TypeChanged)
+    } else {
+        None
+    }
+}
+
+fn worktree_status(flags: Status) -> Option<FileStatus> {
+    if flags.contains(Status::WT_MODIFIED) {
+        Some(FileStatus::Modified)
+    } else if flags.contains(Status::WT_DELETED) {
+        Some(FileStatus::Deleted)
+    } else if flags.contains(Status::WT_RENAMED) {
+        Some(FileStatus::Renamed)
+    } else if flags.contains(Status::WT_TYPECHANGE) {
+        Some(FileStatus::TypeChanged)
+    } else {
+        None
+    }
+}
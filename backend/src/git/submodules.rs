@@ -0,0 +1,39 @@
+//! Submodule listing.
+//!
+//! Reads the submodules declared in `.gitmodules` together with the commit
+//! each one is pinned to in HEAD and, when initialized, the commit checked out
+//! in its working directory. A checked-out commit differing from the pinned one
+//! means the submodule has moved since the last commit.
+//!
+//! Supports: GET /api/v1/repository/submodules
+
+use crate::error::Result;
+use crate::git::repository::GitRepository;
+use crate::models::SubmoduleInfo;
+
+impl GitRepository {
+    /// All submodules, sorted by path; empty for bare repositories
+    pub fn list_submodules(&self) -> Result<Vec<SubmoduleInfo>> {
+        self.with_repo(|repo| {
+            if repo.is_bare() {
+                return Ok(Vec::new());
+            }
+
+            let mut submodules: Vec<SubmoduleInfo> = repo
+                .submodules()?
+                .iter()
+                .map(|submodule| SubmoduleInfo {
+                    name: submodule.name().unwrap_or("").to_string(),
+                    path: submodule.path().to_string_lossy().to_string(),
+                    url: submodule.url().map(|u| u.to_string()),
+                    branch: submodule.branch().map(|b| b.to_string()),
+                    pinned_commit: submodule.head_id().map(|oid| oid.to_string()),
+                    checked_out_commit: submodule.workdir_id().map(|oid| oid.to_string()),
+                })
+                .collect();
+
+            submodules.sort_by(|a, b| a.path.cmp(&b.path));
+            Ok(submodules)
+        })
+    }
+}
@@ -0,0 +1,36 @@
+//! Tag operations.
+//!
+//! - `delete_tag()`: Remove a lightweight or annotated tag reference
+//!
+//! Supports frontend: tag management alongside the branch switcher
+
+use crate::error::{AppError, Result};
+use crate::git::repository::GitRepository;
+use crate::models::DeletedTag;
+
+impl GitRepository {
+    /// Delete a tag by name, returning what it pointed at
+    pub fn delete_tag(&self, name: &str) -> Result<DeletedTag> {
+        self.with_repo(|repo| {
+            let refname = format!("refs/tags/{}", name);
+            let reference = repo.find_reference(&refname)
+                .map_err(|_| AppError::PathNotFound(format!("Tag not found: {}", name)))?;
+
+            let target = reference.target()
+                .ok_or_else(|| AppError::Internal(format!("Tag '{}' is a symbolic reference", name)))?;
+            let is_annotated = repo.find_tag(target).is_ok();
+            let commit = reference.peel_to_commit().ok().map(|c| c.id().to_string());
+
+            repo.tag_delete(name)?;
+
+            tracing::info!("Deleted tag '{}' (was {})", name, target);
+
+            Ok(DeletedTag {
+                name: name.to_string(),
+                target: target.to_string(),
+                commit,
+                is_annotated,
+            })
+        })
+    }
+}
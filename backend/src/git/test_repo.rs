@@ -0,0 +1,98 @@
+//! Scratch repositories for unit tests.
+//!
+//! `TestRepo` creates a repository in a fresh temporary directory, commits
+//! files to it with chosen authors (checking each commit out, so the working
+//! tree and index match HEAD), and deletes the directory when dropped.
+//! `shared()` opens it as a `SharedRepo` for the API routes.
+
+use git2::build::CheckoutBuilder;
+use git2::{Oid, Repository, Signature, Time};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use crate::git::{GitRepository, SharedRepo};
+
+/// Distinguishes repositories created by tests running in parallel
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Commit times start here and advance by a minute per commit
+const FIRST_COMMIT_TIME: i64 = 1_700_000_000;
+
+pub struct TestRepo {
+    pub repo: Repository,
+    dir: PathBuf,
+    commits: i64,
+}
+
+impl TestRepo {
+    pub fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "git-viewer-test-{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let repo = Repository::init(&dir).expect("init test repository");
+        // Committing through the API signs as the configured user
+        let mut config = repo.config().expect("config");
+        config.set_str("user.name", "Test User").expect("user.name");
+        config.set_str("user.email", "test@example.com").expect("user.email");
+        Self { repo, dir, commits: 0 }
+    }
+
+    /// The repository opened as the server opens it, for route and middleware tests
+    pub fn shared(&self) -> SharedRepo {
+        Arc::new(RwLock::new(GitRepository::open(&self.dir).expect("open test repository")))
+    }
+
+    /// Commit `content` to `file` on HEAD as `author` (name, email); returns the new commit
+    pub fn commit(&mut self, author: (&str, &str), file: &str, content: &str, message: &str) -> Oid {
+        let time = Time::new(FIRST_COMMIT_TIME + self.commits * 60, 0);
+        self.commits += 1;
+        let signature = Signature::new(author.0, author.1, &time).expect("signature");
+
+        let parent = self.repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let base = parent.as_ref().map(|c| c.tree().expect("parent tree"));
+        let mut builder = self.repo.treebuilder(base.as_ref()).expect("tree builder");
+        let blob = self.repo.blob(content.as_bytes()).expect("blob");
+        builder.insert(file, blob, 0o100644).expect("tree entry");
+        let tree = self.repo.find_tree(builder.write().expect("tree")).expect("find tree");
+
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        let oid = self.repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents).expect("commit");
+        self.repo.checkout_head(Some(CheckoutBuilder::new().force())).expect("check out commit");
+        oid
+    }
+
+    /// Write `content` to `file` in the working tree only
+    pub fn write(&self, file: &str, content: &str) {
+        std::fs::write(self.dir.join(file), content).expect("write working tree file");
+    }
+
+    /// Stage `file` as it is in the working tree
+    pub fn stage(&self, file: &str) {
+        let mut index = self.repo.index().expect("index");
+        index.add_path(std::path::Path::new(file)).expect("stage file");
+        index.write().expect("write index");
+    }
+
+    /// Create branch `name` at HEAD without checking it out
+    pub fn branch(&self, name: &str) {
+        let head = self.repo.head().and_then(|h| h.peel_to_commit()).expect("HEAD commit");
+        self.repo.branch(name, &head, false).expect("create branch");
+    }
+
+    /// Check out local branch `name`
+    pub fn checkout(&self, name: &str) {
+        let refname = format!("refs/heads/{}", name);
+        self.repo.set_head(&refname).expect("set HEAD");
+        self.repo.checkout_head(Some(CheckoutBuilder::new().force())).expect("check out branch");
+    }
+}
+
+impl Drop for TestRepo {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
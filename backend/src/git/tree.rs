@@ -1,26 +1,173 @@
 //! File tree operations - directory listing and file content retrieval.
 //!
+//! Every operation reads HEAD unless given a ref (commit OID, branch or tag),
+//! so any point in history can be browsed without checking it out. Before the
+//! first commit, HEAD reads as an empty tree.
+//!
 //! Provides methods to:
 //! - `get_tree_entries()`: List directory contents with metadata and last commit info
+//!   (folder counts are immediate children, or recursive totals with `include_counts`;
+//!   Git LFS files report the real size and the pointer's OID), a page at a time
 //! - `get_full_tree()`: Get complete recursive tree structure (for file tree sidebar)
-//! - `get_file_content()`: Read file content as UTF-8 string
+//! - `get_file_content()`: Read file content as UTF-8 string (a symlink's
+//!   content is its target path; a Git LFS file's is the fetched content),
+//!   at most `FILE_CONTENT_MAX_BYTES` of it, from any byte offset
+//! - `find_raw_blob()` / `read_raw_blob()`: A file's bytes for download and
+//!   media previews, whole or a byte range; loaded when small and streamed
+//!   from `git cat-file` otherwise
+//! - `with_bootstrap()`: Resolve the `--path` / `--ref` deep link the UI opens at
 //!
 //! Supports frontend: FileTree sidebar, FileList directory view, file preview
 
-use git2::ObjectType;
-use std::path::Path;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use encoding_rs::UTF_8;
+use git2::{ObjectType, Oid, Repository, Tree};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::io::{Read, Seek, SeekFrom};
+use std::process::{Child, Command, Stdio};
 
 use crate::error::{AppError, Result};
+use crate::git::encoding;
 use crate::git::history::get_last_commits_for_paths;
-use crate::git::repository::GitRepository;
-use crate::models::{EntryType, FullTreeEntry, TreeEntry};
+use crate::git::lfs::{blob_pointer, local_object_path, parse_pointer};
+use crate::git::repository::{resolve_commit, resolve_commit_or_head, resolve_tree_or_head, GitRepository};
+use crate::models::{Bootstrap, ByteRange, EntryType, FileContent, FullTreeEntry, TreeEntry, TreeListing};
+
+/// Memoized tree counts kept at most (about 10 MB); a listing that finds the
+/// map full clears it, and one that fills it stops memoizing further trees
+const MAX_TREE_COUNT_ENTRIES: usize = 200_000;
+
+/// Blobs up to this size are read into memory; larger ones are streamed
+const RAW_INLINE_MAX_SIZE: u64 = 1024 * 1024;
+
+/// Most bytes read into memory for a bounded range of a larger blob; the
+/// client asks again for the rest
+const RAW_RANGE_MAX_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Leading bytes of a blob kept to tell its content type
+const RAW_SNIFF_LEN: usize = 8000;
+
+/// Most bytes of a file `get_file_content()` returns; longer files are cut
+/// there, and the rest is read by asking for a range
+pub const FILE_CONTENT_MAX_BYTES: u64 = 1024 * 1024;
+
+/// Tree entry mode of a symbolic link (a blob holding the target path)
+const SYMLINK_MODE: i32 = 0o120000;
+
+/// Listing type of a tree entry, `None` for kinds listings skip
+fn entry_type_of(entry: &git2::TreeEntry) -> Option<EntryType> {
+    match entry.kind() {
+        Some(ObjectType::Blob) if entry.filemode() == SYMLINK_MODE => Some(EntryType::Symlink),
+        Some(ObjectType::Blob) => Some(EntryType::File),
+        Some(ObjectType::Tree) => Some(EntryType::Directory),
+        Some(ObjectType::Commit) => Some(EntryType::Submodule),
+        _ => None,
+    }
+}
+
+/// `path` relative to the repository root, `None` for the root itself
+///
+/// Absolute paths inside the work tree are made relative; `./` and trailing
+/// slashes are dropped.
+fn workdir_relative(repo: &Repository, path: &str) -> Option<String> {
+    let path = Path::new(path);
+    let relative = match repo.workdir() {
+        Some(workdir) if path.is_absolute() => path.strip_prefix(workdir).unwrap_or(path),
+        _ => path,
+    };
+    let relative = relative
+        .components()
+        .filter(|c| !matches!(c, std::path::Component::CurDir))
+        .collect::<std::path::PathBuf>()
+        .to_string_lossy()
+        .into_owned();
+    (!relative.is_empty()).then_some(relative)
+}
+
+/// Path a symlink blob points to
+fn symlink_target(repo: &Repository, oid: Oid) -> Option<String> {
+    let blob = repo.find_blob(oid).ok()?;
+    Some(String::from_utf8_lossy(blob.content()).into_owned())
+}
+
+/// A file's blob, or part of it, as served by the raw endpoint
+pub struct RawBlob {
+    /// First bytes of the whole blob, to tell its content type from
+    pub head: Vec<u8>,
+    /// Bytes of the blob `content` holds, end exclusive
+    pub range: ByteRange,
+    pub content: RawContent,
+}
+
+pub enum RawContent {
+    /// The bytes, for blobs up to `RAW_INLINE_MAX_SIZE` and bounded ranges
+    /// of larger ones
+    Inline(Vec<u8>),
+    /// `git cat-file blob` writing the bytes to its stdout, except `read`,
+    /// the first of them, which were already read from it
+    Stream { child: Child, read: Vec<u8> },
+}
 
 impl GitRepository {
-    pub fn get_tree_entries(&self, path: Option<&str>, include_last_commit: bool) -> Result<Vec<TreeEntry>> {
+    /// Set where the UI opens on load from `--path` and `--ref`
+    ///
+    /// The path is repository-relative (or absolute within the work tree) and
+    /// must exist at HEAD, which is what the UI browses; the ref may be any
+    /// revision `resolve_commit` accepts.
+    pub fn with_bootstrap(mut self, path: Option<&str>, rev: Option<&str>) -> Result<Self> {
+        let path = match path {
+            Some(path) => self.with_repo(|repo| Ok(workdir_relative(repo, path)))?,
+            None => None,
+        };
+        let path = path.map(|p| self.canonical_path(&p));
+
+        self.bootstrap = self.with_repo(|repo| {
+            let path_type = match &path {
+                Some(path) => {
+                    let entry = resolve_tree_or_head(repo, None)?
+                        .get_path(Path::new(path))
+                        .map_err(|_| AppError::PathNotFound(path.clone()))?;
+                    Some(entry_type_of(&entry).ok_or_else(|| AppError::PathNotFound(path.clone()))?)
+                }
+                None => None,
+            };
+            let commit = rev.map(|rev| resolve_commit(repo, rev).map(|c| c.id().to_string())).transpose()?;
+            Ok(Bootstrap { path, path_type, commit })
+        })?;
+        Ok(self)
+    }
+
+    pub fn get_tree_entries(
+        &self,
+        path: Option<&str>,
+        rev: Option<&str>,
+        include_last_commit: bool,
+        include_counts: bool,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<TreeListing> {
+        let _op = self.op_log.start(
+            "tree",
+            format!(
+                "path={} ref={} last_commit={} counts={} offset={} limit={}",
+                path.unwrap_or(""),
+                rev.unwrap_or("HEAD"),
+                include_last_commit,
+                include_counts,
+                offset,
+                limit.map_or("all".to_string(), |l| l.to_string())
+            ),
+        );
         self.with_repo(|repo| {
-            let head = repo.head()?;
-            let commit = head.peel_to_commit()?;
-            let tree = commit.tree()?;
+            let mut tree_counts = self.tree_counts
+                .lock()
+                .map_err(|_| AppError::Internal("Tree count lock poisoned".to_string()))?;
+            if tree_counts.len() >= MAX_TREE_COUNT_ENTRIES {
+                tree_counts.clear();
+            }
+
+            let tree = resolve_tree_or_head(repo, rev)?;
 
             let target_tree = if let Some(p) = path {
                 if p.is_empty() || p == "/" {
@@ -37,10 +184,22 @@ impl GitRepository {
             };
 
             let base_path = path.unwrap_or("");
+
+            // Sort: directories first, then files, alphabetically. Sorting the
+            // bare entries first means sizes, counts and last commits are only
+            // looked up for the requested page.
+            let mut listed: Vec<(git2::TreeEntry, EntryType)> = target_tree
+                .iter()
+                .filter_map(|entry| entry_type_of(&entry).map(|entry_type| (entry, entry_type)))
+                .collect();
+            listed.sort_by_cached_key(|(entry, entry_type)| {
+                (*entry_type != EntryType::Directory, entry.name().unwrap_or("").to_lowercase())
+            });
+            let total = listed.len();
             let mut entries = Vec::new();
 
-            // First pass: collect all entries without commit info
-            for entry in target_tree.iter() {
+            // First pass: collect the page's entries without commit info
+            for (entry, entry_type) in listed.into_iter().skip(offset).take(limit.unwrap_or(usize::MAX)) {
                 let name = entry.name().unwrap_or("").to_string();
                 let entry_path = if base_path.is_empty() {
                     name.clone()
@@ -48,18 +207,27 @@ impl GitRepository {
                     format!("{}/{}", base_path, name)
                 };
 
-                let entry_type = match entry.kind() {
-                    Some(ObjectType::Blob) => EntryType::File,
-                    Some(ObjectType::Tree) => EntryType::Directory,
-                    Some(ObjectType::Commit) => EntryType::Submodule,
-                    _ => continue,
-                };
-
-                let (size, file_count, directory_count) = if entry_type == EntryType::File {
+                // A symlink's size is that of its target path, like `ls -l`
+                let mut lfs = None;
+                let (size, file_count, directory_count) = if matches!(entry_type, EntryType::File | EntryType::Symlink) {
                     let file_size = entry.to_object(repo).ok().and_then(|obj| {
-                        obj.as_blob().map(|b| b.size() as u64)
+                        let blob = obj.as_blob()?;
+                        if entry_type == EntryType::File {
+                            lfs = parse_pointer(repo, blob.content());
+                        }
+                        // An LFS file's size is the content's, not the pointer's
+                        Some(lfs.as_ref().map_or(blob.size() as u64, |pointer| pointer.size))
                     });
                     (file_size, None, None)
+                } else if entry_type == EntryType::Directory && include_counts {
+                    // Everything below the directory, memoized per tree OID
+                    let counts = entry.to_object(repo).ok().and_then(|obj| {
+                        obj.as_tree().map(|subtree| recursive_counts(repo, subtree, &mut tree_counts))
+                    });
+                    match counts {
+                        Some((f, d)) => (None, Some(f), Some(d)),
+                        None => (None, None, None),
+                    }
                 } else if entry_type == EntryType::Directory {
                     // Count immediate children for directories
                     let counts = entry.to_object(repo).ok().and_then(|obj| {
@@ -84,6 +252,12 @@ impl GitRepository {
                     (None, None, None)
                 };
 
+                let submodule_commit = (entry_type == EntryType::Submodule).then(|| entry.id().to_string());
+                let symlink_target = if entry_type == EntryType::Symlink {
+                    symlink_target(repo, entry.id())
+                } else {
+                    None
+                };
                 entries.push(TreeEntry {
                     name,
                     path: entry_path,
@@ -92,38 +266,38 @@ impl GitRepository {
                     file_count,
                     directory_count,
                     last_commit: None,
+                    submodule_commit,
+                    symlink_target,
+                    lfs,
                 });
             }
 
+            drop(tree_counts);
+
             // Second pass: batch fetch commit info for all paths at once
-            if include_last_commit {
+            // (an unborn HEAD has no entries, and no commit to look up)
+            if include_last_commit && !entries.is_empty() {
+                let commit = resolve_commit_or_head(repo, rev)?;
                 let paths: Vec<String> = entries.iter().map(|e| e.path.clone()).collect();
-                let commit_map = get_last_commits_for_paths(repo, &paths)?;
+                let commit_map = get_last_commits_for_paths(repo, &commit, &paths)?;
 
                 for entry in &mut entries {
                     entry.last_commit = commit_map.get(&entry.path).cloned();
                 }
             }
 
-            // Sort: directories first, then files, alphabetically
-            entries.sort_by(|a, b| {
-                match (&a.entry_type, &b.entry_type) {
-                    (EntryType::Directory, EntryType::Directory) => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-                    (EntryType::Directory, _) => std::cmp::Ordering::Less,
-                    (_, EntryType::Directory) => std::cmp::Ordering::Greater,
-                    _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-                }
-            });
-
-            Ok(entries)
+            Ok(TreeListing {
+                has_more: offset + entries.len() < total,
+                entries,
+                total,
+            })
         })
     }
 
-    pub fn get_full_tree(&self) -> Result<Vec<FullTreeEntry>> {
+    pub fn get_full_tree(&self, rev: Option<&str>) -> Result<Vec<FullTreeEntry>> {
+        let _op = self.op_log.start("full_tree", format!("ref={}", rev.unwrap_or("HEAD")));
         self.with_repo(|repo| {
-            let head = repo.head()?;
-            let commit = head.peel_to_commit()?;
-            let tree = commit.tree()?;
+            let tree = resolve_tree_or_head(repo, rev)?;
 
             fn build_tree(repo: &git2::Repository, tree: &git2::Tree, base_path: &str) -> Vec<FullTreeEntry> {
                 let mut entries = Vec::new();
@@ -136,11 +310,8 @@ impl GitRepository {
                         format!("{}/{}", base_path, name)
                     };
 
-                    let entry_type = match entry.kind() {
-                        Some(ObjectType::Blob) => EntryType::File,
-                        Some(ObjectType::Tree) => EntryType::Directory,
-                        Some(ObjectType::Commit) => EntryType::Submodule,
-                        _ => continue,
+                    let Some(entry_type) = entry_type_of(&entry) else {
+                        continue;
                     };
 
                     let children = if entry_type == EntryType::Directory {
@@ -151,11 +322,13 @@ impl GitRepository {
                         None
                     };
 
+                    let submodule_commit = (entry_type == EntryType::Submodule).then(|| entry.id().to_string());
                     entries.push(FullTreeEntry {
                         name,
                         path,
                         entry_type,
                         children,
+                        submodule_commit,
                     });
                 }
 
@@ -176,21 +349,239 @@ impl GitRepository {
         })
     }
 
-    pub fn get_file_content(&self, path: &str) -> Result<String> {
-        self.with_repo(|repo| {
-            let head = repo.head()?;
-            let commit = head.peel_to_commit()?;
-            let tree = commit.tree()?;
-
-            let entry = tree.get_path(Path::new(path))
+    /// Text of the file at `path` in `rev` (HEAD when `None`): bytes
+    /// `start..end` (to the end of the file by default), at most
+    /// `FILE_CONTENT_MAX_BYTES` of them
+    ///
+    /// Only the bytes served are held in memory: large blobs are read from
+    /// `git cat-file`, fetched LFS content from its file. Range edges inside a
+    /// multi-byte character move to the character's boundary. Text in another
+    /// encoding is transcoded to UTF-8, and binary content is base64-encoded
+    /// (see encoding.rs).
+    pub fn get_file_content(&self, path: &str, rev: Option<&str>, start: u64, end: Option<u64>) -> Result<FileContent> {
+        let _op = self.op_log.start("file", format!("path={} ref={} start={}", path, rev.unwrap_or("HEAD"), start));
+        let (source, total_size) = self.with_repo(|repo| {
+            let entry = resolve_tree_or_head(repo, rev)?.get_path(Path::new(path))
                 .map_err(|_| AppError::PathNotFound(path.to_string()))?;
+            if entry.kind() != Some(ObjectType::Blob) {
+                return Err(AppError::InvalidPath(format!("{} is not a file", path)));
+            }
+
+            // Never show the pointer text: serve the fetched content or explain why there is none
+            let oid = entry.id();
+            match blob_pointer(repo, oid) {
+                Some(pointer) if pointer.downloaded => {
+                    Ok((FileSource::Lfs(local_object_path(repo, &pointer.oid)), pointer.size))
+                }
+                Some(pointer) => Err(AppError::BadRequest(format!(
+                    "{} is stored in Git LFS ({} bytes, sha256:{}) and has not been fetched",
+                    path, pointer.size, pointer.oid
+                ))),
+                None => {
+                    let (size, _) = repo.odb()?.read_header(oid)?;
+                    Ok((FileSource::Blob(oid), size as u64))
+                }
+            }
+        })?;
 
-            let obj = entry.to_object(repo)?;
-            let blob = obj.as_blob()
-                .ok_or_else(|| AppError::InvalidPath(format!("{} is not a file", path)))?;
+        if start > total_size {
+            return Err(AppError::BadRequest(format!(
+                "Range starts at byte {}, past the end of {} ({} bytes)",
+                start, path, total_size
+            )));
+        }
+        let end = end.unwrap_or(total_size).min(total_size).min(start + FILE_CONTENT_MAX_BYTES);
 
-            String::from_utf8(blob.content().to_vec())
-                .map_err(|_| AppError::Internal("File is not valid UTF-8".to_string()))
+        let bytes = match source {
+            FileSource::Blob(oid) if total_size <= FILE_CONTENT_MAX_BYTES => self.with_repo(|repo| {
+                Ok(repo.find_blob(oid)?.content()[start as usize..end as usize].to_vec())
+            })?,
+            FileSource::Blob(oid) => {
+                let mut child = self.cat_file(oid)?;
+                let read = child.stdout.take().map(|stdout| read_range(stdout, start, end - start));
+                let _ = child.kill();
+                let _ = child.wait();
+                read.unwrap_or(Ok(Vec::new()))
+                    .map_err(|e| AppError::Internal(format!("Failed to read blob {}: {}", oid, e)))?
+            }
+            FileSource::Lfs(file) => std::fs::File::open(&file)
+                .and_then(|mut f| f.seek(SeekFrom::Start(start)).map(|_| f))
+                .and_then(|f| read_range(f, 0, end - start))
+                .map_err(|e| AppError::Internal(format!("Failed to read LFS object {}: {}", file.display(), e)))?,
+        };
+
+        let end = start + bytes.len() as u64;
+        let (content, encoding, range) = match utf8_within(bytes, start, total_size) {
+            Ok((text, range)) => (text, UTF_8.name(), range),
+            Err(bytes) => match encoding::decode_part(&bytes, start > 0, end < total_size) {
+                Some((text, encoding, (front, back))) => {
+                    (text, encoding.name(), ByteRange { start: start + front as u64, end: end - back as u64 })
+                }
+                None => (BASE64_STANDARD.encode(&bytes), encoding::BASE64, ByteRange { start, end }),
+            },
+        };
+        Ok(FileContent {
+            path: path.to_string(),
+            content,
+            encoding: encoding.to_string(),
+            total_size,
+            truncated: range.start > 0 || range.end < total_size,
+            range,
         })
     }
+
+    /// Blob at `path` in `rev` (HEAD when `None`), with its size read from the
+    /// object header so large files are never loaded whole
+    pub fn find_raw_blob(&self, path: &str, rev: Option<&str>) -> Result<(Oid, u64)> {
+        self.with_repo(|repo| {
+            let entry = resolve_tree_or_head(repo, rev)?.get_path(Path::new(path))
+                .map_err(|_| AppError::PathNotFound(path.to_string()))?;
+            if entry.kind() != Some(ObjectType::Blob) {
+                return Err(AppError::InvalidPath(format!("{} is not a file", path)));
+            }
+            let (size, _) = repo.odb()?.read_header(entry.id())?;
+            Ok((entry.id(), size as u64))
+        })
+    }
+
+    /// Bytes `start..end` of blob `oid`, `size` bytes long (to its end when
+    /// `end` is `None`)
+    ///
+    /// Blobs up to `RAW_INLINE_MAX_SIZE` are loaded. Larger ones are read from
+    /// `git cat-file`, which can't seek: a bounded range is read into memory
+    /// (at most `RAW_RANGE_MAX_BYTES` of it, so `range` may end early), and an
+    /// open one is skipped to and streamed from there.
+    pub fn read_raw_blob(&self, oid: Oid, size: u64, start: u64, end: Option<u64>) -> Result<RawBlob> {
+        let _op = self.op_log.start(
+            "raw_blob",
+            format!("oid={} range={}-{}", oid, start, end.map_or(String::new(), |e| e.to_string())),
+        );
+        let end = end.map_or(size, |end| end.min(size));
+        let start = start.min(end);
+
+        if size <= RAW_INLINE_MAX_SIZE {
+            return self.with_repo(|repo| {
+                let blob = repo.find_blob(oid)?;
+                let content = blob.content();
+                Ok(RawBlob {
+                    head: content[..content.len().min(RAW_SNIFF_LEN)].to_vec(),
+                    range: ByteRange { start, end },
+                    content: RawContent::Inline(content[start as usize..end as usize].to_vec()),
+                })
+            });
+        }
+
+        let mut child = self.cat_file(oid)?;
+        let bounded = end < size;
+        let read = child.stdout.as_mut().map(|stdout| -> std::io::Result<(Vec<u8>, Vec<u8>)> {
+            let mut head = Vec::with_capacity(RAW_SNIFF_LEN);
+            stdout.by_ref().take(RAW_SNIFF_LEN as u64).read_to_end(&mut head)?;
+            if bounded {
+                let len = (end - start).min(RAW_RANGE_MAX_BYTES);
+                let bytes = read_range(std::io::Cursor::new(&head).chain(stdout), start, len)?;
+                return Ok((head, bytes));
+            }
+            // The part of `head` in the range is sent ahead of the stream
+            let read = head.get(start as usize..).unwrap_or_default().to_vec();
+            let skip = start.saturating_sub(head.len() as u64);
+            std::io::copy(&mut stdout.take(skip), &mut std::io::sink())?;
+            Ok((head, read))
+        });
+        let (head, bytes) = match read.unwrap_or_else(|| Ok(Default::default())) {
+            Ok(read) => read,
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(AppError::Internal(format!("Failed to read blob {}: {}", oid, e)));
+            }
+        };
+
+        if bounded {
+            let _ = child.kill();
+            let _ = child.wait();
+            let range = ByteRange { start, end: start + bytes.len() as u64 };
+            return Ok(RawBlob { head, range, content: RawContent::Inline(bytes) });
+        }
+        Ok(RawBlob { head, range: ByteRange { start, end }, content: RawContent::Stream { child, read: bytes } })
+    }
+
+    /// `git cat-file blob`, writing blob `oid` to its stdout
+    fn cat_file(&self, oid: Oid) -> Result<Child> {
+        Command::new("git")
+            .arg("--git-dir")
+            .arg(self.git_dir()?)
+            .args(["cat-file", "blob"])
+            .arg(oid.to_string())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| AppError::Internal(format!("Failed to run git: {}", e)))
+    }
+}
+
+/// Where `get_file_content()` reads a file's bytes from
+enum FileSource {
+    Blob(Oid),
+    /// Fetched Git LFS content
+    Lfs(PathBuf),
+}
+
+/// `len` bytes of `reader` after skipping `skip`, fewer if it ends first
+fn read_range(mut reader: impl Read, skip: u64, len: u64) -> std::io::Result<Vec<u8>> {
+    std::io::copy(&mut reader.by_ref().take(skip), &mut std::io::sink())?;
+    let mut bytes = Vec::with_capacity(len.min(FILE_CONTENT_MAX_BYTES) as usize);
+    reader.take(len).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// `bytes`, read from offset `start` of a file `total_size` long, as UTF-8
+/// text without the pieces of characters cut off at either edge, with the
+/// range of the file the text covers; the bytes back when they aren't UTF-8
+fn utf8_within(mut bytes: Vec<u8>, start: u64, total_size: u64) -> std::result::Result<(String, ByteRange), Vec<u8>> {
+    // Continuation bytes of a character that began before the range
+    let cut = if start > 0 { bytes.iter().take(3).take_while(|&&b| b & 0xC0 == 0x80).count() } else { 0 };
+    let end = start + bytes.len() as u64;
+    let valid = match std::str::from_utf8(&bytes[cut..]) {
+        Ok(text) => text.len(),
+        // The range ends inside a character
+        Err(e) if end < total_size && e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => return Err(bytes),
+    };
+
+    bytes.truncate(cut + valid);
+    bytes.drain(..cut);
+    let range = ByteRange { start: start + cut as u64, end: start + (cut + valid) as u64 };
+    String::from_utf8(bytes).map(|text| (text, range)).map_err(|e| e.into_bytes())
+}
+
+/// Files and directories anywhere below `tree`, reusing totals of subtrees seen before
+///
+/// Unchanged subtrees keep their OID between commits, so after the first listing
+/// only directories that actually changed are walked again.
+fn recursive_counts(repo: &Repository, tree: &Tree, memo: &mut HashMap<Oid, (u32, u32)>) -> (u32, u32) {
+    if let Some(&counts) = memo.get(&tree.id()) {
+        return counts;
+    }
+
+    let mut files = 0u32;
+    let mut dirs = 0u32;
+    for child in tree.iter() {
+        match child.kind() {
+            Some(ObjectType::Blob) => files += 1,
+            Some(ObjectType::Tree) => {
+                dirs += 1;
+                if let Ok(subtree) = repo.find_tree(child.id()) {
+                    let (sub_files, sub_dirs) = recursive_counts(repo, &subtree, memo);
+                    files += sub_files;
+                    dirs += sub_dirs;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if memo.len() < MAX_TREE_COUNT_ENTRIES {
+        memo.insert(tree.id(), (files, dirs));
+    }
+    (files, dirs)
 }
@@ -0,0 +1,262 @@
+//! Repository watching for live updates.
+//!
+//! - `ChangeNotifier`: Broadcast channel of change events; it is carried over
+//!   repository switches so subscribers stay connected
+//! - `RepoWatcher`: File system watcher on one repository, stopped when dropped
+//! - `subscribe_changes()`: Change events for the current repository
+//! - `announce_switch()`: Tell subscribers the repository was replaced
+//!
+//! The git directory is watched for HEAD, loose and packed refs and the index,
+//! and the working tree for file changes; object writes, lock files and
+//! ignored paths are skipped. Raw events are batched until things are quiet
+//! for a moment, so a checkout touching thousands of files is one event.
+//!
+//! Watching starts with the first subscriber, so a server nobody listens to
+//! doesn't spend inotify watches on a large working tree.
+//!
+//! Supports frontend: automatic refresh of commit list, branches and working tree status
+
+use git2::{Oid, Repository};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+use crate::error::{AppError, Result};
+use crate::git::repository::GitRepository;
+use crate::models::{ChangeKind, RepoChangeEvent};
+
+/// Quiet time after the last file system event before a batch is sent
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Longest a batch is held back while events keep arriving (e.g. a running build)
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(2);
+
+/// Working tree paths listed per event
+const MAX_EVENT_PATHS: usize = 100;
+
+/// Events buffered per subscriber; one that falls further behind is told to refresh everything
+const EVENT_BUFFER: usize = 64;
+
+pub struct ChangeNotifier {
+    sender: broadcast::Sender<RepoChangeEvent>,
+}
+
+impl Default for ChangeNotifier {
+    fn default() -> Self {
+        Self { sender: broadcast::channel(EVENT_BUFFER).0 }
+    }
+}
+
+impl ChangeNotifier {
+    pub fn subscribe(&self) -> broadcast::Receiver<RepoChangeEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    /// Send to the current subscribers; without any, the event is dropped
+    pub fn send(&self, event: RepoChangeEvent) {
+        let _ = self.sender.send(event);
+    }
+}
+
+/// Keeps a repository watched; dropping it stops the watcher and its batching thread
+pub struct RepoWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl RepoWatcher {
+    fn start(repo: &Repository, notifier: Arc<ChangeNotifier>) -> Result<Self> {
+        let git_dir = canonical(repo.path());
+        let workdir = repo.workdir().map(canonical);
+
+        // A handle of its own for ignore checks and HEAD lookups, as the
+        // batching thread runs outside any request
+        let own_repo = Repository::open(repo.path())?;
+        if let Some(workdir) = repo.workdir() {
+            own_repo.set_workdir(workdir, false)?;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event
+                && !matches!(event.kind, EventKind::Access(_))
+            {
+                let _ = tx.send(event.paths);
+            }
+        })
+        .map_err(watch_error)?;
+
+        if let Some(workdir) = &workdir {
+            watcher.watch(workdir, RecursiveMode::Recursive).map_err(watch_error)?;
+        }
+        // Covered by the working tree watch unless the git directory lives elsewhere
+        if workdir.as_ref().is_none_or(|w| !git_dir.starts_with(w)) {
+            watcher.watch(&git_dir, RecursiveMode::Recursive).map_err(watch_error)?;
+        }
+
+        let head = head_state(&own_repo);
+        let classifier = Classifier { repo: own_repo, git_dir, workdir, head };
+        std::thread::spawn(move || batch_events(rx, classifier, notifier));
+
+        tracing::info!("Watching {} for changes", repo.workdir().unwrap_or(repo.path()).display());
+        Ok(Self { _watcher: watcher })
+    }
+}
+
+impl GitRepository {
+    /// Change events for this repository (and the ones switched to later),
+    /// starting the watcher if it isn't running yet
+    pub fn subscribe_changes(&self) -> Result<broadcast::Receiver<RepoChangeEvent>> {
+        self.ensure_watching()?;
+        Ok(self.changes.subscribe())
+    }
+
+    /// Tell subscribers this repository replaced the previous one, and keep
+    /// them supplied with its changes
+    pub fn announce_switch(&self) -> Result<()> {
+        if !self.changes.has_subscribers() {
+            return Ok(());
+        }
+        self.ensure_watching()?;
+        self.changes.send(RepoChangeEvent {
+            kinds: vec![ChangeKind::Repository],
+            paths: Vec::new(),
+            paths_truncated: false,
+        });
+        Ok(())
+    }
+
+    fn ensure_watching(&self) -> Result<()> {
+        let mut watcher = self.watcher.lock().map_err(|_| AppError::Internal("Watcher lock poisoned".to_string()))?;
+        if watcher.is_none() {
+            *watcher = Some(self.with_repo(|repo| RepoWatcher::start(repo, self.changes.clone()))?);
+        }
+        Ok(())
+    }
+}
+
+/// Turns raw file system paths into change kinds
+struct Classifier {
+    repo: Repository,
+    git_dir: PathBuf,
+    workdir: Option<PathBuf>,
+    /// Branch name and commit HEAD resolved to when last checked
+    head: (Option<String>, Option<Oid>),
+}
+
+impl Classifier {
+    fn classify(&self, path: &Path) -> Option<(ChangeKind, Option<String>)> {
+        if let Ok(relative) = path.strip_prefix(&self.git_dir) {
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            let kind = match relative.as_str() {
+                "HEAD" => ChangeKind::Head,
+                "index" => ChangeKind::Index,
+                "packed-refs" => ChangeKind::Refs,
+                r if r.starts_with("refs/") && !r.ends_with(".lock") => ChangeKind::Refs,
+                _ => return None,
+            };
+            return Some((kind, None));
+        }
+
+        let relative = path.strip_prefix(self.workdir.as_ref()?).ok()?;
+        if relative.as_os_str().is_empty() {
+            return None;
+        }
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        if self.repo.is_path_ignored(&relative).unwrap_or(false) {
+            return None;
+        }
+        Some((ChangeKind::WorkingTree, Some(relative)))
+    }
+
+    /// Whether HEAD now names another branch or commit; a commit on the
+    /// current branch only touches refs/, so this is checked on ref changes too
+    fn head_moved(&mut self) -> bool {
+        let head = head_state(&self.repo);
+        let moved = head != self.head;
+        self.head = head;
+        moved
+    }
+}
+
+#[derive(Default)]
+struct Batch {
+    kinds: BTreeSet<ChangeKind>,
+    paths: BTreeSet<String>,
+    paths_truncated: bool,
+}
+
+impl Batch {
+    fn add(&mut self, classifier: &Classifier, paths: Vec<PathBuf>) {
+        for path in paths {
+            let Some((kind, relative)) = classifier.classify(&path) else {
+                continue;
+            };
+            self.kinds.insert(kind);
+            if let Some(relative) = relative {
+                if self.paths.len() < MAX_EVENT_PATHS {
+                    self.paths.insert(relative);
+                } else if !self.paths.contains(&relative) {
+                    self.paths_truncated = true;
+                }
+            }
+        }
+    }
+}
+
+/// Collect raw events into batches and broadcast them; ends when the watcher is dropped
+fn batch_events(rx: mpsc::Receiver<Vec<PathBuf>>, mut classifier: Classifier, notifier: Arc<ChangeNotifier>) {
+    while let Ok(paths) = rx.recv() {
+        let started = Instant::now();
+        let mut batch = Batch::default();
+        batch.add(&classifier, paths);
+
+        while started.elapsed() < MAX_BATCH_DELAY {
+            match rx.recv_timeout(DEBOUNCE) {
+                Ok(paths) => batch.add(&classifier, paths),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        // Head is reported by what HEAD resolves to, not by writes to the
+        // file: a rewrite with the same target (a no-op checkout) isn't a move
+        let refs_touched = batch.kinds.remove(&ChangeKind::Head) | batch.kinds.contains(&ChangeKind::Refs);
+        if refs_touched && classifier.head_moved() {
+            batch.kinds.insert(ChangeKind::Head);
+        }
+
+        if batch.kinds.is_empty() {
+            continue;
+        }
+        notifier.send(RepoChangeEvent {
+            kinds: batch.kinds.into_iter().collect(),
+            paths: batch.paths.into_iter().collect(),
+            paths_truncated: batch.paths_truncated,
+        });
+    }
+}
+
+/// Branch HEAD points at (`None` when detached) and the commit it resolves to
+fn head_state(repo: &Repository) -> (Option<String>, Option<Oid>) {
+    let branch = repo.find_reference("HEAD").ok()
+        .and_then(|head| head.symbolic_target().map(str::to_string));
+    let oid = repo.refname_to_id("HEAD").ok();
+    (branch, oid)
+}
+
+/// Paths as the watcher reports them, so prefixes can be stripped
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn watch_error(e: notify::Error) -> AppError {
+    AppError::Internal(format!("Failed to watch the repository: {}", e))
+}
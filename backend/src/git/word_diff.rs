@@ -0,0 +1,154 @@
+//! Intra-line (word-level) change spans.
+//!
+//! Within each run of deleted lines directly followed by added lines, the k-th
+//! deletion is paired with the k-th addition and the two are compared word by
+//! word (identifier runs, whitespace runs and single punctuation characters).
+//! Tokens outside the longest common subsequence become spans on each line, so
+//! the viewer can highlight exactly what changed. Pairs that share too little
+//! get no spans: the whole line changed, and scattered highlights would be noise.
+//!
+//! Supports frontend: DiffViewer changed-word highlighting
+
+use crate::models::{DiffLine, IntraLineSpan, LineType};
+
+/// Lines with more tokens than this are not compared (quadratic LCS)
+const MAX_TOKENS: usize = 500;
+
+/// Minimum share of the longer line that must be unchanged for spans to be emitted
+const MIN_COMMON_RATIO: f64 = 0.3;
+
+/// Set `spans` on paired deletion/addition lines of one hunk
+pub fn mark_changed_spans(lines: &mut [DiffLine]) {
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].line_type != LineType::Deletion {
+            i += 1;
+            continue;
+        }
+        let del_start = i;
+        while i < lines.len() && lines[i].line_type == LineType::Deletion {
+            i += 1;
+        }
+        let add_start = i;
+        while i < lines.len() && lines[i].line_type == LineType::Addition {
+            i += 1;
+        }
+
+        let pairs = (add_start - del_start).min(i - add_start);
+        for k in 0..pairs {
+            if let Some((old_spans, new_spans)) =
+                changed_spans(&lines[del_start + k].content, &lines[add_start + k].content)
+            {
+                lines[del_start + k].spans = old_spans;
+                lines[add_start + k].spans = new_spans;
+            }
+        }
+    }
+}
+
+/// Spans (UTF-16 offsets, for direct use with JavaScript strings) that differ
+/// between `old` and `new`, or `None` when the lines are too different
+fn changed_spans(old: &str, new: &str) -> Option<(Vec<IntraLineSpan>, Vec<IntraLineSpan>)> {
+    let old = old.trim_end_matches(['\n', '\r']);
+    let new = new.trim_end_matches(['\n', '\r']);
+    let old_tokens = tokenize(old);
+    let new_tokens = tokenize(new);
+    if old_tokens.len() > MAX_TOKENS || new_tokens.len() > MAX_TOKENS {
+        return None;
+    }
+
+    let (old_common, new_common) = lcs(&old_tokens, &new_tokens);
+    let common_len: usize = old_tokens
+        .iter()
+        .zip(&old_common)
+        .filter(|(token, common)| **common && !token.trim().is_empty())
+        .map(|(token, _)| token.chars().count())
+        .sum();
+    let longer = old.trim().chars().count().max(new.trim().chars().count());
+    if longer == 0 || (common_len as f64) < MIN_COMMON_RATIO * longer as f64 {
+        return None;
+    }
+
+    Some((spans(&old_tokens, &old_common), spans(&new_tokens, &new_common)))
+}
+
+/// Split into identifier runs, whitespace runs and single other characters
+fn tokenize(line: &str) -> Vec<&str> {
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut prev: Option<u8> = None;
+    for (idx, c) in line.char_indices() {
+        let cls = class(c);
+        if let Some(p) = prev
+            && (p != cls || cls == 2)
+        {
+            tokens.push(&line[start..idx]);
+            start = idx;
+        }
+        prev = Some(cls);
+    }
+    if start < line.len() {
+        tokens.push(&line[start..]);
+    }
+    tokens
+}
+
+/// Which tokens of each side belong to a longest common subsequence
+fn lcs(old: &[&str], new: &[&str]) -> (Vec<bool>, Vec<bool>) {
+    let (n, m) = (old.len(), new.len());
+    // table[i][j]: LCS length of old[i..] and new[j..]
+    let mut table = vec![vec![0u16; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if old[i] == new[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let mut old_common = vec![false; n];
+    let mut new_common = vec![false; m];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            old_common[i] = true;
+            new_common[j] = true;
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    (old_common, new_common)
+}
+
+/// Merge consecutive changed tokens into spans
+fn spans(tokens: &[&str], common: &[bool]) -> Vec<IntraLineSpan> {
+    let mut spans: Vec<IntraLineSpan> = Vec::new();
+    let mut offset = 0u32;
+    for (token, is_common) in tokens.iter().zip(common) {
+        let len = token.encode_utf16().count() as u32;
+        if !is_common {
+            match spans.last_mut() {
+                Some(last) if last.end == offset => last.end += len,
+                _ => spans.push(IntraLineSpan { start: offset, end: offset + len }),
+            }
+        }
+        offset += len;
+    }
+    spans
+}
@@ -0,0 +1,346 @@
+//! Rate limiting and concurrency caps for the API.
+//!
+//! Every handler takes the single repository mutex, so a tab firing diffs or
+//! blames in a loop would otherwise queue everyone else behind it.
+//!
+//! - Expensive endpoints (diff, compare, blame, directory-info, pickaxe
+//!   search, author and hotspot statistics, archives, fsck, range-diff) are
+//!   capped per group. A request waits briefly for a slot, then gets `503`
+//!   with `Retry-After`. No client gets all of a group's slots: past its share
+//!   its requests wait for its own to finish, so another client always finds
+//!   one free. Each client may also only have a few expensive requests in
+//!   flight; more get `429` right away.
+//! - Every `/api` request draws from a per-client token bucket
+//!   (`--rate-limit` per second, bursts of four seconds' worth); an empty
+//!   bucket gives `429` with `Retry-After`.
+//!
+//! A client is its IP address. The `X-Client-Id` header the frontend sends
+//! per tab only splits the client's expensive requests fairly between its
+//! busy tabs: it is self-declared, so a fresh id must not buy a fresh budget.
+//!
+//! Streaming endpoints give their slot back once the stream starts.
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{AcquireError, OwnedSemaphorePermit, Semaphore};
+
+use crate::error::AppError;
+
+/// Default sustained requests per second per client
+pub const DEFAULT_RATE_LIMIT: u32 = 50;
+
+/// Bucket size, in seconds of the sustained rate
+const BURST_SECONDS: u32 = 4;
+
+/// Expensive endpoints by path prefix, and how many of each group run at once
+const EXPENSIVE_GROUPS: [(&str, usize); 10] = [
+    ("/api/v1/repository/diff", 4),
+    ("/api/v1/repository/compare-file", 4),
+    ("/api/v1/repository/blame", 4),
+    ("/api/v1/repository/directory-info", 2),
+    ("/api/v1/repository/commits/pickaxe", 2),
+    ("/api/v1/repository/stats/hotspots", 2),
+    ("/api/v1/repository/stats/authors", 2),
+    ("/api/v1/repository/archive", 2),
+    ("/api/v1/repository/fsck", 1),
+    ("/api/v1/repository/range-diff", 2),
+];
+
+/// Expensive requests one client may have in flight across all groups and tabs
+const MAX_EXPENSIVE_PER_CLIENT: usize = 6;
+
+/// Expensive requests one tab may have in flight; with more tabs busy, each
+/// gets an equal share of the client's cap
+const MAX_EXPENSIVE_PER_TAB: usize = 3;
+
+/// How long an expensive request waits for a free slot
+const SLOT_WAIT: Duration = Duration::from_secs(5);
+
+/// Clients tracked before idle ones are forgotten
+const MAX_TRACKED_CLIENTS: usize = 1000;
+
+/// A client idle this long starts over with a full bucket
+const CLIENT_IDLE: Duration = Duration::from_secs(300);
+
+pub struct Limits {
+    /// Sustained requests per second per client; `None` disables the bucket
+    rate: Option<f64>,
+    /// Path prefix, slots and the group's semaphore
+    groups: Vec<(&'static str, usize, Arc<Semaphore>)>,
+    /// Per client IP (`None` when the connection's address is unknown)
+    clients: Mutex<HashMap<Option<IpAddr>, ClientState>>,
+}
+
+struct ClientState {
+    tokens: f64,
+    last_seen: Instant,
+    expensive_in_flight: usize,
+    /// Expensive requests in flight per `X-Client-Id` ("" without one)
+    tabs_in_flight: HashMap<String, usize>,
+    /// The client's share of each group's slots (by path prefix): all but
+    /// one, so a busy client leaves another a slot
+    group_shares: HashMap<&'static str, Arc<Semaphore>>,
+}
+
+impl ClientState {
+    /// Whether `tab` may start another expensive request: the client is under
+    /// its cap, and the tab under its share of it
+    fn may_start_expensive(&self, tab: &str) -> bool {
+        let busy_tabs = self.tabs_in_flight.len() + usize::from(!self.tabs_in_flight.contains_key(tab));
+        let share = MAX_EXPENSIVE_PER_CLIENT.div_ceil(busy_tabs).min(MAX_EXPENSIVE_PER_TAB);
+        self.expensive_in_flight < MAX_EXPENSIVE_PER_CLIENT
+            && self.tabs_in_flight.get(tab).copied().unwrap_or(0) < share
+    }
+}
+
+impl Limits {
+    /// `rate` requests per second per client; 0 disables rate limiting (the
+    /// concurrency caps always apply)
+    pub fn new(rate: u32) -> Self {
+        Self {
+            rate: (rate > 0).then_some(rate as f64),
+            groups: EXPENSIVE_GROUPS
+                .iter()
+                .map(|(prefix, slots)| (*prefix, *slots, Arc::new(Semaphore::new(*slots))))
+                .collect(),
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a token from the client's bucket; on failure, the seconds until one is available
+    fn take_token(&self, clients: &mut HashMap<Option<IpAddr>, ClientState>, ip: Option<IpAddr>) -> Result<(), u64> {
+        let now = Instant::now();
+        if clients.len() >= MAX_TRACKED_CLIENTS {
+            clients.retain(|_, c| c.expensive_in_flight > 0 || now.duration_since(c.last_seen) < CLIENT_IDLE);
+        }
+        let burst = self.rate.map_or(0.0, |rate| rate * BURST_SECONDS as f64);
+        let client = clients.entry(ip).or_insert_with(|| ClientState {
+            tokens: burst,
+            last_seen: now,
+            expensive_in_flight: 0,
+            tabs_in_flight: HashMap::new(),
+            group_shares: HashMap::new(),
+        });
+
+        let Some(rate) = self.rate else {
+            client.last_seen = now;
+            return Ok(());
+        };
+        client.tokens = (client.tokens + now.duration_since(client.last_seen).as_secs_f64() * rate).min(burst);
+        client.last_seen = now;
+        if client.tokens >= 1.0 {
+            client.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - client.tokens) / rate).ceil() as u64)
+        }
+    }
+}
+
+/// Releases a client's expensive-request slot when the request finishes
+struct InFlight<'a> {
+    limits: &'a Limits,
+    ip: Option<IpAddr>,
+    tab: String,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut clients) = self.limits.clients.lock()
+            && let Some(client) = clients.get_mut(&self.ip)
+        {
+            client.expensive_in_flight = client.expensive_in_flight.saturating_sub(1);
+            if let Some(count) = client.tabs_in_flight.get_mut(&self.tab) {
+                *count -= 1;
+                if *count == 0 {
+                    client.tabs_in_flight.remove(&self.tab);
+                }
+            }
+        }
+    }
+}
+
+/// A request let through: its client's in-flight count, share and group
+/// slot, all held until dropped
+struct Admitted<'a> {
+    _in_flight: Option<InFlight<'a>>,
+    _permits: Option<(OwnedSemaphorePermit, OwnedSemaphorePermit)>,
+}
+
+impl Limits {
+    /// Let a request to `path` from `ip` (tab `tab`) through, or answer it
+    /// with `429`/`503`
+    async fn admit(&self, ip: Option<IpAddr>, tab: String, path: &str) -> Result<Admitted<'_>, Response> {
+        let group = self.groups.iter().find(|(prefix, _, _)| path.starts_with(prefix));
+
+        let expensive = {
+            let Ok(mut clients) = self.clients.lock() else {
+                return Err(AppError::Internal("Rate limiter lock poisoned".to_string()).into_response());
+            };
+            if let Err(retry_after) = self.take_token(&mut clients, ip) {
+                return Err(too_many(
+                    AppError::TooManyRequests("Rate limit exceeded; slow down".to_string()),
+                    retry_after,
+                ));
+            }
+            let Some((prefix, slots, group_slots)) = group else {
+                return Ok(Admitted { _in_flight: None, _permits: None });
+            };
+            let client = clients.get_mut(&ip).expect("client entry created by take_token");
+            if !client.may_start_expensive(&tab) {
+                return Err(too_many(
+                    AppError::TooManyRequests(format!(
+                        "At most {} expensive requests (diffs, blames, searches, statistics, archives) per client \
+                         at a time, shared between its tabs",
+                        MAX_EXPENSIVE_PER_CLIENT
+                    )),
+                    1,
+                ));
+            }
+            client.expensive_in_flight += 1;
+            *client.tabs_in_flight.entry(tab.clone()).or_default() += 1;
+            let share = client.group_shares
+                .entry(prefix)
+                .or_insert_with(|| Arc::new(Semaphore::new(slots.saturating_sub(1).max(1))))
+                .clone();
+            (InFlight { limits: self, ip, tab }, share, group_slots.clone())
+        };
+
+        let (in_flight, share, group_slots) = expensive;
+        let acquire = async {
+            Ok::<_, AcquireError>((share.acquire_owned().await?, group_slots.acquire_owned().await?))
+        };
+        match tokio::time::timeout(SLOT_WAIT, acquire).await {
+            Ok(Ok(permits)) => Ok(Admitted { _in_flight: Some(in_flight), _permits: Some(permits) }),
+            _ => Err(too_many(
+                AppError::Busy("Too many expensive requests running; try again shortly".to_string()),
+                SLOT_WAIT.as_secs(),
+            )),
+        }
+    }
+}
+
+/// Middleware: per-client rate limit on `/api`, concurrency caps on expensive endpoints
+pub async fn limit_requests(State(limits): State<Arc<Limits>>, request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    if !(path == "/api" || path.starts_with("/api/")) {
+        return next.run(request).await;
+    }
+
+    let ip = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0.ip());
+    let tab: String = request.headers()
+        .get("x-client-id")
+        .and_then(|v| v.to_str().ok())
+        .map(|id| id.chars().take(64).collect())
+        .unwrap_or_default();
+
+    let admitted = match limits.admit(ip, tab, path).await {
+        Ok(admitted) => admitted,
+        Err(response) => return response,
+    };
+    let response = next.run(request).await;
+    drop(admitted);
+    response
+}
+
+/// Error response with a `Retry-After` header
+fn too_many(error: AppError, retry_after_secs: u64) -> Response {
+    let mut response = error.into_response();
+    if let Ok(value) = HeaderValue::from_str(&retry_after_secs.max(1).to_string()) {
+        response.headers_mut().insert(header::RETRY_AFTER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+
+    const DIFF: &str = "/api/v1/repository/diff";
+
+    fn ip(last: u8) -> Option<IpAddr> {
+        Some(IpAddr::from([10, 0, 0, last]))
+    }
+
+    #[tokio::test]
+    async fn busy_client_leaves_another_client_a_slot() {
+        let limits = Limits::new(0);
+        let (busy, other) = (ip(1), ip(2));
+
+        // Three diffs from one tab, the most a tab may run
+        let mut held = Vec::new();
+        for _ in 0..3 {
+            held.push(limits.admit(busy, "one".to_string(), DIFF).await.unwrap());
+        }
+        let refused = limits.admit(busy, "one".to_string(), DIFF).await.err().expect("over the tab's share");
+        assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // Another tab of the same client waits for one of its own instead of
+        // taking the group's last slot
+        let waiting = tokio::time::timeout(Duration::from_millis(100), limits.admit(busy, "two".to_string(), DIFF)).await;
+        assert!(waiting.is_err(), "the busy client took the last slot");
+
+        let admitted = tokio::time::timeout(Duration::from_millis(100), limits.admit(other, String::new(), DIFF)).await;
+        assert!(matches!(admitted, Ok(Ok(_))), "the second client was starved");
+
+        // Once the busy client finishes one, its waiting tab gets through
+        drop(admitted);
+        held.pop();
+        let resumed = tokio::time::timeout(Duration::from_millis(100), limits.admit(busy, "two".to_string(), DIFF)).await;
+        assert!(matches!(resumed, Ok(Ok(_))));
+    }
+
+    #[tokio::test]
+    async fn each_client_ip_gets_its_own_token_bucket() {
+        use crate::git::test_repo::TestRepo;
+        use crate::routes::test_client::{request, send};
+        use axum::middleware;
+
+        let mut test = TestRepo::new();
+        test.commit(("Ada", "ada@example.com"), "a.txt", "a\n", "first");
+        let app = crate::routes::create_router(test.shared())
+            .layer(middleware::from_fn_with_state(Arc::new(Limits::new(1)), limit_requests));
+        let from = |last: u8, tab: &str| {
+            let mut request = request("GET", "/api/v1/repository", None);
+            request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([10, 0, 0, last], 4000))));
+            request.headers_mut().insert("x-client-id", HeaderValue::from_str(tab).unwrap());
+            request
+        };
+
+        // A burst of four seconds' worth at one request per second
+        for _ in 0..BURST_SECONDS {
+            assert_eq!(send(&app, from(1, "a")).await.0, StatusCode::OK);
+        }
+        // A fresh tab id doesn't buy a fresh bucket
+        let response = tower::ServiceExt::oneshot(app.clone(), from(1, "b")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+
+        assert_eq!(send(&app, from(2, "a")).await.0, StatusCode::OK);
+    }
+
+    #[test]
+    fn searches_statistics_archives_and_fsck_are_expensive() {
+        let limits = Limits::new(0);
+        for path in [
+            "/api/v1/repository/commits/pickaxe",
+            "/api/v1/repository/stats/hotspots",
+            "/api/v1/repository/stats/authors",
+            "/api/v1/repository/archive",
+            "/api/v1/repository/fsck",
+            "/api/v1/repository/range-diff",
+        ] {
+            assert!(limits.groups.iter().any(|(prefix, _, _)| path.starts_with(prefix)), "{}", path);
+        }
+        assert!(!limits.groups.iter().any(|(prefix, _, _)| "/api/v1/repository/commits".starts_with(prefix)));
+    }
+}
@@ -4,32 +4,67 @@
 //! ```bash
 //! git-viewer /path/to/repository        # Start server
 //! git-viewer /path/to/repository --open # Start and open browser
-//! git-viewer status                     # Check if running
-//! git-viewer kill                       # Stop running instance
+//! git-viewer . --auth-token             # Require a generated token (printed URL)
+//! git-viewer . --path src/main.rs --open # Open the browser at a file
+//! git-viewer /srv/repo --service --read-only # Shared instance under systemd
+//! git-viewer status                     # List running instances
+//! git-viewer kill                       # Stop the running instance
+//! git-viewer kill --port 3002           # Stop one of several instances
+//! git-viewer status --all --json        # Machine-readable status
+//! ```
+//!
+//! Each instance writes its own PID file (keyed by port) to the temp directory,
+//! so several repositories can be served side by side. The auth token is
+//! never written there; only the startup output shows it.
+//!
+//! With `--service` there is no banner and no PID file: the service manager
+//! tracks the process, and with socket activation owns the listening socket.
+//! A unit pair for a team-shared instance:
+//!
+//! ```ini
+//! # git-viewer.socket
+//! [Socket]
+//! ListenStream=0.0.0.0:3001
+//!
+//! # git-viewer.service
+//! [Service]
+//! ExecStart=/usr/local/bin/git-viewer /srv/repo --service --read-only
+//! Environment=GIT_VIEWER_AUTH_TOKEN=<token>
 //! ```
 
+mod access_log;
+mod auth;
 mod error;
 mod git;
+mod limits;
 mod models;
 mod routes;
+mod service;
 
 use std::fs;
 use std::io::{Read, Write};
+use std::net::SocketAddr;
 use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 
 use axum::Router;
 use axum::body::Body;
 use axum::http::{header, Request, Response, StatusCode};
+use axum::middleware;
 use axum::routing::get;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use rust_embed::Embed;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use git::GitRepository;
 
+/// How long open connections get to finish after a shutdown signal
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(3);
+
 /// Embedded frontend static files
 #[derive(Embed)]
 #[folder = "../frontend/dist"]
@@ -54,14 +89,148 @@ struct Cli {
     /// Port to run the server on
     #[arg(short, long, default_value = "3001")]
     port: u16,
+
+    /// File or directory (relative to the repository root) the UI opens at
+    #[arg(long, value_name = "PATH")]
+    path: Option<String>,
+
+    /// Commit (OID, branch, tag or revision expression) the UI opens showing
+    #[arg(long = "ref", value_name = "REV")]
+    rev: Option<String>,
+
+    /// Path to the git directory, for layouts where it is separated from the work tree
+    #[arg(long, value_name = "DIR", conflicts_with = "repo_path")]
+    git_dir: Option<PathBuf>,
+
+    /// Work tree to pair with --git-dir (e.g. $HOME for a dotfiles repo)
+    #[arg(long, value_name = "DIR", requires = "git_dir")]
+    work_tree: Option<PathBuf>,
+
+    /// Memory budget for the commit cache and derived caches, in MB (an
+    /// eighth of it goes to the line counts behind author and hotspot statistics)
+    #[arg(long, value_name = "MB", default_value_t = git::cache::DEFAULT_CACHE_MAX_MB)]
+    cache_max_mb: usize,
+
+    /// Author name/email pattern to treat as a bot, e.g. 'renovate@*' (repeatable;
+    /// added to the defaults '*[bot]' and '*[bot]@*')
+    #[arg(long = "bot-pattern", value_name = "PATTERN")]
+    bot_patterns: Vec<String>,
+
+    /// Allow the UI to open files in your local editor ($GIT_VIEWER_EDITOR, $VISUAL, $EDITOR or `code`)
+    #[arg(long)]
+    open_in_editor: bool,
+
+    /// Resolve path parameters ignoring case and Unicode normalization (NFC/NFD),
+    /// for paths copied from case-insensitive file systems such as macOS
+    #[arg(long)]
+    canonicalize_paths: bool,
+
+    /// Only let the repository switcher browse and open repositories below this
+    /// directory (by default the whole file system is reachable)
+    #[arg(long, value_name = "DIR")]
+    browse_root: Option<PathBuf>,
+
+    /// Require this token on every API request (Bearer header, or the printed
+    /// URL in a browser); without a value a random one is generated. Also read
+    /// from $GIT_VIEWER_AUTH_TOKEN, which keeps it out of the process list
+    #[arg(long, value_name = "TOKEN", num_args = 0..=1, default_missing_value = "")]
+    auth_token: Option<String>,
+
+    /// Run as a background service (systemd and the like): no banner, browser or
+    /// PID file; serves a socket passed via LISTEN_FDS if there is one
+    #[arg(long, conflicts_with = "open")]
+    service: bool,
+
+    /// Refuse requests that change the repository (checkout, commit, stash, merge,
+    /// reset, branch and tag changes) and filesystem browsing and switching
+    #[arg(long)]
+    read_only: bool,
+
+    /// Log every request (client, method, path, status, duration, repository) as
+    /// text lines or JSON objects, to --log-file or stdout
+    #[arg(long, value_name = "FORMAT", value_enum)]
+    access_log: Option<access_log::AccessLogFormat>,
+
+    /// Append the access log and server diagnostics to this file instead of the terminal
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// Build the commit cache in the background right after startup, so the first
+    /// page load doesn't wait for it; `all` also warms top-level directory history
+    /// and the root listing's last commits
+    #[arg(long, value_name = "WHAT", value_enum, num_args = 0..=1, default_missing_value = "commits")]
+    prewarm: Option<Prewarm>,
+
+    /// Sustained API requests per second per client IP address, shared by its
+    /// browser tabs (bursts of four times as many are fine); 0 disables rate limiting
+    #[arg(long, value_name = "REQ_PER_SEC", default_value_t = limits::DEFAULT_RATE_LIMIT)]
+    rate_limit: u32,
+
+    /// Stop blame, diff, directory-info and diff-search requests that run longer
+    /// than this and answer 504, releasing the repository for other requests;
+    /// 0 lets them run to completion
+    #[arg(long, value_name = "SECS", default_value_t = git::deadline::DEFAULT_REQUEST_TIMEOUT_SECS)]
+    request_timeout: u64,
+}
+
+/// What `--prewarm` builds
+#[derive(Clone, Copy, ValueEnum)]
+enum Prewarm {
+    /// The commit cache
+    Commits,
+    /// The commit cache, top-level directory path caches and the root listing
+    All,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Check if git-viewer is currently running
-    Status,
-    /// Stop the running git-viewer instance
-    Kill,
+    /// Check which git-viewer instances are currently running
+    Status(InstanceArgs),
+    /// Stop running git-viewer instances
+    Kill(InstanceArgs),
+}
+
+/// Selects which running instances a subcommand applies to
+#[derive(Args)]
+struct InstanceArgs {
+    /// Apply to every running instance
+    #[arg(long, conflicts_with_all = ["port", "repo"])]
+    all: bool,
+
+    /// Only the instance serving on this port
+    #[arg(long)]
+    port: Option<u16>,
+
+    /// Only instances serving this repository path
+    #[arg(long, value_name = "PATH")]
+    repo: Option<String>,
+
+    /// Print machine-readable JSON instead of text
+    #[arg(long)]
+    json: bool,
+}
+
+impl InstanceArgs {
+    fn has_selector(&self) -> bool {
+        self.all || self.port.is_some() || self.repo.is_some()
+    }
+
+    fn matches(&self, info: &PidInfo) -> bool {
+        if let Some(port) = self.port
+            && info.port != port
+        {
+            return false;
+        }
+        if let Some(repo) = &self.repo {
+            let wanted = fs::canonicalize(repo)
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|_| repo.clone());
+            if info.repo_path != wanted {
+                return false;
+            }
+        }
+        true
+    }
 }
 
 /// PID file info stored as JSON
@@ -72,29 +241,110 @@ struct PidInfo {
     port: u16,
 }
 
-fn get_pid_file_path() -> PathBuf {
+/// A PID file found on disk, with the file it was read from
+struct Instance {
+    info: PidInfo,
+    file: PathBuf,
+}
+
+/// Per-instance result for `status --json` / `kill --json`
+#[derive(serde::Serialize)]
+struct InstanceReport<'a> {
+    pid: u32,
+    repo_path: &'a str,
+    port: u16,
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stopped: Option<bool>,
+}
+
+impl<'a> InstanceReport<'a> {
+    fn new(info: &'a PidInfo, stopped: Option<bool>) -> Self {
+        Self {
+            pid: info.pid,
+            repo_path: &info.repo_path,
+            port: info.port,
+            url: format!("http://127.0.0.1:{}", info.port),
+            stopped,
+        }
+    }
+}
+
+const PID_FILE_PREFIX: &str = "git-viewer";
+
+fn get_pid_file_path(port: u16) -> PathBuf {
     let mut path = std::env::temp_dir();
-    path.push("git-viewer.pid");
+    path.push(format!("{}-{}.pid", PID_FILE_PREFIX, port));
     path
 }
 
-fn read_pid_info() -> Option<PidInfo> {
-    let path = get_pid_file_path();
-    let mut file = fs::File::open(&path).ok()?;
+fn read_pid_info(path: &std::path::Path) -> Option<PidInfo> {
+    let mut file = fs::File::open(path).ok()?;
     let mut contents = String::new();
     file.read_to_string(&mut contents).ok()?;
     serde_json::from_str(&contents).ok()
 }
 
 fn write_pid_info(info: &PidInfo) -> anyhow::Result<()> {
-    let path = get_pid_file_path();
+    let path = get_pid_file_path(info.port);
     let mut file = fs::File::create(&path)?;
     file.write_all(serde_json::to_string(info)?.as_bytes())?;
     Ok(())
 }
 
-fn remove_pid_file() {
-    let _ = fs::remove_file(get_pid_file_path());
+fn remove_pid_file(port: u16) {
+    let _ = fs::remove_file(get_pid_file_path(port));
+}
+
+/// Removes this instance's PID file when dropped, so returning from `main`
+/// with an error or unwinding from a panic doesn't leave it behind
+///
+/// `std::process::exit` skips destructors; paths that use it remove the file first.
+struct PidFileGuard {
+    port: u16,
+}
+
+impl PidFileGuard {
+    fn write(info: &PidInfo) -> anyhow::Result<Self> {
+        write_pid_info(info)?;
+        Ok(Self { port: info.port })
+    }
+}
+
+impl Drop for PidFileGuard {
+    fn drop(&mut self) {
+        remove_pid_file(self.port);
+    }
+}
+
+/// Read every PID file (including the legacy single `git-viewer.pid`),
+/// removing any whose process is no longer running
+fn running_instances() -> Vec<Instance> {
+    let Ok(entries) = fs::read_dir(std::env::temp_dir()) else {
+        return Vec::new();
+    };
+
+    let mut instances: Vec<Instance> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(PID_FILE_PREFIX) && n.ends_with(".pid"))
+        })
+        .filter_map(|file| {
+            let info = read_pid_info(&file)?;
+            if is_process_running(info.pid) {
+                Some(Instance { info, file })
+            } else {
+                let _ = fs::remove_file(&file);
+                None
+            }
+        })
+        .collect();
+
+    instances.sort_by_key(|i| i.info.port);
+    instances
 }
 
 #[cfg(unix)]
@@ -133,43 +383,81 @@ fn kill_process(pid: u32) -> bool {
         .unwrap_or(false)
 }
 
-fn handle_status() {
-    match read_pid_info() {
-        Some(info) => {
-            if is_process_running(info.pid) {
-                println!("✓ git-viewer is running");
-                println!("  PID:  {}", info.pid);
-                println!("  Repo: {}", info.repo_path);
-                println!("  URL:  http://127.0.0.1:{}", info.port);
-            } else {
-                println!("✗ git-viewer is not running (stale PID file)");
-                remove_pid_file();
-            }
-        }
-        None => {
-            println!("✗ git-viewer is not running");
+fn print_json<T: serde::Serialize>(value: &T) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap_or_default());
+}
+
+fn handle_status(args: &InstanceArgs) {
+    let instances: Vec<Instance> = running_instances()
+        .into_iter()
+        .filter(|i| args.matches(&i.info))
+        .collect();
+
+    if args.json {
+        let reports: Vec<InstanceReport> = instances.iter()
+            .map(|i| InstanceReport::new(&i.info, None))
+            .collect();
+        print_json(&reports);
+        return;
+    }
+
+    if instances.is_empty() {
+        println!("✗ git-viewer is not running");
+        return;
+    }
+
+    if instances.len() == 1 {
+        println!("✓ git-viewer is running");
+    } else {
+        println!("✓ {} git-viewer instances are running", instances.len());
+    }
+    for (idx, instance) in instances.iter().enumerate() {
+        if idx > 0 {
+            println!();
         }
+        println!("  PID:  {}", instance.info.pid);
+        println!("  Repo: {}", instance.info.repo_path);
+        println!("  URL:  http://127.0.0.1:{}", instance.info.port);
     }
 }
 
-fn handle_kill() {
-    match read_pid_info() {
-        Some(info) => {
-            if is_process_running(info.pid) {
-                if kill_process(info.pid) {
-                    println!("✓ Stopped git-viewer (PID {})", info.pid);
-                    remove_pid_file();
-                } else {
-                    println!("✗ Failed to stop git-viewer (PID {})", info.pid);
-                }
+fn handle_kill(args: &InstanceArgs) {
+    let instances: Vec<Instance> = running_instances()
+        .into_iter()
+        .filter(|i| args.matches(&i.info))
+        .collect();
+
+    // Without a selector, only stop an unambiguous single instance
+    if !args.has_selector() && instances.len() > 1 {
+        eprintln!("✗ {} git-viewer instances are running:", instances.len());
+        for instance in &instances {
+            eprintln!("  port {}  {}", instance.info.port, instance.info.repo_path);
+        }
+        eprintln!();
+        eprintln!("Use --port, --repo or --all to choose which to stop.");
+        std::process::exit(1);
+    }
+
+    let mut reports = Vec::new();
+    for instance in &instances {
+        let stopped = kill_process(instance.info.pid);
+        if stopped {
+            let _ = fs::remove_file(&instance.file);
+        }
+        if !args.json {
+            if stopped {
+                println!("✓ Stopped git-viewer (PID {}, port {})", instance.info.pid, instance.info.port);
             } else {
-                println!("✗ git-viewer is not running (stale PID file)");
-                remove_pid_file();
+                println!("✗ Failed to stop git-viewer (PID {})", instance.info.pid);
             }
         }
-        None => {
-            println!("✗ git-viewer is not running");
-        }
+        reports.push(InstanceReport::new(&instance.info, Some(stopped)));
+    }
+
+    if args.json {
+        print_json(&reports);
+    } else if instances.is_empty() {
+        println!("✗ git-viewer is not running");
     }
 }
 
@@ -216,54 +504,109 @@ async fn main() -> anyhow::Result<()> {
 
     // Handle subcommands
     match cli.command {
-        Some(Commands::Status) => {
-            handle_status();
+        Some(Commands::Status(args)) => {
+            handle_status(&args);
             return Ok(());
         }
-        Some(Commands::Kill) => {
-            handle_kill();
+        Some(Commands::Kill(args)) => {
+            handle_kill(&args);
             return Ok(());
         }
         None => {}
     }
 
-    // Need a repo path to start the server
-    let repo_path = cli.repo_path.unwrap_or_else(|| {
-        eprintln!("Usage: git-viewer <REPO_PATH> [--open]");
-        eprintln!("       git-viewer status");
-        eprintln!("       git-viewer kill");
-        eprintln!();
-        eprintln!("Examples:");
-        eprintln!("  git-viewer .              # View current directory");
-        eprintln!("  git-viewer ~/myproject -o # View repo and open browser");
-        std::process::exit(1);
-    });
-
-    // Check if already running
-    if let Some(info) = read_pid_info() {
-        if is_process_running(info.pid) {
-            eprintln!("✗ git-viewer is already running (PID {})", info.pid);
-            eprintln!("  Repo: {}", info.repo_path);
-            eprintln!("  URL:  http://127.0.0.1:{}", info.port);
+    // With no path argument, honor GIT_DIR/GIT_WORK_TREE like git itself does
+    let from_env = cli.repo_path.is_none()
+        && cli.git_dir.is_none()
+        && std::env::var_os("GIT_DIR").is_some();
+
+    // Need a repo path (or explicit --git-dir) to start the server
+    let repo_path = cli.repo_path
+        .or_else(|| cli.git_dir.as_ref().map(|d| d.to_string_lossy().to_string()))
+        .or_else(|| from_env.then(|| std::env::var("GIT_DIR").unwrap_or_default()))
+        .unwrap_or_else(|| {
+            eprintln!("Usage: git-viewer <REPO_PATH> [--open]");
+            eprintln!("       git-viewer --git-dir <DIR> [--work-tree <DIR>]");
+            eprintln!("       git-viewer status [--all | --port <PORT> | --repo <PATH>] [--json]");
+            eprintln!("       git-viewer kill [--all | --port <PORT> | --repo <PATH>] [--json]");
             eprintln!();
-            eprintln!("Run 'git-viewer kill' to stop it first.");
+            eprintln!("Examples:");
+            eprintln!("  git-viewer .              # View current directory");
+            eprintln!("  git-viewer ~/myproject -o # View repo and open browser");
+            std::process::exit(1);
+        });
+
+    let activated = match service::activated_listener() {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("✗ Socket activation failed: {}", e);
             std::process::exit(1);
-        } else {
-            remove_pid_file();
         }
+    };
+
+    // Check if an instance is already running on this port
+    if activated.is_none()
+        && let Some(instance) = running_instances().into_iter().find(|i| i.info.port == cli.port)
+    {
+        eprintln!("✗ git-viewer is already running on port {} (PID {})", cli.port, instance.info.pid);
+        eprintln!("  Repo: {}", instance.info.repo_path);
+        eprintln!("  URL:  http://127.0.0.1:{}", instance.info.port);
+        eprintln!();
+        eprintln!("Run 'git-viewer kill --port {}' to stop it first, or pick another --port.", cli.port);
+        std::process::exit(1);
     }
 
+    let log_file = match &cli.log_file {
+        Some(path) => match fs::OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                eprintln!("✗ Failed to open log file {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
     // Initialize tracing (quieter for production)
+    let log_writer = match &log_file {
+        Some(file) => BoxMakeWriter::new(Mutex::new(file.try_clone()?)),
+        None => BoxMakeWriter::new(std::io::stderr),
+    };
     tracing_subscriber::registry()
         .with(tracing_subscriber::EnvFilter::new(
             std::env::var("RUST_LOG").unwrap_or_else(|_| "warn".into()),
         ))
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_writer(log_writer).with_ansi(log_file.is_none()))
         .init();
 
+    let browse_root = match &cli.browse_root {
+        Some(dir) => match fs::canonicalize(dir) {
+            Ok(root) if root.is_dir() => Some(root),
+            _ => {
+                eprintln!("✗ --browse-root is not a directory: {}", dir.display());
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
     // Open the git repository
-    let repo = match GitRepository::open(&repo_path) {
-        Ok(r) => r,
+    let opened = if let Some(git_dir) = &cli.git_dir {
+        GitRepository::open_separated(git_dir, cli.work_tree.as_deref())
+    } else if from_env {
+        GitRepository::open_from_env()
+    } else {
+        GitRepository::open(&repo_path)
+    };
+    let repo = match opened {
+        Ok(r) => r
+            .with_cache_budget(cli.cache_max_mb.saturating_mul(1024 * 1024))
+            .with_editor(cli.open_in_editor.then(git::editor::default_editor))
+            .with_browse_root(browse_root)
+            .with_request_timeout((cli.request_timeout > 0).then(|| Duration::from_secs(cli.request_timeout)))
+            .with_bot_patterns(cli.bot_patterns.clone())
+            .with_read_only(cli.read_only)
+            .with_path_canonicalization(cli.canonicalize_paths),
         Err(e) => {
             eprintln!("✗ Failed to open repository: {}", e);
             eprintln!("  Path: {}", repo_path);
@@ -271,13 +614,60 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
-    let canonical_path = std::fs::canonicalize(&repo_path)
-        .unwrap_or_else(|_| PathBuf::from(&repo_path))
+    let repo = match repo.with_bootstrap(cli.path.as_deref(), cli.rev.as_deref()) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("✗ Invalid --path or --ref: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let canonical_path = std::fs::canonicalize(&repo.path)
+        .unwrap_or_else(|_| PathBuf::from(&repo.path))
         .to_string_lossy()
         .to_string();
 
     let shared_repo = Arc::new(RwLock::new(repo));
 
+    if let Some(prewarm) = cli.prewarm {
+        let repo = shared_repo.clone();
+        tokio::task::spawn_blocking(move || {
+            let paths = matches!(prewarm, Prewarm::All);
+            let result = match repo.read() {
+                Ok(repo) => repo.prewarm(paths),
+                Err(_) => return,
+            };
+            if let Err(e) = result {
+                tracing::warn!("Prewarming caches failed: {}", e);
+            }
+        });
+    }
+
+    let access_log = cli.access_log.map(|format| {
+        let out: Box<dyn Write + Send> = match log_file {
+            Some(file) => Box::new(file),
+            None => Box::new(std::io::stdout()),
+        };
+        Arc::new(access_log::AccessLog::new(format, shared_repo.clone(), out))
+    });
+
+    let auth_token = match cli.auth_token.or_else(|| std::env::var("GIT_VIEWER_AUTH_TOKEN").ok()) {
+        // A service has no banner to show a generated token in
+        Some(token) if token.is_empty() && cli.service => {
+            eprintln!("✗ --service needs the token given: --auth-token <TOKEN> or $GIT_VIEWER_AUTH_TOKEN");
+            std::process::exit(1);
+        }
+        Some(token) if token.is_empty() => Some(auth::AuthToken::generate()?),
+        Some(token) => match auth::AuthToken::new(&token) {
+            Ok(token) => Some(token),
+            Err(e) => {
+                eprintln!("✗ Invalid --auth-token: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
     // CORS configuration
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -285,33 +675,97 @@ async fn main() -> anyhow::Result<()> {
         .allow_headers(Any);
 
     // Build the router with API routes and static file serving
-    let app = Router::new()
+    let mut app = Router::new()
         .merge(routes::create_router(shared_repo))
         .fallback(get(serve_static))
-        .layer(cors)
-        .layer(TraceLayer::new_for_http());
+        .layer(middleware::from_fn_with_state(
+            Arc::new(limits::Limits::new(cli.rate_limit)),
+            limits::limit_requests,
+        ));
+    if cli.read_only {
+        app = app.layer(middleware::from_fn(service::reject_writes));
+    }
+    // Inside CORS, so preflight requests (which carry no credentials) still succeed
+    if let Some(token) = &auth_token {
+        app = app.layer(middleware::from_fn_with_state(token.clone(), auth::require_token));
+    }
+    app = app.layer(cors);
+    // Outermost, so requests turned away by auth, limits or read-only mode are logged too
+    if let Some(log) = access_log {
+        app = app.layer(middleware::from_fn_with_state(log, access_log::log_requests));
+    }
+    let app = app.layer(TraceLayer::new_for_http());
 
-    // Bind to the port
-    let addr = format!("127.0.0.1:{}", cli.port);
-    let listener = match tokio::net::TcpListener::bind(&addr).await {
-        Ok(l) => l,
-        Err(e) => {
-            eprintln!("✗ Failed to bind to port {}: {}", cli.port, e);
-            eprintln!("  Try a different port with --port <PORT>");
-            std::process::exit(1);
+    // Bind to the port, unless systemd passed in a socket
+    let listener = match activated {
+        Some(listener) => tokio::net::TcpListener::from_std(listener)?,
+        None => {
+            let addr = format!("127.0.0.1:{}", cli.port);
+            match tokio::net::TcpListener::bind(&addr).await {
+                Ok(l) => l,
+                Err(e) => {
+                    eprintln!("✗ Failed to bind to port {}: {}", cli.port, e);
+                    eprintln!("  Try a different port with --port <PORT>");
+                    std::process::exit(1);
+                }
+            }
         }
     };
+    let local_addr = listener.local_addr()?;
+    let port = local_addr.port();
+
+    let _pid_file = if cli.service {
+        println!(
+            "git-viewer {} serving {} on {}{}",
+            env!("CARGO_PKG_VERSION"),
+            canonical_path,
+            local_addr,
+            if cli.read_only { " (read-only)" } else { "" }
+        );
+        None
+    } else {
+        // Write PID file
+        let pid_info = PidInfo {
+            pid: std::process::id(),
+            repo_path: canonical_path.clone(),
+            port,
+        };
+        let pid_file = PidFileGuard::write(&pid_info)?;
+        print_banner(&canonical_path, port, auth_token.as_ref(), cli.open);
+        Some(pid_file)
+    };
 
-    // Write PID file
-    let pid_info = PidInfo {
-        pid: std::process::id(),
-        repo_path: canonical_path.clone(),
-        port: cli.port,
+    // Set up graceful shutdown
+    let service_mode = cli.service;
+    let shutdown = async move {
+        service::shutdown_signal().await;
+        if !service_mode {
+            remove_pid_file(port);
+            // After SIGHUP the terminal may be gone; println! would panic
+            let _ = writeln!(std::io::stdout(), "\n  Shutting down...");
+        }
+        // Live update streams never end on their own; don't wait on them forever
+        // (the PID file is already gone, as exiting skips its guard)
+        tokio::spawn(async {
+            tokio::time::sleep(SHUTDOWN_GRACE).await;
+            std::process::exit(0);
+        });
     };
-    write_pid_info(&pid_info)?;
 
-    // Print startup message
-    let url = format!("http://127.0.0.1:{}", cli.port);
+    // Start the server with graceful shutdown
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown)
+        .await?;
+
+    Ok(())
+}
+
+/// Startup banner of an interactive instance; opens the browser with `--open`
+fn print_banner(canonical_path: &str, port: u16, auth_token: Option<&auth::AuthToken>, open: bool) {
+    let url = match auth_token {
+        Some(token) => format!("http://127.0.0.1:{}/?token={}", port, token.as_str()),
+        None => format!("http://127.0.0.1:{}", port),
+    };
     println!();
     println!("  ┌─────────────────────────────────────────────┐");
     println!("  │            Git Repository Viewer            │");
@@ -319,34 +773,21 @@ async fn main() -> anyhow::Result<()> {
     println!();
     println!("  Repository: {}", canonical_path);
     println!("  Server:     {}", url);
+    if auth_token.is_some() {
+        println!("  Access:     token required (API clients: Authorization: Bearer <token>)");
+    }
     println!();
     println!("  Commands:");
-    println!("    git-viewer status  - Check if running");
-    println!("    git-viewer kill    - Stop the server");
+    println!("    git-viewer status                 - Check if running");
+    println!("    git-viewer kill --port {:<5}      - Stop this server", port);
     println!();
     println!("  Press Ctrl+C to stop");
     println!();
 
     // Open browser if requested
-    if cli.open {
-        if let Err(e) = open::that(&url) {
-            eprintln!("  Warning: Could not open browser: {}", e);
-        }
+    if open
+        && let Err(e) = open::that(&url)
+    {
+        eprintln!("  Warning: Could not open browser: {}", e);
     }
-
-    // Set up graceful shutdown
-    let shutdown = async {
-        tokio::signal::ctrl_c()
-            .await
-            .expect("Failed to listen for Ctrl+C");
-        println!("\n  Shutting down...");
-        remove_pid_file();
-    };
-
-    // Start the server with graceful shutdown
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown)
-        .await?;
-
-    Ok(())
 }
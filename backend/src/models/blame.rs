@@ -2,22 +2,48 @@
 //!
 //! Provides per-line author attribution for file content at a specific commit.
 //! Used by the diff viewer to show who last modified each line.
+//!
+//! `BlameEvent` is the NDJSON event stream variant: a `start` header, `lines`
+//! chunks in file order, then `done`.
 
 use serde::Serialize;
 
 /// Response for blame request on a file at a specific commit.
 #[derive(Debug, Serialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
 pub struct BlameResponse {
     /// Path of the file
     pub path: String,
     /// Commit OID where blame was calculated
     pub commit: String,
+    /// Commits looked through (ignore file and `ignore_revs`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ignored_revs: Vec<String>,
     /// Per-line blame information
     pub lines: Vec<BlameLine>,
 }
 
+/// Event emitted by the streaming blame endpoint.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BlameEvent {
+    Start {
+        path: String,
+        commit: String,
+        /// Number of lines in the file, so the gutter can be sized up front
+        total_lines: usize,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        ignored_revs: Vec<String>,
+    },
+    Lines {
+        lines: Vec<BlameLine>,
+    },
+    Done,
+}
+
 /// Blame information for a single line.
 #[derive(Debug, Serialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
 pub struct BlameLine {
     /// Line number (1-indexed)
     pub line_number: u32,
@@ -29,4 +55,9 @@ pub struct BlameLine {
     pub commit_oid: String,
     /// Unix timestamp of when this line was last modified
     pub timestamp: i64,
+    /// Path in `commit_oid` when it differs (file renamed or line moved since)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orig_path: Option<String>,
+    /// Line number in `commit_oid`'s version of the file
+    pub orig_line_number: u32,
 }
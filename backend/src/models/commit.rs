@@ -2,11 +2,20 @@
 //!
 //! - `CommitDetail`: Full commit info for history list (HistoryTab)
 //! - `CommitListResponse`: Paginated commit list with totals and contributors
+//! - `QueryMatchCounts`: How many listed commits matched `query=` in each field
 //! - `AuthorInfo`: Author name and email (used in contributor filter)
+//! - `SignatureStatus`: Whether a commit carries a signature (verification badges)
+//! - `PathMode`: How several `path` filters combine (union or intersection)
+//! - `CommitNotes` / `NoteInfo`: Notes attached to a commit, per notes ref
+//! - `PickaxeMode`: Whether a diff search counts a string (`-S`) or greps lines (`-G`)
+//! - `ConventionalCommit` / `Trailer`: Message parsed as a conventional commit
 
 use serde::{Deserialize, Serialize};
 
+use super::VerificationStatus;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
 pub struct CommitDetail {
     pub oid: String,
     pub message: String,
@@ -16,19 +25,120 @@ pub struct CommitDetail {
     pub relative_time: String,
     pub parent_count: usize,
     pub parents: Vec<String>,
+    /// Only populated when requested with `include_signatures=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<SignatureStatus>,
+    /// Signature checked against the configured keys / allowed signers, only
+    /// populated with `verify_signatures=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification: Option<VerificationStatus>,
+    /// Note from `refs/notes/commits`, only populated with `include_notes=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Set when the message follows the Conventional Commits format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conventional: Option<ConventionalCommit>,
+}
+
+/// `type(scope)!: subject`, the body, and the trailers closing the message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
+pub struct ConventionalCommit {
+    /// As written, e.g. `feat` or `fix`
+    #[serde(rename = "type")]
+    #[cfg_attr(feature = "graphql", graphql(name = "type"))]
+    pub commit_type: String,
+    pub scope: Option<String>,
+    /// `!` after the type/scope, or a `BREAKING CHANGE:` trailer
+    pub breaking: bool,
+    pub subject: String,
+    /// Everything between the header and the trailers
+    pub body: Option<String>,
+    pub trailers: Vec<Trailer>,
+}
+
+/// `Key: value` line from the last paragraph (`Signed-off-by`, `Refs`, ...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
+pub struct Trailer {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum), graphql(rename_items = "lowercase"))]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureStatus {
+    Signed,
+    Unsigned,
+    Unknown,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
 pub struct AuthorInfo {
     pub name: String,
     pub email: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
 pub struct CommitListResponse {
     pub commits: Vec<CommitDetail>,
     pub total: usize,
     pub filtered_total: usize,
     pub has_more: bool,
     pub contributors: Vec<AuthorInfo>,
+    /// Only present when the list was filtered with `query=`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub match_counts: Option<QueryMatchCounts>,
+}
+
+/// Commits (after path and author filters) whose field matched the query;
+/// a commit matching in several fields is counted in each
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
+pub struct QueryMatchCounts {
+    pub message: usize,
+    pub author_name: usize,
+    pub author_email: usize,
+}
+
+/// How multiple `path` filters on the commit list are combined
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PathMode {
+    /// Commits touching any of the paths
+    #[default]
+    Union,
+    /// Commits touching all of the paths
+    Intersect,
+}
+
+/// How a pickaxe search matches a commit's diff
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PickaxeMode {
+    /// Commits that change the number of occurrences of the string (`git log -S`)
+    #[default]
+    String,
+    /// Commits with an added or removed line matching the regex (`git log -G`)
+    Regex,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitNotes {
+    pub commit: String,
+    /// One entry per notes ref that annotates the commit, `refs/notes/commits` first
+    pub notes: Vec<NoteInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteInfo {
+    /// e.g. `refs/notes/commits` or `refs/notes/review`
+    pub notes_ref: String,
+    pub message: String,
+    pub author: AuthorInfo,
+    /// When the note was written (Unix seconds)
+    pub timestamp: i64,
 }
@@ -0,0 +1,42 @@
+//! Per-contributor DTOs.
+//!
+//! - `ContributorProfile`: One author's commits (paginated), directories and activity
+//! - `DirectoryActivity`: Directory with the number of the author's commits touching it
+//! - `ActivityBucket`: Commit count for one day, week or calendar month
+//!
+//! Used by: contributor "person page"
+
+use serde::{Deserialize, Serialize};
+
+use super::CommitDetail;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContributorProfile {
+    /// Most recent name used with this email
+    pub name: String,
+    pub email: String,
+    pub commit_count: usize,
+    pub first_commit_timestamp: i64,
+    pub last_commit_timestamp: i64,
+    /// Requested page of the author's commits, newest first
+    pub commits: Vec<CommitDetail>,
+    pub has_more: bool,
+    /// Directories the author touched, most frequent first
+    pub directories: Vec<DirectoryActivity>,
+    /// Commits per month, oldest first, including months without commits
+    pub activity: Vec<ActivityBucket>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryActivity {
+    /// Directory path ("" for the repository root)
+    pub path: String,
+    pub commit_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityBucket {
+    /// `YYYY-MM-DD` (day), `YYYY-Www` (ISO week) or `YYYY-MM` (month), in UTC
+    pub period: String,
+    pub commit_count: usize,
+}
@@ -0,0 +1,99 @@
+//! Diagnostics DTOs.
+//!
+//! - `OperationTiming`: How long one git operation took, with its parameters
+//! - `SlowOpsResponse`: Slowest operations from the recent-operations ring buffer
+//! - `HealthResponse`: Uptime, repository, HEAD and commit cache state
+//! - `CacheStats`: Size and age of the commit cache
+//! - `CacheStatus`: Whether the commit cache is ready, and a running build's progress
+//!
+//! Used by: GET /api/v1/debug/slow-ops when tracking down a sluggish view,
+//! GET /api/v1/health for liveness checks and cache diagnostics,
+//! GET /api/v1/cache/status for the frontend's "indexing history" progress
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationTiming {
+    /// Operation name, e.g. "diff" or "path_cache_build"
+    pub operation: &'static str,
+    /// Parameters the operation ran with, in `key=value` form
+    pub params: String,
+    pub duration_ms: f64,
+    /// When the operation finished (Unix seconds)
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowOpsResponse {
+    /// Size of the ring buffer the operations are taken from
+    pub capacity: usize,
+    /// Operations recorded since startup (older ones have been dropped)
+    pub total_recorded: usize,
+    pub operations: Vec<OperationTiming>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthResponse {
+    /// Server version (`CARGO_PKG_VERSION`)
+    pub version: &'static str,
+    /// Seconds since the server started (not since the last repository switch)
+    pub uptime_secs: u64,
+    pub repository_path: String,
+    /// `None` on an unborn branch
+    pub head_oid: Option<String>,
+    /// `None` until the first history query builds the cache, and while the
+    /// repository is too large for the budget
+    pub cache: Option<CacheStats>,
+    /// Memory budget for the commit cache and derived caches
+    pub cache_budget_bytes: usize,
+    /// The last build exceeded the budget; queries walk history uncached
+    pub cache_over_budget: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    /// HEAD the cache was built from; differs from the current HEAD until the next query rebuilds it
+    pub head_oid: String,
+    pub total_commits: usize,
+    /// Paths with a cached history, including the root
+    pub cached_paths: usize,
+    pub path_cache_bytes: usize,
+    /// Commit list plus the root path entry
+    pub commits_bytes: usize,
+    /// Seconds since the cache was built
+    pub age_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheBuildState {
+    /// Not built yet, or dropped because HEAD moved; the next history query starts a build
+    Cold,
+    Building,
+    Ready,
+    /// The repository is too large for the budget; history is queried uncached
+    OverBudget,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStatus {
+    pub state: CacheBuildState,
+    /// Commits read so far (while building)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commits_read: Option<usize>,
+    /// Commits to read, known once the walk has listed them (while building)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_commits: Option<usize>,
+    /// Time since the build started (while building)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<u64>,
+    /// Estimated time until the build finishes (while building)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eta_ms: Option<u64>,
+    /// How long the last finished build took
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_build_ms: Option<u64>,
+    /// The cache as it is; may be from an older HEAD while a rebuild runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheStats>,
+}
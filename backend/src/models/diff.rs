@@ -2,16 +2,26 @@
 //!
 //! - `DiffResponse`: Complete diff with files, stats, and contributors
 //! - `FileDiff`: Single file's changes with hunks and author info
+//! - `UntrackedFile`: A new file in the working tree that git doesn't track yet
 //! - `DiffHunk`: Contiguous block of changes with context
-//! - `DiffLine`: Single line (addition, deletion, or context)
+//! - `ImageDiff`: Old/new image versions (OIDs, mime, dimensions) for image comparison
+//! - `BinaryDiff`: Old/new blob OIDs and sizes of a binary file, with the size change
+//! - `LfsDiff`: Old/new LFS pointers of a file stored in Git LFS, with the size change
+//! - `ModeChange`: Executable-bit or symlink change between a file's old and new mode
+//! - `DiffLine`: Single line (addition, deletion, or context), optionally with `LineBlame`
+//! - `IntraLineSpan`: Changed part of a modified line (word-level highlighting)
 //! - `FileAuthorInfo`: Who touched a file, with commit count (for author badges)
+//! - `DiffTreeSummary`: Per-directory change totals (folder badges when comparing refs)
+//! - `FileCompareResponse`: One file's diff between two refs
+//! - `RangeDiffResponse`: Commits of two ranges paired up (`git range-diff`)
 //!
 //! Used by: DiffViewer to render side-by-side or unified diff view
 
 use serde::{Deserialize, Serialize};
-use super::AuthorInfo;
+use super::{AuthorInfo, CommitInfo, LfsPointer};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
 pub struct FileAuthorInfo {
     pub email: String,
     pub name: String,
@@ -20,6 +30,7 @@ pub struct FileAuthorInfo {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
 pub struct DiffResponse {
     pub from_commit: Option<String>,
     pub to_commit: String,
@@ -29,9 +40,14 @@ pub struct DiffResponse {
     pub contributors: Vec<AuthorInfo>,
     pub total_files: usize,
     pub filtered_files: usize,
+    /// Files not tracked by git, only for working tree diffs; they are not
+    /// part of `files` or `stats`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub untracked: Vec<UntrackedFile>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
 pub struct FileDiff {
     pub old_path: Option<String>,
     pub new_path: Option<String>,
@@ -39,12 +55,137 @@ pub struct FileDiff {
     pub hunks: Vec<DiffHunk>,
     pub old_content: Option<String>,
     pub new_content: Option<String>,
+    /// Set when the file isn't UTF-8: the encoding (WHATWG name) its contents
+    /// and hunk lines were transcoded from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
     pub is_binary: bool,
+    /// Marked `linguist-generated` in .gitattributes; contents aren't loaded
+    /// and the viewer shows the file collapsed
+    pub is_generated: bool,
     pub authors: Vec<FileAuthorInfo>,
     pub biggest_change_author: Option<String>,
+    /// Set for image files; fetch the pixels from /repository/objects/{oid}
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageDiff>,
+    /// Set for binary files, which have no hunks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binary: Option<BinaryDiff>,
+    /// Set when either side is a Git LFS pointer; such files have no hunks
+    /// or contents, since those would be the pointer text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lfs: Option<LfsDiff>,
+    /// Octal file mode on each side ("100644", "100755", "120000"), absent
+    /// for the side where the file doesn't exist
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_mode: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_mode: Option<String>,
+    /// Set when the mode differs between the sides; a mode-only change has
+    /// no hunks, so this is what the viewer shows for it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode_change: Option<ModeChange>,
+    /// Owners of the file's path per CODEOWNERS in the newer commit (HEAD
+    /// for uncommitted changes)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
+pub struct UntrackedFile {
+    pub path: String,
+    /// Size on disk in bytes (the target path's length for symlinks)
+    pub size: u64,
+    /// NUL bytes or not text in any encoding detected, or marked `binary` in
+    /// .gitattributes
+    pub is_binary: bool,
+    pub is_generated: bool,
+    pub is_symlink: bool,
+    /// Content type from the file's magic bytes (images, audio, video, PDF, text)
+    pub mime: Option<String>,
+    /// Set for text files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_count: Option<usize>,
+    /// Text content, or the link target for symlinks; `None` for binary,
+    /// generated and oversized files and when contents weren't requested
+    pub content: Option<String>,
+    /// Set when the text isn't UTF-8: the encoding `content` was transcoded from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    /// Larger than the content limit, so `content` and `line_count` are left out
+    pub too_large: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum), graphql(rename_items = "snake_case"))]
+#[serde(rename_all = "snake_case")]
+pub enum ModeChange {
+    /// 100644 -> 100755
+    ExecutableAdded,
+    /// 100755 -> 100644
+    ExecutableRemoved,
+    /// A file became a symlink (status `typechanged`)
+    ToSymlink,
+    /// A symlink became a file (status `typechanged`)
+    FromSymlink,
+    /// Any other mode change, e.g. a file replaced by a submodule
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
+pub struct BinaryDiff {
+    /// `None` when the file is added (old) or deleted (new)
+    pub old: Option<BinaryVersion>,
+    pub new: Option<BinaryVersion>,
+    /// New size minus old size in bytes (a missing side counts as 0)
+    pub size_delta: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
+pub struct LfsDiff {
+    /// `None` when that side doesn't exist or isn't a pointer (e.g. a file
+    /// moved into LFS, or a working tree file holding the fetched content)
+    pub old: Option<LfsPointer>,
+    pub new: Option<LfsPointer>,
+    /// New size minus old size in bytes, using the real size for pointers
+    pub size_delta: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
+pub struct BinaryVersion {
+    pub oid: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
+pub struct ImageDiff {
+    /// `None` when the image is added (old) or deleted (new), and for the
+    /// working tree side of uncommitted changes
+    pub old: Option<ImageVersion>,
+    pub new: Option<ImageVersion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
+pub struct ImageVersion {
+    /// Blob OID, stable for as long as the object exists
+    pub oid: String,
+    pub mime: String,
+    pub size: u64,
+    /// Pixel dimensions, when the header could be read (not for SVG)
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum), graphql(rename_items = "lowercase"))]
 #[serde(rename_all = "lowercase")]
 pub enum DiffStatus {
     Added,
@@ -57,6 +198,7 @@ pub enum DiffStatus {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
 pub struct DiffHunk {
     pub old_start: u32,
     pub old_lines: u32,
@@ -67,14 +209,41 @@ pub struct DiffHunk {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
 pub struct DiffLine {
     pub line_type: LineType,
     pub old_lineno: Option<u32>,
     pub new_lineno: Option<u32>,
     pub content: String,
+    /// Origin of a context/deletion line, when requested with `include_blame`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blame: Option<LineBlame>,
+    /// Changed parts of a deletion/addition paired with a similar line on the
+    /// other side; empty for unpaired lines and lines that changed entirely
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<IntraLineSpan>,
+}
+
+/// Half-open range `[start, end)` of `DiffLine::content` in UTF-16 code units
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
+pub struct IntraLineSpan {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Commit and author a line was last changed in, as of the diff's old side
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
+pub struct LineBlame {
+    pub commit_oid: String,
+    pub author_name: String,
+    pub author_email: String,
+    pub timestamp: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum), graphql(rename_items = "lowercase"))]
 #[serde(rename_all = "lowercase")]
 pub enum LineType {
     Context,
@@ -84,6 +253,7 @@ pub enum LineType {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
 pub struct DiffStats {
     pub files_changed: usize,
     pub insertions: usize,
@@ -91,7 +261,70 @@ pub struct DiffStats {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WorkingTreeStatus {
-    pub has_changes: bool,
+pub struct DirectoryDiffSummary {
+    /// Directory path ("" for the repository root)
+    pub path: String,
     pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffTreeSummary {
+    pub from_commit: Option<String>,
+    pub to_commit: String,
+    pub path: Option<String>,
+    /// Every directory containing at least one changed file, sorted by path
+    pub directories: Vec<DirectoryDiffSummary>,
+    pub stats: DiffStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileCompareResponse {
+    pub path: String,
+    /// Resolved OIDs of the `base` and `head` refs
+    pub base_commit: String,
+    pub head_commit: String,
+    /// Both versions and the hunks between them; `old_path`/`new_path` are
+    /// `None` on the side where the file does not exist
+    pub file: FileDiff,
+    pub stats: DiffStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RangeDiffResponse {
+    /// The ranges as requested, e.g. `main..feature@{1}` and `main..feature`
+    pub old_range: String,
+    pub new_range: String,
+    /// In new-range order, with removed commits placed before the first
+    /// new commit that follows them
+    pub entries: Vec<RangeDiffEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RangeDiffEntry {
+    pub status: RangeDiffStatus,
+    /// 1-based position within the old range; `None` for added commits
+    pub old_position: Option<usize>,
+    pub old_commit: Option<CommitInfo>,
+    /// 1-based position within the new range; `None` for removed commits
+    pub new_position: Option<usize>,
+    pub new_commit: Option<CommitInfo>,
+    /// Diff of the old commit's message and patch against the new one's;
+    /// only for modified pairs
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub interdiff: Vec<DiffHunk>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RangeDiffStatus {
+    /// Same message and patch
+    Equal,
+    /// Paired, but the message or patch changed
+    Modified,
+    /// Only in the old range
+    Removed,
+    /// Only in the new range
+    Added,
 }
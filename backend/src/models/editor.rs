@@ -0,0 +1,23 @@
+//! Open-in-editor DTOs.
+//!
+//! - `OpenInEditorRequest`: Working tree path and optional line to jump to
+//! - `OpenInEditorResponse`: What was opened and with which editor command
+//!
+//! Used by: FileViewer / BlameView "Open in editor" action
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenInEditorRequest {
+    /// Path relative to the repository root
+    pub path: String,
+    /// 1-based line number
+    pub line: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenInEditorResponse {
+    pub path: String,
+    pub line: Option<u32>,
+    pub editor: String,
+}
@@ -0,0 +1,35 @@
+//! Live update DTOs.
+//!
+//! - `RepoChangeEvent`: One batch of changes seen by the repository watcher
+//! - `ChangeKind`: What changed (HEAD, refs, index, working tree, or the repository itself)
+//!
+//! Used by: live update subscription (commit list, branches, working tree status)
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoChangeEvent {
+    /// Each kind at most once, in `ChangeKind` order
+    pub kinds: Vec<ChangeKind>,
+    /// Working tree paths that changed (repository-relative), capped; empty
+    /// when only git metadata changed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    /// More working tree paths changed than `paths` lists
+    pub paths_truncated: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// HEAD moved to another branch or commit (checkout, commit, reset)
+    Head,
+    /// A branch, tag or remote-tracking ref was created, moved or deleted
+    Refs,
+    /// The staging area changed
+    Index,
+    /// A file in the working tree (not ignored) was created, changed or removed
+    WorkingTree,
+    /// The viewer switched to another repository; everything is stale
+    Repository,
+}
@@ -0,0 +1,44 @@
+//! Commit graph DTOs.
+//!
+//! - `GraphResponse`: Commits in topological order with their lane layout
+//! - `GraphCommit`: One row of the graph: commit, lane and lines to the next row
+//! - `GraphEdge`: Line from a lane in one row to a lane in the next row
+//!
+//! Used by: branch graph next to the commit list
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphResponse {
+    /// Children before parents, one row each
+    pub commits: Vec<GraphCommit>,
+    /// Widest row, in lanes
+    pub lane_count: usize,
+    /// More commits exist beyond `limit`
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphCommit {
+    pub oid: String,
+    pub parents: Vec<String>,
+    pub summary: String,
+    pub author: String,
+    pub timestamp: i64,
+    /// Column of the commit's dot, 0 = leftmost
+    pub lane: usize,
+    /// Lines from this row down to the next: to each parent in the graph, and
+    /// for every lane passing by
+    pub edges: Vec<GraphEdge>,
+    /// Branches and tags pointing at the commit
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub refs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphEdge {
+    /// Lane in this row
+    pub from_lane: usize,
+    /// Lane in the next row
+    pub to_lane: usize,
+}
@@ -0,0 +1,108 @@
+//! Repository maintenance DTOs.
+//!
+//! - `MaintenanceStatus`: Object storage and reflog statistics relevant to gc
+//! - `MaintenanceRequest`: Body for running a maintenance action
+//! - `MaintenanceResult`: Outcome of an action with before/after statistics
+//! - `FsckEvent`: Progress/problem/summary events streamed by the integrity check
+//! - `PackInfo`/`PackListResponse`: Per-packfile size, object count and age
+//!
+//! Used by: repository maintenance panel (tidying long-lived local repos)
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    pub loose_objects: usize,
+    pub loose_size: u64,
+    pub pack_count: usize,
+    pub pack_size: u64,
+    pub reflog_entries: usize,
+    pub reflog_size: u64,
+    /// Heuristic mirroring `gc.auto` / `gc.autoPackLimit` defaults
+    pub gc_recommended: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum MaintenanceAction {
+    /// `git gc`
+    Gc,
+    /// `git repack -a -d`
+    Repack,
+    /// `git prune-packed`
+    PrunePacked,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MaintenanceRequest {
+    pub action: MaintenanceAction,
+    /// Must be true; guards against accidental invocations
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceResult {
+    pub action: MaintenanceAction,
+    pub success: bool,
+    pub output: String,
+    pub duration_ms: u128,
+    pub before: MaintenanceStatus,
+    pub after: MaintenanceStatus,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FsckProblemKind {
+    /// Object is referenced but not present in the object database
+    Missing,
+    /// Object is present but fails to load or parse
+    Corrupt,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum FsckEvent {
+    Progress {
+        objects_checked: usize,
+        commits_checked: usize,
+    },
+    Problem {
+        oid: String,
+        kind: FsckProblemKind,
+        /// Where the object was referenced from (ref name or parent object)
+        referenced_by: String,
+        message: String,
+    },
+    Done {
+        objects_checked: usize,
+        commits_checked: usize,
+        problems: usize,
+        duration_ms: u128,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackInfo {
+    /// Pack file name (`pack-<hash>.pack`)
+    pub name: String,
+    pub size: u64,
+    pub index_size: u64,
+    /// Object count from the `.idx` fan-out table (None if the index is unreadable)
+    pub object_count: Option<u32>,
+    /// Unix timestamp (creation time where the filesystem supports it, else mtime)
+    pub created: Option<i64>,
+    pub has_bitmap: bool,
+    /// `.keep` file present - excluded from repacks
+    pub is_kept: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackListResponse {
+    /// Largest pack first
+    pub packs: Vec<PackInfo>,
+    pub total_size: u64,
+    pub total_objects: u64,
+    /// More than one non-kept pack: `repack -a -d` would consolidate them
+    pub repack_recommended: bool,
+}
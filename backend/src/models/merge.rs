@@ -0,0 +1,39 @@
+//! Merge DTOs.
+//!
+//! - `MergeResult`: What merging a branch into HEAD does (or did)
+//! - `MergeAnalysis`: Up to date, fast-forward, clean merge commit, or conflicts
+//!
+//! Used by: merge action in the branch switcher
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeResult {
+    /// Branch or revision that was merged, as requested
+    pub branch: String,
+    pub analysis: MergeAnalysis,
+    /// Whether HEAD and the working tree were changed; `false` for dry runs,
+    /// up-to-date branches and conflicts
+    pub applied: bool,
+    /// HEAD after the merge (unchanged unless applied)
+    pub head: Option<String>,
+    /// Normal merges only: the new merge commit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merge_commit: Option<String>,
+    /// Paths that conflict; a merge with conflicts is never applied
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeAnalysis {
+    /// Everything on the branch is already in HEAD
+    UpToDate,
+    /// HEAD is an ancestor of the branch (or unborn) and can simply move forward
+    FastForward,
+    /// The histories diverged and merge without conflicts
+    Normal,
+    /// The histories diverged and some files conflict
+    Conflicts,
+}
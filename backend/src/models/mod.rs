@@ -1,20 +1,71 @@
 //! Data transfer objects (DTOs) for API responses.
 //!
 //! These structs are serialized to JSON for frontend consumption.
-//! - `tree`: TreeEntry, RepositoryInfo, DirectoryInfo, CommitInfo
+//! - `tree`: TreeEntry, TreeListing, FileContent, RepositoryInfo, DirectoryInfo, CommitInfo, LfsPointer
 //! - `commit`: CommitDetail, CommitListResponse, AuthorInfo
+//! - `contributor`: ContributorProfile, DirectoryActivity, ActivityBucket
+//! - `debug`: OperationTiming, SlowOpsResponse, HealthResponse, CacheStats, CacheStatus for diagnostics
+//! - `events`: RepoChangeEvent, ChangeKind for live updates
 //! - `diff`: DiffResponse, FileDiff, DiffHunk, DiffLine
 //! - `blame`: BlameResponse, BlameLine for per-line author attribution
+//! - `graph`: GraphResponse, GraphCommit, GraphEdge for the laid-out commit graph
 //! - `filesystem`: DirectoryListing, FilesystemEntry for repo switching
+//! - `object`: GitObject (tree/commit/tag) for the object-by-OID endpoint
+//! - `refs`: RefInfo, RefKind for the unified ref listing
+//! - `remote`: Permalink, ForgeKind for links to the hosted repository
+//! - `reset`: ResetMode, ResetResult for moving HEAD to another commit
+//! - `stats`: ActivityResponse, PunchCardResponse, AuthorStatsResponse, HotspotsResponse for repository statistics
+//! - `signature`: SignatureVerification for GPG/SSH signature checks
+//! - `stash`: StashEntry for stash push/pop
+//! - `status`: WorkingTreeStatus, StatusEntry for staged/unstaged/untracked files, IgnoredFiles, IgnoreCheck for ignored ones
+//! - `submodule`: SubmoduleInfo for the submodule listing
+//! - `tag`: DeletedTag for tag lifecycle operations
+//! - `editor`: OpenInEditorRequest, OpenInEditorResponse
+//! - `maintenance`: MaintenanceStatus, MaintenanceResult for gc-style actions
+//! - `merge`: MergeResult, MergeAnalysis for merging a branch into HEAD
 
 pub mod blame;
 pub mod commit;
+pub mod contributor;
+pub mod debug;
 pub mod diff;
+pub mod editor;
+pub mod events;
 pub mod filesystem;
+pub mod graph;
+pub mod maintenance;
+pub mod merge;
+pub mod object;
+pub mod refs;
+pub mod remote;
+pub mod reset;
+pub mod signature;
+pub mod stats;
+pub mod stash;
+pub mod status;
+pub mod submodule;
+pub mod tag;
 pub mod tree;
 
 pub use blame::*;
 pub use commit::*;
+pub use contributor::*;
+pub use debug::*;
 pub use diff::*;
+pub use editor::*;
+pub use events::*;
 pub use filesystem::*;
+pub use graph::*;
+pub use maintenance::*;
+pub use merge::*;
+pub use object::*;
+pub use refs::*;
+pub use remote::*;
+pub use reset::*;
+pub use signature::*;
+pub use stats::*;
+pub use stash::*;
+pub use status::*;
+pub use submodule::*;
+pub use tag::*;
 pub use tree::*;
@@ -0,0 +1,63 @@
+//! Raw git object DTOs.
+//!
+//! - `GitObject`: A tree, commit or tag object, tagged by `type` (blobs are served as raw bytes)
+//! - `ObjectTreeEntry`: One entry of a tree object
+//! - `ObjectSignature`: Author, committer or tagger with timestamp
+//!
+//! Used by: permalinks and object debugging via /repository/objects/{oid}
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum GitObject {
+    Tree(TreeObject),
+    Commit(CommitObject),
+    Tag(TagObject),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeObject {
+    pub oid: String,
+    pub entries: Vec<ObjectTreeEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectTreeEntry {
+    pub name: String,
+    /// File mode in octal, as git prints it (e.g. "100644", "040000")
+    pub mode: String,
+    /// Object type: "blob", "tree" or "commit" (submodule)
+    pub kind: String,
+    pub oid: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitObject {
+    pub oid: String,
+    pub tree: String,
+    pub parents: Vec<String>,
+    pub author: ObjectSignature,
+    pub committer: ObjectSignature,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagObject {
+    pub oid: String,
+    pub name: String,
+    pub target: String,
+    pub target_type: String,
+    /// Absent for tags created without a tagger line
+    pub tagger: Option<ObjectSignature>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectSignature {
+    pub name: String,
+    pub email: String,
+    pub timestamp: i64,
+    /// Offset from UTC in minutes
+    pub offset_minutes: i32,
+}
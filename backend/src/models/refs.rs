@@ -0,0 +1,78 @@
+//! Reference listing DTOs.
+//!
+//! - `RefInfo`: One ref with its direct target and the commit it peels to
+//! - `RefKind`: Branch, remote-tracking branch, tag, note, stash or other
+//! - `HeadState`: Current branch, HEAD OID and index checksum (change polling)
+//! - `ReflogResponse` / `ReflogEntry`: Where a ref pointed over time
+//!
+//! Used by: unified ref picker, compare view, external-change polling, and
+//! reflog browsing
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RefKind {
+    Branch,
+    Remote,
+    Tag,
+    Note,
+    Stash,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefInfo {
+    /// Full name (`refs/heads/main`), or `stash@{n}` for stash entries
+    pub name: String,
+    /// Name without the `refs/<kind>/` prefix (`main`, `origin/main`, `v1.0`)
+    pub short_name: String,
+    pub kind: RefKind,
+    /// OID the ref points at directly (a tag object for annotated tags)
+    pub target: Option<String>,
+    /// Ref name for symbolic refs such as `refs/remotes/origin/HEAD`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbolic_target: Option<String>,
+    /// Commit the ref resolves to after peeling tags
+    pub commit: Option<String>,
+    /// Whether HEAD currently points at this branch
+    pub is_head: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeadState {
+    /// Current branch name; `None` when HEAD is detached
+    pub branch: Option<String>,
+    /// Commit HEAD points at; `None` on an unborn branch
+    pub oid: Option<String>,
+    /// Trailing SHA-1 of the index file, which changes whenever the index is written
+    pub index_checksum: Option<String>,
+    /// Fingerprint of all loose and packed refs, which changes on fetches and
+    /// branch or tag writes that leave HEAD alone
+    pub refs_stamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReflogResponse {
+    /// Full ref name the reflog belongs to (`HEAD`, `refs/heads/main`)
+    pub ref_name: String,
+    /// Entries in the reflog, including ones beyond `limit`
+    pub total: usize,
+    /// Newest first
+    pub entries: Vec<ReflogEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReflogEntry {
+    /// Selector for this entry, e.g. `HEAD@{2}`; usable as `ref=` elsewhere
+    pub selector: String,
+    /// `None` when the ref was created by this entry
+    pub old_oid: Option<String>,
+    pub new_oid: String,
+    /// e.g. "checkout: moving from main to feature"
+    pub message: String,
+    pub committer_name: String,
+    pub committer_email: String,
+    pub timestamp: i64,
+    pub relative_time: String,
+}
@@ -0,0 +1,31 @@
+//! Remote hosting DTOs.
+//!
+//! - `Permalink`: Web URL of a commit, file or line range on the remote's forge
+//! - `ForgeKind`: Hosting software, which decides the URL layout
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Permalink {
+    /// Remote the link points at, e.g. `origin`
+    pub remote: String,
+    /// Project page derived from the remote URL, e.g. `https://github.com/owner/repo`
+    pub web_url: String,
+    pub forge: ForgeKind,
+    /// Full OID the link is pinned to, so it keeps pointing at the same content
+    pub commit: String,
+    pub path: Option<String>,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    /// github.com and GitHub Enterprise; also assumed for unrecognized hosts
+    GitHub,
+    /// gitlab.com and self-hosted GitLab
+    GitLab,
+    Bitbucket,
+    /// Gitea, Forgejo and Codeberg
+    Gitea,
+}
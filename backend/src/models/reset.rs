@@ -0,0 +1,43 @@
+//! Reset DTOs.
+//!
+//! - `ResetMode`: soft / mixed / hard, as in `git reset`
+//! - `ResetResult`: What moving HEAD to another commit does (or did)
+//!
+//! Used by: "undo commit" / reset action in the history view
+
+use serde::{Deserialize, Serialize};
+
+use super::CommitInfo;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResetMode {
+    /// Move HEAD only; index and working tree keep the changes (staged)
+    Soft,
+    /// Move HEAD and reset the index; changes stay in the working tree (unstaged)
+    #[default]
+    Mixed,
+    /// Move HEAD and reset index and working tree; uncommitted changes are lost
+    Hard,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResetResult {
+    pub mode: ResetMode,
+    /// Commit HEAD was (or would be) moved to
+    pub target: String,
+    pub previous_head: String,
+    /// `false` for dry runs
+    pub applied: bool,
+    /// Commits HEAD had that the target lacks; they stay reachable via the reflog
+    pub removed_commit_count: usize,
+    /// Newest first, at most `MAX_RESET_COMMITS_LISTED`
+    pub removed_commits: Vec<CommitInfo>,
+    /// Commits the target has that HEAD lacked (resetting forward or sideways)
+    pub added_commit_count: usize,
+    /// Files that differ between the old HEAD and the target
+    pub files_changed: usize,
+    /// Hard resets only: paths whose uncommitted changes are thrown away
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub discarded_changes: Vec<String>,
+}
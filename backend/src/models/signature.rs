@@ -0,0 +1,56 @@
+//! Signature verification DTOs.
+//!
+//! - `SignatureVerification`: Whether a commit or tag is signed, how, by whom,
+//!   and whether the signature checks out
+//! - `SignatureFormat`: GPG (OpenPGP), SSH or X.509
+//! - `VerificationStatus`: Outcome of checking the signature (git's `%G?` codes)
+//!
+//! Used by: commit detail and tag verification badges
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureVerification {
+    /// Resolved OID of the commit or tag object
+    pub oid: String,
+    /// "commit" or "tag"
+    pub object_type: String,
+    /// `None` when the object is not signed
+    pub format: Option<SignatureFormat>,
+    pub status: VerificationStatus,
+    /// Key user ID (GPG) or principal from the allowed signers file (SSH)
+    pub signer: Option<String>,
+    /// Primary key fingerprint (GPG) or key fingerprint (SSH)
+    pub key: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureFormat {
+    Gpg,
+    Ssh,
+    X509,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum), graphql(rename_items = "snake_case"))]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationStatus {
+    /// Valid signature from a trusted key or allowed signer (`G`)
+    Good,
+    /// Valid signature, but the key is not trusted / no principal matched (`U`)
+    Untrusted,
+    /// The signature does not match the content (`B`)
+    Bad,
+    /// Valid signature that has expired (`X`)
+    ExpiredSignature,
+    /// Valid signature made by a key that has since expired (`Y`)
+    ExpiredKey,
+    /// Valid signature made by a revoked key (`R`)
+    RevokedKey,
+    /// Signed, but cannot be checked, e.g. the key is missing or no allowed
+    /// signers file is configured (`E`)
+    CannotVerify,
+    /// No signature (`N`)
+    Unsigned,
+}
@@ -0,0 +1,15 @@
+//! Stash DTOs.
+//!
+//! - `StashEntry`: One entry of the stash list (`stash@{n}`)
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StashEntry {
+    /// Position in the stash list, 0 = most recent
+    pub index: usize,
+    /// e.g. `On main: WIP` (or `WIP on main: abc1234 summary` without a message)
+    pub message: String,
+    /// Stash commit OID
+    pub oid: String,
+}
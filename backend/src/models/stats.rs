@@ -0,0 +1,128 @@
+//! Repository statistics DTOs.
+//!
+//! - `ActivityResponse`: Commit counts per day, week or month, optionally for one path or author
+//! - `ActivityGranularity`: Bucket size of an activity series
+//! - `PunchCardResponse`: Commit counts per day of the week and hour of the day
+//! - `PunchCardCell`: One hour of the week with its commit count
+//! - `AuthorStatsResponse`: Lines added and removed per author over a range or time window
+//! - `AuthorLineStats`: One author's commit, insertion and deletion counts
+//! - `HotspotsResponse`: Most frequently changed files within a time window
+//! - `FileHotspot`: One file's commit count, churn and authors in the window
+//! - `HotspotSort`: Rank hotspots by commit count or by churn
+//!
+//! Used by: activity chart, punch card, author statistics table, hotspot list
+
+use serde::{Deserialize, Serialize};
+
+use super::ActivityBucket;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityResponse {
+    pub granularity: ActivityGranularity,
+    /// Path (or glob) the commits were limited to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Author email the commits were limited to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub total_commits: usize,
+    /// Oldest first, from the first to the last commit, including empty periods
+    pub buckets: Vec<ActivityBucket>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityGranularity {
+    Day,
+    /// ISO weeks, Monday to Sunday
+    #[default]
+    Week,
+    Month,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PunchCardResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub total_commits: usize,
+    /// All 168 hours of the week, Sunday 00:00 first, including empty ones
+    pub cells: Vec<PunchCardCell>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PunchCardCell {
+    /// 0 = Sunday ... 6 = Saturday, in the author's timezone
+    pub day: u8,
+    /// 0-23, in the author's timezone
+    pub hour: u8,
+    pub commit_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorStatsResponse {
+    /// `from..to` the commits were taken from; HEAD's history when absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<i64>,
+    /// Totals over all listed authors (merge commits not included)
+    pub commit_count: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// Most lines changed (insertions + deletions) first
+    pub authors: Vec<AuthorLineStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorLineStats {
+    /// Most recent name used with this email
+    pub name: String,
+    pub email: String,
+    pub commit_count: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotspotsResponse {
+    /// Path, directory or glob the files were limited to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Start of the window; absent when all history was counted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<i64>,
+    pub sort: HotspotSort,
+    /// Non-merge commits in the window that changed any of the files
+    pub commit_count: usize,
+    /// Files changed in the window, before `limit` was applied
+    pub total_files: usize,
+    pub files: Vec<FileHotspot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileHotspot {
+    pub path: String,
+    /// Commits in the window that changed the file
+    pub commit_count: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// Distinct author emails among those commits
+    pub author_count: usize,
+    pub last_changed_timestamp: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HotspotSort {
+    /// Most commits first
+    #[default]
+    Frequency,
+    /// Most lines added plus removed first
+    Churn,
+}
@@ -0,0 +1,144 @@
+//! Working tree status DTOs.
+//!
+//! - `WorkingTreeStatus`: Staged, unstaged, untracked and conflicted files
+//! - `StatusEntry`: One file with its per-file status code
+//! - `FileStatus`: What happened to the file (added, modified, ...)
+//! - `StatusFiles`: Every changed path with its index and working tree status side by side
+//! - `StatusFile`: One path's staged and unstaged state, untracked or conflicted
+//! - `ConflictKind`: Which sides of a merge conflict changed or removed the file
+//! - `IgnoredFiles`: Ignored files and directories under a path
+//! - `IgnoredEntry`: One ignored path with the rule that ignores it
+//! - `IgnoreCheck`: Whether one path is ignored (`git check-ignore -v`)
+//! - `IgnoreRule`: An ignore file line: where it is and what it says
+//!
+//! Used by: StatusTab, HistoryTab "uncommitted changes" entry, Changes panel,
+//! checkout guard error messages, and the file tree's ignored-file explanation
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkingTreeStatus {
+    pub has_changes: bool,
+    /// Number of distinct paths with any change (staged, unstaged, untracked or conflicted)
+    pub files_changed: usize,
+    /// Changes between HEAD and the index
+    pub staged: Vec<StatusEntry>,
+    /// Changes between the index and the working directory
+    pub unstaged: Vec<StatusEntry>,
+    pub untracked: Vec<String>,
+    pub conflicted: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusEntry {
+    pub path: String,
+    /// Previous path for renames
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    pub status: FileStatus,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    TypeChanged,
+}
+
+impl FileStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileStatus::Added => "added",
+            FileStatus::Modified => "modified",
+            FileStatus::Deleted => "deleted",
+            FileStatus::Renamed => "renamed",
+            FileStatus::TypeChanged => "typechanged",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusFiles {
+    /// Sorted by path
+    pub files: Vec<StatusFile>,
+    pub staged_count: usize,
+    pub unstaged_count: usize,
+    pub untracked_count: usize,
+    pub conflicted_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusFile {
+    pub path: String,
+    /// Staged renames: the path in HEAD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    /// HEAD vs index; `None` when nothing is staged for the path
+    pub index_status: Option<FileStatus>,
+    /// Index vs working tree; `None` when the working tree matches the index
+    pub worktree_status: Option<FileStatus>,
+    /// Not in the index at all (both statuses are `None` then)
+    pub is_untracked: bool,
+    /// Only set for conflicted paths
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflict: Option<ConflictKind>,
+}
+
+/// Named like the `git status` short codes (`UU`, `AA`, `DU`, ...)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    BothModified,
+    BothAdded,
+    BothDeleted,
+    AddedByUs,
+    AddedByThem,
+    DeletedByUs,
+    DeletedByThem,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IgnoredFiles {
+    /// Directory the listing is limited to, `None` for the whole working tree
+    pub path: Option<String>,
+    pub entries: Vec<IgnoredEntry>,
+    /// More ignored paths exist than `limit` allowed
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IgnoredEntry {
+    pub path: String,
+    /// An ignored directory is listed instead of its contents
+    pub is_dir: bool,
+    /// `None` if no ignore file line explains it (e.g. a rule libgit2 applies
+    /// from a source not read here)
+    pub rule: Option<IgnoreRule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IgnoreCheck {
+    pub path: String,
+    pub ignored: bool,
+    /// Tracked files are never ignored, even when a rule matches them
+    pub tracked: bool,
+    pub exists: bool,
+    pub is_dir: bool,
+    /// The deciding rule; a `negated` rule re-includes the path
+    pub rule: Option<IgnoreRule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IgnoreRule {
+    /// Ignore file: `.gitignore` path in the repository, `.git/info/exclude`,
+    /// or the `core.excludesFile` path
+    pub source: String,
+    /// 1-based line in `source`
+    pub line: usize,
+    /// The line as written, including a leading `!`
+    pub pattern: String,
+    pub negated: bool,
+}
@@ -0,0 +1,22 @@
+//! Submodule DTOs.
+//!
+//! - `SubmoduleInfo`: A submodule's path, URL, pinned commit and checkout state
+//!
+//! Used by: submodule listing and the FileList submodule badge
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmoduleInfo {
+    /// Name from `.gitmodules` (usually the same as the path)
+    pub name: String,
+    pub path: String,
+    pub url: Option<String>,
+    /// Branch to track, if configured in `.gitmodules`
+    pub branch: Option<String>,
+    /// Commit recorded in HEAD's tree
+    pub pinned_commit: Option<String>,
+    /// Commit checked out in the submodule's working directory; `None` when
+    /// the submodule is not initialized
+    pub checked_out_commit: Option<String>,
+}
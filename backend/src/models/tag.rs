@@ -0,0 +1,15 @@
+//! Tag DTOs.
+//!
+//! - `DeletedTag`: What a tag pointed at before it was deleted (for undo/audit)
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletedTag {
+    pub name: String,
+    /// OID the tag reference pointed at (tag object for annotated tags)
+    pub target: String,
+    /// Commit the tag resolved to
+    pub commit: Option<String>,
+    pub is_annotated: bool,
+}
@@ -1,26 +1,84 @@
 //! Tree and repository-related DTOs.
 //!
 //! - `TreeEntry`: Single file/directory in a listing (FileList view)
+//! - `TreeListing`: One page of a directory's entries, with the total
 //! - `FullTreeEntry`: Recursive tree node (FileTree sidebar)
 //! - `RepositoryInfo`: Repo metadata (header display)
+//! - `Bootstrap`: Where the UI opens on load (`--path` / `--ref`)
+//! - `LfsPointer`: Real size and SHA-256 of a file stored in Git LFS
+//! - `FileContent`: A text file, or the byte range of it that was served
+//! - `DetachedHead`: Commit a detached HEAD points at, with its describe name
 //! - `DirectoryInfo`: Directory statistics (StatusTab)
+//! - `CodeOwnersMatch`: CODEOWNERS rule and owners for a path
 //! - `CommitInfo`: Basic commit info (last commit in tree entries)
 //! - `ContributorInfo`: Author with commit count
+//! - `BranchInfo`: Branch with last commit and upstream divergence (BranchSwitcher)
+//! - `UpstreamStatus`: Commits ahead of / behind the branch's upstream
+//! - `DeletedBranch`: Where a local branch pointed before it was deleted (for undo)
+//! - `RenamedBranch`: A local branch's old and new name after a rename
+//! - `AheadBehind`: Divergence between any two refs
+//! - `ArchiveFormat`: Archive type for tree snapshot downloads
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
 pub struct TreeEntry {
     pub name: String,
     pub path: String,
     pub entry_type: EntryType,
     pub size: Option<u64>,
+    /// Directories only: immediate children, or all descendants with `include_counts`
     pub file_count: Option<u32>,
     pub directory_count: Option<u32>,
     pub last_commit: Option<CommitInfo>,
+    /// Submodules only: the commit the submodule is pinned to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submodule_commit: Option<String>,
+    /// Symlinks only: the path the link points to, as stored in the repository
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symlink_target: Option<String>,
+    /// Set when the file is an LFS pointer; `size` is then the real content's size
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lfs: Option<LfsPointer>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+/// Entries `offset..offset + limit` of a directory, in listing order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
+pub struct TreeListing {
+    pub entries: Vec<TreeEntry>,
+    /// Entries in the directory, including ones outside the page
+    pub total: usize,
+    pub has_more: bool,
+}
+
+/// Deep link given at startup; empty when the UI opens at the root
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Bootstrap {
+    /// Repository-relative file or directory to open
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_type: Option<EntryType>,
+    /// Full OID of the commit to show
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
+pub struct LfsPointer {
+    /// SHA-256 of the content, in hex
+    pub oid: String,
+    /// Size of the content in bytes (not of the pointer)
+    pub size: u64,
+    /// The content has been fetched into `.git/lfs/objects`
+    pub downloaded: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::Enum), graphql(rename_items = "lowercase"))]
 #[serde(rename_all = "lowercase")]
 pub enum EntryType {
     File,
@@ -30,6 +88,7 @@ pub enum EntryType {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
 pub struct CommitInfo {
     pub oid: String,
     pub message: String,
@@ -44,18 +103,62 @@ pub struct FullTreeEntry {
     pub path: String,
     pub entry_type: EntryType,
     pub children: Option<Vec<FullTreeEntry>>,
+    /// Submodules only: the commit the submodule is pinned to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submodule_commit: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
 pub struct RepositoryInfo {
     pub name: String,
     pub path: String,
+    /// Branch HEAD points at; `None` when detached or unborn
     pub head_branch: Option<String>,
     pub head_commit: Option<CommitInfo>,
+    /// Set when HEAD points directly at a commit instead of a branch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detached_head: Option<DetachedHead>,
+    /// Branch HEAD points at while it has no commits yet (empty repository
+    /// or new orphan branch); tree, history and stats are empty then
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unborn_branch: Option<String>,
     pub is_bare: bool,
     pub is_empty: bool,
 }
 
+/// Text of a file; only part of it for files over the size limit or when a range was asked for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileContent {
+    pub path: String,
+    pub content: String,
+    /// What `content` was decoded from: `UTF-8`, another encoding it was
+    /// transcoded from (`windows-1252`, `Shift_JIS`, ...), or `base64` for
+    /// binary files, whose bytes `content` holds base64-encoded
+    pub encoding: String,
+    /// Size of the whole file in bytes
+    pub total_size: u64,
+    /// Bytes of the file `content` holds
+    pub range: ByteRange,
+    /// `content` is not the whole file
+    pub truncated: bool,
+}
+
+/// Bytes `start..end` of a file
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject), graphql(rename_fields = "snake_case"))]
+pub struct DetachedHead {
+    pub oid: String,
+    /// `git describe --tags --always`, e.g. `v1.2.0-3-gabc1234` or `abc1234`
+    pub describe: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DirectoryInfo {
     pub path: String,
@@ -65,6 +168,21 @@ pub struct DirectoryInfo {
     pub contributors: Vec<ContributorInfo>,
     pub first_commit: Option<CommitInfo>,
     pub latest_commit: Option<CommitInfo>,
+    /// Owners from CODEOWNERS at HEAD (users, teams or emails as written)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeOwnersMatch {
+    pub path: String,
+    /// CODEOWNERS file consulted, `None` if the commit has none
+    pub codeowners_file: Option<String>,
+    /// The last matching pattern and its 1-based line; `None` if no line matches
+    pub pattern: Option<String>,
+    pub line: Option<usize>,
+    /// Empty when unowned, including a matching line that lists no owners
+    pub owners: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,4 +198,62 @@ pub struct BranchInfo {
     pub is_current: bool,
     pub is_remote: bool,
     pub last_commit: Option<CommitInfo>,
+    /// Local branches with a configured upstream only
+    pub upstream: Option<UpstreamStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamStatus {
+    /// Upstream branch name, e.g. `origin/main`
+    pub name: String,
+    /// Commits on the branch that the upstream lacks
+    pub ahead: usize,
+    /// Commits on the upstream that the branch lacks
+    pub behind: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletedBranch {
+    pub name: String,
+    /// Commit the branch pointed at, to recreate it from
+    pub commit: String,
+    /// Upstream the branch tracked, e.g. `origin/feature`
+    pub upstream: Option<String>,
+    /// Whether the branch was merged into its upstream (or HEAD without one);
+    /// `false` means it was force-deleted
+    pub was_merged: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenamedBranch {
+    pub old_name: String,
+    pub name: String,
+    /// The branch is checked out; HEAD now points at the new name
+    pub is_current: bool,
+    /// Upstream carried over from the old name, e.g. `origin/feature`
+    pub upstream: Option<String>,
+    /// Local branches that tracked the old name and now track the new one
+    pub retracked: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AheadBehind {
+    pub base: String,
+    pub head: String,
+    pub base_commit: String,
+    pub head_commit: String,
+    /// Commits reachable from `head` but not from `base`
+    pub ahead: usize,
+    /// Commits reachable from `base` but not from `head`
+    pub behind: usize,
+}
+
+/// Archive type for `GET /api/v1/repository/archive`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    #[default]
+    #[serde(rename = "zip")]
+    Zip,
+    #[serde(rename = "tar.gz")]
+    TarGz,
 }
@@ -0,0 +1,58 @@
+//! Archive download endpoint.
+//!
+//! GET /api/v1/repository/archive?ref=&format=zip
+//!
+//! Streams the tree at `ref` (HEAD by default) as a `zip` or `tar.gz` archive
+//! with a `<repo>-<short oid>/` top-level directory. Built from the object
+//! database, so it works on bare repositories and ignores uncommitted changes.
+//! An unknown ref fails before any bytes are sent; a failure while streaming
+//! cuts the download short.
+//! Used by: sharing snapshots, downloads from bare repositories
+
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::Response,
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+
+use crate::error::Result;
+use crate::git::archive::{content_type, ArchiveSpec};
+use crate::git::SharedRepo;
+use crate::models::ArchiveFormat;
+use crate::routes::blocking::blocking;
+use crate::routes::stream::process_body;
+
+pub fn routes(repo: SharedRepo) -> Router {
+    Router::new()
+        .route("/api/v1/repository/archive", get(get_archive))
+        .with_state(repo)
+}
+
+#[derive(Debug, Deserialize)]
+struct ArchiveQuery {
+    #[serde(rename = "ref")]
+    rev: Option<String>,
+    #[serde(default)]
+    format: ArchiveFormat,
+}
+
+async fn get_archive(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<ArchiveQuery>,
+) -> Result<Response> {
+    let rev = query.rev.clone();
+    let spec: ArchiveSpec = blocking(repo, move |repo| repo.archive_spec(rev.as_deref())).await?;
+    let child = spec.spawn(query.format)?;
+
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, content_type(query.format))
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", spec.file_name(query.format)),
+        )
+        .body(process_body(child, Vec::new())?)
+        .unwrap())
+}
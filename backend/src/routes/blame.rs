@@ -1,26 +1,47 @@
 //! Blame endpoint.
 //!
-//! GET /api/v1/repository/blame?path=<path>&commit=<optional>
+//! GET /api/v1/repository/blame?path=<path>&commit=<optional>&before=&oldest=&ignore_revs=&ignore_revs_file=true
 //!
-//! Returns per-line author attribution for a file at a specific commit:
+//! Returns per-line author attribution for a file at a specific commit
+//! (`commit`, `before` and `oldest` take any revision expression, default HEAD):
 //! - Line number, author name/email, commit OID, timestamp
+//! - Commits in `.git-blame-ignore-revs` (or `blame.ignoreRevsFile`) and in
+//!   `ignore_revs` (comma-separated) are looked through, like
+//!   `git blame --ignore-revs-file`; `ignore_revs_file=false` skips the file.
+//!   The commits actually ignored are listed in `ignored_revs`
+//! - `before=<commit>` blames the file as it was just before that commit (at
+//!   its first parent) instead of at `commit`, for "blame previous version"
+//!   from any line; `oldest=<commit>` attributes everything older to that commit
 //!
 //! Used by: DiffViewer to show who last modified each line
+//!
+//! GET /api/v1/repository/blame/stream?path=<path>&commit=<optional>&before=&oldest=&chunk_lines=500&ignore_revs=&ignore_revs_file=true
+//!
+//! Same attribution as newline-delimited JSON: a `start` event with the line
+//! count, one `lines` event per chunk of the file, then `done`. Large files
+//! show partial blame after the first chunk instead of blocking on the whole file.
+//! Every chunk walks history again, so files are split into at most 20 chunks
+//! however small `chunk_lines` is.
 
 use axum::{
     extract::{Query, State},
+    response::Response,
     routing::get,
     Json, Router,
 };
 use serde::Deserialize;
 
 use crate::error::Result;
+use crate::git::blame::BlameParams;
 use crate::git::SharedRepo;
 use crate::models::BlameResponse;
+use crate::routes::stream::{ndjson_response, NdjsonSink};
+use crate::routes::timeout::limited;
 
 pub fn routes(repo: SharedRepo) -> Router {
     Router::new()
         .route("/api/v1/repository/blame", get(get_blame))
+        .route("/api/v1/repository/blame/stream", get(stream_blame))
         .with_state(repo)
 }
 
@@ -28,13 +49,76 @@ pub fn routes(repo: SharedRepo) -> Router {
 struct BlameQuery {
     path: String,
     commit: Option<String>,
+    before: Option<String>,
+    oldest: Option<String>,
+    ignore_revs: Option<String>,
+    #[serde(default = "default_true")]
+    ignore_revs_file: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn split_revs(revs: Option<&str>) -> Vec<String> {
+    revs.map(|s| s.split(',').map(|r| r.trim().to_string()).collect())
+        .unwrap_or_default()
 }
 
 async fn get_blame(
     State(repo): State<SharedRepo>,
     Query(query): Query<BlameQuery>,
 ) -> Result<Json<BlameResponse>> {
-    let repo = repo.read().map_err(|_| crate::error::AppError::Internal("Lock poisoned".to_string()))?;
-    let response = repo.get_blame(&query.path, query.commit.as_deref())?;
-    Ok(Json(response))
+    let operation = format!("Blame of {}", query.path);
+    limited(repo, operation, move |repo| {
+        let path = repo.canonical_path(&query.path);
+        let ignore_revs = split_revs(query.ignore_revs.as_deref());
+        let params = BlameParams {
+            before: query.before.as_deref(),
+            oldest: query.oldest.as_deref(),
+            ignore_revs: &ignore_revs,
+            use_ignore_file: query.ignore_revs_file,
+        };
+        repo.get_blame(&path, query.commit.as_deref(), params)
+    })
+    .await
+    .map(Json)
+}
+
+#[derive(Debug, Deserialize)]
+struct BlameStreamQuery {
+    path: String,
+    commit: Option<String>,
+    before: Option<String>,
+    oldest: Option<String>,
+    #[serde(default = "default_chunk_lines")]
+    chunk_lines: usize,
+    ignore_revs: Option<String>,
+    #[serde(default = "default_true")]
+    ignore_revs_file: bool,
+}
+
+fn default_chunk_lines() -> usize {
+    500
+}
+
+async fn stream_blame(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<BlameStreamQuery>,
+) -> Response {
+    ndjson_response(repo, move |repo| {
+        let ignore_revs = split_revs(query.ignore_revs.as_deref());
+        let stream = repo.stream_blame(
+            &repo.canonical_path(&query.path),
+            query.commit.as_deref(),
+            query.chunk_lines,
+            BlameParams {
+                before: query.before.as_deref(),
+                oldest: query.oldest.as_deref(),
+                ignore_revs: &ignore_revs,
+                use_ignore_file: query.ignore_revs_file,
+            },
+        )?;
+        Ok(move |sink: &mut NdjsonSink| stream.run(|event| sink.send(&event)))
+    })
 }
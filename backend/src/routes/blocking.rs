@@ -0,0 +1,31 @@
+//! Running git work off the async runtime.
+//!
+//! - `blocking()`: Runs a handler's git work on a blocking thread, with the
+//!   `SharedRepo` read guard taken there
+//!
+//! `GitRepository` methods block: on libgit2, on the repository mutex, and on
+//! the `SharedRepo` lock while a repository switch holds it. Handlers hand an
+//! owned `SharedRepo` clone to `blocking()` (or `limited()` for work under the
+//! request timeout), so the runtime's worker threads never wait on any of them.
+
+use crate::error::{AppError, Result};
+use crate::git::{GitRepository, SharedRepo};
+
+/// Run `work` with the repository on a blocking thread
+pub async fn blocking<T, F>(repo: SharedRepo, work: F) -> Result<T>
+where
+    F: FnOnce(&GitRepository) -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        let repo = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
+        work(&repo)
+    })
+    .await
+    .map_err(task_failed)?
+}
+
+/// The error for a blocking task that panicked or was cancelled
+pub fn task_failed(e: tokio::task::JoinError) -> AppError {
+    AppError::Internal(format!("Git task failed: {}", e))
+}
@@ -1,9 +1,30 @@
-//! Branch listing and switching endpoints.
+//! Branch listing, creation, renaming, deletion and switching endpoints.
 //!
 //! - GET /api/v1/repository/branches
 //!   Lists all local and remote branches with current branch flagged.
 //!   Used by: BranchSwitcher dropdown in header
 //!
+//!   Local branches with an upstream carry ahead/behind counts against it.
+//!
+//! - POST /api/v1/repository/branches { name: string, start_point?: string, checkout?: bool }
+//!   Creates a local branch at `start_point` (any revision, default HEAD) and
+//!   switches to it with `checkout: true`. Invalid or existing names are
+//!   rejected; a remote-tracking start point becomes the branch's upstream.
+//!   Returns the new branch.
+//!
+//! - POST /api/v1/repository/branches/rename { name: string, new_name: string }
+//!   Renames a local branch. Its upstream settings move along, HEAD follows
+//!   if it is checked out, and local branches tracking it are repointed.
+//!
+//! - DELETE /api/v1/repository/branches/{name}?force=false
+//!   Deletes a local branch. The checked-out branch is refused, and so is a
+//!   branch not merged into its upstream (or HEAD) unless `force=true`.
+//!   Returns the commit it pointed at so the client can offer to restore it.
+//!
+//! - GET /api/v1/repository/ahead-behind?base=&head=
+//!   Commits `head` is ahead of and behind `base`; both may be any branch, tag or OID.
+//!   Used by: compare view and branch staleness indicators
+//!
 //! - POST /api/v1/repository/checkout { branch: string }
 //!   Switches to a local branch.
 //!   Updates HEAD and working directory. Cache auto-invalidates on next query.
@@ -12,28 +33,106 @@
 //!   Creates a local tracking branch from a remote and checks it out.
 
 use axum::{
-    extract::State,
-    routing::{get, post},
+    extract::{Path, Query, State},
+    routing::{delete, get, post},
     Json, Router,
 };
 use serde::Deserialize;
 
-use crate::error::{AppError, Result};
+use crate::error::Result;
 use crate::git::SharedRepo;
-use crate::models::BranchInfo;
+use crate::models::{AheadBehind, BranchInfo, DeletedBranch, RenamedBranch};
+use crate::routes::blocking::blocking;
 
 pub fn routes(repo: SharedRepo) -> Router {
     Router::new()
-        .route("/api/v1/repository/branches", get(list_branches))
+        .route("/api/v1/repository/branches", get(list_branches).post(create_branch))
+        .route("/api/v1/repository/branches/rename", post(rename_branch))
+        .route("/api/v1/repository/branches/{*name}", delete(delete_branch))
+        .route("/api/v1/repository/ahead-behind", get(ahead_behind))
         .route("/api/v1/repository/checkout", post(checkout_branch))
         .route("/api/v1/repository/checkout-remote", post(checkout_remote_branch))
         .with_state(repo)
 }
 
 async fn list_branches(State(repo): State<SharedRepo>) -> Result<Json<Vec<BranchInfo>>> {
-    let repo = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
-    let branches = repo.list_branches()?;
-    Ok(Json(branches))
+    blocking(repo, move |repo| {
+        let branches = repo.list_branches()?;
+        Ok(Json(branches))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateBranchRequest {
+    name: String,
+    start_point: Option<String>,
+    #[serde(default)]
+    checkout: bool,
+}
+
+async fn create_branch(
+    State(repo): State<SharedRepo>,
+    Json(request): Json<CreateBranchRequest>,
+) -> Result<Json<BranchInfo>> {
+    blocking(repo, move |repo| {
+        let start_point = request.start_point.as_deref().map(str::trim).filter(|s| !s.is_empty());
+        let branch = repo.create_branch(request.name.trim(), start_point, request.checkout)?;
+        Ok(Json(branch))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct RenameBranchRequest {
+    name: String,
+    new_name: String,
+}
+
+async fn rename_branch(
+    State(repo): State<SharedRepo>,
+    Json(request): Json<RenameBranchRequest>,
+) -> Result<Json<RenamedBranch>> {
+    blocking(repo, move |repo| {
+        let renamed = repo.rename_branch(&request.name, request.new_name.trim())?;
+        Ok(Json(renamed))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct DeleteBranchQuery {
+    #[serde(default)]
+    force: bool,
+}
+
+async fn delete_branch(
+    State(repo): State<SharedRepo>,
+    Path(name): Path<String>,
+    Query(query): Query<DeleteBranchQuery>,
+) -> Result<Json<DeletedBranch>> {
+    blocking(repo, move |repo| {
+        let deleted = repo.delete_branch(&name, query.force)?;
+        Ok(Json(deleted))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct AheadBehindQuery {
+    base: String,
+    head: String,
+}
+
+async fn ahead_behind(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<AheadBehindQuery>,
+) -> Result<Json<AheadBehind>> {
+    blocking(repo, move |repo| {
+        let counts = repo.ahead_behind(&query.base, &query.head)?;
+        Ok(Json(counts))
+    })
+    .await
 }
 
 #[derive(Debug, Deserialize)]
@@ -45,9 +144,11 @@ async fn checkout_branch(
     State(repo): State<SharedRepo>,
     Json(request): Json<CheckoutRequest>,
 ) -> Result<Json<()>> {
-    let repo = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
-    repo.checkout_branch(&request.branch)?;
-    Ok(Json(()))
+    blocking(repo, move |repo| {
+        repo.checkout_branch(&request.branch)?;
+        Ok(Json(()))
+    })
+    .await
 }
 
 #[derive(Debug, Deserialize)]
@@ -60,7 +161,131 @@ async fn checkout_remote_branch(
     State(repo): State<SharedRepo>,
     Json(request): Json<CheckoutRemoteRequest>,
 ) -> Result<Json<()>> {
-    let repo = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
-    repo.checkout_remote_branch(&request.remote_branch, &request.local_name)?;
-    Ok(Json(()))
+    blocking(repo, move |repo| {
+        repo.checkout_remote_branch(&request.remote_branch, &request.local_name)?;
+        Ok(Json(()))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::git::test_repo::TestRepo;
+    use crate::routes::test_client::{request, send};
+    use axum::http::StatusCode;
+    use git2::BranchType;
+    use serde_json::json;
+
+    const ADA: (&str, &str) = ("Ada", "ada@example.com");
+
+    #[tokio::test]
+    async fn create_branch_at_a_start_point_and_optionally_switch_to_it() {
+        let mut test = TestRepo::new();
+        let first = test.commit(ADA, "a.txt", "a\n", "first");
+        let second = test.commit(ADA, "b.txt", "b\n", "second");
+        test.repo.remote("origin", "https://example.invalid/repo.git").unwrap();
+        test.repo.reference("refs/remotes/origin/main", first, false, "test remote").unwrap();
+        let app = crate::routes::create_router(test.shared());
+        let create = |body| request("POST", "/api/v1/repository/branches", Some(body));
+
+        let (status, body) = send(&app, create(json!({ "name": " older ", "start_point": "HEAD~1" }))).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["name"], "older");
+        assert_eq!(body["is_current"], false);
+        assert_eq!(body["last_commit"]["oid"], first.to_string());
+        assert_eq!(test.repo.head().unwrap().shorthand(), Some("master"));
+
+        let (status, body) = send(&app, create(json!({ "name": "tracking", "start_point": "origin/main" }))).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["upstream"]["name"], "origin/main");
+
+        let (status, body) = send(&app, create(json!({ "name": "feature", "checkout": true }))).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["is_current"], true);
+        assert_eq!(test.repo.head().unwrap().shorthand(), Some("feature"));
+        assert_eq!(test.repo.head().unwrap().target(), Some(second));
+
+        for name in ["older", "bad..name", "-dash", ""] {
+            let (status, body) = send(&app, create(json!({ "name": name }))).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{:?}: {}", name, body);
+        }
+        assert!(test.repo.find_branch("bad..name", BranchType::Local).is_err());
+    }
+
+    #[tokio::test]
+    async fn delete_branch_refuses_unmerged_and_checked_out_branches() {
+        let mut test = TestRepo::new();
+        let first = test.commit(ADA, "a.txt", "a\n", "first");
+        test.branch("feature/merged");
+        test.branch("unmerged");
+        test.checkout("unmerged");
+        let unmerged = test.commit(ADA, "b.txt", "b\n", "unmerged work");
+        test.checkout("master");
+        let app = crate::routes::create_router(test.shared());
+        let delete = |uri: &str| request("DELETE", uri, None);
+
+        let (status, body) = send(&app, delete("/api/v1/repository/branches/feature/merged")).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["name"], "feature/merged");
+        assert_eq!(body["commit"], first.to_string());
+        assert_eq!(body["was_merged"], true);
+        assert!(test.repo.find_branch("feature/merged", BranchType::Local).is_err());
+
+        let (status, body) = send(&app, delete("/api/v1/repository/branches/unmerged")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+        assert!(body["error"].as_str().unwrap().contains("not fully merged"), "{}", body);
+        assert!(test.repo.find_branch("unmerged", BranchType::Local).is_ok());
+
+        let (status, body) = send(&app, delete("/api/v1/repository/branches/unmerged?force=true")).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["commit"], unmerged.to_string());
+        assert_eq!(body["was_merged"], false);
+        assert!(test.repo.find_branch("unmerged", BranchType::Local).is_err());
+
+        let (status, _) = send(&app, delete("/api/v1/repository/branches/master?force=true")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = send(&app, delete("/api/v1/repository/branches/missing")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn rename_branch_moves_head_upstream_and_tracking_branches_along() {
+        let mut test = TestRepo::new();
+        let first = test.commit(ADA, "a.txt", "a\n", "first");
+        test.repo.remote("origin", "https://example.invalid/repo.git").unwrap();
+        test.repo.reference("refs/remotes/origin/topic", first, false, "test remote").unwrap();
+        test.branch("topic");
+        test.branch("follower");
+        test.repo.find_branch("topic", BranchType::Local).unwrap().set_upstream(Some("origin/topic")).unwrap();
+        test.repo.find_branch("follower", BranchType::Local).unwrap().set_upstream(Some("topic")).unwrap();
+        let app = crate::routes::create_router(test.shared());
+        let rename = |name: &str, new_name: &str| {
+            request("POST", "/api/v1/repository/branches/rename", Some(json!({ "name": name, "new_name": new_name })))
+        };
+
+        let (status, body) = send(&app, rename("topic", "renamed")).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["is_current"], false);
+        assert_eq!(body["upstream"], "origin/topic");
+        assert_eq!(body["retracked"], json!(["follower"]));
+        assert!(test.repo.find_branch("topic", BranchType::Local).is_err());
+        let follower = test.repo.find_branch("follower", BranchType::Local).unwrap();
+        assert_eq!(follower.upstream().unwrap().name().unwrap(), Some("renamed"));
+
+        let (status, body) = send(&app, rename("master", "main")).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["is_current"], true);
+        assert_eq!(test.repo.head().unwrap().name(), Some("refs/heads/main"));
+
+        for (name, new_name, expected) in [
+            ("main", "main", StatusCode::BAD_REQUEST),
+            ("main", "renamed", StatusCode::BAD_REQUEST),
+            ("main", "bad..name", StatusCode::BAD_REQUEST),
+            ("missing", "other", StatusCode::NOT_FOUND),
+        ] {
+            let (status, body) = send(&app, rename(name, new_name)).await;
+            assert_eq!(status, expected, "{} -> {}: {}", name, new_name, body);
+        }
+        assert!(test.repo.find_branch("main", BranchType::Local).is_ok());
+    }
 }
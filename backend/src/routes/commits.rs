@@ -1,41 +1,109 @@
 //! Commit history endpoint.
 //!
-//! GET /api/v1/repository/commits?path=&limit=50&offset=0&exclude_authors=
+//! GET /api/v1/repository/commits?path=&limit=50&offset=0&exclude_authors=&type=&since=&until=&include_signatures=&verify_signatures=&include_notes=&path_mode=&query=&query_regex=
 //!
 //! Returns paginated commit history with:
-//! - Commits filtered by path (only commits touching that path)
+//! - Commits filtered by path (only commits touching that path); `path` may also
+//!   be a glob (`**/*.sql`, `src/**/test_*`) matched against each commit's changed files.
+//!   `path` may be repeated; `path_mode=union` (default) or `intersect` combines them
 //! - Author exclusion filter (comma-separated emails)
+//! - Conventional Commits type filter (`type=feat,fix`); commits whose message
+//!   follows the format carry its parsed type, scope, subject, body and trailers
+//! - Date range (`since`/`until`, Unix seconds, inclusive): only commits in the
+//!   range count, for the totals and contributor list too
+//! - Text search (`query`) over message, author name and email: a case-insensitive
+//!   substring, or a regex with `query_regex=true`; per-field match counts are
+//!   returned in `match_counts`
+//! - Optional signed/unsigned/unknown status per commit (`include_signatures=true`)
+//! - Optional signature verification per commit (`verify_signatures=true`):
+//!   good/untrusted/bad/... as checked by git against the configured keys
+//! - Optional note from `refs/notes/commits` per commit (`include_notes=true`)
 //! - Total and filtered counts for pagination
-//! - Contributor list for the filter dropdown
+//! - Contributor list for the filter dropdown (bot authors omitted unless `include_bots=true`)
 //!
 //! Uses commit cache for fast repeated queries.
 //! Used by: HistoryTab commit list and contributor filter
+//!
+//! GET /api/v1/repository/commits/stream?path=&limit=&exclude_authors=&path_mode=
+//!
+//! Emits commits as newline-delimited JSON while the revwalk progresses, so the
+//! history view can render before the commit cache is built. A failure mid-walk
+//! is reported as a final `{"error": ...}` line.
+//!
+//! GET /api/v1/repository/commits/pickaxe?pattern=&mode=string&path=&limit=50&offset=0&exclude_authors=
+//!
+//! Commits whose diffs add or remove `pattern`, like `git log -S` (`mode=string`)
+//! or `git log -G` (`mode=regex`), optionally limited to files under `path`.
+//! Same response shape as the commit list; results are cached per search, so
+//! paging and author filters are fast after the first request.
+//! Used by: "when was this introduced/removed" searches
+//!
+//! GET /api/v1/repository/commits/{oid}/notes
+//!
+//! Notes attached to a commit from every `refs/notes/*` ref (review metadata,
+//! build IDs, ...), `refs/notes/commits` first.
+//! Used by: commit detail view
+//!
+//! GET /api/v1/repository/commits/{oid}/children
+//!
+//! Commits that have `oid` (or a ref) as a parent, newest first; more than one
+//! where history branches. Only commits reachable from HEAD are considered.
+//! Used by: commit detail view "next commit" navigation
 
 use axum::{
-    extract::{Query, State},
+    extract::{Path, State},
+    response::Response,
     routing::get,
     Json, Router,
 };
+use axum_extra::extract::Query;
 use serde::Deserialize;
 
-use crate::error::{AppError, Result};
+use crate::error::Result;
+use crate::git::cache::{CommitFilters, CommitSearch, TimeWindow};
 use crate::git::SharedRepo;
-use crate::models::CommitListResponse;
+use crate::models::{CommitDetail, CommitListResponse, CommitNotes, PathMode, PickaxeMode};
+use crate::routes::stream::{ndjson_response, NdjsonSink};
+use crate::routes::timeout::limited;
+use crate::routes::blocking::blocking;
 
 pub fn routes(repo: SharedRepo) -> Router {
     Router::new()
         .route("/api/v1/repository/commits", get(get_commits))
+        .route("/api/v1/repository/commits/stream", get(stream_commits))
+        .route("/api/v1/repository/commits/pickaxe", get(search_diffs))
+        .route("/api/v1/repository/commits/{oid}/children", get(get_children))
+        .route("/api/v1/repository/commits/{oid}/notes", get(get_notes))
         .with_state(repo)
 }
 
 #[derive(Debug, Deserialize)]
 struct CommitsQuery {
-    path: Option<String>,
+    #[serde(default)]
+    path: Vec<String>,
+    #[serde(default)]
+    path_mode: PathMode,
     #[serde(default = "default_limit")]
     limit: usize,
     #[serde(default)]
     offset: usize,
     exclude_authors: Option<String>,
+    query: Option<String>,
+    #[serde(default)]
+    query_regex: bool,
+    #[serde(default)]
+    include_signatures: bool,
+    #[serde(default)]
+    verify_signatures: bool,
+    #[serde(default)]
+    include_notes: bool,
+    #[serde(default)]
+    include_bots: bool,
+    /// Comma-separated Conventional Commits types, e.g. `feat,fix`
+    #[serde(rename = "type")]
+    commit_type: Option<String>,
+    since: Option<i64>,
+    until: Option<i64>,
 }
 
 fn default_limit() -> usize {
@@ -46,14 +114,129 @@ async fn get_commits(
     State(repo): State<SharedRepo>,
     Query(query): Query<CommitsQuery>,
 ) -> Result<Json<CommitListResponse>> {
-    let repo = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
-    let exclude_authors: Option<Vec<String>> = query.exclude_authors
-        .map(|s| s.split(',').map(|e| e.trim().to_string()).collect());
-    let response = repo.get_commits(
-        query.path.as_deref(),
-        query.limit,
-        query.offset,
-        exclude_authors.as_deref(),
-    )?;
-    Ok(Json(response))
+    blocking(repo, move |repo| {
+        let exclude_authors: Option<Vec<String>> = query.exclude_authors
+            .map(|s| s.split(',').map(|e| e.trim().to_string()).collect());
+        let search = query.query
+            .as_deref()
+            .filter(|q| !q.is_empty())
+            .map(|q| CommitSearch::new(q, query.query_regex))
+            .transpose()?;
+        let types: Option<Vec<String>> = query.commit_type
+            .filter(|s| !s.is_empty())
+            .map(|s| s.split(',').map(|t| t.trim().to_ascii_lowercase()).collect());
+        let paths: Vec<String> = query.path.iter().map(|p| repo.canonical_path(p)).collect();
+        let mut response = repo.get_commits(
+            &paths,
+            query.path_mode,
+            query.limit,
+            query.offset,
+            CommitFilters {
+                exclude_authors: exclude_authors.as_deref(),
+                search: search.as_ref(),
+                types: types.as_deref(),
+                window: TimeWindow { since: query.since, until: query.until },
+            },
+            query.include_signatures,
+        )?;
+        if query.verify_signatures {
+            repo.attach_verification(&mut response.commits)?;
+        }
+        if query.include_notes {
+            repo.attach_notes(&mut response.commits)?;
+        }
+        if !query.include_bots {
+            response.contributors.retain(|a| !repo.is_bot(&a.name, &a.email));
+        }
+        Ok(Json(response))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamQuery {
+    #[serde(default)]
+    path: Vec<String>,
+    #[serde(default)]
+    path_mode: PathMode,
+    limit: Option<usize>,
+    exclude_authors: Option<String>,
+}
+
+async fn stream_commits(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<StreamQuery>,
+) -> Response {
+    ndjson_response(repo, move |repo| {
+        let exclude_authors: Option<Vec<String>> = query.exclude_authors
+            .map(|s| s.split(',').map(|e| e.trim().to_string()).collect());
+
+        let paths: Vec<String> = query.path.iter().map(|p| repo.canonical_path(p)).collect();
+
+        let stream = repo.stream_commits(&paths, query.path_mode, query.limit, exclude_authors.as_deref())?;
+        Ok(move |sink: &mut NdjsonSink| stream.run(|commit| sink.send(&commit)))
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct PickaxeQuery {
+    pattern: String,
+    #[serde(default)]
+    mode: PickaxeMode,
+    path: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+    exclude_authors: Option<String>,
+    #[serde(default)]
+    include_bots: bool,
+}
+
+async fn search_diffs(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<PickaxeQuery>,
+) -> Result<Json<CommitListResponse>> {
+    let operation = format!("Search for \"{}\"", query.pattern);
+    limited(repo, operation, move |repo| {
+        let exclude_authors: Option<Vec<String>> = query.exclude_authors
+            .map(|s| s.split(',').map(|e| e.trim().to_string()).collect());
+        let path = repo.canonical_path_opt(query.path.as_deref());
+        let mut response = repo.search_diffs(
+            &query.pattern,
+            query.mode,
+            path.as_deref(),
+            query.limit,
+            query.offset,
+            exclude_authors.as_deref(),
+        )?;
+        if !query.include_bots {
+            response.contributors.retain(|a| !repo.is_bot(&a.name, &a.email));
+        }
+        Ok(response)
+    })
+    .await
+    .map(Json)
+}
+
+async fn get_children(
+    State(repo): State<SharedRepo>,
+    Path(oid): Path<String>,
+) -> Result<Json<Vec<CommitDetail>>> {
+    blocking(repo, move |repo| {
+        let children = repo.get_children(&oid)?;
+        Ok(Json(children))
+    })
+    .await
+}
+
+async fn get_notes(
+    State(repo): State<SharedRepo>,
+    Path(oid): Path<String>,
+) -> Result<Json<CommitNotes>> {
+    blocking(repo, move |repo| {
+        let notes = repo.get_notes(&oid)?;
+        Ok(Json(notes))
+    })
+    .await
 }
@@ -0,0 +1,51 @@
+//! Contributor endpoint.
+//!
+//! GET /api/v1/repository/contributors/{email}?limit=50&offset=0
+//!
+//! One author's page: total commits, first/last commit time, a page of their
+//! commits (newest first), directories they touched ranked by how many of
+//! their commits touched them, and commits per month. Email is matched
+//! case-insensitively; unknown authors return 404.
+//! Used by: contributor person page (linked from contributor lists)
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::error::Result;
+use crate::git::SharedRepo;
+use crate::models::ContributorProfile;
+use crate::routes::blocking::blocking;
+
+pub fn routes(repo: SharedRepo) -> Router {
+    Router::new()
+        .route("/api/v1/repository/contributors/{email}", get(get_contributor))
+        .with_state(repo)
+}
+
+#[derive(Debug, Deserialize)]
+struct ContributorQuery {
+    #[serde(default = "default_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+}
+
+fn default_limit() -> usize {
+    50
+}
+
+async fn get_contributor(
+    State(repo): State<SharedRepo>,
+    Path(email): Path<String>,
+    Query(query): Query<ContributorQuery>,
+) -> Result<Json<ContributorProfile>> {
+    blocking(repo, move |repo| {
+        let profile = repo.get_contributor(&email, query.limit, query.offset)?;
+        Ok(Json(profile))
+    })
+    .await
+}
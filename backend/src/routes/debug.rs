@@ -0,0 +1,54 @@
+//! Diagnostics endpoint.
+//!
+//! GET /api/v1/debug/slow-ops?limit=50&min_ms=0
+//!
+//! Slowest of the most recent git operations (cache builds, path cache builds,
+//! diffs, blames, tree walks, history queries), each with its parameters and
+//! duration, slowest first. Operations are timed in a fixed-size ring buffer,
+//! so only recent activity is covered.
+//! Used by: diagnosing which query makes the viewer sluggish on a big repository
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::error::Result;
+use crate::git::profiling::OP_LOG_CAPACITY;
+use crate::git::SharedRepo;
+use crate::models::SlowOpsResponse;
+use crate::routes::blocking::blocking;
+
+pub fn routes(repo: SharedRepo) -> Router {
+    Router::new()
+        .route("/api/v1/debug/slow-ops", get(get_slow_ops))
+        .with_state(repo)
+}
+
+#[derive(Debug, Deserialize)]
+struct SlowOpsQuery {
+    #[serde(default = "default_limit")]
+    limit: usize,
+    #[serde(default)]
+    min_ms: f64,
+}
+
+fn default_limit() -> usize {
+    50
+}
+
+async fn get_slow_ops(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<SlowOpsQuery>,
+) -> Result<Json<SlowOpsResponse>> {
+    blocking(repo, move |repo| {
+        Ok(Json(SlowOpsResponse {
+            capacity: OP_LOG_CAPACITY,
+            total_recorded: repo.op_log.total(),
+            operations: repo.op_log.slowest(query.limit, query.min_ms),
+        }))
+    })
+    .await
+}
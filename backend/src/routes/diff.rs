@@ -1,31 +1,77 @@
 //! Diff endpoint.
 //!
-//! GET /api/v1/repository/diff?from=&to=&path=&exclude_authors=
+//! GET /api/v1/repository/diff?from=&to=&path=&exclude_authors=&include_contents=true&include_blame=false&include_bots=false&context_lines=3
 //!
-//! Returns diff between two commits (or commit and its parent if `from` omitted):
+//! Returns diff between two commits (or commit and its parent if `from` omitted).
+//! `from`/`to` take any revision expression (`main`, `v1.2.0`, `HEAD~3`, an
+//! abbreviated OID); the response reports the resolved OIDs:
 //! - File list with status (added/modified/deleted/renamed)
 //! - Hunks with line-by-line changes
-//! - Full file contents for side-by-side diff view
+//! - Full file contents for side-by-side diff view (omitted with
+//!   `include_contents=false`; fetch per file from /repository/file instead)
 //! - Author attribution per file (who touched each file)
+//! - With `include_blame=true`, context and deletion lines carry the commit and
+//!   author they originate from, so the viewer needs no per-file /blame calls
 //! - Author filtering to hide files by excluded contributors
+//! - Bot authors left out of author badges and contributors unless `include_bots=true`
+//! - `context_lines` unchanged lines around each change (default 3), or
+//!   `context_lines=full` for one hunk per file covering the whole file
+//! - With `to=WORKING_TREE`, untracked files come in a separate `untracked`
+//!   list (size, binary/mime, line count, text contents up to 1 MiB) instead
+//!   of `files`
 //!
 //! Used by: DiffViewer modal (single commit view or compare two commits)
+//!
+//! GET /api/v1/repository/diff/tree-summary?from=&to=&path=
+//!
+//! Per-directory totals (files changed, insertions, deletions) for the same
+//! comparison, without hunks or contents. `from`/`to` accept refs as well as OIDs.
+//! Used by: FileList/FileTree change badges on folders when comparing refs
+//!
+//! GET /api/v1/repository/compare-file?path=&base=&head=&context_lines=3
+//!
+//! One file across two refs (e.g. `main` and `release/2.x`): both versions and
+//! their hunks, without generating the whole-repo diff.
+//! Used by: DiffViewer single-file compare
+//!
+//! GET /api/v1/repository/range-diff?old=&new=&creation_factor=60
+//!
+//! Pairs the commits of two ranges (`A..B`), like `git range-diff` - e.g. a
+//! branch before and after a rebase (`main..feature@{1}` vs `main..feature`).
+//! Each entry is equal, modified (with the diff between the two patches),
+//! removed or added.
+//! Used by: reviewing what a rebase or force-push changed
+//!
+//! GET /api/v1/repository/format-patch?commits=
+//!
+//! One commit (`commits=<oid or ref>`) or every non-merge commit of a range
+//! (`commits=main..feature`) as mbox-style patches, like `git format-patch
+//! --stdout`, served as a download that `git am` applies.
+//! Used by: pulling a patch out of the viewer to apply elsewhere
 
 use axum::{
     extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
     routing::get,
     Json, Router,
 };
 use serde::Deserialize;
 
 use crate::error::{AppError, Result};
+use crate::git::diff::{DEFAULT_CONTEXT_LINES, FULL_FILE_CONTEXT};
 use crate::git::SharedRepo;
-use crate::models::{DiffResponse, WorkingTreeStatus};
+use crate::models::{DiffResponse, DiffTreeSummary, FileCompareResponse, RangeDiffResponse};
+use crate::routes::timeout::limited;
+use crate::routes::blocking::blocking;
 
 pub fn routes(repo: SharedRepo) -> Router {
     Router::new()
         .route("/api/v1/repository/diff", get(get_diff))
-        .route("/api/v1/repository/working-tree-status", get(get_working_tree_status))
+        .route("/api/v1/repository/diff/tree-summary", get(get_diff_tree_summary))
+        .route("/api/v1/repository/compare-file", get(compare_file))
+        .route("/api/v1/repository/range-diff", get(range_diff))
+        .route("/api/v1/repository/format-patch", get(format_patch))
         .with_state(repo)
 }
 
@@ -35,57 +81,173 @@ struct DiffQuery {
     to: String,
     path: Option<String>,
     exclude_authors: Option<String>,
+    #[serde(default = "default_true")]
+    include_contents: bool,
+    #[serde(default)]
+    include_blame: bool,
+    #[serde(default)]
+    include_bots: bool,
+    context_lines: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// `context_lines` as a number, `full`, or the default when absent
+fn parse_context_lines(param: Option<&str>) -> Result<u32> {
+    match param {
+        None | Some("") => Ok(DEFAULT_CONTEXT_LINES),
+        Some("full") => Ok(FULL_FILE_CONTEXT),
+        Some(n) => n.parse().map_err(|_| {
+            AppError::BadRequest(format!("context_lines must be a number or \"full\", got '{}'", n))
+        }),
+    }
 }
 
 async fn get_diff(
     State(repo): State<SharedRepo>,
     Query(query): Query<DiffQuery>,
 ) -> Result<Json<DiffResponse>> {
-    let repo = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
+    let context_lines = parse_context_lines(query.context_lines.as_deref())?;
+    let operation = format!("Diff of {}", query.to);
+    limited(repo, operation, move |repo| {
+        let path = repo.canonical_path_opt(query.path.as_deref());
 
-    // Intercept WORKING_TREE sentinel to diff HEAD vs working directory
-    if query.to == "WORKING_TREE" {
-        let response = repo.get_working_tree_diff(query.path.as_deref())?;
-        return Ok(Json(response));
-    }
+        // Intercept WORKING_TREE sentinel to diff HEAD vs working directory
+        if query.to == "WORKING_TREE" {
+            let mut response = repo.get_working_tree_diff(
+                path.as_deref(),
+                query.include_contents,
+                query.include_blame,
+                context_lines,
+            )?;
+            if !query.include_bots {
+                repo.strip_bots_from_diff(&mut response);
+            }
+            return Ok(response);
+        }
+
+        let mut response = repo.get_diff(
+            query.from.as_deref(),
+            &query.to,
+            path.as_deref(),
+            query.include_contents,
+            query.include_blame,
+            context_lines,
+        )?;
 
-    let mut response = repo.get_diff(
-        query.from.as_deref(),
-        &query.to,
-        query.path.as_deref(),
-    )?;
-
-    // Apply author filtering if requested
-    if let Some(ref exclude_str) = query.exclude_authors {
-        let excluded_emails: std::collections::HashSet<&str> = exclude_str
-            .split(',')
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-            .collect();
-
-        if !excluded_emails.is_empty() {
-            // Filter out files where ALL authors are excluded
-            response.files.retain(|file| {
-                // Keep files with no authors (shouldn't happen) or with at least one non-excluded author
-                file.authors.is_empty() || file.authors.iter().any(|a| !excluded_emails.contains(a.email.as_str()))
-            });
-            response.filtered_files = response.files.len();
+        // Apply author filtering if requested
+        if let Some(ref exclude_str) = query.exclude_authors {
+            let excluded_emails: std::collections::HashSet<&str> = exclude_str
+                .split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .collect();
+
+            if !excluded_emails.is_empty() {
+                // Filter out files where ALL authors are excluded
+                response.files.retain(|file| {
+                    // Keep files with no authors (shouldn't happen) or with at least one non-excluded author
+                    file.authors.is_empty() || file.authors.iter().any(|a| !excluded_emails.contains(a.email.as_str()))
+                });
+                response.filtered_files = response.files.len();
+            }
         }
-    }
 
-    Ok(Json(response))
+        if !query.include_bots {
+            repo.strip_bots_from_diff(&mut response);
+        }
+
+        Ok(response)
+    })
+    .await
+    .map(Json)
 }
 
 #[derive(Debug, Deserialize)]
-struct WorkingTreeStatusQuery {
+struct TreeSummaryQuery {
+    from: Option<String>,
+    to: String,
     path: Option<String>,
 }
 
-async fn get_working_tree_status(
+async fn get_diff_tree_summary(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<TreeSummaryQuery>,
+) -> Result<Json<DiffTreeSummary>> {
+    let operation = format!("Diff summary of {}", query.to);
+    limited(repo, operation, move |repo| {
+        let path = repo.canonical_path_opt(query.path.as_deref());
+        repo.get_diff_tree_summary(query.from.as_deref(), &query.to, path.as_deref())
+    })
+    .await
+    .map(Json)
+}
+
+#[derive(Debug, Deserialize)]
+struct CompareFileQuery {
+    path: String,
+    base: String,
+    head: String,
+    context_lines: Option<String>,
+}
+
+async fn compare_file(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<CompareFileQuery>,
+) -> Result<Json<FileCompareResponse>> {
+    let context_lines = parse_context_lines(query.context_lines.as_deref())?;
+    let operation = format!("Comparison of {}", query.path);
+    limited(repo, operation, move |repo| {
+        let path = repo.canonical_path(&query.path);
+        repo.compare_file(&path, &query.base, &query.head, context_lines)
+    })
+    .await
+    .map(Json)
+}
+
+#[derive(Debug, Deserialize)]
+struct RangeDiffQuery {
+    old: String,
+    new: String,
+    #[serde(default = "default_creation_factor")]
+    creation_factor: u32,
+}
+
+fn default_creation_factor() -> u32 {
+    60
+}
+
+async fn range_diff(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<RangeDiffQuery>,
+) -> Result<Json<RangeDiffResponse>> {
+    let operation = format!("Range diff of {} and {}", query.old, query.new);
+    limited(repo, operation, move |repo| repo.range_diff(&query.old, &query.new, query.creation_factor))
+        .await
+        .map(Json)
+}
+
+#[derive(Debug, Deserialize)]
+struct FormatPatchQuery {
+    commits: String,
+}
+
+async fn format_patch(
     State(repo): State<SharedRepo>,
-    Query(query): Query<WorkingTreeStatusQuery>,
-) -> Result<Json<WorkingTreeStatus>> {
-    let repo = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
-    let status = repo.get_working_tree_status(query.path.as_deref())?;
-    Ok(Json(status))
+    Query(query): Query<FormatPatchQuery>,
+) -> Result<Response> {
+    blocking(repo, move |repo| {
+        let export = repo.format_patches(&query.commits)?;
+        Ok((
+            [
+                (header::CONTENT_TYPE, "text/plain; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", export.file_name)),
+            ],
+            export.content,
+        )
+            .into_response())
+    })
+    .await
 }
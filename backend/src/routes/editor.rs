@@ -0,0 +1,71 @@
+//! Open-in-editor endpoint.
+//!
+//! POST /api/v1/repository/open-in-editor { path, line? }
+//!
+//! Launches the user's editor on a working tree file. Only served when the
+//! server was started with --open-in-editor, and only to loopback clients
+//! (403 otherwise), since it runs a local program. A loopback connection can
+//! still come from any web page open in the user's browser (CORS allows any
+//! origin, and a rebound DNS name reaches 127.0.0.1), so the `Host` must name
+//! a loopback address and so must the `Origin`, if the request has one. Any
+//! port is accepted, for the Vite dev server's proxy.
+//! Used by: FileViewer / BlameView "Open in editor" action
+
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap},
+    routing::post,
+    Json, Router,
+};
+
+use crate::error::{AppError, Result};
+use crate::git::SharedRepo;
+use crate::models::{OpenInEditorRequest, OpenInEditorResponse};
+use crate::routes::blocking::blocking;
+
+pub fn routes(repo: SharedRepo) -> Router {
+    Router::new()
+        .route("/api/v1/repository/open-in-editor", post(open_in_editor))
+        .with_state(repo)
+}
+
+async fn open_in_editor(
+    State(repo): State<SharedRepo>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<OpenInEditorRequest>,
+) -> Result<Json<OpenInEditorResponse>> {
+    if !addr.ip().is_loopback() {
+        return Err(AppError::Forbidden("Open in editor is only available to local clients".to_string()));
+    }
+    let host = headers.get(header::HOST).and_then(|v| v.to_str().ok());
+    if !host.is_some_and(is_loopback_authority) {
+        return Err(AppError::Forbidden("Open in editor needs a localhost or loopback Host".to_string()));
+    }
+    let origin = headers.get(header::ORIGIN).map(|v| v.to_str().unwrap_or(""));
+    if let Some(origin) = origin {
+        let authority = origin.strip_prefix("http://").or_else(|| origin.strip_prefix("https://"));
+        if !authority.is_some_and(is_loopback_authority) {
+            return Err(AppError::Forbidden(format!("Open in editor is not available to pages from {}", origin)));
+        }
+    }
+
+    blocking(repo, move |repo| {
+        let response = repo.open_in_editor(&request.path, request.line)?;
+        Ok(Json(response))
+    })
+    .await
+}
+
+/// Whether `authority` (`host[:port]`) names this machine: `localhost` or a
+/// loopback address
+fn is_loopback_authority(authority: &str) -> bool {
+    let host = match authority.strip_prefix('[') {
+        // [::1]:port
+        Some(rest) => rest.split_once(']').map_or("", |(host, _)| host),
+        None => authority.rsplit_once(':').map_or(authority, |(host, _)| host),
+    };
+    host.eq_ignore_ascii_case("localhost") || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
@@ -0,0 +1,48 @@
+//! Live update endpoint.
+//!
+//! GET /api/v1/events
+//!
+//! Server-sent events stream of repository changes made outside the viewer
+//! (or by it). Each `change` event carries a RepoChangeEvent: the kinds that
+//! changed (`head`, `refs`, `index`, `working_tree`, `repository` after a
+//! switch) and up to 100 working tree paths. The first subscriber starts the
+//! file system watcher. A client that falls behind gets one event listing
+//! every kind instead of the ones it missed.
+//! Used by: useLiveUpdates hook refreshing commits, branches and working tree status
+
+use axum::{
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+    routing::get,
+    Router,
+};
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::error::Result;
+use crate::git::SharedRepo;
+use crate::models::{ChangeKind, RepoChangeEvent};
+use crate::routes::blocking::blocking;
+
+pub fn routes(repo: SharedRepo) -> Router {
+    Router::new()
+        .route("/api/v1/events", get(get_events))
+        .with_state(repo)
+}
+
+async fn get_events(
+    State(repo): State<SharedRepo>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, axum::Error>>>> {
+    let receiver = blocking(repo, |repo| repo.subscribe_changes()).await?;
+
+    let events = BroadcastStream::new(receiver).map(|message| {
+        let change = message.unwrap_or_else(|_lagged| RepoChangeEvent {
+            kinds: vec![ChangeKind::Head, ChangeKind::Refs, ChangeKind::Index, ChangeKind::WorkingTree],
+            paths: Vec::new(),
+            paths_truncated: true,
+        });
+        Event::default().event("change").json_data(change)
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
@@ -6,8 +6,15 @@
 //!
 //! - POST /api/v1/filesystem/switch { path: string }
 //!   Switches the backend to serve a different git repository.
-//!   Replaces the shared GitRepository instance.
+//!   Replaces the shared GitRepository instance. Live update subscribers
+//!   get a `repository` change event and are fed from the new repository.
 //!   Used by: RepoSwitcher when user selects a new repo
+//!
+//! With `--browse-root`, both only accept paths that resolve (symlinks and
+//! `..` included) to the root or below it; anything else is `403`. Listings
+//! start at the root when the current repository lies outside it, and the
+//! root itself has no parent to navigate up to. A switch is also refused when
+//! the path is inside the root but the repository git finds for it is not.
 
 use axum::{
     extract::{Query, State},
@@ -15,11 +22,13 @@ use axum::{
     Json, Router,
 };
 use serde::Deserialize;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::error::{AppError, Result};
+use crate::git::repository::is_git_repository;
 use crate::git::{GitRepository, SharedRepo};
 use crate::models::{DirectoryListing, FilesystemEntry, RepositoryInfo, SwitchRepoRequest};
+use crate::routes::blocking::task_failed;
 
 pub fn routes(repo: SharedRepo) -> Router {
     Router::new()
@@ -37,32 +46,50 @@ async fn list_directory(
     State(repo): State<SharedRepo>,
     Query(params): Query<ListParams>,
 ) -> Result<Json<DirectoryListing>> {
-    // If no path provided, use parent of current repo
-    let target_path = match params.path {
-        Some(p) => p,
-        None => {
-            let repo_guard = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
-            let current_repo_path = &repo_guard.path;
-            Path::new(current_repo_path)
+    let listing = tokio::task::spawn_blocking(move || list(&repo, params.path)).await.map_err(task_failed)??;
+    Ok(Json(listing))
+}
+
+async fn switch_repository(
+    State(repo): State<SharedRepo>,
+    Json(request): Json<SwitchRepoRequest>,
+) -> Result<Json<RepositoryInfo>> {
+    // Opening the new repository and waiting for the write lock both block
+    let info = tokio::task::spawn_blocking(move || switch(&repo, &request.path)).await.map_err(task_failed)??;
+    Ok(Json(info))
+}
+
+fn list(repo: &SharedRepo, requested: Option<String>) -> Result<DirectoryListing> {
+    let (browse_root, target_path) = {
+        let repo_guard = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
+        let browse_root = repo_guard.browse_root.clone();
+        // If no path provided, use parent of current repo (the root if that's outside it)
+        let target_path = requested.unwrap_or_else(|| {
+            let current_parent = Path::new(&repo_guard.path)
                 .parent()
                 .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|| "/".to_string())
-        }
+                .unwrap_or_else(|| "/".to_string());
+            match &browse_root {
+                Some(root) if confine(Some(root), &current_parent).is_err() => root.to_string_lossy().to_string(),
+                _ => current_parent,
+            }
+        });
+        (browse_root, target_path)
     };
 
-    let path = Path::new(&target_path);
+    let path = confine(browse_root.as_deref(), &target_path)?;
     if !path.is_dir() {
         return Err(AppError::PathNotFound(target_path));
     }
 
     let mut entries = Vec::new();
-    let read_dir = std::fs::read_dir(path).map_err(|e| AppError::Internal(e.to_string()))?;
+    let read_dir = std::fs::read_dir(&path).map_err(|e| AppError::Internal(e.to_string()))?;
 
     for entry in read_dir {
         let entry = entry.map_err(|e| AppError::Internal(e.to_string()))?;
         let entry_path = entry.path();
         let is_directory = entry_path.is_dir();
-        let is_git_repo = is_directory && entry_path.join(".git").exists();
+        let is_git_repo = is_directory && is_git_repository(&entry_path);
 
         // Skip hidden files/directories
         let name = entry.file_name().to_string_lossy().to_string();
@@ -84,26 +111,113 @@ async fn list_directory(
     }
 
     // Sort alphabetically
-    entries.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    entries.sort_by_key(|a| a.name.to_lowercase());
 
-    let parent_path = path.parent().map(|p| p.to_string_lossy().to_string());
+    let parent_path = path.parent()
+        .filter(|parent| browse_root.as_deref().is_none_or(|root| parent.starts_with(root)))
+        .map(|p| p.to_string_lossy().to_string());
 
-    Ok(Json(DirectoryListing {
-        current_path: target_path,
+    Ok(DirectoryListing {
+        current_path: path.to_string_lossy().to_string(),
         parent_path,
         entries,
-    }))
+    })
 }
 
-async fn switch_repository(
-    State(repo): State<SharedRepo>,
-    Json(request): Json<SwitchRepoRequest>,
-) -> Result<Json<RepositoryInfo>> {
-    let new_repo = GitRepository::open(&request.path)?;
+fn switch(repo: &SharedRepo, requested: &str) -> Result<RepositoryInfo> {
+    let browse_root = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?.browse_root.clone();
+    let path = confine(browse_root.as_deref(), requested)?;
+
+    let new_repo = GitRepository::open(&path)?;
+    if browse_root.is_some() {
+        // Discovery walks up from `path`, possibly past the root
+        let location = new_repo.with_repo(|r| Ok(r.workdir().unwrap_or_else(|| r.path()).to_path_buf()))?;
+        confine(browse_root.as_deref(), &location.to_string_lossy())?;
+    }
     let info = new_repo.info()?;
 
     let mut repo_guard = repo.write().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
-    *repo_guard = new_repo;
+    *repo_guard = new_repo.with_settings_from(&repo_guard);
+    if let Err(e) = repo_guard.announce_switch() {
+        tracing::warn!("Live updates unavailable for {}: {}", requested, e);
+    }
 
-    Ok(Json(info))
+    Ok(info)
+}
+
+/// Resolve `path` and check that it lies within the browse root, if there is one
+///
+/// Without a root the path is returned as given.
+fn confine(root: Option<&Path>, path: &str) -> Result<PathBuf> {
+    let Some(root) = root else {
+        return Ok(PathBuf::from(path));
+    };
+    let resolved = std::fs::canonicalize(path).map_err(|_| AppError::PathNotFound(path.to_string()))?;
+    if !resolved.starts_with(root) {
+        return Err(AppError::Forbidden(format!(
+            "{} is outside the browse root {}",
+            path,
+            root.display()
+        )));
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::git::test_repo::TestRepo;
+    use crate::git::GitRepository;
+    use crate::routes::test_client::{request, send};
+    use axum::http::StatusCode;
+    use serde_json::json;
+    use std::sync::{Arc, RwLock};
+
+    // The escape hatch under test is a symlink
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn browse_root_confines_listing_and_switching() {
+        let mut outer = TestRepo::new();
+        outer.commit(("Ada", "ada@example.com"), "a.txt", "a\n", "first");
+        let outer_dir = std::fs::canonicalize(outer.repo.workdir().unwrap()).unwrap();
+        let root = outer_dir.join("root");
+        std::fs::create_dir_all(root.join("plain")).unwrap();
+        git2::Repository::init(root.join("nested")).unwrap();
+        std::os::unix::fs::symlink(&outer_dir, root.join("escape")).unwrap();
+
+        let repo = GitRepository::open(&outer_dir).unwrap().with_browse_root(Some(root.clone()));
+        let shared = Arc::new(RwLock::new(repo));
+        let app = crate::routes::create_router(shared.clone());
+        let list = |path: &str| request("GET", &format!("/api/v1/filesystem/list?path={}", path), None);
+        let switch = |path: &str| request("POST", "/api/v1/filesystem/switch", Some(json!({ "path": path })));
+        let under_root = |name: &str| root.join(name).to_string_lossy().to_string();
+
+        // The served repository is outside the root, so browsing starts at the root
+        let (status, body) = send(&app, request("GET", "/api/v1/filesystem/list", None)).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["current_path"], root.to_string_lossy().as_ref());
+        assert_eq!(body["parent_path"], serde_json::Value::Null);
+        let names: Vec<&str> = body["entries"].as_array().unwrap().iter().map(|e| e["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["escape", "nested", "plain"]);
+
+        let (status, body) = send(&app, list(&under_root("nested"))).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["parent_path"], root.to_string_lossy().as_ref());
+
+        for path in [under_root(".."), under_root("escape"), outer_dir.to_string_lossy().to_string()] {
+            let (status, body) = send(&app, list(&path)).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{}: {}", path, body);
+            let (status, body) = send(&app, switch(&path)).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{}: {}", path, body);
+        }
+
+        // Inside the root, but git discovers the outer repository
+        let (status, body) = send(&app, switch(&under_root("plain"))).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+        let (status, body) = send(&app, switch(&under_root("nested"))).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let repo = shared.read().unwrap();
+        assert!(std::path::Path::new(&repo.path).starts_with(&root), "{}", repo.path);
+        assert_eq!(repo.browse_root.as_deref(), Some(root.as_path()));
+    }
 }
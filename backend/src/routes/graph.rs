@@ -0,0 +1,68 @@
+//! Commit graph endpoint.
+//!
+//! GET /api/v1/repository/graph?format=json&from=&to=&limit=500
+//!
+//! The commit DAG reachable from `to` (default HEAD) but not from `from`.
+//! - `format=json` (default): commits in topological order with parents,
+//!   refs, a lane per commit and the line segments to the next row, so a
+//!   gitk-style graph can be drawn without client-side layout; `has_more`
+//!   tells whether `limit` cut the walk short
+//! - `format=dot`: Graphviz DOT source (text/vnd.graphviz)
+//!
+//! Used by: branch graph view, documentation/export tooling (`curl ... | dot -Tsvg`)
+
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::error::{AppError, Result};
+use crate::git::SharedRepo;
+use crate::routes::blocking::blocking;
+
+pub fn routes(repo: SharedRepo) -> Router {
+    Router::new()
+        .route("/api/v1/repository/graph", get(get_graph))
+        .with_state(repo)
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQuery {
+    format: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: usize,
+}
+
+fn default_limit() -> usize {
+    500
+}
+
+async fn get_graph(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<GraphQuery>,
+) -> Result<Response> {
+    blocking(repo, move |repo| {
+
+        match query.format.as_deref() {
+            None | Some("json") => {
+                let graph = repo.get_graph_layout(query.from.as_deref(), query.to.as_deref(), query.limit)?;
+                Ok(Json(graph).into_response())
+            }
+            Some("dot") => {
+                let dot = repo.get_graph_dot(query.from.as_deref(), query.to.as_deref(), query.limit)?;
+                Ok(([(header::CONTENT_TYPE, "text/vnd.graphviz; charset=utf-8")], dot).into_response())
+            }
+            Some(other) => Err(AppError::BadRequest(format!(
+                "Unsupported graph format: {} (expected 'json' or 'dot')",
+                other
+            ))),
+        }
+    })
+    .await
+}
@@ -0,0 +1,187 @@
+//! GraphQL endpoint (built with `--features graphql`).
+//!
+//! POST /api/graphql { query, variables?, operationName? }
+//!
+//! Repository, commits, tree, diff and blame as one graph, so a client asks
+//! for exactly the fields it renders in a single round trip. Fields and enum
+//! values are named as in the REST JSON, so the same TypeScript types apply.
+//! Expensive parts are only computed when selected: last commits per tree
+//! entry, file contents and line blame in diffs, signatures, verification
+//! and notes in commit lists.
+//!
+//! GET /api/graphql/schema
+//!
+//! The schema in SDL, for client code generation.
+//! Used by: scripts and external clients; the bundled UI uses REST
+
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema};
+use axum::{
+    extract::State,
+    routing::{get, post},
+    Json, Router,
+};
+
+use crate::error::Result;
+use crate::git::blame::BlameParams;
+use crate::git::cache::{CommitFilters, TimeWindow};
+use crate::git::diff::DEFAULT_CONTEXT_LINES;
+use crate::git::{GitRepository, SharedRepo};
+use crate::models::{BlameResponse, CommitListResponse, DiffResponse, PathMode, RepositoryInfo, TreeListing};
+use crate::routes::blocking::blocking;
+
+type RepoSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+pub fn routes(repo: SharedRepo) -> Router {
+    let schema = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(repo)
+        .finish();
+
+    Router::new()
+        .route("/api/graphql", post(execute))
+        .route("/api/graphql/schema", get(get_schema))
+        .with_state(schema)
+}
+
+async fn execute(State(schema): State<RepoSchema>, Json(request): Json<async_graphql::Request>) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+async fn get_schema(State(schema): State<RepoSchema>) -> String {
+    schema.sdl()
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Repository metadata: name, path, current branch and HEAD commit
+    async fn repository(&self, ctx: &Context<'_>) -> async_graphql::Result<RepositoryInfo> {
+        run(ctx, |repo| repo.info()).await
+    }
+
+    /// Commit history, optionally limited to commits touching `paths`
+    async fn commits(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] paths: Vec<String>,
+        #[graphql(default = 50)] limit: usize,
+        #[graphql(default)] offset: usize,
+        #[graphql(default)] exclude_authors: Vec<String>,
+        #[graphql(default)] include_bots: bool,
+    ) -> async_graphql::Result<CommitListResponse> {
+        let commits = ctx.look_ahead().field("commits");
+        let (include_signature, include_verification, include_note) = (
+            commits.field("signature").exists(),
+            commits.field("verification").exists(),
+            commits.field("note").exists(),
+        );
+        run(ctx, move |repo| {
+            let paths: Vec<String> = paths.iter().map(|p| repo.canonical_path(p)).collect();
+
+            let mut response = repo.get_commits(
+                &paths,
+                PathMode::Union,
+                limit,
+                offset,
+                CommitFilters {
+                    exclude_authors: Some(&exclude_authors).filter(|a| !a.is_empty()).map(Vec::as_slice),
+                    search: None,
+                    types: None,
+                    window: TimeWindow::default(),
+                },
+                include_signature,
+            )?;
+            if include_verification {
+                repo.attach_verification(&mut response.commits)?;
+            }
+            if include_note {
+                repo.attach_notes(&mut response.commits)?;
+            }
+            if !include_bots {
+                response.contributors.retain(|a| !repo.is_bot(&a.name, &a.email));
+            }
+            Ok(response)
+        })
+        .await
+    }
+
+    /// Entries of a directory (the root by default) at `rev` (HEAD by default),
+    /// all of them or `limit` from `offset` on
+    async fn tree(
+        &self,
+        ctx: &Context<'_>,
+        path: Option<String>,
+        rev: Option<String>,
+        #[graphql(default)] include_counts: bool,
+        limit: Option<usize>,
+        #[graphql(default)] offset: usize,
+    ) -> async_graphql::Result<TreeListing> {
+        let include_last_commit = ctx.look_ahead().field("entries").field("last_commit").exists();
+        run(ctx, move |repo| {
+            let path = repo.canonical_path_opt(path.as_deref());
+            repo.get_tree_entries(
+                path.as_deref(),
+                rev.as_deref(),
+                include_last_commit,
+                include_counts,
+                offset,
+                limit,
+            )
+        })
+        .await
+    }
+
+    /// Changes between two commits, or of the working tree with `to: "WORKING_TREE"`
+    async fn diff(
+        &self,
+        ctx: &Context<'_>,
+        to: String,
+        from: Option<String>,
+        path: Option<String>,
+        #[graphql(default)] include_bots: bool,
+    ) -> async_graphql::Result<DiffResponse> {
+        let lookahead = ctx.look_ahead();
+        let files = lookahead.field("files");
+        let include_contents = files.field("old_content").exists()
+            || files.field("new_content").exists()
+            || lookahead.field("untracked").field("content").exists();
+        let include_blame = files.field("hunks").field("lines").field("blame").exists();
+
+        run(ctx, move |repo| {
+            let path = repo.canonical_path_opt(path.as_deref());
+            let mut response = if to == "WORKING_TREE" {
+                repo.get_working_tree_diff(path.as_deref(), include_contents, include_blame, DEFAULT_CONTEXT_LINES)?
+            } else {
+                repo.get_diff(
+                    from.as_deref(),
+                    &to,
+                    path.as_deref(),
+                    include_contents,
+                    include_blame,
+                    DEFAULT_CONTEXT_LINES,
+                )?
+            };
+            if !include_bots {
+                repo.strip_bots_from_diff(&mut response);
+            }
+            Ok(response)
+        })
+        .await
+    }
+
+    /// Author of each line of `path` at `commit` (HEAD by default)
+    async fn blame(&self, ctx: &Context<'_>, path: String, commit: Option<String>) -> async_graphql::Result<BlameResponse> {
+        let params = BlameParams { use_ignore_file: true, ..Default::default() };
+        run(ctx, move |repo| repo.get_blame(&repo.canonical_path(&path), commit.as_deref(), params)).await
+    }
+}
+
+/// Run a resolver's git work with the repository on a blocking thread
+async fn run<T, F>(ctx: &Context<'_>, work: F) -> async_graphql::Result<T>
+where
+    F: FnOnce(&GitRepository) -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let repo = ctx.data::<SharedRepo>()?.clone();
+    Ok(blocking(repo, work).await?)
+}
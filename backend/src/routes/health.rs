@@ -0,0 +1,60 @@
+//! Health and cache status endpoints.
+//!
+//! GET /api/v1/health
+//!
+//! Server version and uptime, the repository being served, its HEAD OID and
+//! the commit cache's statistics (commits, cached paths, bytes, age, and the
+//! HEAD it was built from). Never builds the cache itself, so it stays cheap
+//! enough to poll.
+//! Used by: liveness checks in scripts, diagnosing memory use on large repositories
+//!
+//! GET /api/v1/cache/status
+//!
+//! Whether the commit cache is cold, building, ready or over budget, and
+//! while it builds, commits read so far, the total and an ETA. Never starts
+//! a build either.
+//! Used by: the "indexing history" progress shown while history requests get `202`
+
+use axum::{extract::State, routing::get, Json, Router};
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use crate::error::Result;
+use crate::git::SharedRepo;
+use crate::models::{CacheStatus, HealthResponse};
+use crate::routes::blocking::blocking;
+
+/// When the router was built, i.e. server start
+static STARTED: LazyLock<Instant> = LazyLock::new(Instant::now);
+
+pub fn routes(repo: SharedRepo) -> Router {
+    LazyLock::force(&STARTED);
+    Router::new()
+        .route("/api/v1/health", get(get_health))
+        .route("/api/v1/cache/status", get(get_cache_status))
+        .with_state(repo)
+}
+
+async fn get_health(State(repo): State<SharedRepo>) -> Result<Json<HealthResponse>> {
+    blocking(repo, move |repo| {
+        let head_oid = repo.with_repo(|r| Ok(r.refname_to_id("HEAD").ok().map(|oid| oid.to_string())))?;
+
+        Ok(Json(HealthResponse {
+            version: env!("CARGO_PKG_VERSION"),
+            uptime_secs: STARTED.elapsed().as_secs(),
+            repository_path: repo.path.clone(),
+            head_oid,
+            cache: repo.cache_stats()?,
+            cache_budget_bytes: repo.cache_max_bytes,
+            cache_over_budget: repo.is_cache_over_budget(),
+        }))
+    })
+    .await
+}
+
+async fn get_cache_status(State(repo): State<SharedRepo>) -> Result<Json<CacheStatus>> {
+    blocking(repo, move |repo| {
+        Ok(Json(repo.cache_status()?))
+    })
+    .await
+}
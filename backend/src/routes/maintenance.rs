@@ -0,0 +1,123 @@
+//! Repository maintenance endpoints.
+//!
+//! - GET /api/v1/repository/maintenance
+//!   Loose object, pack and reflog statistics plus a gc recommendation.
+//!
+//! - POST /api/v1/repository/maintenance { action: "gc" | "repack" | "prune-packed", confirm: true }
+//!   Runs the action via the `git` binary and returns before/after statistics.
+//!   Rejected unless `confirm` is true; 409 if another action is running.
+//!
+//! - GET /api/v1/repository/packs
+//!   Packfiles with size, object count, creation time, bitmap/keep flags, and
+//!   whether a repack would consolidate them.
+//!
+//! - POST /api/v1/repository/fsck
+//!   Integrity check of all objects reachable from refs, streamed as NDJSON
+//!   events: `progress`, `problem` (missing/corrupt object), and a final `done`.
+
+use axum::{
+    extract::State,
+    response::Response,
+    routing::{get, post},
+    Json, Router,
+};
+
+use crate::error::{AppError, Result};
+use crate::git::SharedRepo;
+use crate::models::{MaintenanceRequest, MaintenanceResult, MaintenanceStatus, PackListResponse};
+use crate::routes::stream::{ndjson_response, NdjsonSink};
+use crate::routes::blocking::{blocking, task_failed};
+
+pub fn routes(repo: SharedRepo) -> Router {
+    Router::new()
+        .route(
+            "/api/v1/repository/maintenance",
+            get(get_maintenance_status).post(run_maintenance),
+        )
+        .route("/api/v1/repository/packs", get(list_packs))
+        .route("/api/v1/repository/fsck", post(run_fsck))
+        .with_state(repo)
+}
+
+async fn get_maintenance_status(State(repo): State<SharedRepo>) -> Result<Json<MaintenanceStatus>> {
+    blocking(repo, move |repo| {
+        let status = repo.get_maintenance_status()?;
+        Ok(Json(status))
+    })
+    .await
+}
+
+async fn run_maintenance(
+    State(repo): State<SharedRepo>,
+    Json(request): Json<MaintenanceRequest>,
+) -> Result<Json<MaintenanceResult>> {
+    if !request.confirm {
+        return Err(AppError::BadRequest(
+            "Maintenance actions require \"confirm\": true".to_string(),
+        ));
+    }
+
+    let maintenance = blocking(repo, move |repo| repo.start_maintenance(request.action)).await?;
+    // gc and repack can take minutes; without the guard, a repository switch
+    // isn't held up by them
+    let result = tokio::task::spawn_blocking(move || maintenance.run()).await.map_err(task_failed)??;
+    Ok(Json(result))
+}
+
+async fn list_packs(State(repo): State<SharedRepo>) -> Result<Json<PackListResponse>> {
+    blocking(repo, move |repo| {
+        let packs = repo.list_packs()?;
+        Ok(Json(packs))
+    })
+    .await
+}
+
+async fn run_fsck(State(repo): State<SharedRepo>) -> Response {
+    ndjson_response(repo, |repo| {
+        let fsck = repo.fsck()?;
+        Ok(move |sink: &mut NdjsonSink| fsck.run(|event| sink.send(&event)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::AppError;
+    use crate::git::test_repo::TestRepo;
+    use crate::models::MaintenanceAction;
+    use crate::routes::test_client::{request, send};
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn maintenance_needs_confirmation_and_reports_before_and_after() {
+        let mut test = TestRepo::new();
+        test.commit(("Ada", "ada@example.com"), "a.txt", "a\n", "first");
+        let app = crate::routes::create_router(test.shared());
+        let run = |body| request("POST", "/api/v1/repository/maintenance", Some(body));
+
+        let (status, body) = send(&app, run(json!({ "action": "repack" }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+        let (status, body) = send(&app, run(json!({ "action": "repack", "confirm": true }))).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["success"], true, "{}", body);
+        assert_eq!(body["before"]["pack_count"], 0);
+        assert!(body["before"]["loose_objects"].as_u64().unwrap() > 0);
+        assert_eq!(body["after"]["pack_count"], 1);
+        assert_eq!(body["after"]["loose_objects"], 0);
+    }
+
+    #[test]
+    fn only_one_maintenance_action_runs_at_a_time() {
+        let mut test = TestRepo::new();
+        test.commit(("Ada", "ada@example.com"), "a.txt", "a\n", "first");
+        let shared = test.shared();
+        let repo = shared.read().unwrap();
+
+        let running = repo.start_maintenance(MaintenanceAction::Gc).unwrap();
+        let refused = repo.start_maintenance(MaintenanceAction::Repack);
+        assert!(matches!(refused, Err(AppError::CheckoutConflict(_))));
+        drop(running);
+        assert!(repo.start_maintenance(MaintenanceAction::Repack).is_ok());
+    }
+}
@@ -2,35 +2,93 @@
 //!
 //! Each submodule defines routes for a feature area:
 //! - `repository`: Basic repo info (GET /api/v1/repository)
-//! - `branches`: Branch listing and switching
+//! - `branches`: Branch listing, creation, renaming, deletion and switching
 //! - `tree`: Directory listing and file content
+//! - `archive`: zip / tar.gz snapshot of the tree at a ref
 //! - `commits`: Commit history with filtering
+//! - `contributors`: Per-author commits, directories and activity
 //! - `diff`: Diff between commits
+//! - `graph`: Commit DAG layout and DOT export
+//! - `stats`: Commit activity over time, punch card, per-author line counts and file hotspots
+//! - `editor`: Open a working tree file in the local editor
+//! - `maintenance`: Object storage statistics and gc actions
 //! - `blame`: Per-line author attribution
-//! - `status`: Directory statistics
+//! - `status`: Directory statistics and working tree status
+//! - `workflow`: Committing the staged index, stash push and pop, merging, resetting
+//! - `objects`: Raw objects by OID (image diff blobs)
+//! - `refs`: All references in one listing, HEAD change polling
+//! - `tags`: Tag deletion
+//! - `submodules`: Submodule paths, URLs and pinned commits
+//! - `events`: Server-sent live updates when HEAD, refs, the index or working tree change
+//! - `health`: Uptime, HEAD and commit cache statistics, cache build progress
+//! - `debug`: Slowest recent git operations
+//! - `graphql`: Repository, commits, tree, diff and blame as a GraphQL graph (`graphql` feature)
 //! - `filesystem`: Browse filesystem and switch repositories
+//!
+//! Helpers: `blocking` and `timeout` run git work off the async runtime,
+//! `stream` sends NDJSON and process output, `test_client` drives routers in
+//! unit tests.
 
+pub mod archive;
 pub mod blame;
+pub mod blocking;
 pub mod branches;
 pub mod commits;
+pub mod contributors;
+pub mod debug;
 pub mod diff;
+pub mod editor;
+pub mod events;
 pub mod filesystem;
+pub mod graph;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+pub mod health;
+pub mod maintenance;
+pub mod objects;
+pub mod refs;
 pub mod repository;
+pub mod stats;
 pub mod status;
+pub mod stream;
+pub mod submodules;
+pub mod tags;
+#[cfg(test)]
+pub mod test_client;
+pub mod timeout;
 pub mod tree;
+pub mod workflow;
 
 use axum::Router;
 
 use crate::git::SharedRepo;
 
 pub fn create_router(repo: SharedRepo) -> Router {
-    Router::new()
+    let router = Router::new()
         .merge(repository::routes(repo.clone()))
         .merge(branches::routes(repo.clone()))
         .merge(tree::routes(repo.clone()))
+        .merge(archive::routes(repo.clone()))
         .merge(commits::routes(repo.clone()))
+        .merge(contributors::routes(repo.clone()))
         .merge(diff::routes(repo.clone()))
+        .merge(graph::routes(repo.clone()))
+        .merge(stats::routes(repo.clone()))
+        .merge(editor::routes(repo.clone()))
+        .merge(maintenance::routes(repo.clone()))
         .merge(blame::routes(repo.clone()))
         .merge(status::routes(repo.clone()))
-        .merge(filesystem::routes(repo))
+        .merge(workflow::routes(repo.clone()))
+        .merge(objects::routes(repo.clone()))
+        .merge(refs::routes(repo.clone()))
+        .merge(tags::routes(repo.clone()))
+        .merge(submodules::routes(repo.clone()))
+        .merge(health::routes(repo.clone()))
+        .merge(debug::routes(repo.clone()))
+        .merge(events::routes(repo.clone()));
+
+    #[cfg(feature = "graphql")]
+    let router = router.merge(graphql::routes(repo.clone()));
+
+    router.merge(filesystem::routes(repo))
 }
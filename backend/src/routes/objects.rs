@@ -0,0 +1,60 @@
+//! Object endpoint.
+//!
+//! GET /api/v1/repository/objects/{oid}
+//!
+//! Serves any object by full or abbreviated (4+ hex digits) OID:
+//! - blobs as raw bytes with a content type sniffed from their contents
+//! - trees as an entry listing (name, mode, type, OID)
+//! - commits and annotated tags as structured JSON
+//!
+//! Objects never change, so full-OID responses are marked immutable.
+//! Used by: DiffViewer image comparison (`FileDiff.image` OIDs), permalinks, debugging
+
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+
+use crate::error::Result;
+use crate::git::objects::ObjectContent;
+use crate::git::SharedRepo;
+use crate::routes::blocking::blocking;
+
+pub fn routes(repo: SharedRepo) -> Router {
+    Router::new()
+        .route("/api/v1/repository/objects/{oid}", get(get_object))
+        .with_state(repo)
+}
+
+async fn get_object(State(repo): State<SharedRepo>, Path(oid): Path<String>) -> Result<Response> {
+    blocking(repo, move |repo| {
+
+        // An abbreviated OID may resolve to a different object once more objects exist
+        let cache_control = if oid.len() == 40 {
+            "public, max-age=31536000, immutable"
+        } else {
+            "no-cache"
+        };
+
+        match repo.get_object(&oid)? {
+            ObjectContent::Blob(blob) => Ok((
+                [
+                    (header::CONTENT_TYPE, blob.mime),
+                    (header::CACHE_CONTROL, cache_control),
+                    // Never let the browser render repository content as a page
+                    (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+                    (header::CONTENT_SECURITY_POLICY, "default-src 'none'; style-src 'unsafe-inline'; sandbox"),
+                ],
+                blob.content,
+            )
+                .into_response()),
+            ObjectContent::Structured(object) => {
+                Ok(([(header::CACHE_CONTROL, cache_control)], Json(object)).into_response())
+            }
+        }
+    })
+    .await
+}
@@ -0,0 +1,134 @@
+//! Reference listing endpoint.
+//!
+//! GET /api/v1/repository/refs
+//!
+//! Returns all refs in one call: branches, remote-tracking branches, tags,
+//! notes and stash entries, each with its direct target and peeled commit.
+//! Sorted by kind, then name.
+//! Used by: unified ref picker and compare view
+//!
+//! GET /api/v1/repository/head
+//!
+//! Current branch, HEAD OID, index checksum and refs stamp, with a strong ETag
+//! derived from all four. Clients poll with `If-None-Match` and get `304 Not
+//! Modified` until a commit, checkout, reset, staging change, fetch or branch
+//! or tag write happens outside the viewer.
+//! Used by: frontend polling for external changes
+//!
+//! GET /api/v1/repository/reflog?ref=HEAD&limit=100
+//!
+//! Reflog entries of a ref (HEAD by default, or a branch), newest first: old
+//! and new OID, message and time. Each entry's `selector` (`HEAD@{3}`) can be
+//! passed as a ref to the other endpoints.
+//! Used by: recovering from an accidental checkout or reset
+//!
+//! GET /api/v1/repository/signature?ref=
+//!
+//! Whether a commit or annotated tag is GPG-, SSH- or X.509-signed and whether
+//! the signature verifies against the keys git is configured with (GPG keyring,
+//! `gpg.ssh.allowedSignersFile`). `ref` defaults to HEAD; a tag name checks the
+//! tag object's own signature.
+//! Used by: commit detail and tag verification badges
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::error::Result;
+use crate::git::SharedRepo;
+use crate::models::{RefInfo, ReflogResponse, SignatureVerification};
+use crate::routes::blocking::blocking;
+
+pub fn routes(repo: SharedRepo) -> Router {
+    Router::new()
+        .route("/api/v1/repository/refs", get(list_refs))
+        .route("/api/v1/repository/head", get(get_head))
+        .route("/api/v1/repository/reflog", get(get_reflog))
+        .route("/api/v1/repository/signature", get(verify_signature))
+        .with_state(repo)
+}
+
+async fn list_refs(State(repo): State<SharedRepo>) -> Result<Json<Vec<RefInfo>>> {
+    blocking(repo, move |repo| {
+        let refs = repo.list_refs()?;
+        Ok(Json(refs))
+    })
+    .await
+}
+
+async fn get_head(State(repo): State<SharedRepo>, headers: HeaderMap) -> Result<Response> {
+    blocking(repo, move |repo| {
+        let state = repo.get_head_state()?;
+
+        let mut hasher = DefaultHasher::new();
+        (&state.branch, &state.oid, &state.index_checksum, &state.refs_stamp).hash(&mut hasher);
+        let etag = format!("\"{:016x}\"", hasher.finish());
+
+        let not_modified = headers
+            .get(header::IF_NONE_MATCH)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*"));
+
+        if not_modified {
+            return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+        }
+
+        Ok((
+            [(header::ETAG, etag), (header::CACHE_CONTROL, "no-cache".to_string())],
+            Json(state),
+        )
+            .into_response())
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct ReflogQuery {
+    #[serde(rename = "ref", default = "default_ref")]
+    name: String,
+    #[serde(default = "default_limit")]
+    limit: usize,
+}
+
+fn default_ref() -> String {
+    "HEAD".to_string()
+}
+
+fn default_limit() -> usize {
+    100
+}
+
+async fn get_reflog(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<ReflogQuery>,
+) -> Result<Json<ReflogResponse>> {
+    blocking(repo, move |repo| {
+        let reflog = repo.get_reflog(&query.name, query.limit)?;
+        Ok(Json(reflog))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct SignatureQuery {
+    #[serde(rename = "ref", default = "default_ref")]
+    name: String,
+}
+
+async fn verify_signature(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<SignatureQuery>,
+) -> Result<Json<SignatureVerification>> {
+    blocking(repo, move |repo| {
+        let verification = repo.verify_signature(&query.name)?;
+        Ok(Json(verification))
+    })
+    .await
+}
@@ -2,23 +2,91 @@
 //!
 //! GET /api/v1/repository - Returns basic repository metadata:
 //! name, path, current branch, HEAD commit, bare/empty status.
+//! A detached HEAD is reported with its OID and `git describe` name, and an
+//! unborn branch (no commits yet) by name; in that state tree listings,
+//! history and statistics come back empty instead of failing.
 //!
 //! Used by: AppLayout header to display repo name and branch
+//!
+//! GET /api/v1/repository/permalink?remote=origin&ref=&path=&line=&end_line=
+//!
+//! Web URL of a commit (no `path`), a file or directory, or lines of a file on
+//! the forge hosting `remote` (GitHub, GitLab, Bitbucket, Gitea; other hosts
+//! get GitHub-style links). `ref` defaults to HEAD and is pinned to its full
+//! OID; `end_line` defaults to `line`.
+//! Used by: "copy link" actions in the file, blame and commit views
+//!
+//! GET /api/v1/bootstrap
+//!
+//! Where the UI opens, from `--path` (a file or directory, with its type) and
+//! `--ref` (pinned to its full OID); `{}` when neither was given. Only the
+//! repository the server started with has one.
+//! Used by: AppLayout on load, to open at the linked file, directory or commit
 
-use axum::{extract::State, routing::get, Json, Router};
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
 
 use crate::error::{AppError, Result};
+use crate::git::permalinks::LineRange;
 use crate::git::SharedRepo;
-use crate::models::RepositoryInfo;
+use crate::models::{Bootstrap, Permalink, RepositoryInfo};
+use crate::routes::blocking::blocking;
 
 pub fn routes(repo: SharedRepo) -> Router {
     Router::new()
         .route("/api/v1/repository", get(get_repository_info))
+        .route("/api/v1/repository/permalink", get(get_permalink))
+        .route("/api/v1/bootstrap", get(get_bootstrap))
         .with_state(repo)
 }
 
 async fn get_repository_info(State(repo): State<SharedRepo>) -> Result<Json<RepositoryInfo>> {
-    let repo = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
-    let info = repo.info()?;
-    Ok(Json(info))
+    blocking(repo, move |repo| {
+        let info = repo.info()?;
+        Ok(Json(info))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct PermalinkQuery {
+    #[serde(default = "default_remote")]
+    remote: String,
+    #[serde(rename = "ref")]
+    rev: Option<String>,
+    path: Option<String>,
+    line: Option<u32>,
+    end_line: Option<u32>,
+}
+
+fn default_remote() -> String {
+    "origin".to_string()
+}
+
+async fn get_permalink(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<PermalinkQuery>,
+) -> Result<Json<Permalink>> {
+    blocking(repo, move |repo| {
+        let path = repo.canonical_path_opt(query.path.as_deref());
+        let lines = match (query.line, query.end_line) {
+            (Some(start), end) => Some(LineRange { start, end: end.unwrap_or(start) }),
+            (None, None) => None,
+            (None, Some(_)) => return Err(AppError::BadRequest("end_line needs line".to_string())),
+        };
+        let permalink = repo.permalink(&query.remote, query.rev.as_deref(), path.as_deref(), lines)?;
+        Ok(Json(permalink))
+    })
+    .await
+}
+
+async fn get_bootstrap(State(repo): State<SharedRepo>) -> Result<Json<Bootstrap>> {
+    blocking(repo, move |repo| {
+        Ok(Json(repo.bootstrap.clone()))
+    })
+    .await
 }
@@ -0,0 +1,161 @@
+//! Repository statistics endpoints.
+//!
+//! GET /api/v1/repository/stats/activity?granularity=week&path=&author=&include_bots=
+//!
+//! Commit counts per `day`, `week` (ISO, default) or `month`, oldest first,
+//! from the first to the last commit with empty periods included. `path`
+//! (a path or glob) limits the count to commits touching it, `author` to one
+//! author email (case-insensitive). Bot authors are left out unless
+//! `include_bots=true` or `author` names one. Computed from the commit cache.
+//! Used by: activity chart
+//!
+//! GET /api/v1/repository/stats/punchcard?path=&author=&include_bots=
+//!
+//! Commits per day of the week (0 = Sunday) and hour of the day, all 168
+//! cells, using each commit's author time in the author's own timezone.
+//! `path`, `author` and `include_bots` filter like the activity endpoint.
+//! Used by: GitHub-style punch card
+//!
+//! GET /api/v1/repository/stats/authors?range=&since=&until=&include_bots=
+//!
+//! Insertions, deletions and commit counts per author, most lines changed
+//! first. `range` (`v1.0..main`) takes the commits reachable from its end
+//! but not its start, otherwise HEAD's history is used; `since`/`until`
+//! (Unix seconds, inclusive) narrow either to a time window. Merge commits
+//! and bot authors (unless `include_bots=true`) are left out.
+//! Used by: author statistics table
+//!
+//! GET /api/v1/repository/stats/hotspots?days=90&path=&sort=frequency&limit=50&include_bots=
+//!
+//! Files changed in the last `days` days (`0` for all history), ranked by
+//! the number of commits that changed them (`sort=frequency`) or by lines
+//! added plus removed (`sort=churn`), with their author count and last
+//! change. `path` (a path, directory or glob) limits the ranking to the files
+//! it covers. Merge commits and bot authors (unless `include_bots=true`) are
+//! left out.
+//! Used by: hotspot list for spotting frequently changed files
+
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::error::Result;
+use crate::git::cache::TimeWindow;
+use crate::git::SharedRepo;
+use crate::models::{
+    ActivityGranularity, ActivityResponse, AuthorStatsResponse, HotspotSort, HotspotsResponse, PunchCardResponse,
+};
+use crate::routes::blocking::blocking;
+
+pub fn routes(repo: SharedRepo) -> Router {
+    Router::new()
+        .route("/api/v1/repository/stats/activity", get(get_activity))
+        .route("/api/v1/repository/stats/punchcard", get(get_punch_card))
+        .route("/api/v1/repository/stats/authors", get(get_author_stats))
+        .route("/api/v1/repository/stats/hotspots", get(get_hotspots))
+        .with_state(repo)
+}
+
+#[derive(Debug, Deserialize)]
+struct ActivityQuery {
+    #[serde(default)]
+    granularity: ActivityGranularity,
+    path: Option<String>,
+    author: Option<String>,
+    #[serde(default)]
+    include_bots: bool,
+}
+
+async fn get_activity(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<ActivityQuery>,
+) -> Result<Json<ActivityResponse>> {
+    blocking(repo, move |repo| {
+        let path = repo.canonical_path_opt(query.path.as_deref());
+        let author = query.author.as_deref().map(str::trim).filter(|a| !a.is_empty());
+        let activity = repo.get_activity(path.as_deref(), author, query.granularity, query.include_bots)?;
+        Ok(Json(activity))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct PunchCardQuery {
+    path: Option<String>,
+    author: Option<String>,
+    #[serde(default)]
+    include_bots: bool,
+}
+
+async fn get_punch_card(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<PunchCardQuery>,
+) -> Result<Json<PunchCardResponse>> {
+    blocking(repo, move |repo| {
+        let path = repo.canonical_path_opt(query.path.as_deref());
+        let author = query.author.as_deref().map(str::trim).filter(|a| !a.is_empty());
+        let punch_card = repo.get_punch_card(path.as_deref(), author, query.include_bots)?;
+        Ok(Json(punch_card))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthorStatsQuery {
+    range: Option<String>,
+    since: Option<i64>,
+    until: Option<i64>,
+    #[serde(default)]
+    include_bots: bool,
+}
+
+async fn get_author_stats(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<AuthorStatsQuery>,
+) -> Result<Json<AuthorStatsResponse>> {
+    blocking(repo, move |repo| {
+        let range = query.range.as_deref().filter(|r| !r.is_empty());
+        let window = TimeWindow { since: query.since, until: query.until };
+        let stats = repo.get_author_stats(range, window, query.include_bots)?;
+        Ok(Json(stats))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct HotspotsQuery {
+    #[serde(default = "default_hotspot_days")]
+    days: u32,
+    path: Option<String>,
+    #[serde(default)]
+    sort: HotspotSort,
+    #[serde(default = "default_hotspot_limit")]
+    limit: usize,
+    #[serde(default)]
+    include_bots: bool,
+}
+
+fn default_hotspot_days() -> u32 {
+    90
+}
+
+fn default_hotspot_limit() -> usize {
+    50
+}
+
+async fn get_hotspots(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<HotspotsQuery>,
+) -> Result<Json<HotspotsResponse>> {
+    blocking(repo, move |repo| {
+        let path = repo.canonical_path_opt(query.path.as_deref());
+        let since = (query.days > 0).then(|| chrono::Utc::now().timestamp() - i64::from(query.days) * 86_400);
+        let window = TimeWindow { since, until: None };
+        let hotspots = repo.get_hotspots(path.as_deref(), window, query.sort, query.limit, query.include_bots)?;
+        Ok(Json(hotspots))
+    })
+    .await
+}
@@ -1,14 +1,54 @@
 //! Directory status/info endpoint.
 //!
-//! GET /api/v1/repository/directory-info?path=
+//! GET /api/v1/repository/directory-info?path=&include_bots=&since=&until=
 //!
 //! Returns directory statistics:
 //! - File and directory counts
 //! - Total size
-//! - Contributors (who committed to files in this directory)
+//! - Contributors (who committed to files in this directory; bots omitted
+//!   unless `include_bots=true`), counting only commits between `since` and
+//!   `until` (Unix seconds, inclusive) when given
 //! - First and latest commit dates
+//! - Owners of the directory from CODEOWNERS
 //!
 //! Used by: StatusTab in bottom panel (directory statistics view)
+//!
+//! GET /api/v1/repository/codeowners?path=&ref=
+//!
+//! Owners of a file or directory per the CODEOWNERS file of `ref` (default
+//! HEAD), with the matching pattern and its line.
+//! Used by: owner badges next to author badges in the diff view
+//!
+//! GET /api/v1/repository/status?path=
+//!
+//! Working tree status: staged and unstaged files with per-file status codes,
+//! untracked and conflicted paths. `/working-tree-status` is an alias kept for
+//! the HistoryTab's "uncommitted changes" entry.
+//! Used by: StatusTab, HistoryTab
+//!
+//! GET /api/v1/repository/status/files?path=&include_untracked=true
+//!
+//! One entry per changed path with its index status (HEAD vs index) and
+//! working tree status (index vs working tree) side by side, so a file both
+//! staged and modified again appears once. Untracked files are flagged, and
+//! conflicted files carry the conflict kind (`both_modified`,
+//! `deleted_by_them`, ...). Includes per-category counts.
+//! Used by: Changes panel
+//!
+//! GET /api/v1/repository/ignored?path=&limit=1000
+//!
+//! Ignored files under `path` (default the whole working tree), each with the
+//! ignore file line that matches it. Ignored directories are listed once
+//! rather than file by file. At most `limit` entries (capped at 10000);
+//! `truncated` says whether there were more.
+//!
+//! GET /api/v1/repository/check-ignore?path=
+//!
+//! Whether a working tree path is ignored and which line of which ignore
+//! file decides it (source, line, pattern), like `git check-ignore -v`. A
+//! negated (`!`) rule means the path is re-included; tracked files are never
+//! ignored but still report a matching rule.
+//! Used by: file tree "why is this file missing" explanation
 
 use axum::{
     extract::{Query, State},
@@ -17,26 +57,146 @@ use axum::{
 };
 use serde::Deserialize;
 
-use crate::error::{AppError, Result};
+use crate::error::Result;
+use crate::git::cache::TimeWindow;
+use crate::git::ignore::{DEFAULT_IGNORED_LIMIT, MAX_IGNORED_LIMIT};
 use crate::git::SharedRepo;
-use crate::models::DirectoryInfo;
+use crate::models::{CodeOwnersMatch, DirectoryInfo, IgnoreCheck, IgnoredFiles, StatusFiles, WorkingTreeStatus};
+use crate::routes::timeout::limited;
+use crate::routes::blocking::blocking;
 
 pub fn routes(repo: SharedRepo) -> Router {
     Router::new()
         .route("/api/v1/repository/directory-info", get(get_directory_info))
+        .route("/api/v1/repository/codeowners", get(get_code_owners))
+        .route("/api/v1/repository/status", get(get_working_tree_status))
+        .route("/api/v1/repository/working-tree-status", get(get_working_tree_status))
+        .route("/api/v1/repository/status/files", get(get_status_files))
+        .route("/api/v1/repository/ignored", get(list_ignored))
+        .route("/api/v1/repository/check-ignore", get(check_ignore))
         .with_state(repo)
 }
 
 #[derive(Debug, Deserialize)]
 struct DirectoryQuery {
     path: Option<String>,
+    #[serde(default)]
+    include_bots: bool,
+    since: Option<i64>,
+    until: Option<i64>,
 }
 
 async fn get_directory_info(
     State(repo): State<SharedRepo>,
     Query(query): Query<DirectoryQuery>,
 ) -> Result<Json<DirectoryInfo>> {
-    let repo = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
-    let info = repo.get_directory_info(query.path.as_deref())?;
-    Ok(Json(info))
+    let operation = format!("Directory info of /{}", query.path.as_deref().unwrap_or(""));
+    limited(repo, operation, move |repo| {
+        let path = repo.canonical_path_opt(query.path.as_deref());
+        let window = TimeWindow { since: query.since, until: query.until };
+        let mut info = repo.get_directory_info(path.as_deref(), window)?;
+        if !query.include_bots {
+            info.contributors.retain(|c| !repo.is_bot(&c.name, &c.email));
+        }
+        Ok(info)
+    })
+    .await
+    .map(Json)
+}
+
+#[derive(Debug, Deserialize)]
+struct CodeOwnersQuery {
+    #[serde(default)]
+    path: String,
+    #[serde(rename = "ref")]
+    rev: Option<String>,
+}
+
+async fn get_code_owners(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<CodeOwnersQuery>,
+) -> Result<Json<CodeOwnersMatch>> {
+    blocking(repo, move |repo| {
+        let path = repo.canonical_path(&query.path);
+        let owners = repo.get_code_owners(&path, query.rev.as_deref())?;
+        Ok(Json(owners))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkingTreeStatusQuery {
+    path: Option<String>,
+}
+
+async fn get_working_tree_status(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<WorkingTreeStatusQuery>,
+) -> Result<Json<WorkingTreeStatus>> {
+    blocking(repo, move |repo| {
+        let status = repo.get_working_tree_status(query.path.as_deref())?;
+        Ok(Json(status))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusFilesQuery {
+    path: Option<String>,
+    #[serde(default = "default_include_untracked")]
+    include_untracked: bool,
+}
+
+fn default_include_untracked() -> bool {
+    true
+}
+
+async fn get_status_files(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<StatusFilesQuery>,
+) -> Result<Json<StatusFiles>> {
+    blocking(repo, move |repo| {
+        let files = repo.get_status_files(query.path.as_deref(), query.include_untracked)?;
+        Ok(Json(files))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct IgnoredQuery {
+    path: Option<String>,
+    #[serde(default = "default_ignored_limit")]
+    limit: usize,
+}
+
+fn default_ignored_limit() -> usize {
+    DEFAULT_IGNORED_LIMIT
+}
+
+async fn list_ignored(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<IgnoredQuery>,
+) -> Result<Json<IgnoredFiles>> {
+    blocking(repo, move |repo| {
+        let ignored = repo.list_ignored(query.path.as_deref(), query.limit.min(MAX_IGNORED_LIMIT))?;
+        Ok(Json(ignored))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckIgnoreQuery {
+    #[serde(default)]
+    path: String,
+}
+
+async fn check_ignore(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<CheckIgnoreQuery>,
+) -> Result<Json<IgnoreCheck>> {
+    blocking(repo, move |repo| {
+        let check = repo.check_ignore(&query.path)?;
+        Ok(Json(check))
+    })
+    .await
 }
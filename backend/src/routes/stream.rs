@@ -0,0 +1,125 @@
+//! Streaming response helpers.
+//!
+//! - `ndjson_response()`: Runs a git operation on a blocking thread and forwards
+//!   each emitted item to the client as one JSON line, so long walks (history,
+//!   integrity checks) show results immediately. If the operation fails midway,
+//!   a final `{"error": "..."}` line is written before the stream ends.
+//! - `process_body()`: Forwards a `git` child process's stdout (archives, large
+//!   blobs) without buffering it; a failing process aborts the body.
+
+use axum::{
+    body::{Body, Bytes},
+    http::header,
+    response::Response,
+};
+use serde::Serialize;
+use std::io::Read;
+use std::process::Child;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::error::{AppError, Result};
+use crate::git::{GitRepository, SharedRepo};
+
+type Chunk = std::result::Result<Bytes, std::io::Error>;
+
+/// Bytes read from a child process per chunk sent to the client
+const PROCESS_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Sending half handed to the producer
+pub struct NdjsonSink {
+    tx: mpsc::Sender<Chunk>,
+}
+
+impl NdjsonSink {
+    /// Send one item; returns false once the client has disconnected
+    pub fn send<T: Serialize>(&mut self, item: &T) -> bool {
+        let mut line = serde_json::to_vec(item).unwrap_or_default();
+        line.push(b'\n');
+        self.tx.blocking_send(Ok(Bytes::from(line))).is_ok()
+    }
+}
+
+/// Stream the items produced by the producer `prepare` returns as `application/x-ndjson`
+///
+/// `prepare` runs with the repository read guard held and should only resolve
+/// what the producer needs (commits, paths, a `RepoLocation`). The guard is
+/// released before the producer runs, so a client reading slowly, which stalls
+/// the producer once the channel is full, never holds up a repository switch.
+pub fn ndjson_response<P, F>(repo: SharedRepo, prepare: P) -> Response
+where
+    P: FnOnce(&GitRepository) -> Result<F> + Send + 'static,
+    F: FnOnce(&mut NdjsonSink) -> Result<()>,
+{
+    let (tx, rx) = mpsc::channel::<Chunk>(64);
+
+    tokio::task::spawn_blocking(move || {
+        let mut sink = NdjsonSink { tx };
+
+        let produce = repo
+            .read()
+            .map_err(|_| AppError::Internal("Lock poisoned".to_string()))
+            .and_then(|repo| prepare(&repo));
+        let result = produce.and_then(|produce| produce(&mut sink));
+
+        if let Err(e) = result {
+            sink.send(&serde_json::json!({ "error": e.to_string() }));
+        }
+    });
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .body(Body::from_stream(ReceiverStream::new(rx)))
+        .unwrap()
+}
+
+/// Response body that sends `head` (bytes already read from the child, if
+/// any) followed by the rest of the child's stdout
+///
+/// The child is killed if the client disconnects. A non-zero exit is logged and
+/// ends the body with an error, so the client sees a truncated transfer rather
+/// than a silently incomplete file.
+pub fn process_body(mut child: Child, head: Vec<u8>) -> Result<Body> {
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| AppError::Internal("git process has no stdout".to_string()))?;
+    let (tx, rx) = mpsc::channel::<Chunk>(16);
+
+    tokio::task::spawn_blocking(move || {
+        if !head.is_empty() && tx.blocking_send(Ok(Bytes::from(head))).is_err() {
+            let _ = child.kill();
+        } else {
+            let mut buf = vec![0u8; PROCESS_CHUNK_SIZE];
+            loop {
+                match stdout.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        if tx.blocking_send(Ok(Bytes::copy_from_slice(&buf[..n]))).is_err() {
+                            // Client went away
+                            let _ = child.kill();
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = tx.blocking_send(Err(e));
+                        break;
+                    }
+                }
+            }
+        }
+        drop(stdout);
+
+        match child.wait_with_output() {
+            Ok(output) if !output.status.success() => {
+                let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                tracing::warn!("git process failed: {}", stderr);
+                let _ = tx.blocking_send(Err(std::io::Error::other(stderr)));
+            }
+            Err(e) => tracing::warn!("git process failed: {}", e),
+            Ok(_) => {}
+        }
+    });
+
+    Ok(Body::from_stream(ReceiverStream::new(rx)))
+}
@@ -0,0 +1,27 @@
+//! Submodule listing endpoint.
+//!
+//! - GET /api/v1/repository/submodules
+//!   Each submodule's name, path, URL, tracked branch, the commit it is pinned
+//!   to in HEAD and the commit checked out in the working tree (if initialized).
+//!   Used by: FileList details for submodule entries
+
+use axum::{extract::State, routing::get, Json, Router};
+
+use crate::error::Result;
+use crate::git::SharedRepo;
+use crate::models::SubmoduleInfo;
+use crate::routes::blocking::blocking;
+
+pub fn routes(repo: SharedRepo) -> Router {
+    Router::new()
+        .route("/api/v1/repository/submodules", get(list_submodules))
+        .with_state(repo)
+}
+
+async fn list_submodules(State(repo): State<SharedRepo>) -> Result<Json<Vec<SubmoduleInfo>>> {
+    blocking(repo, move |repo| {
+        let submodules = repo.list_submodules()?;
+        Ok(Json(submodules))
+    })
+    .await
+}
@@ -0,0 +1,88 @@
+//! Tag management endpoints.
+//!
+//! - DELETE /api/v1/repository/tags/{name}?confirm={name}
+//!   Deletes a tag. The `confirm` parameter must repeat the tag name, so a
+//!   stray request can't remove a tag by accident. Returns the deleted tag's
+//!   target so the client can offer to recreate it.
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::delete,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::error::{AppError, Result};
+use crate::git::SharedRepo;
+use crate::models::DeletedTag;
+use crate::routes::blocking::blocking;
+
+pub fn routes(repo: SharedRepo) -> Router {
+    Router::new()
+        .route("/api/v1/repository/tags/{*name}", delete(delete_tag))
+        .with_state(repo)
+}
+
+#[derive(Debug, Deserialize)]
+struct DeleteTagQuery {
+    confirm: Option<String>,
+}
+
+async fn delete_tag(
+    State(repo): State<SharedRepo>,
+    Path(name): Path<String>,
+    Query(query): Query<DeleteTagQuery>,
+) -> Result<Json<DeletedTag>> {
+    if query.confirm.as_deref() != Some(name.as_str()) {
+        return Err(AppError::BadRequest(format!(
+            "Refusing to delete tag '{}' without confirm={}",
+            name, name
+        )));
+    }
+
+    blocking(repo, move |repo| {
+        let deleted = repo.delete_tag(&name)?;
+        Ok(Json(deleted))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::git::test_repo::TestRepo;
+    use crate::routes::test_client::{request, send};
+    use axum::http::StatusCode;
+
+    #[tokio::test]
+    async fn delete_tag_needs_the_name_confirmed_and_reports_what_it_pointed_at() {
+        let mut test = TestRepo::new();
+        let commit = test.commit(("Ada", "ada@example.com"), "a.txt", "a\n", "first");
+        let object = test.repo.find_object(commit, None).unwrap();
+        let tagger = test.repo.signature().unwrap();
+        let annotated = test.repo.tag("release/v1", &object, &tagger, "Release 1", false).unwrap();
+        test.repo.tag_lightweight("light", &object, false).unwrap();
+        let app = crate::routes::create_router(test.shared());
+        let delete = |uri: &str| request("DELETE", uri, None);
+
+        for uri in ["/api/v1/repository/tags/release/v1", "/api/v1/repository/tags/release/v1?confirm=v1"] {
+            let (status, body) = send(&app, delete(uri)).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", uri, body);
+        }
+        assert!(test.repo.find_reference("refs/tags/release/v1").is_ok());
+
+        let (status, body) = send(&app, delete("/api/v1/repository/tags/release/v1?confirm=release/v1")).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["target"], annotated.to_string());
+        assert_eq!(body["commit"], commit.to_string());
+        assert_eq!(body["is_annotated"], true);
+        assert!(test.repo.find_reference("refs/tags/release/v1").is_err());
+
+        let (status, body) = send(&app, delete("/api/v1/repository/tags/light?confirm=light")).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["target"], commit.to_string());
+        assert_eq!(body["is_annotated"], false);
+
+        let (status, _) = send(&app, delete("/api/v1/repository/tags/light?confirm=light")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,86 @@
+//! Running expensive handlers under the request timeout.
+//!
+//! - `limited()`: Runs a git operation on a blocking thread with a `Deadline`
+//!   (see git/deadline.rs) from `--request-timeout`
+//!
+//! The client gets `504` as soon as the limit passes, and the operation stops
+//! at its next check, releasing the repository lock. If the client disconnects
+//! first, the dropped request cancels the operation the same way.
+
+use crate::error::{AppError, Result};
+use crate::git::deadline::Deadline;
+use crate::git::{GitRepository, SharedRepo};
+use crate::routes::blocking::{blocking, task_failed};
+
+/// Cancels the deadline when the request future is dropped, finished or not
+struct CancelOnDrop(Deadline);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// Run `work` on a blocking thread, stopped after the repository's request
+/// timeout; `operation` names it in the 504 message (e.g. "Blame of a.rs")
+pub async fn limited<T, F>(repo: SharedRepo, operation: String, work: F) -> Result<T>
+where
+    F: FnOnce(&GitRepository) -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let timeout = blocking(repo.clone(), |repo| Ok(repo.request_timeout)).await?;
+    let Some(timeout) = timeout else {
+        return blocking(repo, work).await;
+    };
+    let run = move || {
+        let repo = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
+        work(&repo)
+    };
+
+    let deadline = Deadline::new(operation, timeout);
+    let _cancel = CancelOnDrop(deadline.clone());
+    let task = tokio::task::spawn_blocking({
+        let deadline = deadline.clone();
+        move || deadline.scope(run)
+    });
+    match tokio::time::timeout(timeout, task).await {
+        Ok(joined) => joined.map_err(task_failed)?,
+        Err(_) => Err(deadline.timeout_error()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::deadline;
+    use crate::git::test_repo::TestRepo;
+    use axum::http::StatusCode;
+    use axum::response::IntoResponse;
+    use std::sync::{Arc, RwLock};
+    use std::time::{Duration, Instant};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn operation_past_the_request_timeout_is_answered_with_504() {
+        let mut test = TestRepo::new();
+        test.commit(("Ada", "ada@example.com"), "a.txt", "a\n", "first");
+        let repo = GitRepository::open(test.repo.workdir().unwrap())
+            .unwrap()
+            .with_request_timeout(Some(Duration::from_millis(100)));
+        let repo: SharedRepo = Arc::new(RwLock::new(repo));
+
+        let started = Instant::now();
+        let result = limited(repo, "Slow walk".to_string(), |_| {
+            for _ in 0..1000 {
+                deadline::check()?;
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Ok(())
+        })
+        .await;
+
+        let error = result.expect_err("stopped by the timeout");
+        assert!(error.to_string().contains("Slow walk"), "{}", error);
+        assert_eq!(error.into_response().status(), StatusCode::GATEWAY_TIMEOUT);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -1,41 +1,89 @@
 //! Tree and file content endpoints.
 //!
-//! - GET /api/v1/repository/tree?path=&include_last_commit=true
+//! All three take an optional `ref` (commit OID, branch or tag, default HEAD)
+//! to browse the repository at any point in history without checking out.
+//!
+//! - GET /api/v1/repository/tree?path=&ref=&include_last_commit=true&include_counts=false&limit=&offset=0
 //!   Directory listing with file metadata and last commit info.
+//!   Returns `{ entries, total, has_more }`; with `limit`, only that many
+//!   entries from `offset` on (directories first, then files, by name), and
+//!   metadata and last commits are only looked up for those.
+//!   Folder counts cover immediate children; with `include_counts=true` they are
+//!   totals for everything below the folder ("src/ — 214 files").
+//!   Git LFS files carry `lfs` (content OID, whether it was fetched) and
+//!   report the content's size rather than the pointer's.
 //!   Used by: FileList component for directory browsing
 //!
-//! - GET /api/v1/repository/tree/full
+//! - GET /api/v1/repository/tree/full?ref=
 //!   Complete recursive tree structure.
 //!   Used by: FileTree sidebar for expandable navigation
 //!
-//! - GET /api/v1/repository/file?path=
-//!   File content as UTF-8 string.
+//! - GET /api/v1/repository/file?path=&ref=&range=
+//!   File content as UTF-8 string (for a symlink, the path it points to), with
+//!   the file's `total_size`, the byte `range` served and whether it is
+//!   `truncated`. At most 1 MiB is served; `range=START-END` (end exclusive)
+//!   or `range=START-` reads further into larger files.
+//!   Git LFS files serve the fetched content from `.git/lfs/objects`; one
+//!   that hasn't been fetched is a 400 naming its size and OID, never the
+//!   pointer text. `/raw` still serves the pointer as committed.
 //!   Used by: File preview (if implemented)
+//!
+//! - GET /api/v1/repository/raw?path=&ref=
+//!   File bytes as-is with Content-Type (sniffed, else from the extension) and
+//!   Content-Length. Files over 1 MiB are streamed instead of loaded whole.
+//!   Served with `nosniff` and a sandboxing CSP, like the objects endpoint.
+//!   `Range: bytes=` requests get `206` with that part of the file (a bounded
+//!   range of a large file may come back shorter), so video and audio can
+//!   seek; several ranges in one request get the whole file.
+//!   The blob OID is the `ETag`, and `If-None-Match` gets `304`. With `ref`
+//!   a full commit OID the response is cached as immutable; otherwise the
+//!   browser revalidates, since branches move.
+//!   Used by: image, video and audio previews, downloading files
 
 use axum::{
+    body::Body,
     extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::Response,
     routing::get,
     Json, Router,
 };
 use serde::Deserialize;
 
 use crate::error::{AppError, Result};
+use crate::git::objects::raw_content_type;
+use crate::git::tree::RawContent;
 use crate::git::SharedRepo;
-use crate::models::{FullTreeEntry, TreeEntry};
+use crate::models::{FileContent, FullTreeEntry, TreeListing};
+use crate::routes::stream::process_body;
+use crate::routes::timeout::limited;
+use crate::routes::blocking::blocking;
+
+/// Cache-Control for content pinned by a full commit OID
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
 
 pub fn routes(repo: SharedRepo) -> Router {
     Router::new()
         .route("/api/v1/repository/tree", get(get_tree))
         .route("/api/v1/repository/tree/full", get(get_full_tree))
         .route("/api/v1/repository/file", get(get_file_content))
+        .route("/api/v1/repository/raw", get(get_raw))
         .with_state(repo)
 }
 
 #[derive(Debug, Deserialize)]
 struct TreeQuery {
     path: Option<String>,
+    #[serde(rename = "ref")]
+    rev: Option<String>,
     #[serde(default = "default_true")]
     include_last_commit: bool,
+    #[serde(default)]
+    include_counts: bool,
+    /// Page size; the whole directory when absent
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
 }
 
 fn default_true() -> bool {
@@ -45,31 +93,256 @@ fn default_true() -> bool {
 async fn get_tree(
     State(repo): State<SharedRepo>,
     Query(query): Query<TreeQuery>,
-) -> Result<Json<Vec<TreeEntry>>> {
-    let repo = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
-    let entries = repo.get_tree_entries(
-        query.path.as_deref(),
-        query.include_last_commit,
-    )?;
-    Ok(Json(entries))
+) -> Result<Json<TreeListing>> {
+    blocking(repo, move |repo| {
+        let path = repo.canonical_path_opt(query.path.as_deref());
+        let listing = repo.get_tree_entries(
+            path.as_deref(),
+            query.rev.as_deref(),
+            query.include_last_commit,
+            query.include_counts,
+            query.offset,
+            query.limit,
+        )?;
+        Ok(Json(listing))
+    })
+    .await
 }
 
-async fn get_full_tree(State(repo): State<SharedRepo>) -> Result<Json<Vec<FullTreeEntry>>> {
-    let repo = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
-    let tree = repo.get_full_tree()?;
-    Ok(Json(tree))
+#[derive(Debug, Deserialize)]
+struct FullTreeQuery {
+    #[serde(rename = "ref")]
+    rev: Option<String>,
+}
+
+async fn get_full_tree(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<FullTreeQuery>,
+) -> Result<Json<Vec<FullTreeEntry>>> {
+    blocking(repo, move |repo| {
+        let tree = repo.get_full_tree(query.rev.as_deref())?;
+        Ok(Json(tree))
+    })
+    .await
 }
 
 #[derive(Debug, Deserialize)]
 struct FileQuery {
     path: String,
+    #[serde(rename = "ref")]
+    rev: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileContentQuery {
+    path: String,
+    #[serde(rename = "ref")]
+    rev: Option<String>,
+    range: Option<String>,
+}
+
+/// `range` as a start and optional end byte; the start of the file when absent
+fn parse_range(param: Option<&str>) -> Result<(u64, Option<u64>)> {
+    let Some(range) = param.filter(|r| !r.is_empty()) else {
+        return Ok((0, None));
+    };
+    let invalid = || AppError::BadRequest(format!("range must be START-END or START- (bytes), got '{}'", range));
+    let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+    let start: u64 = start.parse().map_err(|_| invalid())?;
+    let end = match end {
+        "" => None,
+        end => Some(end.parse::<u64>().ok().filter(|&end| end >= start).ok_or_else(invalid)?),
+    };
+    Ok((start, end))
 }
 
 async fn get_file_content(
+    State(repo): State<SharedRepo>,
+    Query(query): Query<FileContentQuery>,
+) -> Result<Json<FileContent>> {
+    let (start, end) = parse_range(query.range.as_deref())?;
+    let operation = format!("Reading {}", query.path);
+    limited(repo, operation, move |repo| {
+        repo.get_file_content(&repo.canonical_path(&query.path), query.rev.as_deref(), start, end)
+    })
+    .await
+    .map(Json)
+}
+
+/// What a `Range` header asks of a blob
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RangeRequest {
+    /// Bytes from `start` to `end` (exclusive), or to the end of the blob
+    Bytes { start: u64, end: Option<u64> },
+    /// Starts past the end of the blob
+    Unsatisfiable,
+}
+
+/// `Range: bytes=START-END`, `START-` or `-SUFFIX` for a blob `size` bytes
+/// long; `None` for anything else (several ranges, other units, garbage),
+/// which HTTP lets the server answer with the whole blob
+fn parse_range_header(value: &str, size: u64) -> Option<RangeRequest> {
+    let spec = value.trim().strip_prefix("bytes=")?.trim();
+    if spec.contains(',') {
+        return None;
+    }
+    let (first, last) = spec.split_once('-')?;
+    let (start, end) = match (first, last) {
+        ("", suffix) => {
+            let len: u64 = suffix.parse().ok()?;
+            if len == 0 {
+                return Some(RangeRequest::Unsatisfiable);
+            }
+            (size.saturating_sub(len), None)
+        }
+        (first, "") => (first.parse().ok()?, None),
+        (first, last) => {
+            let (start, last): (u64, u64) = (first.parse().ok()?, last.parse().ok()?);
+            if last < start {
+                return None;
+            }
+            // HTTP ranges include their last byte
+            (start, Some(last.saturating_add(1).min(size)))
+        }
+    };
+    if start >= size {
+        return Some(RangeRequest::Unsatisfiable);
+    }
+    Some(RangeRequest::Bytes { start, end })
+}
+
+/// `416` for a range that selects no bytes of a `size`-byte file
+fn range_not_satisfiable(size: u64) -> Response {
+    Response::builder()
+        .status(StatusCode::RANGE_NOT_SATISFIABLE)
+        .header(header::CONTENT_RANGE, format!("bytes */{}", size))
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// `If-None-Match` lists `etag`, weak or not, or is `*`
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    let listed = |tag: &str| {
+        let tag = tag.trim();
+        tag == "*" || tag.trim_start_matches("W/") == etag
+    };
+    headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(listed))
+}
+
+async fn get_raw(
     State(repo): State<SharedRepo>,
     Query(query): Query<FileQuery>,
-) -> Result<Json<String>> {
-    let repo = repo.read().map_err(|_| AppError::Internal("Lock poisoned".to_string()))?;
-    let content = repo.get_file_content(&query.path)?;
-    Ok(Json(content))
+    headers: HeaderMap,
+) -> Result<Response> {
+    let (requested, rev) = (query.path.clone(), query.rev.clone());
+    let (path, oid, size) = blocking(repo.clone(), move |repo| {
+        let path = repo.canonical_path(&requested);
+        let (oid, size) = repo.find_raw_blob(&path, rev.as_deref())?;
+        Ok((path, oid, size))
+    })
+    .await?;
+
+    let etag = format!("\"{}\"", oid);
+    // A full commit OID pins the file; a branch, tag or HEAD may move
+    let pinned = query.rev.as_deref().is_some_and(|rev| rev.len() == 40 && rev.chars().all(|c| c.is_ascii_hexdigit()));
+    let cache_control = if pinned { IMMUTABLE } else { "no-cache" };
+
+    if etag_matches(&headers, &etag) {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(header::ETAG, etag)
+            .header(header::CACHE_CONTROL, cache_control)
+            .body(Body::empty())
+            .unwrap());
+    }
+
+    // `If-Range` only lets the range through while the client's copy is current
+    let range = headers
+        .get(header::RANGE)
+        .filter(|_| headers.get(header::IF_RANGE).is_none_or(|tag| tag.as_bytes() == etag.as_bytes()))
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| parse_range_header(value, size));
+    let (start, end) = match range {
+        None => (0, None),
+        Some(RangeRequest::Bytes { start, end }) => (start, end),
+        Some(RangeRequest::Unsatisfiable) => return Ok(range_not_satisfiable(size)),
+    };
+
+    // Reaching a range of a large file means reading through what precedes it
+    let operation = format!("Reading {}", path);
+    let blob = limited(repo, operation, move |repo| repo.read_raw_blob(oid, size, start, end)).await?;
+    // A range that came back empty has no last byte to name in `Content-Range`
+    if range.is_some() && blob.range.end <= blob.range.start {
+        return Ok(range_not_satisfiable(size));
+    }
+    let content_type = raw_content_type(&path, &blob.head);
+    let body = match blob.content {
+        RawContent::Inline(bytes) => Body::from(bytes),
+        RawContent::Stream { child, read } => process_body(child, read)?,
+    };
+
+    let mut response = Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_LENGTH, blob.range.end - blob.range.start)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ETAG, etag)
+        .header(header::CACHE_CONTROL, cache_control)
+        // Never let the browser render repository content as a page
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .header(header::CONTENT_SECURITY_POLICY, "default-src 'none'; style-src 'unsafe-inline'; sandbox");
+    if range.is_some() {
+        response = response
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", blob.range.start, blob.range.end - 1, size));
+    }
+    Ok(response.body(body).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_headers_select_bytes_of_the_blob() {
+        use RangeRequest::{Bytes, Unsatisfiable};
+        let cases: &[(&str, u64, Option<RangeRequest>)] = &[
+            // Closed ranges include their last byte, clamped to the blob
+            ("bytes=0-49", 100, Some(Bytes { start: 0, end: Some(50) })),
+            ("bytes=99-99", 100, Some(Bytes { start: 99, end: Some(100) })),
+            ("bytes=90-150", 100, Some(Bytes { start: 90, end: Some(100) })),
+            (" bytes= 5-9 ", 100, Some(Bytes { start: 5, end: Some(10) })),
+            // Open-ended
+            ("bytes=10-", 100, Some(Bytes { start: 10, end: None })),
+            ("bytes=100-", 100, Some(Unsatisfiable)),
+            ("bytes=0-", 0, Some(Unsatisfiable)),
+            // Suffix: the last N bytes, all of them when N is larger
+            ("bytes=-20", 100, Some(Bytes { start: 80, end: None })),
+            ("bytes=-200", 100, Some(Bytes { start: 0, end: None })),
+            ("bytes=-0", 100, Some(Unsatisfiable)),
+            ("bytes=-5", 0, Some(Unsatisfiable)),
+            // Past the end
+            ("bytes=150-160", 100, Some(Unsatisfiable)),
+            // Inverted
+            ("bytes=50-10", 100, None),
+            // Overflow: the largest last byte saturates, anything beyond u64 isn't a range
+            ("bytes=90-18446744073709551615", 100, Some(Bytes { start: 90, end: Some(100) })),
+            ("bytes=0-18446744073709551616", 100, None),
+            ("bytes=18446744073709551616-", 100, None),
+            ("bytes=-18446744073709551616", 100, None),
+            // Several ranges, other units and garbage get the whole blob
+            ("bytes=0-1,5-6", 100, None),
+            ("bytes=0-1, -5", 100, None),
+            ("items=0-5", 100, None),
+            ("bytes=abc", 100, None),
+            ("bytes=", 100, None),
+            ("bytes=-", 100, None),
+        ];
+
+        for (header, size, expected) in cases {
+            assert_eq!(parse_range_header(header, *size), *expected, "{:?} of {} bytes", header, size);
+        }
+    }
 }
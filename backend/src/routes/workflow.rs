@@ -0,0 +1,367 @@
+//! Working tree write endpoints.
+//!
+//! - POST /api/v1/repository/commit { message: string }
+//!   Commits the staged index with the configured user identity and returns
+//!   the new commit. Empty messages, an empty index diff and unresolved
+//!   conflicts are rejected. Cache auto-invalidates on next query.
+//!   Used by: StatusTab commit box
+//!
+//! - POST /api/v1/repository/stash { message?: string, include_untracked?: bool }
+//!   Stashes local changes and resets the working tree to HEAD. Returns the
+//!   new `stash@{0}` entry.
+//!
+//! - POST /api/v1/repository/stash/pop { index?: number }
+//!   Reapplies a stash entry (default the most recent) and drops it. Refused
+//!   with 409 when it would overwrite local changes; the entry is kept then.
+//!   Used by: "stash and switch" offered on a refused branch checkout
+//!
+//! - POST /api/v1/repository/merge { branch: string, dry_run?: bool }
+//!   Merges a branch (or any revision) into HEAD. The result's `analysis` is
+//!   `up_to_date`, `fast_forward`, `normal` (clean merge commit) or
+//!   `conflicts`; the merge is computed in memory first, so conflicts are
+//!   reported with their paths and nothing is changed. `dry_run` only
+//!   reports the analysis. Applying needs a clean working tree (409 otherwise).
+//!   Used by: merge action in the branch switcher
+//!
+//! - POST /api/v1/repository/reset { ref: string, mode?: "soft"|"mixed"|"hard", dry_run?: bool, force?: bool }
+//!   Moves HEAD and its branch to `ref` (default mode `mixed`), like
+//!   `git reset`. The result lists the commits the branch loses, how many it
+//!   gains and how many files differ; `dry_run` only reports that. A hard
+//!   reset that would discard uncommitted changes is refused with 409 unless
+//!   `force=true`. Refused while a merge or rebase is in progress.
+//!   Used by: "undo last commit" in the history view
+
+use axum::{extract::State, routing::post, Json, Router};
+use serde::Deserialize;
+
+use crate::error::Result;
+use crate::git::SharedRepo;
+use crate::models::{CommitDetail, MergeResult, ResetMode, ResetResult, StashEntry};
+use crate::routes::blocking::blocking;
+
+pub fn routes(repo: SharedRepo) -> Router {
+    Router::new()
+        .route("/api/v1/repository/commit", post(create_commit))
+        .route("/api/v1/repository/stash", post(stash_push))
+        .route("/api/v1/repository/stash/pop", post(stash_pop))
+        .route("/api/v1/repository/merge", post(merge_branch))
+        .route("/api/v1/repository/reset", post(reset_head))
+        .with_state(repo)
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitRequest {
+    message: String,
+}
+
+async fn create_commit(
+    State(repo): State<SharedRepo>,
+    Json(request): Json<CommitRequest>,
+) -> Result<Json<CommitDetail>> {
+    blocking(repo, move |repo| {
+        let commit = repo.create_commit(&request.message)?;
+        Ok(Json(commit))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct StashPushRequest {
+    message: Option<String>,
+    #[serde(default)]
+    include_untracked: bool,
+}
+
+async fn stash_push(
+    State(repo): State<SharedRepo>,
+    Json(request): Json<StashPushRequest>,
+) -> Result<Json<StashEntry>> {
+    blocking(repo, move |repo| {
+        let message = request.message.as_deref().map(str::trim).filter(|m| !m.is_empty());
+        let entry = repo.stash_push(message, request.include_untracked)?;
+        Ok(Json(entry))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct StashPopRequest {
+    #[serde(default)]
+    index: usize,
+}
+
+async fn stash_pop(
+    State(repo): State<SharedRepo>,
+    Json(request): Json<StashPopRequest>,
+) -> Result<Json<StashEntry>> {
+    blocking(repo, move |repo| {
+        let entry = repo.stash_pop(request.index)?;
+        Ok(Json(entry))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct MergeRequest {
+    branch: String,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+async fn merge_branch(
+    State(repo): State<SharedRepo>,
+    Json(request): Json<MergeRequest>,
+) -> Result<Json<MergeResult>> {
+    blocking(repo, move |repo| {
+        let result = repo.merge_branch(request.branch.trim(), request.dry_run)?;
+        Ok(Json(result))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct ResetRequest {
+    #[serde(rename = "ref")]
+    target: String,
+    #[serde(default)]
+    mode: ResetMode,
+    #[serde(default)]
+    dry_run: bool,
+    #[serde(default)]
+    force: bool,
+}
+
+async fn reset_head(
+    State(repo): State<SharedRepo>,
+    Json(request): Json<ResetRequest>,
+) -> Result<Json<ResetResult>> {
+    blocking(repo, move |repo| {
+        let result = repo.reset_head(request.target.trim(), request.mode, request.dry_run, request.force)?;
+        Ok(Json(result))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::git::test_repo::TestRepo;
+    use crate::routes::test_client::{request, send};
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    const ADA: (&str, &str) = ("Ada", "ada@example.com");
+
+    #[tokio::test]
+    async fn reset_reports_dry_runs_and_refuses_to_discard_changes_unless_forced() {
+        let mut test = TestRepo::new();
+        let first = test.commit(ADA, "a.txt", "a\n", "first");
+        let second = test.commit(ADA, "b.txt", "b\n", "second");
+        let app = crate::routes::create_router(test.shared());
+
+        let dry = json!({ "ref": "HEAD~1", "mode": "hard", "dry_run": true });
+        let (status, body) = send(&app, request("POST", "/api/v1/repository/reset", Some(dry))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["applied"], false);
+        assert_eq!(body["target"], first.to_string());
+        assert_eq!(body["previous_head"], second.to_string());
+        assert_eq!(body["removed_commit_count"], 1);
+        assert_eq!(body["files_changed"], 1);
+        assert_eq!(test.repo.head().unwrap().target(), Some(second));
+
+        test.write("a.txt", "edited\n");
+        let hard = json!({ "ref": "HEAD~1", "mode": "hard" });
+        let (status, body) = send(&app, request("POST", "/api/v1/repository/reset", Some(hard.clone()))).await;
+        assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+        assert_eq!(test.repo.head().unwrap().target(), Some(second));
+
+        let forced = json!({ "ref": "HEAD~1", "mode": "hard", "force": true });
+        let (status, body) = send(&app, request("POST", "/api/v1/repository/reset", Some(forced))).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["applied"], true);
+        assert_eq!(body["discarded_changes"], json!(["a.txt"]));
+        assert_eq!(test.repo.head().unwrap().target(), Some(first));
+        assert_eq!(std::fs::read_to_string(test.repo.workdir().unwrap().join("a.txt")).unwrap(), "a\n");
+        assert!(!test.repo.workdir().unwrap().join("b.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn soft_reset_keeps_the_undone_commit_staged() {
+        let mut test = TestRepo::new();
+        let first = test.commit(ADA, "a.txt", "a\n", "first");
+        test.commit(ADA, "b.txt", "b\n", "second");
+        let app = crate::routes::create_router(test.shared());
+
+        let soft = json!({ "ref": "HEAD~1", "mode": "soft" });
+        let (status, body) = send(&app, request("POST", "/api/v1/repository/reset", Some(soft))).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["mode"], "soft");
+        assert_eq!(test.repo.head().unwrap().target(), Some(first));
+        let status = test.repo.status_file(std::path::Path::new("b.txt")).unwrap();
+        assert_eq!(status, git2::Status::INDEX_NEW);
+    }
+
+    #[tokio::test]
+    async fn merge_fast_forwards_merges_and_leaves_conflicts_untouched() {
+        let mut test = TestRepo::new();
+        test.commit(ADA, "a.txt", "a\n", "first");
+        test.branch("ahead");
+        test.branch("clean");
+        test.branch("clashing");
+        test.checkout("ahead");
+        let ahead = test.commit(ADA, "b.txt", "b\n", "ahead");
+        test.checkout("clean");
+        test.commit(ADA, "c.txt", "c\n", "clean");
+        test.checkout("clashing");
+        test.commit(ADA, "a.txt", "theirs\n", "clashing");
+        test.checkout("master");
+        let app = crate::routes::create_router(test.shared());
+        let merge = |branch: &str, dry_run: bool| {
+            request("POST", "/api/v1/repository/merge", Some(json!({ "branch": branch, "dry_run": dry_run })))
+        };
+
+        let (status, body) = send(&app, merge("ahead", true)).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["analysis"], "fast_forward");
+        assert_eq!(body["applied"], false);
+        let (_, body) = send(&app, merge("ahead", false)).await;
+        assert_eq!(body["applied"], true);
+        assert_eq!(body["head"], ahead.to_string());
+        assert_eq!(test.repo.head().unwrap().target(), Some(ahead));
+        assert!(test.repo.workdir().unwrap().join("b.txt").exists());
+        let (_, body) = send(&app, merge("ahead", false)).await;
+        assert_eq!(body["analysis"], "up_to_date");
+
+        let (status, body) = send(&app, merge("clean", false)).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["analysis"], "normal");
+        let merge_commit = test.repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(body["merge_commit"], merge_commit.id().to_string());
+        assert_eq!(merge_commit.parent_count(), 2);
+        drop(merge_commit);
+        assert!(test.repo.workdir().unwrap().join("c.txt").exists());
+
+        test.commit(ADA, "a.txt", "ours\n", "ours");
+        let head = test.repo.head().unwrap().target();
+        let (status, body) = send(&app, merge("clashing", false)).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["analysis"], "conflicts");
+        assert_eq!(body["applied"], false);
+        assert_eq!(body["conflicts"], json!(["a.txt"]));
+        assert_eq!(test.repo.head().unwrap().target(), head);
+        assert_eq!(std::fs::read_to_string(test.repo.workdir().unwrap().join("a.txt")).unwrap(), "ours\n");
+    }
+
+    #[tokio::test]
+    async fn merge_needs_a_clean_working_tree() {
+        let mut test = TestRepo::new();
+        let first = test.commit(ADA, "a.txt", "a\n", "first");
+        test.branch("ahead");
+        test.checkout("ahead");
+        test.commit(ADA, "b.txt", "b\n", "ahead");
+        test.checkout("master");
+        test.write("a.txt", "edited\n");
+        let app = crate::routes::create_router(test.shared());
+
+        let body = json!({ "branch": "ahead" });
+        let (status, body) = send(&app, request("POST", "/api/v1/repository/merge", Some(body))).await;
+        assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+        assert_eq!(test.repo.head().unwrap().target(), Some(first));
+    }
+
+    #[tokio::test]
+    async fn stash_push_cleans_the_tree_and_pop_restores_it() {
+        let mut test = TestRepo::new();
+        test.commit(ADA, "a.txt", "a\n", "first");
+        let app = crate::routes::create_router(test.shared());
+        let read = |test: &TestRepo, file: &str| std::fs::read_to_string(test.repo.workdir().unwrap().join(file));
+
+        let (status, body) = send(&app, request("POST", "/api/v1/repository/stash", Some(json!({})))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+        test.write("a.txt", "edited\n");
+        test.write("new.txt", "untracked\n");
+        let push = json!({ "message": "  wip  ", "include_untracked": true });
+        let (status, body) = send(&app, request("POST", "/api/v1/repository/stash", Some(push))).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["index"], 0);
+        assert!(body["message"].as_str().unwrap().ends_with("wip"), "{}", body);
+        assert_eq!(read(&test, "a.txt").unwrap(), "a\n");
+        assert!(read(&test, "new.txt").is_err());
+
+        let (status, body) = send(&app, request("POST", "/api/v1/repository/stash/pop", Some(json!({})))).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(read(&test, "a.txt").unwrap(), "edited\n");
+        assert_eq!(read(&test, "new.txt").unwrap(), "untracked\n");
+        let mut left = 0;
+        test.repo
+            .stash_foreach(|_, _, _| {
+                left += 1;
+                true
+            })
+            .unwrap();
+        assert_eq!(left, 0);
+    }
+
+    #[tokio::test]
+    async fn stash_pop_keeps_the_entry_when_it_would_overwrite_changes() {
+        let mut test = TestRepo::new();
+        test.commit(ADA, "a.txt", "a\n", "first");
+        let app = crate::routes::create_router(test.shared());
+
+        test.write("a.txt", "stashed\n");
+        let (status, _) = send(&app, request("POST", "/api/v1/repository/stash", Some(json!({})))).await;
+        assert_eq!(status, StatusCode::OK);
+        test.write("a.txt", "local\n");
+
+        let (status, body) = send(&app, request("POST", "/api/v1/repository/stash/pop", Some(json!({})))).await;
+        assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+        assert_eq!(std::fs::read_to_string(test.repo.workdir().unwrap().join("a.txt")).unwrap(), "local\n");
+        let mut left = 0;
+        test.repo
+            .stash_foreach(|_, _, _| {
+                left += 1;
+                true
+            })
+            .unwrap();
+        assert_eq!(left, 1);
+
+        let pop = json!({ "index": 3 });
+        let (status, _) = send(&app, request("POST", "/api/v1/repository/stash/pop", Some(pop))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn commit_records_only_the_staged_changes() {
+        let mut test = TestRepo::new();
+        let first = test.commit(ADA, "a.txt", "a\n", "first");
+        let repo = test.shared();
+        let app = crate::routes::create_router(repo.clone());
+        let commit = |message: &str| request("POST", "/api/v1/repository/commit", Some(json!({ "message": message })));
+
+        test.write("a.txt", "staged\n");
+        test.stage("a.txt");
+        test.write("a.txt", "unstaged\n");
+        let (status, body) = send(&app, commit("# only a comment\n")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+        let (status, body) = send(&app, commit("Stage a\n\n# comment stripped\n")).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["message"], "Stage a");
+        assert_eq!(body["author"]["name"], "Test User");
+        assert_eq!(body["parents"], json!([first.to_string()]));
+        let head = test.repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(body["oid"], head.id().to_string());
+        assert_eq!(head.message(), Some("Stage a\n"));
+        let blob = head.tree().unwrap().get_name("a.txt").unwrap().to_object(&test.repo).unwrap();
+        assert_eq!(blob.as_blob().unwrap().content(), b"staged\n");
+
+        let (status, body) = send(&app, commit("again")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+        repo.read().unwrap().wait_for_cache().unwrap();
+        let (status, body) = send(&app, request("GET", "/api/v1/repository/commits", None)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["commits"][0]["oid"], head.id().to_string());
+    }
+}
@@ -0,0 +1,155 @@
+//! Running as a persistent service, e.g. a team-shared instance under systemd.
+//!
+//! - `activated_listener()`: The socket handed over by systemd socket activation
+//! - `shutdown_signal()`: Ctrl+C, `SIGTERM` or `SIGHUP`, whichever comes first
+//! - `reject_writes()`: Middleware for `--read-only`
+//!
+//! With socket activation systemd owns the socket, so the address, port and
+//! firewalling live in the `.socket` unit, and the viewer only starts on the
+//! first connection. Without it the server binds 127.0.0.1:`--port` as usual.
+
+use axum::{
+    extract::Request,
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use std::net::TcpListener;
+
+use crate::error::{AppError, Result};
+
+/// First file descriptor systemd passes (after stdin, stdout and stderr)
+#[cfg(unix)]
+const LISTEN_FDS_START: i32 = 3;
+
+/// The listening socket systemd passed in, if this process was socket activated
+///
+/// Follows sd_listen_fds(3): the variables only count when `LISTEN_PID` is
+/// this process, and of several sockets only the first is served. The socket
+/// is marked close-on-exec so git and editor child processes don't hold it.
+#[cfg(unix)]
+pub fn activated_listener() -> anyhow::Result<Option<TcpListener>> {
+    use std::os::fd::FromRawFd;
+
+    let for_us = std::env::var("LISTEN_PID").ok().and_then(|pid| pid.parse::<u32>().ok()) == Some(std::process::id());
+    let count = std::env::var("LISTEN_FDS").ok().and_then(|n| n.parse::<i32>().ok()).unwrap_or(0);
+    if !for_us || count < 1 {
+        return Ok(None);
+    }
+
+    // SAFETY: systemd hands fd 3 over to this process, and nothing else owns it
+    let listener = unsafe {
+        libc::fcntl(LISTEN_FDS_START, libc::F_SETFD, libc::FD_CLOEXEC);
+        TcpListener::from_raw_fd(LISTEN_FDS_START)
+    };
+    listener.local_addr()
+        .map_err(|e| anyhow::anyhow!("Socket from systemd is not a TCP socket: {}", e))?;
+    listener.set_nonblocking(true)?;
+    Ok(Some(listener))
+}
+
+#[cfg(windows)]
+pub fn activated_listener() -> anyhow::Result<Option<TcpListener>> {
+    Ok(None)
+}
+
+/// Resolves on Ctrl+C, on `SIGTERM` as sent by `systemctl stop` and
+/// `git-viewer kill`, or on `SIGHUP` when the terminal running the server closes
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for Ctrl+C");
+    };
+
+    #[cfg(unix)]
+    let (terminate, hangup) = {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM");
+        let mut hangup = signal(SignalKind::hangup()).expect("Failed to listen for SIGHUP");
+        (async move { terminate.recv().await }, async move { hangup.recv().await })
+    };
+    #[cfg(windows)]
+    let (terminate, hangup) = (std::future::pending::<()>(), std::future::pending::<()>());
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+        _ = hangup => {}
+    }
+}
+
+/// Middleware: refuse requests that would change the repository or reach
+/// outside it
+///
+/// Reads (GET, HEAD, OPTIONS and GraphQL queries) pass. Everything else, i.e.
+/// checkout, commit, stash, merge, reset, branch and tag changes, fsck and
+/// open-in-editor, is `403`, as is browsing the filesystem for other
+/// repositories.
+pub async fn reject_writes(request: Request, next: Next) -> Result<Response> {
+    let path = request.uri().path();
+    if path.starts_with("/api/v1/filesystem/") {
+        return Err(AppError::Forbidden(
+            "Browsing and switching repositories is disabled on this read-only instance".to_string(),
+        ));
+    }
+
+    let is_read = matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS)
+        || path == "/api/graphql";
+    if !is_read {
+        return Err(AppError::Forbidden("This instance is read-only".to_string()));
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_repo::TestRepo;
+    use crate::git::GitRepository;
+    use crate::routes::test_client::{request, send};
+    use axum::{http::StatusCode, middleware};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn read_only_instance_serves_reads_and_refuses_writes() {
+        let mut test = TestRepo::new();
+        test.commit(("Ada", "ada@example.com"), "a.txt", "a\n", "first");
+        let app = crate::routes::create_router(test.shared()).layer(middleware::from_fn(reject_writes));
+
+        let (status, body) = send(&app, request("GET", "/api/v1/repository/branches", None)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.as_array().unwrap().len(), 1);
+
+        let writes = [
+            request("POST", "/api/v1/repository/branches", Some(json!({ "name": "feature" }))),
+            request("DELETE", "/api/v1/repository/branches/master", None),
+            request("POST", "/api/v1/repository/commit", Some(json!({ "message": "sneaky" }))),
+            request("POST", "/api/v1/repository/reset", Some(json!({ "ref": "HEAD" }))),
+            request("POST", "/api/v1/repository/fsck", None),
+            request("GET", "/api/v1/filesystem/list", None),
+        ];
+        for write in writes {
+            let uri = write.uri().to_string();
+            let (status, body) = send(&app, write).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{}", uri);
+            assert!(body["error"].as_str().unwrap().contains("read-only"), "{}: {}", uri, body);
+        }
+
+        assert!(test.repo.find_branch("feature", git2::BranchType::Local).is_err());
+        assert!(test.repo.find_branch("master", git2::BranchType::Local).is_ok());
+    }
+
+    #[test]
+    fn read_only_repository_writes_no_path_index() {
+        let mut test = TestRepo::new();
+        test.commit(("Ada", "ada@example.com"), "a.txt", "a\n", "first");
+        test.commit(("Ada", "ada@example.com"), "b.txt", "b\n", "second");
+        let repo = GitRepository::open(test.repo.workdir().unwrap()).unwrap().with_read_only(true);
+
+        repo.wait_for_cache().unwrap();
+        let history = repo.with_cache(|cache, r| cache.get_path_commit_indices(r, "a.txt")).unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(!test.repo.path().join("git-viewer").exists());
+    }
+}
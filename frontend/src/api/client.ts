@@ -5,30 +5,82 @@
  * React Query automatically cancels in-flight requests when query keys change,
  * improving UI responsiveness when users navigate quickly.
  *
+ * Every request carries a per-tab X-Client-Id header, so the server can split
+ * the machine's share of diff, blame and directory requests fairly between tabs.
+ *
+ * While the server builds its commit cache, history requests are answered
+ * with 202 and Retry-After; fetchJson waits and asks again, so callers only
+ * see the final response.
+ *
  * Functions map 1:1 to backend endpoints. Used by hooks in hooks.ts.
  */
 
 import type {
   RepositoryInfo,
-  TreeEntry,
+  Bootstrap,
+  TreeListing,
   FullTreeEntry,
+  FileContent,
   CommitListResponse,
+  CommitDetail,
   DiffResponse,
   DirectoryInfo,
   DirectoryListing,
   BranchInfo,
+  DeletedBranch,
+  RenamedBranch,
   BlameResponse,
   WorkingTreeStatus,
+  StatusFiles,
+  IgnoredFiles,
+  IgnoreCheck,
+  StashEntry,
+  MergeResult,
+  ResetMode,
+  ResetResult,
+  RepoChangeEvent,
+  HealthResponse,
+  CacheStatus,
 } from './types'
 
 const API_BASE = '/api/v1'
 
+/** Identifies this tab to the server, which shares expensive requests out between tabs */
+const CLIENT_ID = `${Date.now().toString(36)}-${Math.random().toString(36).slice(2)}`
+
+function apiFetch(url: string, init: RequestInit = {}): Promise<Response> {
+  const headers = new Headers(init.headers)
+  headers.set('X-Client-Id', CLIENT_ID)
+  return fetch(url, { ...init, headers })
+}
+
+/** Wait `ms`, rejecting with an AbortError as soon as the signal aborts */
+function delay(ms: number, signal?: AbortSignal): Promise<void> {
+  return new Promise((resolve, reject) => {
+    if (signal?.aborted) {
+      reject(new DOMException('Aborted', 'AbortError'))
+      return
+    }
+    const timer = setTimeout(resolve, ms)
+    signal?.addEventListener('abort', () => {
+      clearTimeout(timer)
+      reject(new DOMException('Aborted', 'AbortError'))
+    }, { once: true })
+  })
+}
+
 /**
  * Fetch JSON with optional abort signal for request cancellation.
  * When the signal is aborted, the request is cancelled and an AbortError is thrown.
+ * A 202 (commit cache still warming up) is retried after its Retry-After.
  */
 async function fetchJson<T>(url: string, signal?: AbortSignal): Promise<T> {
-  const response = await fetch(url, { signal })
+  let response = await apiFetch(url, { signal })
+  while (response.status === 202) {
+    const retryAfter = Number(response.headers.get('Retry-After')) || 1
+    await delay(retryAfter * 1000, signal)
+    response = await apiFetch(url, { signal })
+  }
   if (!response.ok) {
     const error = await response.json().catch(() => ({ error: response.statusText }))
     throw new Error(error.error || 'Request failed')
@@ -40,11 +92,21 @@ export const api = {
   getRepository: (signal?: AbortSignal) =>
     fetchJson<RepositoryInfo>(`${API_BASE}/repository`, signal),
 
-  getTree: (path?: string, includeLastCommit = true, signal?: AbortSignal) => {
+  getBootstrap: (signal?: AbortSignal) =>
+    fetchJson<Bootstrap>(`${API_BASE}/bootstrap`, signal),
+
+  getHealth: (signal?: AbortSignal) =>
+    fetchJson<HealthResponse>(`${API_BASE}/health`, signal),
+
+  getCacheStatus: (signal?: AbortSignal) =>
+    fetchJson<CacheStatus>(`${API_BASE}/cache/status`, signal),
+
+  getTree: (path?: string, includeLastCommit = true, limit?: number, signal?: AbortSignal) => {
     const params = new URLSearchParams()
     if (path) params.set('path', path)
     params.set('include_last_commit', String(includeLastCommit))
-    return fetchJson<TreeEntry[]>(`${API_BASE}/repository/tree?${params}`, signal)
+    if (limit !== undefined) params.set('limit', String(limit))
+    return fetchJson<TreeListing>(`${API_BASE}/repository/tree?${params}`, signal)
   },
 
   getFullTree: (signal?: AbortSignal) =>
@@ -52,9 +114,12 @@ export const api = {
 
   getFileContent: (path: string, signal?: AbortSignal) => {
     const params = new URLSearchParams({ path })
-    return fetchJson<string>(`${API_BASE}/repository/file?${params}`, signal)
+    return fetchJson<FileContent>(`${API_BASE}/repository/file?${params}`, signal)
   },
 
+  /** The file's bytes as committed, for downloads and image, video and audio previews */
+  rawFileUrl: (path: string) => `${API_BASE}/repository/raw?${new URLSearchParams({ path })}`,
+
   getCommits: (path?: string, limit = 50, offset = 0, excludeAuthors?: string[], signal?: AbortSignal) => {
     const params = new URLSearchParams()
     if (path) params.set('path', path)
@@ -89,7 +154,7 @@ export const api = {
   },
 
   switchRepository: async (path: string, signal?: AbortSignal): Promise<RepositoryInfo> => {
-    const response = await fetch(`${API_BASE}/filesystem/switch`, {
+    const response = await apiFetch(`${API_BASE}/filesystem/switch`, {
       method: 'POST',
       headers: { 'Content-Type': 'application/json' },
       body: JSON.stringify({ path }),
@@ -105,8 +170,53 @@ export const api = {
   getBranches: (signal?: AbortSignal) =>
     fetchJson<BranchInfo[]>(`${API_BASE}/repository/branches`, signal),
 
+  createBranch: async (
+    name: string,
+    options: { startPoint?: string; checkout?: boolean } = {},
+    signal?: AbortSignal
+  ): Promise<BranchInfo> => {
+    const response = await apiFetch(`${API_BASE}/repository/branches`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ name, start_point: options.startPoint, checkout: options.checkout ?? false }),
+      signal,
+    })
+    if (!response.ok) {
+      const error = await response.json().catch(() => ({ error: response.statusText }))
+      throw new Error(error.error || 'Request failed')
+    }
+    return response.json()
+  },
+
+  renameBranch: async (name: string, newName: string, signal?: AbortSignal): Promise<RenamedBranch> => {
+    const response = await apiFetch(`${API_BASE}/repository/branches/rename`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ name, new_name: newName }),
+      signal,
+    })
+    if (!response.ok) {
+      const error = await response.json().catch(() => ({ error: response.statusText }))
+      throw new Error(error.error || 'Request failed')
+    }
+    return response.json()
+  },
+
+  deleteBranch: async (name: string, force = false, signal?: AbortSignal): Promise<DeletedBranch> => {
+    const params = new URLSearchParams({ force: String(force) })
+    const response = await apiFetch(`${API_BASE}/repository/branches/${encodeURI(name)}?${params}`, {
+      method: 'DELETE',
+      signal,
+    })
+    if (!response.ok) {
+      const error = await response.json().catch(() => ({ error: response.statusText }))
+      throw new Error(error.error || 'Request failed')
+    }
+    return response.json()
+  },
+
   checkoutBranch: async (branch: string, signal?: AbortSignal): Promise<void> => {
-    const response = await fetch(`${API_BASE}/repository/checkout`, {
+    const response = await apiFetch(`${API_BASE}/repository/checkout`, {
       method: 'POST',
       headers: { 'Content-Type': 'application/json' },
       body: JSON.stringify({ branch }),
@@ -119,7 +229,7 @@ export const api = {
   },
 
   checkoutRemoteBranch: async (remoteBranch: string, localName: string, signal?: AbortSignal): Promise<void> => {
-    const response = await fetch(`${API_BASE}/repository/checkout-remote`, {
+    const response = await apiFetch(`${API_BASE}/repository/checkout-remote`, {
       method: 'POST',
       headers: { 'Content-Type': 'application/json' },
       body: JSON.stringify({ remote_branch: remoteBranch, local_name: localName }),
@@ -136,9 +246,123 @@ export const api = {
     return fetchJson<BlameResponse>(`${API_BASE}/repository/blame?${params}`, signal)
   },
 
+  createCommit: async (message: string, signal?: AbortSignal): Promise<CommitDetail> => {
+    const response = await apiFetch(`${API_BASE}/repository/commit`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ message }),
+      signal,
+    })
+    if (!response.ok) {
+      const error = await response.json().catch(() => ({ error: response.statusText }))
+      throw new Error(error.error || 'Request failed')
+    }
+    return response.json()
+  },
+
+  stashPush: async (
+    options: { message?: string; includeUntracked?: boolean } = {},
+    signal?: AbortSignal
+  ): Promise<StashEntry> => {
+    const response = await apiFetch(`${API_BASE}/repository/stash`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ message: options.message, include_untracked: options.includeUntracked ?? false }),
+      signal,
+    })
+    if (!response.ok) {
+      const error = await response.json().catch(() => ({ error: response.statusText }))
+      throw new Error(error.error || 'Request failed')
+    }
+    return response.json()
+  },
+
+  stashPop: async (index = 0, signal?: AbortSignal): Promise<StashEntry> => {
+    const response = await apiFetch(`${API_BASE}/repository/stash/pop`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ index }),
+      signal,
+    })
+    if (!response.ok) {
+      const error = await response.json().catch(() => ({ error: response.statusText }))
+      throw new Error(error.error || 'Request failed')
+    }
+    return response.json()
+  },
+
+  mergeBranch: async (branch: string, dryRun = false, signal?: AbortSignal): Promise<MergeResult> => {
+    const response = await apiFetch(`${API_BASE}/repository/merge`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ branch, dry_run: dryRun }),
+      signal,
+    })
+    if (!response.ok) {
+      const error = await response.json().catch(() => ({ error: response.statusText }))
+      throw new Error(error.error || 'Request failed')
+    }
+    return response.json()
+  },
+
+  resetHead: async (
+    ref: string,
+    options: { mode?: ResetMode; dryRun?: boolean; force?: boolean } = {},
+    signal?: AbortSignal
+  ): Promise<ResetResult> => {
+    const response = await apiFetch(`${API_BASE}/repository/reset`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({
+        ref,
+        mode: options.mode ?? 'mixed',
+        dry_run: options.dryRun ?? false,
+        force: options.force ?? false,
+      }),
+      signal,
+    })
+    if (!response.ok) {
+      const error = await response.json().catch(() => ({ error: response.statusText }))
+      throw new Error(error.error || 'Request failed')
+    }
+    return response.json()
+  },
+
   getWorkingTreeStatus: (path?: string, signal?: AbortSignal) => {
     const params = new URLSearchParams()
     if (path) params.set('path', path)
     return fetchJson<WorkingTreeStatus>(`${API_BASE}/repository/working-tree-status?${params}`, signal)
   },
+
+  getStatusFiles: (path?: string, includeUntracked = true, signal?: AbortSignal) => {
+    const params = new URLSearchParams()
+    if (path) params.set('path', path)
+    params.set('include_untracked', String(includeUntracked))
+    return fetchJson<StatusFiles>(`${API_BASE}/repository/status/files?${params}`, signal)
+  },
+
+  getIgnoredFiles: (path?: string, limit = 1000, signal?: AbortSignal) => {
+    const params = new URLSearchParams()
+    if (path) params.set('path', path)
+    params.set('limit', String(limit))
+    return fetchJson<IgnoredFiles>(`${API_BASE}/repository/ignored?${params}`, signal)
+  },
+
+  checkIgnore: (path: string, signal?: AbortSignal) => {
+    const params = new URLSearchParams({ path })
+    return fetchJson<IgnoreCheck>(`${API_BASE}/repository/check-ignore?${params}`, signal)
+  },
+
+  /**
+   * Subscribe to live repository changes (server-sent events).
+   * EventSource reconnects on its own after a dropped connection.
+   * Returns a function that closes the subscription.
+   */
+  subscribeToChanges: (onChange: (event: RepoChangeEvent) => void) => {
+    const source = new EventSource(`${API_BASE}/events`)
+    source.addEventListener('change', (message) => {
+      onChange(JSON.parse((message as MessageEvent<string>).data))
+    })
+    return () => source.close()
+  },
 }
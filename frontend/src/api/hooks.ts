@@ -10,11 +10,16 @@
  * - useTree(): Directory listing for FileList
  * - useFullTree(): Complete tree for FileTree sidebar
  * - useCommits(): Commit history for HistoryTab
+ * - useCacheStatus(): Commit cache build progress while history is loading
  * - useDiff(): Diff data for DiffViewer
+ * - useLiveUpdates(): Invalidates queries when the repository changes on disk
+ * - useBootstrap(): Opens the file, directory or commit given by --path / --ref
  */
 
+import { useEffect, useRef } from 'react'
 import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query'
 import { api } from './client'
+import type { ChangeKind } from './types'
 import { useSelectionStore } from '@/store/selectionStore'
 
 export function useRepository() {
@@ -24,10 +29,12 @@ export function useRepository() {
   })
 }
 
-export function useTree(path?: string, includeLastCommit = true) {
+export function useTree(path?: string, includeLastCommit = true, limit?: number) {
   return useQuery({
-    queryKey: ['tree', path, includeLastCommit],
-    queryFn: ({ signal }) => api.getTree(path, includeLastCommit, signal),
+    queryKey: ['tree', path, includeLastCommit, limit],
+    queryFn: ({ signal }) => api.getTree(path, includeLastCommit, limit, signal),
+    // Keep showing the shorter listing of the same directory while more is loaded
+    placeholderData: (previous, previousQuery) => (previousQuery?.queryKey[1] === path ? previous : undefined),
   })
 }
 
@@ -53,6 +60,19 @@ export function useCommits(path?: string, limit = 50, offset = 0, excludeAuthors
   })
 }
 
+/** Polled every second until the server's commit cache is ready */
+export function useCacheStatus(enabled: boolean) {
+  return useQuery({
+    queryKey: ['cacheStatus'],
+    queryFn: ({ signal }) => api.getCacheStatus(signal),
+    enabled,
+    refetchInterval: (query) => {
+      const state = query.state.data?.state
+      return state === 'ready' || state === 'over_budget' ? false : 1000
+    },
+  })
+}
+
 export function useDiff(toCommit: string | null, fromCommit?: string, path?: string, excludeAuthors?: string[]) {
   return useQuery({
     queryKey: ['diff', toCommit, fromCommit, path, excludeAuthors],
@@ -155,6 +175,65 @@ export function useBlame(path: string | null, commit: string | null) {
   })
 }
 
+/** Queries made stale by each kind of change */
+const LIVE_UPDATE_KEYS: Record<ChangeKind, string[]> = {
+  head: ['repository', 'branches', 'tree', 'fullTree', 'commits', 'file', 'directoryInfo', 'diff', 'workingTreeStatus'],
+  refs: ['repository', 'branches', 'commits'],
+  index: ['workingTreeStatus', 'diff'],
+  working_tree: ['workingTreeStatus', 'diff'],
+  repository: [
+    'repository', 'branches', 'tree', 'fullTree', 'commits', 'file', 'directoryInfo', 'diff', 'workingTreeStatus', 'blame',
+  ],
+}
+
+/**
+ * Keep the UI in sync with commits, checkouts, staging and edits made outside
+ * the viewer. Mount once; the server pushes a change event per batch of edits.
+ */
+export function useLiveUpdates() {
+  const queryClient = useQueryClient()
+
+  useEffect(() => {
+    return api.subscribeToChanges((event) => {
+      const keys = new Set(event.kinds.flatMap((kind) => LIVE_UPDATE_KEYS[kind] ?? []))
+      keys.forEach((key) => queryClient.invalidateQueries({ queryKey: [key] }))
+    })
+  }, [queryClient])
+}
+
+/**
+ * Applies the server's startup deep link once per page load: a directory is
+ * opened, a file selected (with its history), and a commit shown in the diff
+ * modal, filtered to the path if there is one.
+ */
+export function useBootstrap() {
+  const applied = useRef(false)
+  const { data } = useQuery({
+    queryKey: ['bootstrap'],
+    queryFn: ({ signal }) => api.getBootstrap(signal),
+    staleTime: Infinity,
+  })
+
+  useEffect(() => {
+    if (!data || applied.current) return
+    applied.current = true
+
+    const { setCurrentPath, setSelectedFile, setHistoryPath, openDiffModal } = useSelectionStore.getState()
+    if (data.path) {
+      if (data.path_type === 'directory') {
+        setCurrentPath(data.path)
+      } else {
+        setCurrentPath(data.path.split('/').slice(0, -1).join('/'))
+        setSelectedFile(data.path)
+        setHistoryPath(data.path)
+      }
+    }
+    if (data.commit) {
+      openDiffModal(null, data.commit)
+    }
+  }, [data])
+}
+
 export function useWorkingTreeStatus(path?: string) {
   return useQuery({
     queryKey: ['workingTreeStatus', path],
@@ -21,6 +21,40 @@ export interface TreeEntry {
   file_count?: number
   directory_count?: number
   last_commit?: CommitInfo
+  submodule_commit?: string
+  symlink_target?: string
+  /** Set for Git LFS files; `size` is then the real content's size */
+  lfs?: LfsPointer
+}
+
+/** Entries `offset..offset + limit` of a directory */
+export interface TreeListing {
+  entries: TreeEntry[]
+  /** Entries in the directory, including ones outside the page */
+  total: number
+  has_more: boolean
+}
+
+/** A text file, or the part of it the server sent (at most 1 MiB) */
+export interface FileContent {
+  path: string
+  /** Text transcoded to UTF-8, or base64 of the bytes when `encoding` is 'base64' */
+  content: string
+  /** Encoding the file was decoded from ('UTF-8', 'windows-1252', 'Shift_JIS', ...) or 'base64' for binary files */
+  encoding: string
+  /** Size of the whole file in bytes */
+  total_size: number
+  /** Bytes of the file `content` holds, end exclusive */
+  range: { start: number; end: number }
+  truncated: boolean
+}
+
+export interface LfsPointer {
+  /** SHA-256 of the content */
+  oid: string
+  size: number
+  /** Content fetched into .git/lfs/objects */
+  downloaded: boolean
 }
 
 export interface FullTreeEntry {
@@ -28,6 +62,7 @@ export interface FullTreeEntry {
   path: string
   entry_type: 'file' | 'directory' | 'symlink' | 'submodule'
   children?: FullTreeEntry[]
+  submodule_commit?: string
 }
 
 export interface RepositoryInfo {
@@ -35,10 +70,24 @@ export interface RepositoryInfo {
   path: string
   head_branch?: string
   head_commit?: CommitInfo
+  detached_head?: DetachedHead
+  unborn_branch?: string
   is_bare: boolean
   is_empty: boolean
 }
 
+/** Where the UI opens on load, from the server's --path / --ref */
+export interface Bootstrap {
+  path?: string
+  path_type?: 'file' | 'directory' | 'symlink' | 'submodule'
+  commit?: string
+}
+
+export interface DetachedHead {
+  oid: string
+  describe: string
+}
+
 export interface DirectoryInfo {
   path: string
   file_count: number
@@ -47,6 +96,15 @@ export interface DirectoryInfo {
   contributors: ContributorInfo[]
   first_commit?: CommitInfo
   latest_commit?: CommitInfo
+  owners?: string[]
+}
+
+export interface CodeOwnersMatch {
+  path: string
+  codeowners_file?: string
+  pattern?: string
+  line?: number
+  owners: string[]
 }
 
 export interface ContributorInfo {
@@ -69,14 +127,51 @@ export interface CommitDetail {
   relative_time: string
   parent_count: number
   parents: string[]
+  signature?: SignatureStatus
+  verification?: VerificationStatus
+  note?: string
+  conventional?: ConventionalCommit
+}
+
+export interface ConventionalCommit {
+  type: string
+  scope?: string
+  breaking: boolean
+  subject: string
+  body?: string
+  trailers: Trailer[]
+}
+
+export interface Trailer {
+  key: string
+  value: string
 }
 
+export type SignatureStatus = 'signed' | 'unsigned' | 'unknown'
+
+export type VerificationStatus =
+  | 'good'
+  | 'untrusted'
+  | 'bad'
+  | 'expired_signature'
+  | 'expired_key'
+  | 'revoked_key'
+  | 'cannot_verify'
+  | 'unsigned'
+
 export interface CommitListResponse {
   commits: CommitDetail[]
   total: number
   filtered_total: number
   has_more: boolean
   contributors: AuthorInfo[]
+  match_counts?: QueryMatchCounts
+}
+
+export interface QueryMatchCounts {
+  message: number
+  author_name: number
+  author_email: number
 }
 
 export interface FileAuthorInfo {
@@ -95,6 +190,24 @@ export interface DiffResponse {
   contributors: AuthorInfo[]
   total_files: number
   filtered_files: number
+  /** Working tree diffs only: files git doesn't track yet (not in `files`/`stats`) */
+  untracked?: UntrackedFile[]
+}
+
+export interface UntrackedFile {
+  path: string
+  size: number
+  is_binary: boolean
+  is_generated: boolean
+  is_symlink: boolean
+  mime?: string
+  line_count?: number
+  /** Text contents (link target for symlinks); null for binary, generated or too large files */
+  content?: string
+  /** Set when the text isn't UTF-8: the encoding `content` was transcoded from */
+  encoding?: string
+  too_large: boolean
+  owners?: string[]
 }
 
 export interface FileDiff {
@@ -104,9 +217,50 @@ export interface FileDiff {
   hunks: DiffHunk[]
   old_content?: string
   new_content?: string
+  /** Set when the file isn't UTF-8: the encoding its contents and hunk lines were transcoded from */
+  encoding?: string
   is_binary: boolean
+  is_generated: boolean
   authors: FileAuthorInfo[]
   biggest_change_author?: string
+  image?: ImageDiff
+  binary?: BinaryDiff
+  /** Set when either side is an LFS pointer; no hunks or contents then */
+  lfs?: LfsDiff
+  old_mode?: string
+  new_mode?: string
+  mode_change?: 'executable_added' | 'executable_removed' | 'to_symlink' | 'from_symlink' | 'other'
+  owners?: string[]
+}
+
+export interface BinaryDiff {
+  old?: BinaryVersion
+  new?: BinaryVersion
+  size_delta: number
+}
+
+export interface LfsDiff {
+  old?: LfsPointer
+  new?: LfsPointer
+  size_delta: number
+}
+
+export interface BinaryVersion {
+  oid: string
+  size: number
+}
+
+export interface ImageDiff {
+  old?: ImageVersion
+  new?: ImageVersion
+}
+
+export interface ImageVersion {
+  oid: string
+  mime: string
+  size: number
+  width?: number
+  height?: number
 }
 
 export interface DiffHunk {
@@ -123,6 +277,20 @@ export interface DiffLine {
   old_lineno?: number
   new_lineno?: number
   content: string
+  blame?: LineBlame
+  spans?: IntraLineSpan[]
+}
+
+export interface IntraLineSpan {
+  start: number
+  end: number
+}
+
+export interface LineBlame {
+  commit_oid: string
+  author_name: string
+  author_email: string
+  timestamp: number
 }
 
 export interface DiffStats {
@@ -149,6 +317,28 @@ export interface BranchInfo {
   is_current: boolean
   is_remote: boolean
   last_commit?: CommitInfo
+  upstream?: UpstreamStatus
+}
+
+export interface UpstreamStatus {
+  name: string
+  ahead: number
+  behind: number
+}
+
+export interface RenamedBranch {
+  old_name: string
+  name: string
+  is_current: boolean
+  upstream?: string
+  retracked: string[]
+}
+
+export interface DeletedBranch {
+  name: string
+  commit: string
+  upstream?: string
+  was_merged: boolean
 }
 
 export interface BlameLine {
@@ -157,17 +347,252 @@ export interface BlameLine {
   author_email: string
   commit_oid: string
   timestamp: number
+  orig_path?: string
+  orig_line_number: number
 }
 
 export interface BlameResponse {
   path: string
   commit: string
+  ignored_revs?: string[]
   lines: BlameLine[]
 }
 
 export interface WorkingTreeStatus {
   has_changes: boolean
   files_changed: number
+  staged: StatusEntry[]
+  unstaged: StatusEntry[]
+  untracked: string[]
+  conflicted: string[]
+}
+
+export interface StatusEntry {
+  path: string
+  old_path?: string
+  status: 'added' | 'modified' | 'deleted' | 'renamed' | 'typechanged'
+}
+
+export interface StatusFiles {
+  files: StatusFile[]
+  staged_count: number
+  unstaged_count: number
+  untracked_count: number
+  conflicted_count: number
+}
+
+export interface StatusFile {
+  path: string
+  old_path?: string
+  index_status?: StatusEntry['status']
+  worktree_status?: StatusEntry['status']
+  is_untracked: boolean
+  conflict?:
+    | 'both_modified'
+    | 'both_added'
+    | 'both_deleted'
+    | 'added_by_us'
+    | 'added_by_them'
+    | 'deleted_by_us'
+    | 'deleted_by_them'
+}
+
+export interface IgnoreRule {
+  /** `.gitignore` path in the repo, `.git/info/exclude` or the core.excludesFile path */
+  source: string
+  line: number
+  pattern: string
+  negated: boolean
+}
+
+export interface IgnoredEntry {
+  path: string
+  is_dir: boolean
+  rule?: IgnoreRule
+}
+
+export interface IgnoredFiles {
+  path?: string
+  entries: IgnoredEntry[]
+  truncated: boolean
+}
+
+export interface IgnoreCheck {
+  path: string
+  ignored: boolean
+  tracked: boolean
+  exists: boolean
+  is_dir: boolean
+  /** Deciding rule; a negated one re-includes the path */
+  rule?: IgnoreRule
+}
+
+export interface Permalink {
+  remote: string
+  web_url: string
+  forge: 'github' | 'gitlab' | 'bitbucket' | 'gitea'
+  commit: string
+  path?: string
+  url: string
+}
+
+export type ActivityGranularity = 'day' | 'week' | 'month'
+
+export interface ActivityBucket {
+  period: string
+  commit_count: number
+}
+
+export interface ActivityResponse {
+  granularity: ActivityGranularity
+  path?: string
+  author?: string
+  total_commits: number
+  buckets: ActivityBucket[]
+}
+
+export interface PunchCardResponse {
+  path?: string
+  author?: string
+  total_commits: number
+  cells: PunchCardCell[]
+}
+
+export interface PunchCardCell {
+  day: number
+  hour: number
+  commit_count: number
+}
+
+export interface AuthorStatsResponse {
+  range?: string
+  since?: number
+  until?: number
+  commit_count: number
+  insertions: number
+  deletions: number
+  authors: AuthorLineStats[]
+}
+
+export interface AuthorLineStats {
+  name: string
+  email: string
+  commit_count: number
+  insertions: number
+  deletions: number
+}
+
+export type HotspotSort = 'frequency' | 'churn'
+
+export interface HotspotsResponse {
+  path?: string
+  since?: number
+  until?: number
+  sort: HotspotSort
+  commit_count: number
+  total_files: number
+  files: FileHotspot[]
+}
+
+export interface FileHotspot {
+  path: string
+  commit_count: number
+  insertions: number
+  deletions: number
+  author_count: number
+  last_changed_timestamp: number
+}
+
+export interface GraphResponse {
+  commits: GraphCommit[]
+  lane_count: number
+  has_more: boolean
+}
+
+export interface GraphCommit {
+  oid: string
+  parents: string[]
+  summary: string
+  author: string
+  timestamp: number
+  lane: number
+  edges: GraphEdge[]
+  refs?: string[]
+}
+
+export interface GraphEdge {
+  from_lane: number
+  to_lane: number
+}
+
+export interface StashEntry {
+  index: number
+  message: string
+  oid: string
+}
+
+export type MergeAnalysis = 'up_to_date' | 'fast_forward' | 'normal' | 'conflicts'
+
+export interface MergeResult {
+  branch: string
+  analysis: MergeAnalysis
+  applied: boolean
+  head?: string
+  merge_commit?: string
+  conflicts?: string[]
+}
+
+export type ResetMode = 'soft' | 'mixed' | 'hard'
+
+export interface ResetResult {
+  mode: ResetMode
+  target: string
+  previous_head: string
+  applied: boolean
+  removed_commit_count: number
+  removed_commits: CommitInfo[]
+  added_commit_count: number
+  files_changed: number
+  discarded_changes?: string[]
+}
+
+export interface CacheStats {
+  head_oid: string
+  total_commits: number
+  cached_paths: number
+  path_cache_bytes: number
+  commits_bytes: number
+  age_secs: number
+}
+
+export type CacheBuildState = 'cold' | 'building' | 'ready' | 'over_budget'
+
+export interface CacheStatus {
+  state: CacheBuildState
+  commits_read?: number
+  total_commits?: number
+  elapsed_ms?: number
+  eta_ms?: number
+  last_build_ms?: number
+  cache?: CacheStats
+}
+
+export interface HealthResponse {
+  version: string
+  uptime_secs: number
+  repository_path: string
+  head_oid?: string
+  cache?: CacheStats
+  cache_budget_bytes: number
+  cache_over_budget: boolean
+}
+
+export type ChangeKind = 'head' | 'refs' | 'index' | 'working_tree' | 'repository'
+
+export interface RepoChangeEvent {
+  kinds: ChangeKind[]
+  paths?: string[]
+  paths_truncated: boolean
 }
 
 export const WORKING_TREE = 'WORKING_TREE'
@@ -2,29 +2,62 @@
  * ContentsTab - File content viewer.
  *
  * Shows file contents in a scrollable monospace view for text files.
- * Markdown files can be rendered or shown as source.
+ * Markdown and SVG files can be rendered or shown as source. Images, video
+ * and audio are previewed from the raw endpoint instead of loading content.
+ * Files over the server's 1 MiB limit show their beginning, with a link to
+ * download the whole file. Non-UTF-8 text arrives transcoded; binary files
+ * (sent base64-encoded) only get the download link.
  */
 
 import { useState } from 'react'
 import Markdown from 'react-markdown'
 import remarkGfm from 'remark-gfm'
 import { useFileContent } from '@/api/hooks'
+import { api } from '@/api/client'
 import { useSelectionStore } from '@/store/selectionStore'
 import { ScrollArea } from '@/components/ui/scroll-area'
 import { Button } from '@/components/ui/button'
 import { Code, FileText } from 'lucide-react'
+import { formatBytes } from '@/lib/utils'
+import type { FileContent } from '@/api/types'
+
+const IMAGE_EXTENSIONS = ['png', 'jpg', 'jpeg', 'gif', 'webp', 'avif', 'bmp', 'ico']
+const VIDEO_EXTENSIONS = ['mp4', 'm4v', 'webm', 'mov', 'ogv']
+const AUDIO_EXTENSIONS = ['mp3', 'wav', 'ogg', 'oga', 'opus', 'flac', 'm4a', 'aac']
+
+type MediaKind = 'image' | 'video' | 'audio'
+
+function extensionOf(path: string): string | undefined {
+  return path.split('.').pop()?.toLowerCase()
+}
 
 function isMarkdownFile(path: string): boolean {
-  const ext = path.split('.').pop()?.toLowerCase()
+  const ext = extensionOf(path)
   return ext === 'md' || ext === 'markdown'
 }
 
+/** Media the browser can show from the file's raw URL; SVG is text and handled apart */
+function mediaKindOf(path: string): MediaKind | null {
+  const ext = extensionOf(path) ?? ''
+  if (IMAGE_EXTENSIONS.includes(ext)) return 'image'
+  if (VIDEO_EXTENSIONS.includes(ext)) return 'video'
+  if (AUDIO_EXTENSIONS.includes(ext)) return 'audio'
+  return null
+}
+
 export function ContentsTab() {
   const { historyPath } = useSelectionStore()
-  const { data: content, isLoading, error } = useFileContent(historyPath || null)
+  const mediaKind = historyPath ? mediaKindOf(historyPath) : null
+  const { data: file, isLoading, error } = useFileContent(mediaKind ? null : historyPath || null)
   const [showRendered, setShowRendered] = useState(true)
 
   const isMarkdown = historyPath ? isMarkdownFile(historyPath) : false
+  const isSvg = historyPath ? extensionOf(historyPath) === 'svg' : false
+  const hasRendered = isMarkdown || isSvg
+
+  if (historyPath && mediaKind) {
+    return <MediaPreview path={historyPath} kind={mediaKind} />
+  }
 
   if (isLoading) {
     return (
@@ -42,16 +75,27 @@ export function ContentsTab() {
     )
   }
 
-  if (!content) {
+  if (!file?.content) {
     return (
       <div className="flex items-center justify-center h-full text-gray-500">
         No content available
       </div>
     )
   }
+  if (file.encoding === 'base64') {
+    return (
+      <div className="flex flex-col items-center justify-center h-full gap-1 text-gray-500">
+        Binary file ({formatBytes(file.total_size)})
+        <a href={api.rawFileUrl(file.path)} download className="text-xs underline">
+          Download
+        </a>
+      </div>
+    )
+  }
+  const content = file.content
 
-  // Render markdown
-  if (isMarkdown && showRendered) {
+  // Render markdown or SVG
+  if (hasRendered && showRendered) {
     return (
       <div className="h-full flex flex-col">
         <div className="flex items-center gap-2 px-3 py-1.5 border-b border-gray-200 bg-gray-50">
@@ -74,17 +118,24 @@ export function ContentsTab() {
             Source
           </Button>
         </div>
+        <TruncationNotice file={file} />
         <ScrollArea className="flex-1">
-          <div
-            className="p-4 prose prose-sm max-w-none prose-headings:mt-4 prose-headings:mb-2 prose-p:my-2"
-            style={{
-              '--tw-prose-pre-code': '#000',
-              '--tw-prose-pre-bg': '#f3f4f6',
-              '--tw-prose-code': '#000',
-            } as React.CSSProperties}
-          >
-            <Markdown remarkPlugins={[remarkGfm]}>{content}</Markdown>
-          </div>
+          {isSvg ? (
+            <div className="p-4">
+              <img src={api.rawFileUrl(file.path)} alt={file.path} className="max-w-full" />
+            </div>
+          ) : (
+            <div
+              className="p-4 prose prose-sm max-w-none prose-headings:mt-4 prose-headings:mb-2 prose-p:my-2"
+              style={{
+                '--tw-prose-pre-code': '#000',
+                '--tw-prose-pre-bg': '#f3f4f6',
+                '--tw-prose-code': '#000',
+              } as React.CSSProperties}
+            >
+              <Markdown remarkPlugins={[remarkGfm]}>{content}</Markdown>
+            </div>
+          )}
         </ScrollArea>
       </div>
     )
@@ -95,7 +146,7 @@ export function ContentsTab() {
 
   return (
     <div className="h-full flex flex-col">
-      {isMarkdown && (
+      {hasRendered && (
         <div className="flex items-center gap-2 px-3 py-1.5 border-b border-gray-200 bg-gray-50">
           <Button
             variant={showRendered ? "secondary" : "ghost"}
@@ -117,6 +168,7 @@ export function ContentsTab() {
           </Button>
         </div>
       )}
+      <TruncationNotice file={file} />
       <ScrollArea className="flex-1">
         <div className="p-2">
           <table className="w-full text-xs font-mono border-collapse">
@@ -138,3 +190,35 @@ export function ContentsTab() {
     </div>
   )
 }
+
+/** An image, video or audio file played from its raw URL, which serves byte ranges for seeking */
+function MediaPreview({ path, kind }: { path: string; kind: MediaKind }) {
+  const url = api.rawFileUrl(path)
+  return (
+    <div className="h-full flex flex-col">
+      <div className="flex-1 min-h-0 flex items-center justify-center p-4 overflow-auto">
+        {kind === 'image' && <img src={url} alt={path} className="max-w-full max-h-full object-contain" />}
+        {kind === 'video' && <video src={url} controls className="max-w-full max-h-full" />}
+        {kind === 'audio' && <audio src={url} controls />}
+      </div>
+      <div className="px-3 py-1.5 border-t border-gray-200 bg-gray-50 text-xs text-gray-500">
+        <a href={url} download className="underline">
+          Download
+        </a>
+      </div>
+    </div>
+  )
+}
+
+/** Says how much of a truncated file is shown, with a link to the whole file */
+function TruncationNotice({ file }: { file: FileContent }) {
+  if (!file.truncated) return null
+  return (
+    <div className="px-3 py-1.5 border-b border-amber-200 bg-amber-50 text-xs text-amber-800">
+      Showing the first {formatBytes(file.range.end)} of {formatBytes(file.total_size)}.{' '}
+      <a href={api.rawFileUrl(file.path)} download className="underline">
+        Download the whole file
+      </a>
+    </div>
+  )
+}
@@ -10,11 +10,12 @@
  *
  * Uses commit cache on backend for fast repeated queries.
  * Makes two API calls: unfiltered (for contributor list) + filtered.
+ * While the backend builds the cache, shows how far along it is.
  */
 
 import { useState, useEffect, useMemo } from 'react'
-import { useCommits, useWorkingTreeStatus } from '@/api/hooks'
-import { WORKING_TREE } from '@/api/types'
+import { useCommits, useWorkingTreeStatus, useCacheStatus } from '@/api/hooks'
+import { WORKING_TREE, type CacheStatus } from '@/api/types'
 import { useSelectionStore } from '@/store/selectionStore'
 import { useSettingsStore } from '@/store/settingsStore'
 import { ScrollArea } from '@/components/ui/scroll-area'
@@ -51,6 +52,8 @@ export function HistoryTab() {
     excludedAuthorsForApi
   )
 
+  const { data: cacheStatus } = useCacheStatus(isLoading)
+
   const getCommitTimestamp = (oid: string) => {
     return data?.commits.find(c => c.oid === oid)?.timestamp ?? null
   }
@@ -94,7 +97,7 @@ export function HistoryTab() {
   if (isLoading) {
     return (
       <div className="flex items-center justify-center h-full text-gray-500">
-        Loading...
+        {cacheStatus?.state === 'building' ? formatCacheProgress(cacheStatus) : 'Loading...'}
       </div>
     )
   }
@@ -273,3 +276,13 @@ export function HistoryTab() {
     </div>
   )
 }
+
+function formatCacheProgress(status: CacheStatus) {
+  if (status.total_commits === undefined) {
+    return 'Indexing history...'
+  }
+  const read = (status.commits_read ?? 0).toLocaleString()
+  const total = status.total_commits.toLocaleString()
+  const eta = status.eta_ms !== undefined ? ` (about ${Math.ceil(status.eta_ms / 1000)}s left)` : ''
+  return `Indexing history: ${read} of ${total} commits${eta}`
+}
@@ -317,7 +317,7 @@ export function BranchSwitcher() {
             className="h-auto py-0 px-1 text-xs text-gray-500 hover:text-gray-700 font-normal"
           >
             <GitBranch className="h-3 w-3 mr-1" />
-            {repo?.head_branch || 'detached'}
+            {repo?.head_branch || repo?.unborn_branch || (repo?.detached_head ? `detached at ${repo.detached_head.describe}` : 'detached')}
           </Button>
         </PopoverTrigger>
         <PopoverContent className="w-72" align="start">
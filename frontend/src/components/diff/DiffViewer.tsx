@@ -18,6 +18,7 @@
  * - Contributor filter (hide or gray out files by author)
  * - File list panel (toggleable) with scroll sync
  * - Author badges on each file showing who modified it
+ * - Untracked files listed after the diffs (working tree only), with contents for text files
  *
  * Performance notes:
  * - All files rendered in DOM (no virtualization)
@@ -37,8 +38,8 @@ import { ScrollArea } from '@/components/ui/scroll-area'
 import { Button } from '@/components/ui/button'
 import { ContributorFilter } from '@/components/bottom-panel/ContributorFilter'
 import { FileEdit, FilePlus, FileMinus, FileX2, Columns2, Rows2, PanelLeftClose, PanelLeft, ChevronDown, ChevronRight, ChevronsDownUp, ChevronsUpDown, Rows3, Rows4, User, EyeOff, Eye, GitCommitHorizontal, Loader2, FolderOpen } from 'lucide-react'
-import { cn } from '@/lib/utils'
-import type { FileDiff, BlameLine } from '@/api/types'
+import { cn, formatBytes } from '@/lib/utils'
+import type { FileDiff, BlameLine, UntrackedFile } from '@/api/types'
 import { WORKING_TREE } from '@/api/types'
 import { loadAuthorFilter, saveAuthorFilter, getExcludedAuthorsForApi, type AuthorFilterState } from '@/utils/authorFilter'
 
@@ -503,7 +504,18 @@ const FileDiffContentBase = memo(function FileDiffContentBase({
 
       {/* Diff content */}
       {!collapsed && (
-        file.is_binary ? (
+        file.lfs ? (
+          <div className={cn("text-gray-500", compact ? "p-2 text-xs" : "p-4 text-sm")}>
+            Stored in Git LFS:{' '}
+            {file.lfs.old ? formatBytes(file.lfs.old.size) : '-'} → {file.lfs.new ? formatBytes(file.lfs.new.size) : '-'}
+            {file.lfs.size_delta !== 0 && ` (${file.lfs.size_delta > 0 ? '+' : '-'}${formatBytes(Math.abs(file.lfs.size_delta))})`}
+            {file.lfs.new && (
+              <span className="ml-2 font-mono text-xs text-gray-400" title={`sha256:${file.lfs.new.oid}`}>
+                {file.lfs.new.oid.slice(0, 12)}
+              </span>
+            )}
+          </div>
+        ) : file.is_binary ? (
           <div className={cn("text-gray-500", compact ? "p-2 text-xs" : "p-4 text-sm")}>
             Binary file not shown
           </div>
@@ -557,6 +569,55 @@ const FileDiffContentBase = memo(function FileDiffContentBase({
   )
 })
 
+// Untracked file: no diff to show, just its metadata and (for text) its contents
+const UntrackedFileCard = memo(function UntrackedFileCard({ file, compact }: {
+  file: UntrackedFile
+  compact: boolean
+}) {
+  const [collapsed, setCollapsed] = useState(file.is_generated)
+
+  let placeholder: string | null = null
+  if (file.too_large) placeholder = 'File too large to display'
+  else if (file.is_binary) placeholder = `Binary file${file.mime ? ` (${file.mime})` : ''}`
+  else if (file.is_generated) placeholder = 'Generated file'
+  else if (file.content === undefined || file.content === null) placeholder = 'Contents not loaded'
+
+  return (
+    <div className="border border-gray-200 rounded-lg overflow-hidden">
+      <button
+        onClick={() => setCollapsed(!collapsed)}
+        className={cn(
+          "w-full flex items-center gap-2 px-4 bg-gray-50 hover:bg-gray-100 transition-colors text-left",
+          !collapsed && "border-b border-gray-200",
+          compact ? "py-1" : "py-2"
+        )}
+      >
+        {collapsed ? (
+          <ChevronRight className={cn("text-gray-500 shrink-0", compact ? "h-3.5 w-3.5" : "h-4 w-4")} />
+        ) : (
+          <ChevronDown className={cn("text-gray-500 shrink-0", compact ? "h-3.5 w-3.5" : "h-4 w-4")} />
+        )}
+        <FilePlus className="h-4 w-4 text-gray-400 shrink-0" />
+        <span className={cn("font-medium truncate", compact ? "text-xs" : "text-sm")}>{file.path}</span>
+        {file.is_symlink && <span className="text-xs text-gray-500 shrink-0">symlink</span>}
+        <span className="ml-auto text-xs text-gray-500 shrink-0">
+          {file.line_count !== undefined && file.line_count !== null && `${file.line_count} lines · `}
+          {formatBytes(file.size)}
+        </span>
+      </button>
+      {!collapsed && (
+        placeholder ? (
+          <div className="px-4 py-3 text-sm text-gray-500">{placeholder}</div>
+        ) : (
+          <pre className={cn("px-4 py-2 font-mono overflow-x-auto whitespace-pre", compact ? "text-[11px]" : "text-xs")}>
+            {file.content}
+          </pre>
+        )
+      )}
+    </div>
+  )
+})
+
 export function DiffViewer({ toCommit, fromCommit, path }: DiffViewerProps) {
   const {
     diffFilePanelOpen: filePanelOpen,
@@ -647,6 +708,9 @@ export function DiffViewer({ toCommit, fromCommit, path }: DiffViewerProps) {
       initializedRef.current = true
       if (diffFilesCollapsedByDefault) {
         setCollapsedFiles(new Set(diff.files.map((_, i) => i)))
+      } else {
+        // Generated files (linguist-generated) start collapsed
+        setCollapsedFiles(new Set(diff.files.flatMap((file, i) => (file.is_generated ? [i] : []))))
       }
     }
   }, [diff, diffFilesCollapsedByDefault])
@@ -827,6 +891,18 @@ export function DiffViewer({ toCommit, fromCommit, path }: DiffViewerProps) {
 
   if (!diff) return null
 
+  const untrackedFiles = diff.untracked ?? []
+  const untrackedSection = untrackedFiles.length > 0 && (
+    <div className="space-y-3">
+      <div className="text-sm font-medium text-gray-600">
+        Untracked files ({untrackedFiles.length})
+      </div>
+      {untrackedFiles.map((file) => (
+        <UntrackedFileCard key={file.path} file={file} compact={compactMode} />
+      ))}
+    </div>
+  )
+
   // Calculate display counts
   const totalFiles = diff.total_files ?? diff.files.length
   const displayedFiles = filterEnabled && filterMode === 'hide'
@@ -871,6 +947,11 @@ export function DiffViewer({ toCommit, fromCommit, path }: DiffViewerProps) {
             <span className="text-sm text-red-600">
               -{diff.stats.deletions} deletions
             </span>
+            {untrackedFiles.length > 0 && (
+              <span className="text-sm text-gray-500">
+                {untrackedFiles.length} untracked
+              </span>
+            )}
           </>
         )}
         <div className="ml-auto flex items-center gap-1">
@@ -1031,7 +1112,9 @@ export function DiffViewer({ toCommit, fromCommit, path }: DiffViewerProps) {
                     </div>
                   ))}
 
-                  {processedFiles.length === 0 && (
+                  {untrackedSection}
+
+                  {processedFiles.length === 0 && untrackedFiles.length === 0 && (
                     <div className="flex items-center justify-center py-8 text-gray-500">
                       No changes in this diff
                     </div>
@@ -1069,7 +1152,9 @@ export function DiffViewer({ toCommit, fromCommit, path }: DiffViewerProps) {
                 </div>
               ))}
 
-              {processedFiles.length === 0 && (
+              {untrackedSection}
+
+              {processedFiles.length === 0 && untrackedFiles.length === 0 && (
                 <div className="flex items-center justify-center py-8 text-gray-500">
                   No changes in this diff
                 </div>
@@ -10,17 +10,23 @@
  * Clicking a file currently does nothing (no file preview implemented).
  *
  * Data comes from /api/v1/repository/tree with include_last_commit=true.
+ * Large directories are listed TREE_PAGE_SIZE entries at a time, with a
+ * "Show more" button below the table.
  */
 
-import { useMemo } from 'react'
-import { Folder, File, ArrowLeft, FolderIcon, FileIcon } from 'lucide-react'
+import { useMemo, useState } from 'react'
+import { Folder, File, FileSymlink, ArrowLeft, FolderIcon, FileIcon } from 'lucide-react'
 import { useTree } from '@/api/hooks'
 import { useSelectionStore } from '@/store/selectionStore'
 import { useSettingsStore } from '@/store/settingsStore'
 import { ScrollArea } from '@/components/ui/scroll-area'
+import { Button } from '@/components/ui/button'
 import { cn, formatBytes } from '@/lib/utils'
 import type { TreeEntry } from '@/api/types'
 
+/** Entries listed at first, and added by each "Show more" */
+const TREE_PAGE_SIZE = 500
+
 function formatFolderContents(fileCount?: number, directoryCount?: number): React.ReactNode {
   const files = fileCount ?? 0
   const dirs = directoryCount ?? 0
@@ -71,10 +77,25 @@ function FileRow({ entry, onDoubleClick, onClick, isSelected, compact }: FileRow
         <div className="flex items-center gap-2">
           {isDirectory ? (
             <Folder className={cn(compact ? "h-3.5 w-3.5" : "h-4 w-4", "text-blue-500")} />
+          ) : entry.entry_type === 'symlink' ? (
+            <FileSymlink className={cn(compact ? "h-3.5 w-3.5" : "h-4 w-4", "text-gray-500")} />
           ) : (
             <File className={cn(compact ? "h-3.5 w-3.5" : "h-4 w-4", "text-gray-500")} />
           )}
           <span className={cn(compact ? "text-xs" : "text-sm")}>{entry.name}</span>
+          {entry.symlink_target && (
+            <span className={cn("text-gray-400 truncate", compact ? "text-xs" : "text-sm")}>
+              → {entry.symlink_target}
+            </span>
+          )}
+          {entry.lfs && (
+            <span
+              className="px-1 text-[10px] rounded bg-gray-100 border border-gray-200 text-gray-500"
+              title={`Stored in Git LFS (sha256:${entry.lfs.oid})${entry.lfs.downloaded ? '' : ', not fetched'}`}
+            >
+              LFS
+            </span>
+          )}
         </div>
       </td>
       <td className={cn("px-4 text-gray-500 truncate max-w-xs", compact ? "py-1 text-xs" : "py-2 text-sm")}>
@@ -96,17 +117,22 @@ export function FileList() {
   const { currentPath, setCurrentPath, selectedFile, setSelectedFile, setHistoryPath } = useSelectionStore()
   const { compactMode } = useSettingsStore()
 
+  // How many entries to list; starts over at one page in another directory
+  const [shown, setShown] = useState({ path: currentPath, limit: TREE_PAGE_SIZE })
+  const limit = shown.path === currentPath ? shown.limit : TREE_PAGE_SIZE
+
   // Fast query: get file list without commit info
-  const { data: fastEntries, isLoading: fastLoading, error } = useTree(currentPath || undefined, false)
+  const { data: fastListing, isLoading: fastLoading, error } = useTree(currentPath || undefined, false, limit)
 
   // Slow query: get file list with commit info (runs in background)
-  const { data: fullEntries } = useTree(currentPath || undefined, true)
+  const { data: fullListing } = useTree(currentPath || undefined, true, limit)
 
   // Use full entries if available, otherwise use fast entries
-  const entries = useMemo(() => {
-    if (fullEntries) return fullEntries
-    return fastEntries
-  }, [fastEntries, fullEntries])
+  const listing = useMemo(() => {
+    if (fullListing && fullListing.entries.length >= (fastListing?.entries.length ?? 0)) return fullListing
+    return fastListing
+  }, [fastListing, fullListing])
+  const entries = listing?.entries
 
   const isLoading = fastLoading
 
@@ -127,7 +153,7 @@ export function FileList() {
   }
 
   const handleEntryClick = (entry: TreeEntry) => {
-    if (entry.entry_type === 'file') {
+    if (entry.entry_type === 'file' || entry.entry_type === 'symlink') {
       setSelectedFile(entry.path)
     } else {
       setSelectedFile(null)
@@ -204,6 +230,18 @@ export function FileList() {
             Empty directory
           </div>
         )}
+        {listing?.has_more && (
+          <div className="flex items-center justify-center gap-3 py-4 text-sm text-gray-500">
+            Showing {listing.entries.length.toLocaleString()} of {listing.total.toLocaleString()} entries
+            <Button
+              variant="outline"
+              size="sm"
+              onClick={() => setShown({ path: currentPath, limit: limit + TREE_PAGE_SIZE })}
+            >
+              Show more
+            </Button>
+          </div>
+        )}
       </ScrollArea>
     </div>
   )
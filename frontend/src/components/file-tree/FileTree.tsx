@@ -12,7 +12,7 @@
  */
 
 import { useState } from 'react'
-import { ChevronRight, ChevronDown, Folder, FolderOpen, GitBranch, File, FolderTree } from 'lucide-react'
+import { ChevronRight, ChevronDown, Folder, FolderOpen, GitBranch, File, FileSymlink, FolderTree } from 'lucide-react'
 import { useFullTree } from '@/api/hooks'
 import { useSelectionStore } from '@/store/selectionStore'
 import { useSettingsStore } from '@/store/settingsStore'
@@ -40,7 +40,7 @@ function TreeNode({ entry, level, expandedPaths, toggleExpand, showFiles }: Tree
 
   // Filter children based on showFiles setting
   const directoryChildren = entry.children?.filter(child => child.entry_type === 'directory')
-  const fileChildren = showFiles ? entry.children?.filter(child => child.entry_type === 'file') : []
+  const fileChildren = showFiles ? entry.children?.filter(child => child.entry_type === 'file' || child.entry_type === 'symlink') : []
   const visibleChildren = [...(directoryChildren || []), ...(fileChildren || [])]
   const hasVisibleChildren = visibleChildren.length > 0
   const canExpand = isDirectory && hasVisibleChildren
@@ -83,6 +83,8 @@ function TreeNode({ entry, level, expandedPaths, toggleExpand, showFiles }: Tree
           ) : (
             <Folder className="h-4 w-4 shrink-0 text-blue-500" />
           )
+        ) : entry.entry_type === 'symlink' ? (
+          <FileSymlink className="h-4 w-4 shrink-0 text-gray-500" />
         ) : (
           <File className="h-4 w-4 shrink-0 text-gray-500" />
         )}
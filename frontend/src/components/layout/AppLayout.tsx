@@ -13,10 +13,12 @@
  *
  * All panels are resizable via react-resizable-panels.
  * DiffModal overlays when viewing commit diffs.
+ * Live updates keep all panels in sync with changes made outside the viewer.
+ * On load, the server's --path / --ref deep link (if any) is opened.
  */
 
 import { Panel, Group, Separator } from 'react-resizable-panels'
-import { useRepository } from '@/api/hooks'
+import { useRepository, useLiveUpdates, useBootstrap } from '@/api/hooks'
 import { useSettingsStore } from '@/store/settingsStore'
 import { FileTree } from '@/components/file-tree/FileTree'
 import { FileList } from '@/components/file-list/FileList'
@@ -42,6 +44,8 @@ function ResizeHandle({ id, direction }: { id: string; direction: 'horizontal' |
 export function AppLayout() {
   const { data: repo, isLoading, error } = useRepository()
   const { compactMode, setCompactMode } = useSettingsStore()
+  useLiveUpdates()
+  useBootstrap()
 
   if (isLoading) {
     return (
@@ -3,6 +3,14 @@ import { createRoot } from 'react-dom/client'
 import './index.css'
 import App from './App.tsx'
 
+// With --auth-token the server has moved the token from the startup URL into a
+// cookie; drop it from the address bar so it doesn't end up in history or screenshots
+const url = new URL(window.location.href)
+if (url.searchParams.has('token')) {
+  url.searchParams.delete('token')
+  window.history.replaceState(null, '', url)
+}
+
 createRoot(document.getElementById('root')!).render(
   <StrictMode>
     <App />